@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS dungeon_run_encounters;
+DROP TABLE IF EXISTS dungeon_runs;
@@ -0,0 +1,27 @@
+-- One row per instanced dungeon visit, from entering the scene to leaving it.
+CREATE TABLE dungeon_runs (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  scene_id INTEGER NOT NULL,
+  dungeon_difficulty INTEGER,
+  started_at_ms INTEGER NOT NULL,
+  ended_at_ms INTEGER,
+  clear_started_at_ms INTEGER,
+  cleared_at_ms INTEGER,
+  wipes INTEGER NOT NULL DEFAULT 0,
+  deaths INTEGER NOT NULL DEFAULT 0,
+  objective_splits TEXT NOT NULL DEFAULT '[]',
+  CHECK(ended_at_ms IS NULL OR ended_at_ms >= started_at_ms),
+  CHECK(cleared_at_ms IS NULL OR clear_started_at_ms IS NOT NULL),
+  CHECK(wipes >= 0),
+  CHECK(deaths >= 0)
+);
+CREATE INDEX idx_dungeon_runs_started ON dungeon_runs(started_at_ms);
+
+-- Segments recorded while a run was open.
+CREATE TABLE dungeon_run_encounters (
+  encounter_id INTEGER PRIMARY KEY NOT NULL,
+  run_id INTEGER NOT NULL,
+  FOREIGN KEY(run_id) REFERENCES dungeon_runs(id) ON DELETE CASCADE,
+  FOREIGN KEY(encounter_id) REFERENCES encounters(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_dungeon_run_encounters_run ON dungeon_run_encounters(run_id);
@@ -0,0 +1,8 @@
+//! Rebuilds encounters from capture archives without the desktop app.
+//! Run with `--help` for usage.
+fn main() {
+    if let Err(error) = resonance_logs_lib::run_capture_batch(std::env::args().skip(1)) {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+}
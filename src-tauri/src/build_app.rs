@@ -16,10 +16,9 @@ pub fn build_and_run(builder: TauriBuilder<tauri::Wry>) {
                 stop_windivert();
                 if let Some(runtime) =
                     app_handle.try_state::<crate::live::runtime_handle::LiveRuntimeHandle>()
+                    && let Err(error) = runtime.shutdown_blocking()
                 {
-                    if let Err(error) = runtime.shutdown_blocking() {
-                        log::warn!(target: "app::live", "shutdown_live_runtime_failed error={error}");
-                    }
+                    log::warn!(target: "app::live", "shutdown_live_runtime_failed error={error}");
                 }
                 if let Err(error) = crate::database::shutdown_database() {
                     log::warn!(target: "app::db", "shutdown_database_failed error={error}");
@@ -0,0 +1,464 @@
+//! Headless batch parsing behind the `parse_captures` binary.
+//!
+//! Each capture, a pcap/pcapng file or a recorded `.bpsrsess` session, is
+//! decoded and applied through [`LiveSessions`] exactly like the live
+//! pipeline, unthrottled and without windows or voice playback. Finished
+//! encounters are written to the chosen SQLite file; with `--json` their
+//! summaries and per-player totals are printed to stdout.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::database::Database;
+use crate::database::commands::EncounterSummaryDto;
+use crate::database::history_query::EncounterTotalsData;
+use crate::live::bootstrap_snapshot::MonitorRuntimeSnapshot;
+use crate::live::history_writer::HistoryWriterHandle;
+use crate::live::replay::{ReplaySpeed, ReplayStats, SessionEnvelopes, replay};
+use crate::live::sessions::LiveSessions;
+use crate::live::sink::NullSink;
+use crate::packets::packet_capture::{CaptureMethod, start_capture};
+use crate::packets::session_recorder::SESSION_FILE_EXTENSION;
+
+const USAGE: &str =
+    "usage: parse_captures [--db <file>] [--json] [--config <monitor-runtime.json>] <capture>...
+
+Replays pcap/pcapng captures and recorded .bpsrsess sessions through the live
+pipeline. Encounters are written to --db; --json prints their summaries and
+per-player totals. At least one of the two is required.";
+
+/// Only totals are reported, so a single chart bucket is enough.
+const SUMMARY_CHART_POINTS: u32 = 1;
+const IN_MEMORY_DB: &str = ":memory:";
+/// Batch progress and warnings; `RES_LOG`/`RUST_LOG` override it.
+const DEFAULT_LOG_FILTER: &str = "warn,app::live=info";
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct BatchOptions {
+    database: Option<PathBuf>,
+    json: bool,
+    config: Option<PathBuf>,
+    captures: Vec<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchReport {
+    encounters: Vec<EncounterReport>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EncounterReport {
+    summary: EncounterSummaryDto,
+    players: Vec<PlayerTotals>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PlayerTotals {
+    entity_id: String,
+    name: Option<String>,
+    class_id: Option<i32>,
+    class_spec_name: Option<String>,
+    ability_score: Option<i32>,
+    totals: EncounterTotalsData,
+    deaths: usize,
+}
+
+/// What a batch produced: the report when `--json` asked for one, and how
+/// many captures could not be replayed.
+#[derive(Debug)]
+struct BatchOutcome {
+    report: Option<BatchReport>,
+    failed: usize,
+}
+
+pub fn run(args: impl IntoIterator<Item = String>) -> Result<(), String> {
+    let Some(options) = parse_args(args)? else {
+        println!("{USAGE}");
+        return Ok(());
+    };
+    init_logging();
+    let outcome = run_batch(&options)?;
+    if let Some(report) = outcome.report {
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|error| format!("failed to serialize batch report: {error}"))?;
+        println!("{json}");
+    }
+    if outcome.failed > 0 {
+        return Err(format!(
+            "{} of {} captures failed",
+            outcome.failed,
+            options.captures.len()
+        ));
+    }
+    Ok(())
+}
+
+fn run_batch(options: &BatchOptions) -> Result<BatchOutcome, String> {
+    let config = match &options.config {
+        Some(path) => load_config(path)?,
+        None => MonitorRuntimeSnapshot::default(),
+    };
+    let path = options
+        .database
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(IN_MEMORY_DB));
+    // The batch owns its connection rather than the process-wide one, so it
+    // never writes into a database someone else opened.
+    let database = Database::open(&path).map_err(|error| error.to_string())?;
+    let outcome = replay_into(options, &config, &database);
+    let shutdown = database.shutdown();
+    let outcome = outcome?;
+    shutdown?;
+    Ok(outcome)
+}
+
+fn replay_into(
+    options: &BatchOptions,
+    config: &MonitorRuntimeSnapshot,
+    database: &Database,
+) -> Result<BatchOutcome, String> {
+    let previous_encounter = database.latest_encounter_id()?;
+
+    let (history_writer, history_join) = HistoryWriterHandle::start_with(database.clone())?;
+    let mut failed = 0usize;
+    for capture in &options.captures {
+        match replay_capture(capture, config, &history_writer) {
+            Ok(stats) => info!(
+                target: "app::live",
+                "batch_capture_replayed path={} envelopes={} recorded_ms={}",
+                capture.display(),
+                stats.envelopes,
+                stats.recorded_duration_ms
+            ),
+            Err(error) => {
+                failed += 1;
+                warn!(
+                    target: "app::live",
+                    "batch_capture_failed path={} error={error}",
+                    capture.display()
+                );
+            }
+        }
+    }
+    let shutdown = history_writer.shutdown();
+    if history_join.join().is_err() {
+        return Err("batch history writer panicked".to_string());
+    }
+    shutdown?;
+
+    let encounter_ids = database.finished_encounter_ids_after(previous_encounter)?;
+    info!(
+        target: "app::live",
+        "batch_finished captures={} failed={failed} encounters={}",
+        options.captures.len(),
+        encounter_ids.len()
+    );
+    let report = if options.json {
+        Some(BatchReport {
+            encounters: encounter_ids
+                .into_iter()
+                .map(|encounter_id| encounter_report(database, encounter_id))
+                .collect::<Result<_, _>>()?,
+        })
+    } else {
+        None
+    };
+    Ok(BatchOutcome { report, failed })
+}
+
+/// Logs go to stderr so stdout stays a clean JSON document.
+fn init_logging() {
+    let _ = tracing_log::LogTracer::init();
+    let filter = tracing_subscriber::EnvFilter::try_from_env("RES_LOG")
+        .or_else(|_| tracing_subscriber::EnvFilter::try_from_default_env())
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(DEFAULT_LOG_FILTER));
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_target(true)
+        .finish();
+    let _ = tracing::subscriber::set_global_default(subscriber);
+}
+
+/// `None` when only the usage text was asked for.
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<BatchOptions>, String> {
+    let mut options = BatchOptions::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--db" => options.database = Some(flag_value(&mut args, "--db")?),
+            "--config" => options.config = Some(flag_value(&mut args, "--config")?),
+            "--json" => options.json = true,
+            "-h" | "--help" => return Ok(None),
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown option {flag}\n\n{USAGE}"));
+            }
+            _ => options.captures.push(PathBuf::from(arg)),
+        }
+    }
+    if options.captures.is_empty() {
+        return Err(format!("no captures given\n\n{USAGE}"));
+    }
+    if options.database.is_none() && !options.json {
+        return Err(format!(
+            "nothing to do: pass --db, --json or both\n\n{USAGE}"
+        ));
+    }
+    Ok(Some(options))
+}
+
+fn flag_value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<PathBuf, String> {
+    args.next()
+        .map(PathBuf::from)
+        .ok_or_else(|| format!("{flag} needs a value\n\n{USAGE}"))
+}
+
+fn load_config(path: &Path) -> Result<MonitorRuntimeSnapshot, String> {
+    let file = std::fs::File::open(path)
+        .map_err(|error| format!("failed to open {}: {error}", path.display()))?;
+    serde_json::from_reader(std::io::BufReader::new(file))
+        .map_err(|error| format!("failed to parse {}: {error}", path.display()))
+}
+
+fn is_session_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == SESSION_FILE_EXTENSION)
+}
+
+/// Every capture gets fresh sessions; history lanes are reused because the
+/// previous capture's segments are finalized by the time it returns.
+fn replay_capture(
+    path: &Path,
+    config: &MonitorRuntimeSnapshot,
+    history_writer: &HistoryWriterHandle,
+) -> Result<ReplayStats, String> {
+    let make_sessions = |clock| {
+        LiveSessions::with_clock(
+            Arc::new(NullSink),
+            history_writer.clone(),
+            config.clone(),
+            clock,
+        )
+    };
+    if is_session_file(path) {
+        return replay(
+            SessionEnvelopes::new([path.to_path_buf()]),
+            ReplaySpeed::Unthrottled,
+            make_sessions,
+            |_| {},
+        );
+    }
+
+    // The capture thread reassembles the file and closes its channel after
+    // the last packet; decoding happens in `replay` like any recording.
+    let capture = start_capture(CaptureMethod::PcapFile(path.to_path_buf()), None);
+    let (mut envelopes, capture_worker, _outstanding) = capture.into_parts();
+    let result = replay(
+        std::iter::from_fn(|| envelopes.blocking_recv().map(Ok)),
+        ReplaySpeed::Unthrottled,
+        make_sessions,
+        |_| {},
+    );
+    drop(envelopes);
+    match capture_worker.join() {
+        Ok(None) => result,
+        // Whatever was read before the error is already applied; the capture
+        // still counts as failed.
+        Ok(Some(error)) => Err(error),
+        Err(_) => Err("packet capture worker panicked".to_string()),
+    }
+}
+
+fn encounter_report(database: &Database, encounter_id: i32) -> Result<EncounterReport, String> {
+    let detail = database.load_history_detail(encounter_id, SUMMARY_CHART_POINTS)?;
+    let players = detail
+        .entities
+        .into_iter()
+        .filter(|entity| entity.monster_id.is_none())
+        .map(|entity| PlayerTotals {
+            entity_id: entity.entity_id,
+            name: entity.name,
+            class_id: entity.class_id,
+            class_spec_name: entity.class_spec_name,
+            ability_score: entity.ability_score,
+            totals: entity.totals,
+            deaths: entity.deaths.len(),
+        })
+        .collect();
+    Ok(EncounterReport {
+        summary: detail.summary,
+        players,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::entity_id::canonical_player_uuid;
+    use crate::packets::opcodes::{FragmentType, Pkt, WORLD_NTF_SERVICE_ID};
+    use blueprotobuf_lib::blueprotobuf;
+    use prost::Message;
+
+    const SERVER_IP: [u8; 4] = [10, 0, 0, 1];
+    const CLIENT_IP: [u8; 4] = [192, 168, 1, 2];
+    const SERVER_PORT: u16 = 5003;
+    const CLIENT_PORT: u16 = 50_123;
+    const FIRST_SECOND: u32 = 1_700_000_000;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(ToString::to_string).collect()
+    }
+
+    /// A world notify frame as the server sends it: length, fragment type,
+    /// then service, stub and method ahead of the protobuf body.
+    fn notify_frame(opcode: Pkt, message: &impl Message) -> Vec<u8> {
+        let body = message.encode_to_vec();
+        let mut frame = ((6 + 16 + body.len()) as u32).to_be_bytes().to_vec();
+        frame.extend_from_slice(&(FragmentType::Notify as u16).to_be_bytes());
+        frame.extend_from_slice(&WORLD_NTF_SERVICE_ID.to_be_bytes());
+        frame.extend_from_slice(&0u32.to_be_bytes());
+        frame.extend_from_slice(&(opcode as u32).to_be_bytes());
+        frame.extend_from_slice(&body);
+        frame
+    }
+
+    fn damage_frame(attacker: i64, target: i64, value: i64) -> Vec<u8> {
+        let message = blueprotobuf::SyncNearDeltaInfo {
+            delta_infos: vec![blueprotobuf::AoiSyncDelta {
+                uuid: Some(target),
+                skill_effects: Some(blueprotobuf::SkillEffect {
+                    damages: vec![blueprotobuf::SyncDamageInfo {
+                        value: Some(value),
+                        attacker_uuid: Some(attacker),
+                        owner_id: Some(1701),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+        };
+        notify_frame(Pkt::SyncNearDeltaInfo, &message)
+    }
+
+    /// Announces the capturing player, which is what makes their hits combat.
+    fn container_frame(char_id: i64) -> Vec<u8> {
+        let message = blueprotobuf::SyncContainerData {
+            v_data: Some(blueprotobuf::CharSerialize {
+                char_id: Some(char_id),
+                ..Default::default()
+            }),
+        };
+        notify_frame(Pkt::SyncContainerData, &message)
+    }
+
+    /// A classic raw-IP pcap with one server-to-client TCP segment per
+    /// `(seconds, payload)`, sequence numbers running on between them.
+    fn write_capture(path: &Path, segments: &[(u32, Vec<u8>)]) {
+        let mut bytes = Vec::new();
+        for field in [0xa1b2_c3d4u32, 0x0004_0002, 0, 0, 65_535, 101] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        let mut sequence = 1_000u32;
+        for (seconds, payload) in segments {
+            let builder = etherparse::PacketBuilder::ipv4(SERVER_IP, CLIENT_IP, 64).tcp(
+                SERVER_PORT,
+                CLIENT_PORT,
+                sequence,
+                65_535,
+            );
+            let mut packet = Vec::with_capacity(builder.size(payload.len()));
+            builder.write(&mut packet, payload).expect("packet builds");
+            let length = packet.len() as u32;
+            for field in [FIRST_SECOND + seconds, 0, length, length] {
+                bytes.extend_from_slice(&field.to_le_bytes());
+            }
+            bytes.extend_from_slice(&packet);
+            sequence = sequence.wrapping_add(payload.len() as u32);
+        }
+        std::fs::write(path, bytes).expect("write capture");
+    }
+
+    #[test]
+    fn parses_outputs_config_and_captures() {
+        let options = parse_args(args(&[
+            "--db",
+            "guild.db",
+            "raid.pcapng",
+            "--json",
+            "--config",
+            "monitor.json",
+            "run.bpsrsess",
+        ]))
+        .expect("valid arguments")
+        .expect("not a help request");
+        assert_eq!(
+            options,
+            BatchOptions {
+                database: Some(PathBuf::from("guild.db")),
+                json: true,
+                config: Some(PathBuf::from("monitor.json")),
+                captures: vec![PathBuf::from("raid.pcapng"), PathBuf::from("run.bpsrsess")],
+            }
+        );
+        assert!(is_session_file(&options.captures[1]));
+        assert!(!is_session_file(&options.captures[0]));
+    }
+
+    #[test]
+    fn rejects_runs_without_captures_or_output() {
+        assert!(parse_args(args(&["--json"])).is_err());
+        assert!(parse_args(args(&["raid.pcap"])).is_err());
+        assert!(parse_args(args(&["--db"])).is_err());
+        assert!(parse_args(args(&["--verbose", "raid.pcap", "--json"])).is_err());
+        assert_eq!(parse_args(args(&["--help"])), Ok(None));
+    }
+
+    #[test]
+    fn replays_a_pcap_into_the_database_and_counts_unreadable_captures() {
+        let directory = tempfile::tempdir().expect("temp dir");
+        let capture = directory.path().join("pull.pcap");
+        let database = directory.path().join("batch.db");
+        let player = canonical_player_uuid(4_242);
+        let monster = (77 << 16) | (1 << 6);
+        write_capture(
+            &capture,
+            &[
+                (0, container_frame(4_242)),
+                (0, damage_frame(player, monster, 1_234)),
+                (1, damage_frame(player, monster, 4_321)),
+            ],
+        );
+
+        let outcome = run_batch(&BatchOptions {
+            database: Some(database.clone()),
+            json: true,
+            config: None,
+            captures: vec![capture, directory.path().join("missing.pcap")],
+        })
+        .expect("batch runs");
+
+        assert_eq!(outcome.failed, 1);
+        assert!(database.exists());
+        let report =
+            serde_json::to_value(outcome.report.expect("json report")).expect("report serializes");
+        // The batch has its own database, so this capture's encounter is the
+        // only one.
+        let encounters = report["encounters"].as_array().expect("encounter list");
+        assert_eq!(encounters.len(), 1);
+        assert_eq!(encounters[0]["summary"]["totalDmg"], "5555");
+        let players = encounters[0]["players"].as_array().expect("player list");
+        assert!(
+            players
+                .iter()
+                .any(|player| player["totals"]["damage"] == "5555")
+        );
+        assert!(encounters[0]["summary"]["endedAtMs"].is_i64());
+    }
+}
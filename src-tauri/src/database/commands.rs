@@ -5,6 +5,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::database::PlayerNameEntry;
 use crate::database::db_exec;
+use crate::database::dungeon_runs::{
+    DungeonRunDetailDto, DungeonRunsResult, load_dungeon_run_detail, load_dungeon_runs,
+};
 use crate::database::history_query::{EncounterDetailData, EncounterRangeData};
 use crate::database::schema as sch;
 use crate::live::history_writer::HistoryWriterHandle;
@@ -531,6 +534,38 @@ pub async fn get_encounter_range(
     .map_err(|error| format!("encounter range worker failed: {error}"))?
 }
 
+#[tauri::command]
+#[specta::specta]
+pub async fn get_dungeon_runs(
+    limit: i32,
+    offset: i32,
+    scene_id: Option<i32>,
+    history_writer: tauri::State<'_, HistoryWriterHandle>,
+) -> Result<DungeonRunsResult, String> {
+    let history_writer = history_writer.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        history_writer.fence()?;
+        with_db(move |conn| load_dungeon_runs(conn, limit, offset, scene_id))
+    })
+    .await
+    .map_err(|error| format!("dungeon run list worker failed: {error}"))?
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_dungeon_run_detail(
+    run_id: i32,
+    history_writer: tauri::State<'_, HistoryWriterHandle>,
+) -> Result<DungeonRunDetailDto, String> {
+    let history_writer = history_writer.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        history_writer.fence()?;
+        with_db(move |conn| load_dungeon_run_detail(conn, run_id))
+    })
+    .await
+    .map_err(|error| format!("dungeon run detail worker failed: {error}"))?
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn delete_encounter(encounter_id: i32) -> Result<(), String> {
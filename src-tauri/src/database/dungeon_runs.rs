@@ -0,0 +1,386 @@
+//! SQLite repository for dungeon runs: one row per instanced scene visit, and
+//! a link row for every encounter recorded while the run was open.
+//!
+//! Like the event journal, every function borrows the actor-owned connection.
+
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use serde::{Deserialize, Serialize};
+
+use crate::database::commands::{EncounterSummaryDto, load_encounter_summary};
+use crate::database::schema as sch;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingDungeonRun {
+    pub scene_id: i32,
+    pub dungeon_difficulty: Option<i32>,
+    pub started_at_ms: i64,
+}
+
+/// A dungeon objective completing, as stored in `objective_splits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DungeonRunSplit {
+    pub target_id: i32,
+    pub completed_at_ms: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinalizeDungeonRun {
+    pub ended_at_ms: i64,
+    pub clear_started_at_ms: Option<i64>,
+    pub cleared_at_ms: Option<i64>,
+    pub wipes: u32,
+    pub deaths: u32,
+    pub objective_splits: Vec<DungeonRunSplit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DungeonRunSummaryDto {
+    pub id: i32,
+    pub scene_id: i32,
+    pub dungeon_difficulty: Option<i32>,
+    pub started_at_ms: i64,
+    pub ended_at_ms: i64,
+    /// From the dungeon flow entering playing to it ending; `None` when the
+    /// run was left before the clear.
+    pub clear_time_ms: Option<i64>,
+    pub wipes: u32,
+    pub deaths: u32,
+    pub encounter_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DungeonRunSplitDto {
+    pub target_id: i32,
+    pub completed_at_ms: i64,
+    /// Since the clear started, or since the scene was entered when the
+    /// dungeon flow never reached playing.
+    pub elapsed_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DungeonRunDetailDto {
+    pub summary: DungeonRunSummaryDto,
+    pub clear_started_at_ms: Option<i64>,
+    pub cleared_at_ms: Option<i64>,
+    pub splits: Vec<DungeonRunSplitDto>,
+    pub encounters: Vec<EncounterSummaryDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DungeonRunsResult {
+    pub rows: Vec<DungeonRunSummaryDto>,
+    pub total_count: i64,
+}
+
+#[derive(Debug, Queryable)]
+struct DungeonRunRow {
+    id: i32,
+    scene_id: i32,
+    dungeon_difficulty: Option<i32>,
+    started_at_ms: i64,
+    ended_at_ms: Option<i64>,
+    clear_started_at_ms: Option<i64>,
+    cleared_at_ms: Option<i64>,
+    wipes: i32,
+    deaths: i32,
+    objective_splits: String,
+}
+
+impl DungeonRunRow {
+    fn summary(&self, encounter_count: u32) -> Result<DungeonRunSummaryDto, String> {
+        let ended_at_ms = self
+            .ended_at_ms
+            .ok_or_else(|| format!("dungeon run {} is still recording", self.id))?;
+        Ok(DungeonRunSummaryDto {
+            id: self.id,
+            scene_id: self.scene_id,
+            dungeon_difficulty: self.dungeon_difficulty,
+            started_at_ms: self.started_at_ms,
+            ended_at_ms,
+            clear_time_ms: self
+                .clear_started_at_ms
+                .zip(self.cleared_at_ms)
+                .map(|(started, cleared)| cleared.saturating_sub(started)),
+            wipes: u32::try_from(self.wipes).unwrap_or_default(),
+            deaths: u32::try_from(self.deaths).unwrap_or_default(),
+            encounter_count,
+        })
+    }
+
+    fn splits(&self) -> Result<Vec<DungeonRunSplitDto>, String> {
+        let splits = serde_json::from_str::<Vec<DungeonRunSplit>>(&self.objective_splits)
+            .map_err(|error| format!("dungeon run {} has invalid splits: {error}", self.id))?;
+        let origin_ms = self.clear_started_at_ms.unwrap_or(self.started_at_ms);
+        Ok(splits
+            .into_iter()
+            .map(|split| DungeonRunSplitDto {
+                target_id: split.target_id,
+                completed_at_ms: split.completed_at_ms,
+                elapsed_ms: split.completed_at_ms.saturating_sub(origin_ms).max(0),
+            })
+            .collect())
+    }
+}
+
+/// Insert the run row when the instanced scene is recognized and return its
+/// stable ID.
+pub fn begin_dungeon_run(
+    conn: &mut SqliteConnection,
+    run: &RecordingDungeonRun,
+) -> Result<i32, String> {
+    use sch::dungeon_runs::dsl as r;
+    diesel::insert_into(r::dungeon_runs)
+        .values((
+            r::scene_id.eq(run.scene_id),
+            r::dungeon_difficulty.eq(run.dungeon_difficulty),
+            r::started_at_ms.eq(run.started_at_ms),
+        ))
+        .execute(conn)
+        .map_err(|error| error.to_string())?;
+    diesel::select(diesel::dsl::sql::<diesel::sql_types::Integer>(
+        "last_insert_rowid()",
+    ))
+    .get_result::<i32>(conn)
+    .map_err(|error| error.to_string())
+}
+
+pub fn link_dungeon_run_encounter(
+    conn: &mut SqliteConnection,
+    run_id: i32,
+    encounter_id: i32,
+) -> Result<(), String> {
+    use sch::dungeon_run_encounters::dsl as l;
+    diesel::insert_into(l::dungeon_run_encounters)
+        .values((l::run_id.eq(run_id), l::encounter_id.eq(encounter_id)))
+        .execute(conn)
+        .map(|_| ())
+        .map_err(|error| error.to_string())
+}
+
+pub fn finalize_dungeon_run(
+    conn: &mut SqliteConnection,
+    run_id: i32,
+    finalize: &FinalizeDungeonRun,
+) -> Result<(), String> {
+    use sch::dungeon_runs::dsl as r;
+    let objective_splits =
+        serde_json::to_string(&finalize.objective_splits).map_err(|error| error.to_string())?;
+    let updated = diesel::update(
+        r::dungeon_runs
+            .filter(r::id.eq(run_id))
+            .filter(r::ended_at_ms.is_null()),
+    )
+    .set((
+        r::ended_at_ms.eq(finalize.ended_at_ms),
+        r::clear_started_at_ms.eq(finalize.clear_started_at_ms),
+        r::cleared_at_ms.eq(finalize.cleared_at_ms),
+        r::wipes.eq(i32::try_from(finalize.wipes).unwrap_or(i32::MAX)),
+        r::deaths.eq(i32::try_from(finalize.deaths).unwrap_or(i32::MAX)),
+        r::objective_splits.eq(objective_splits),
+    ))
+    .execute(conn)
+    .map_err(|error| error.to_string())?;
+    if updated != 1 {
+        return Err(format!(
+            "dungeon run {run_id} is missing or already finalized"
+        ));
+    }
+    Ok(())
+}
+
+/// Finished runs, newest first.
+pub fn load_dungeon_runs(
+    conn: &mut SqliteConnection,
+    limit: i32,
+    offset: i32,
+    scene_id: Option<i32>,
+) -> Result<DungeonRunsResult, String> {
+    use sch::dungeon_runs::dsl as r;
+    if limit <= 0 || offset < 0 {
+        return Err("limit must be positive and offset must be non-negative".to_string());
+    }
+    let mut total = r::dungeon_runs
+        .filter(r::ended_at_ms.is_not_null())
+        .into_boxed();
+    let mut page = r::dungeon_runs
+        .filter(r::ended_at_ms.is_not_null())
+        .into_boxed();
+    if let Some(scene_id) = scene_id {
+        total = total.filter(r::scene_id.eq(scene_id));
+        page = page.filter(r::scene_id.eq(scene_id));
+    }
+    let total_count = total
+        .count()
+        .get_result::<i64>(conn)
+        .map_err(|error| error.to_string())?;
+    let rows = page
+        .order((r::started_at_ms.desc(), r::id.desc()))
+        .limit(i64::from(limit))
+        .offset(i64::from(offset))
+        .load::<DungeonRunRow>(conn)
+        .map_err(|error| error.to_string())?;
+    let counts = finished_encounter_counts(conn, rows.iter().map(|row| row.id).collect())?;
+    let rows = rows
+        .iter()
+        .map(|row| row.summary(counts.get(&row.id).copied().unwrap_or_default()))
+        .collect::<Result<_, _>>()?;
+    Ok(DungeonRunsResult { rows, total_count })
+}
+
+pub fn load_dungeon_run_detail(
+    conn: &mut SqliteConnection,
+    run_id: i32,
+) -> Result<DungeonRunDetailDto, String> {
+    use sch::dungeon_run_encounters::dsl as l;
+    use sch::dungeon_runs::dsl as r;
+    use sch::encounters::dsl as e;
+    let row = r::dungeon_runs
+        .filter(r::id.eq(run_id))
+        .first::<DungeonRunRow>(conn)
+        .map_err(|error| error.to_string())?;
+    let encounter_ids = l::dungeon_run_encounters
+        .inner_join(e::encounters)
+        .filter(l::run_id.eq(run_id))
+        .filter(e::ended_at_ms.is_not_null())
+        .select(e::id)
+        .order((e::started_at_ms.asc(), e::id.asc()))
+        .load::<i32>(conn)
+        .map_err(|error| error.to_string())?;
+    let encounters = encounter_ids
+        .into_iter()
+        .map(|encounter_id| load_encounter_summary(conn, encounter_id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let encounter_count = u32::try_from(encounters.len()).unwrap_or(u32::MAX);
+    Ok(DungeonRunDetailDto {
+        summary: row.summary(encounter_count)?,
+        clear_started_at_ms: row.clear_started_at_ms,
+        cleared_at_ms: row.cleared_at_ms,
+        splits: row.splits()?,
+        encounters,
+    })
+}
+
+fn finished_encounter_counts(
+    conn: &mut SqliteConnection,
+    run_ids: Vec<i32>,
+) -> Result<HashMap<i32, u32>, String> {
+    use sch::dungeon_run_encounters::dsl as l;
+    use sch::encounters::dsl as e;
+    let linked = l::dungeon_run_encounters
+        .inner_join(e::encounters)
+        .filter(l::run_id.eq_any(run_ids))
+        .filter(e::ended_at_ms.is_not_null())
+        .select(l::run_id)
+        .load::<i32>(conn)
+        .map_err(|error| error.to_string())?;
+    let mut counts = HashMap::new();
+    for run_id in linked {
+        *counts.entry(run_id).or_default() += 1;
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel_migrations::MigrationHarness;
+
+    use super::*;
+    use crate::database::event_journal::{RecordingEncounter, begin_recording_encounter};
+    use crate::database::{MIGRATIONS, apply_sqlite_pragmas};
+
+    fn memory_conn() -> SqliteConnection {
+        let mut conn = SqliteConnection::establish(":memory:").expect("open in-memory sqlite");
+        apply_sqlite_pragmas(&mut conn).expect("apply sqlite pragmas");
+        conn.run_pending_migrations(MIGRATIONS)
+            .expect("run embedded migrations");
+        conn
+    }
+
+    fn finished_encounter(conn: &mut SqliteConnection, started_at_ms: i64) -> i32 {
+        use sch::encounters::dsl as e;
+        let encounter_id = begin_recording_encounter(
+            conn,
+            &RecordingEncounter {
+                started_at_ms,
+                local_player_id: Some(1),
+                scene_id: Some(9),
+                dungeon_difficulty: None,
+            },
+        )
+        .expect("begin encounter");
+        diesel::update(e::encounters.filter(e::id.eq(encounter_id)))
+            .set(e::ended_at_ms.eq(started_at_ms + 1_000))
+            .execute(conn)
+            .expect("end encounter");
+        encounter_id
+    }
+
+    #[test]
+    fn finalized_run_lists_clear_time_splits_and_linked_encounters() {
+        let mut conn = memory_conn();
+        let run_id = begin_dungeon_run(
+            &mut conn,
+            &RecordingDungeonRun {
+                scene_id: 9,
+                dungeon_difficulty: Some(2),
+                started_at_ms: 10_000,
+            },
+        )
+        .expect("begin run");
+        let first = finished_encounter(&mut conn, 20_000);
+        let second = finished_encounter(&mut conn, 40_000);
+        link_dungeon_run_encounter(&mut conn, run_id, first).expect("link first");
+        link_dungeon_run_encounter(&mut conn, run_id, second).expect("link second");
+
+        // Unfinished runs are not listed yet.
+        let listed = load_dungeon_runs(&mut conn, 10, 0, None).expect("list runs");
+        assert_eq!(listed.total_count, 0);
+
+        let finalize = FinalizeDungeonRun {
+            ended_at_ms: 90_000,
+            clear_started_at_ms: Some(15_000),
+            cleared_at_ms: Some(75_000),
+            wipes: 1,
+            deaths: 3,
+            objective_splits: vec![DungeonRunSplit {
+                target_id: 501,
+                completed_at_ms: 35_000,
+            }],
+        };
+        finalize_dungeon_run(&mut conn, run_id, &finalize).expect("finalize run");
+        assert!(finalize_dungeon_run(&mut conn, run_id, &finalize).is_err());
+
+        let listed = load_dungeon_runs(&mut conn, 10, 0, Some(9)).expect("list runs");
+        assert_eq!(listed.total_count, 1);
+        let summary = &listed.rows[0];
+        assert_eq!(summary.clear_time_ms, Some(60_000));
+        assert_eq!((summary.wipes, summary.deaths), (1, 3));
+        assert_eq!(summary.encounter_count, 2);
+        assert_eq!(
+            load_dungeon_runs(&mut conn, 10, 0, Some(8))
+                .expect("list other scene")
+                .total_count,
+            0
+        );
+
+        let detail = load_dungeon_run_detail(&mut conn, run_id).expect("load detail");
+        assert_eq!(detail.splits.len(), 1);
+        assert_eq!(detail.splits[0].elapsed_ms, 20_000);
+        assert_eq!(
+            detail
+                .encounters
+                .iter()
+                .map(|encounter| encounter.id)
+                .collect::<Vec<_>>(),
+            vec![first, second]
+        );
+    }
+}
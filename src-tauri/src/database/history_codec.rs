@@ -183,6 +183,53 @@ pub struct HistoryDeath {
     pub replay: Option<DeathReplaySnapshot>,
 }
 
+/// What closed the previous boss phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryPhaseTrigger {
+    /// The tracked boss fell below an HP threshold; `detail` is the percent.
+    BossHp,
+    /// The boss started a mechanic not seen earlier in the segment; `detail`
+    /// is its base skill id.
+    BossMechanic,
+    /// A new dungeon objective appeared; `detail` is its target id.
+    Objective,
+    /// A different boss took over; `detail` is its monster id.
+    BossChanged,
+}
+
+/// A dead entity got back up; stamps its latest death's replay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryRevive {
+    pub entity_id: i64,
+    pub revived_at_ms: i64,
+}
+
+/// Start of phase `phase` (phase 1 always starts at the encounter start and
+/// is never recorded).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPhaseBoundary {
+    pub phase: u32,
+    pub trigger: HistoryPhaseTrigger,
+    pub detail: Option<i64>,
+    pub boss_entity_id: Option<i64>,
+}
+
+/// A span during which one buff from one source held a constant stack count
+/// on an entity. Recorded when the span closes, at its end offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryBuffInterval {
+    pub entity_id: i64,
+    pub base_id: i32,
+    pub source_entity_id: Option<i64>,
+    pub start_ms: u64,
+    pub end_ms_exclusive: u64,
+    pub stacks: u32,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum HistoryEvent {
@@ -190,13 +237,18 @@ pub enum HistoryEvent {
     SkillCast(HistorySkillCast),
     EntityContext(HistoryEntityContext),
     Death(HistoryDeath),
+    PhaseBoundary(HistoryPhaseBoundary),
+    BuffInterval(HistoryBuffInterval),
+    Revive(HistoryRevive),
 }
 
 impl HistoryEvent {
     pub const fn stream_kind(&self) -> HistoryStream {
         match self {
-            Self::Hit(_) | Self::Death(_) => HistoryStream::Combat,
-            Self::SkillCast(_) => HistoryStream::Timeline,
+            Self::Hit(_) | Self::Death(_) | Self::Revive(_) => HistoryStream::Combat,
+            Self::SkillCast(_) | Self::PhaseBoundary(_) | Self::BuffInterval(_) => {
+                HistoryStream::Timeline
+            }
             Self::EntityContext(_) => HistoryStream::Context,
         }
     }
@@ -208,6 +260,9 @@ impl HistoryEvent {
             // the estimate so the encoded document stays near the 1 MiB goal.
             Self::Hit(_) => 384,
             Self::SkillCast(_) => 72,
+            Self::PhaseBoundary(_) => 80,
+            Self::BuffInterval(_) => 112,
+            Self::Revive(_) => 56,
             Self::Death(death) => estimated_death_size(death),
             Self::EntityContext(context) => 96 + context.name.as_ref().map_or(0, String::len),
         }
@@ -430,6 +485,12 @@ impl HistoryChunker {
         Ok(chunks)
     }
 
+    /// Earliest offset in the chunk still being filled for `stream_kind`.
+    #[must_use]
+    pub fn open_chunk_start_ms(&self, stream_kind: HistoryStream) -> Option<u64> {
+        self.buffer(stream_kind).min_offset_ms
+    }
+
     fn buffer(&self, stream_kind: HistoryStream) -> &StreamBuffer {
         match stream_kind {
             HistoryStream::Combat => &self.combat,
@@ -9,8 +9,9 @@ use serde::{Deserialize, Serialize};
 use crate::live::ipc::models::DeathRecord;
 use crate::live::projections::combat::accumulator::{
     CombatAccumulator, CombatHitFact, CombatMetric, CombatSourceStats, CombatTargetStats,
-    CombatantStats,
+    CombatTotals, CombatantStats,
 };
+use crate::live::projections::combat::rolling::{per_second, RollingRates};
 use crate::live::projections::combat::stats::class::{
     get_class_id_from_spec, get_class_spec, ClassSpec,
 };
@@ -23,10 +24,11 @@ use super::event_journal::{
 };
 use super::history_codec::{
     decode_history_chunk, HistoryCastKind, HistoryChunkDocument, HistoryCodecError,
-    HistoryEntityContext, HistoryEnvelope, HistoryEvent, HistoryMetric,
+    HistoryBuffInterval, HistoryEntityContext, HistoryEnvelope, HistoryEvent, HistoryMetric,
+    HistoryPhaseBoundary, HistoryPhaseTrigger,
 };
 
-const KNOWN_QUALITY_FLAGS: i32 = (1 << 3) - 1;
+const KNOWN_QUALITY_FLAGS: i32 = (1 << 5) - 1;
 
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, specta::Type,
@@ -36,6 +38,8 @@ pub enum HistoryQualityFlag {
     IncompleteSegment,
     MissingEntityContext,
     SaturatedAmount,
+    PacketLoss,
+    DecodeFailure,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
@@ -48,6 +52,18 @@ pub struct EncounterTotalsData {
     pub damage_taken: String,
 }
 
+impl From<&CombatTotals> for EncounterTotalsData {
+    fn from(totals: &CombatTotals) -> Self {
+        Self {
+            damage: totals.damage.to_string(),
+            boss_damage: totals.boss_damage.to_string(),
+            healing: totals.healing.to_string(),
+            effective_healing: totals.effective_healing.to_string(),
+            damage_taken: totals.damage_taken.to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
 #[serde(rename_all = "camelCase")]
 pub struct EncounterStatsData {
@@ -119,6 +135,17 @@ pub struct EncounterDeathData {
     pub replay: Option<DeathRecord>,
 }
 
+/// The most damage an entity dealt within one rolling window length.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EncounterBurstData {
+    pub window_ms: u64,
+    pub damage: String,
+    pub dps: String,
+    /// Offset of the last hit inside the peak window.
+    pub end_ms: u64,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
 #[serde(rename_all = "camelCase")]
 pub struct EncounterEntityData {
@@ -141,6 +168,10 @@ pub struct EncounterEntityData {
     pub healing_targets: Vec<EncounterTargetBreakdownData>,
     pub taken_sources: Vec<EncounterSourceBreakdownData>,
     pub deaths: Vec<EncounterDeathData>,
+    /// Peak damage per rolling window length, narrowest first. Projections
+    /// stored before bursts were tracked decode with none.
+    #[serde(default)]
+    pub bursts: Vec<EncounterBurstData>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
@@ -176,6 +207,68 @@ pub struct EncounterMarkerData {
     pub remodel_level: Option<i64>,
 }
 
+/// One boss phase, clipped to the projected window. Phase 1 starts with the
+/// encounter and has no trigger.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EncounterPhaseData {
+    pub phase: u32,
+    pub name: String,
+    pub trigger: Option<HistoryPhaseTrigger>,
+    pub detail: Option<i64>,
+    pub start_ms: u64,
+    pub end_ms_exclusive: u64,
+    pub totals: EncounterTotalsData,
+}
+
+impl EncounterPhaseData {
+    #[must_use]
+    pub fn phase_name(phase: u32) -> String {
+        format!("P{phase}")
+    }
+}
+
+/// How long one buff from one source was up on an entity within the
+/// projected window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EncounterBuffUptimeData {
+    pub entity_id: String,
+    pub base_id: i32,
+    /// `None` when the buff's source was unknown.
+    pub source_entity_id: Option<String>,
+    pub uptime_ms: u64,
+    /// Uptime weighted by stack count: two stacks for one second is 2000.
+    pub stack_ms: u64,
+    pub uptime_pct: f64,
+    /// Mean stack count over the window, counting time without the buff as
+    /// zero stacks.
+    pub average_stacks: f64,
+}
+
+impl EncounterBuffUptimeData {
+    #[must_use]
+    pub fn new(
+        entity_id: i64,
+        base_id: i32,
+        source_entity_id: Option<i64>,
+        uptime_ms: u64,
+        stack_ms: u64,
+        window_ms: u64,
+    ) -> Self {
+        let window_ms = window_ms.max(1) as f64;
+        Self {
+            entity_id: entity_id.to_string(),
+            base_id,
+            source_entity_id: source_entity_id.map(|id| id.to_string()),
+            uptime_ms,
+            stack_ms,
+            uptime_pct: (uptime_ms as f64 * 100.0 / window_ms).min(100.0),
+            average_stacks: stack_ms as f64 / window_ms,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
 #[serde(rename_all = "camelCase")]
 pub struct EncounterDetailData {
@@ -193,9 +286,15 @@ pub struct EncounterDetailData {
     #[serde(default)]
     pub series: Vec<EncounterChartSeriesData>,
     pub markers: Vec<EncounterMarkerData>,
+    /// Empty unless the encounter recorded at least one phase boundary.
+    #[serde(default)]
+    pub phases: Vec<EncounterPhaseData>,
+    /// Configured buffs seen in the window, by entity, buff and source.
+    #[serde(default)]
+    pub buff_uptimes: Vec<EncounterBuffUptimeData>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
 #[serde(rename_all = "camelCase")]
 pub struct EncounterRangeData {
     pub encounter_id: i32,
@@ -209,6 +308,12 @@ pub struct EncounterRangeData {
     #[serde(default)]
     pub series: Vec<EncounterChartSeriesData>,
     pub markers: Vec<EncounterMarkerData>,
+    /// Empty unless the encounter recorded at least one phase boundary.
+    #[serde(default)]
+    pub phases: Vec<EncounterPhaseData>,
+    /// Configured buffs seen in the window, by entity, buff and source.
+    #[serde(default)]
+    pub buff_uptimes: Vec<EncounterBuffUptimeData>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -217,6 +322,22 @@ pub struct DetailProjectionSnapshot {
     pub last_sequence: u64,
     pub contexts: BTreeMap<i64, HistoryEntityContext>,
     pub detail: EncounterDetailData,
+    /// Seeds range replays, whose chunks may not include the boundary events.
+    #[serde(default)]
+    pub phase_boundaries: Vec<PhaseBoundarySnapshot>,
+    /// Seeds range replays like `phase_boundaries`. A span is recorded at its
+    /// end, so only spans that start before the chunk recording them are
+    /// kept: a range may reach them without loading that chunk. The history
+    /// writer fills this in; the live projection leaves it empty.
+    #[serde(default)]
+    pub buff_intervals: Vec<HistoryBuffInterval>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseBoundarySnapshot {
+    pub offset_ms: u64,
+    pub boundary: HistoryPhaseBoundary,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -277,7 +398,15 @@ pub struct HistoryProjectionReducer {
     chart: BTreeMap<u64, ChartProjection>,
     /// Per-actor mirror of `chart`: actor entity id -> bucket offset -> totals.
     entity_chart: BTreeMap<i64, BTreeMap<u64, ChartProjection>>,
+    /// Outgoing damage and healing windows per actor, for peak bursts.
+    rolling: BTreeMap<i64, RollingRates>,
     markers: Vec<EncounterMarkerData>,
+    /// Keyed by phase number, so seeded and replayed boundaries deduplicate.
+    phase_boundaries: BTreeMap<u32, PhaseBoundarySnapshot>,
+    phase_totals: BTreeMap<u32, CombatTotals>,
+    /// A set, so seeded and replayed spans deduplicate. Only range replays
+    /// collect spans; the live reducer would otherwise hold every one.
+    buff_intervals: BTreeSet<HistoryBuffInterval>,
 }
 
 impl HistoryProjectionReducer {
@@ -303,7 +432,11 @@ impl HistoryProjectionReducer {
             deaths: BTreeMap::new(),
             chart: BTreeMap::new(),
             entity_chart: BTreeMap::new(),
+            rolling: BTreeMap::new(),
             markers: Vec::new(),
+            phase_boundaries: BTreeMap::new(),
+            phase_totals: BTreeMap::new(),
+            buff_intervals: BTreeSet::new(),
         })
     }
 
@@ -329,6 +462,23 @@ impl HistoryProjectionReducer {
         }
     }
 
+    pub fn seed_phase_boundaries(
+        &mut self,
+        boundaries: impl IntoIterator<Item = PhaseBoundarySnapshot>,
+    ) {
+        for boundary in boundaries {
+            self.phase_boundaries
+                .insert(boundary.boundary.phase, boundary);
+        }
+    }
+
+    pub fn seed_buff_intervals(
+        &mut self,
+        intervals: impl IntoIterator<Item = HistoryBuffInterval>,
+    ) {
+        self.buff_intervals.extend(intervals);
+    }
+
     pub fn observe_sequence(&mut self, sequence: u64) {
         self.last_sequence = self.last_sequence.max(sequence);
     }
@@ -347,6 +497,24 @@ impl HistoryProjectionReducer {
             }
             return;
         }
+        // Boundaries outside the range still decide which phase a hit is in.
+        if let HistoryEvent::PhaseBoundary(boundary) = &envelope.event {
+            self.phase_boundaries.insert(
+                boundary.phase,
+                PhaseBoundarySnapshot {
+                    offset_ms: envelope.offset_ms,
+                    boundary: *boundary,
+                },
+            );
+            return;
+        }
+        // Spans are clipped to the range when uptime is computed.
+        if let HistoryEvent::BuffInterval(interval) = &envelope.event {
+            if self.collect_dynamic_series {
+                self.buff_intervals.insert(*interval);
+            }
+            return;
+        }
         if envelope.offset_ms < self.range.start || envelope.offset_ms >= self.range.end {
             return;
         }
@@ -357,6 +525,16 @@ impl HistoryProjectionReducer {
                 if self.combat.apply(&fact) {
                     self.quality.insert(HistoryQualityFlag::SaturatedAmount);
                 }
+                let phase = self.phase_at(envelope.offset_ms);
+                if self.phase_totals.entry(phase).or_default().apply(&fact) {
+                    self.quality.insert(HistoryQualityFlag::SaturatedAmount);
+                }
+                if fact.metric != CombatMetric::DamageTaken {
+                    self.rolling
+                        .entry(fact.actor_entity_id)
+                        .or_default()
+                        .observe(&fact, envelope.offset_ms);
+                }
                 if self.collect_dynamic_series {
                     let bucket_offset = self.range.start
                         + ((envelope.offset_ms - self.range.start) / self.bucket_ms)
@@ -394,8 +572,111 @@ impl HistoryProjectionReducer {
                         replay: death.replay.as_ref().map(DeathRecord::from),
                     });
             }
-            HistoryEvent::EntityContext(_) => {}
+            // Like the live death list, only the latest death is revived.
+            HistoryEvent::Revive(revive) => {
+                if let Some(replay) = self
+                    .deaths
+                    .get_mut(&revive.entity_id)
+                    .and_then(|deaths| deaths.last_mut())
+                    .and_then(|death| death.replay.as_mut())
+                    .filter(|replay| replay.revived_timestamp_ms.is_none())
+                {
+                    replay.revived_timestamp_ms = Some(revive.revived_at_ms.max(0).to_string());
+                }
+            }
+            HistoryEvent::EntityContext(_)
+            | HistoryEvent::PhaseBoundary(_)
+            | HistoryEvent::BuffInterval(_) => {}
+        }
+    }
+
+    fn phase_at(&self, offset_ms: u64) -> u32 {
+        self.phase_boundaries
+            .values()
+            .rev()
+            .find(|boundary| boundary.offset_ms <= offset_ms)
+            .map_or(1, |boundary| boundary.boundary.phase)
+    }
+
+    /// Phases intersecting the range. Empty when no boundary was recorded, so
+    /// single-phase encounters carry no redundant totals.
+    fn phases(&mut self) -> Vec<EncounterPhaseData> {
+        if self.phase_boundaries.is_empty() {
+            return Vec::new();
+        }
+        let mut phase_totals = std::mem::take(&mut self.phase_totals);
+        let starts = std::iter::once((1, 0, None, None)).chain(self.phase_boundaries.values().map(
+            |boundary| {
+                (
+                    boundary.boundary.phase,
+                    boundary.offset_ms,
+                    Some(boundary.boundary.trigger),
+                    boundary.boundary.detail,
+                )
+            },
+        ));
+        let ends = self
+            .phase_boundaries
+            .values()
+            .map(|boundary| boundary.offset_ms)
+            .chain(std::iter::once(self.range.end));
+        starts
+            .zip(ends)
+            .filter_map(|((phase, start_ms, trigger, detail), end_ms)| {
+                let start_ms = start_ms.max(self.range.start);
+                let end_ms_exclusive = end_ms.min(self.range.end);
+                (start_ms < end_ms_exclusive).then(|| EncounterPhaseData {
+                    phase,
+                    name: EncounterPhaseData::phase_name(phase),
+                    trigger,
+                    detail,
+                    start_ms,
+                    end_ms_exclusive,
+                    totals: EncounterTotalsData::from(
+                        &phase_totals.remove(&phase).unwrap_or_default(),
+                    ),
+                })
+            })
+            .collect()
+    }
+
+    /// Uptime per entity, buff and source from the recorded spans clipped to
+    /// the range.
+    fn buff_uptimes(&self) -> Vec<EncounterBuffUptimeData> {
+        let mut totals = BTreeMap::<(i64, i32, Option<i64>), (u64, u64)>::new();
+        for interval in &self.buff_intervals {
+            let start_ms = interval.start_ms.max(self.range.start);
+            let end_ms = interval.end_ms_exclusive.min(self.range.end);
+            if start_ms >= end_ms {
+                continue;
+            }
+            let span_ms = end_ms - start_ms;
+            let (uptime_ms, stack_ms) = totals
+                .entry((
+                    interval.entity_id,
+                    interval.base_id,
+                    interval.source_entity_id,
+                ))
+                .or_default();
+            *uptime_ms = uptime_ms.saturating_add(span_ms);
+            *stack_ms = stack_ms.saturating_add(span_ms.saturating_mul(u64::from(interval.stacks)));
         }
+        let window_ms = self.range.end - self.range.start;
+        totals
+            .into_iter()
+            .map(
+                |((entity_id, base_id, source_entity_id), (uptime_ms, stack_ms))| {
+                    EncounterBuffUptimeData::new(
+                        entity_id,
+                        base_id,
+                        source_entity_id,
+                        uptime_ms,
+                        stack_ms,
+                        window_ms,
+                    )
+                },
+            )
+            .collect()
     }
 
     #[cfg(test)]
@@ -422,10 +703,14 @@ impl HistoryProjectionReducer {
         detail.chart_points.clear();
         detail.series.clear();
         detail.markers.clear();
+        detail.phases.clear();
+        detail.buff_uptimes.clear();
         DetailProjectionSnapshot {
             last_sequence: self.last_sequence,
             contexts: self.contexts,
             detail,
+            phase_boundaries: self.phase_boundaries.into_values().collect(),
+            buff_intervals: self.buff_intervals.into_iter().collect(),
         }
     }
 
@@ -443,6 +728,8 @@ impl HistoryProjectionReducer {
             chart_points: detail.chart_points,
             series: detail.series,
             markers: detail.markers,
+            phases: detail.phases,
+            buff_uptimes: detail.buff_uptimes,
         }
     }
 
@@ -521,6 +808,17 @@ impl HistoryProjectionReducer {
                 let taken_sources =
                     stats.map_or_else(Vec::new, |stats| history_sources(&stats.taken_sources));
                 let deaths = self.deaths.remove(&entity_id).unwrap_or_default();
+                let bursts = self.rolling.get(&entity_id).map_or_else(Vec::new, |rates| {
+                    rates
+                        .peaks()
+                        .map(|(window_ms, peak)| EncounterBurstData {
+                            window_ms,
+                            damage: peak.damage.to_string(),
+                            dps: per_second(peak.damage, window_ms).to_string(),
+                            end_ms: peak.end_ms,
+                        })
+                        .collect()
+                });
                 EncounterEntityData {
                     entity_id: entity_id.to_string(),
                     display_uid: context.map_or(entity_id, |value| value.display_uid),
@@ -549,6 +847,7 @@ impl HistoryProjectionReducer {
                     healing_targets,
                     taken_sources,
                     deaths,
+                    bursts,
                 }
             })
             .collect();
@@ -576,17 +875,13 @@ impl HistoryProjectionReducer {
             start_ms: self.range.start,
             end_ms_exclusive: self.range.end,
             bucket_ms: self.bucket_ms,
-            totals: EncounterTotalsData {
-                damage: combat.totals.damage.to_string(),
-                boss_damage: combat.totals.boss_damage.to_string(),
-                healing: combat.totals.healing.to_string(),
-                effective_healing: combat.totals.effective_healing.to_string(),
-                damage_taken: combat.totals.damage_taken.to_string(),
-            },
+            totals: EncounterTotalsData::from(&combat.totals),
             entities,
             chart_points,
             series,
             markers: std::mem::take(&mut self.markers),
+            phases: self.phases(),
+            buff_uptimes: self.buff_uptimes(),
         }
     }
 }
@@ -859,6 +1154,8 @@ pub fn project_encounter_detail(
     snapshot.detail.chart_points = chart.chart_points;
     snapshot.detail.series = chart.series;
     snapshot.detail.markers = chart.markers;
+    snapshot.detail.phases = chart.phases;
+    snapshot.detail.buff_uptimes = chart.buff_uptimes;
     for entity in &mut snapshot.detail.entities {
         // Projections stored before `class_spec_name` existed decode it as
         // `None`; resolve it from the persisted spec discriminant instead.
@@ -930,6 +1227,8 @@ fn replay_chunks(
     let mut reducer = HistoryProjectionReducer::new(start_ms..end_ms_exclusive, bucket_ms)?
         .with_seeded_contexts_only();
     reducer.seed_contexts(snapshot.contexts.values().cloned());
+    reducer.seed_phase_boundaries(snapshot.phase_boundaries.iter().copied());
+    reducer.seed_buff_intervals(snapshot.buff_intervals.iter().copied());
     reducer.add_quality_flags(quality_flags_from_bits(quality_flags));
 
     for chunk in chunks {
@@ -1038,7 +1337,7 @@ fn bucket_width_for_points(
     }
     let duration = end_ms_exclusive.saturating_sub(start_ms).max(1);
     let points = u64::from(target_points);
-    Ok((duration / points + u64::from(duration % points != 0)).max(1))
+    Ok((duration / points + u64::from(!duration.is_multiple_of(points))).max(1))
 }
 
 pub fn quality_flags_to_bits(flags: &[HistoryQualityFlag]) -> i32 {
@@ -1047,12 +1346,14 @@ pub fn quality_flags_to_bits(flags: &[HistoryQualityFlag]) -> i32 {
             HistoryQualityFlag::IncompleteSegment => 1 << 0,
             HistoryQualityFlag::MissingEntityContext => 1 << 1,
             HistoryQualityFlag::SaturatedAmount => 1 << 2,
+            HistoryQualityFlag::PacketLoss => 1 << 3,
+            HistoryQualityFlag::DecodeFailure => 1 << 4,
         }
     })
 }
 
 fn quality_flags_from_bits(bits: i32) -> Vec<HistoryQualityFlag> {
-    let mut flags = Vec::with_capacity(3);
+    let mut flags = Vec::with_capacity(5);
     if bits & (1 << 0) != 0 {
         flags.push(HistoryQualityFlag::IncompleteSegment);
     }
@@ -1062,6 +1363,12 @@ fn quality_flags_from_bits(bits: i32) -> Vec<HistoryQualityFlag> {
     if bits & (1 << 2) != 0 {
         flags.push(HistoryQualityFlag::SaturatedAmount);
     }
+    if bits & (1 << 3) != 0 {
+        flags.push(HistoryQualityFlag::PacketLoss);
+    }
+    if bits & (1 << 4) != 0 {
+        flags.push(HistoryQualityFlag::DecodeFailure);
+    }
     flags
 }
 
@@ -1079,6 +1386,8 @@ fn unavailable_detail(summary: EncounterSummaryDto, quality_flags: i32) -> Encou
         chart_points: Vec::new(),
         series: Vec::new(),
         markers: Vec::new(),
+        phases: Vec::new(),
+        buff_uptimes: Vec::new(),
     }
 }
 
@@ -1104,11 +1413,14 @@ fn add_chart_amount(
     }
 }
 
+/// Reads one metric's total out of a chart bucket.
+type ChartSelector = (HistoryMetric, fn(&ChartProjection) -> u128);
+
 /// Drain per-actor buckets into sparse (entity, metric) series rows.
 fn chart_series_from_entity_chart(
     entity_chart: BTreeMap<i64, BTreeMap<u64, ChartProjection>>,
 ) -> Vec<EncounterChartSeriesData> {
-    const SELECTORS: [(HistoryMetric, fn(&ChartProjection) -> u128); 3] = [
+    const SELECTORS: [ChartSelector; 3] = [
         (HistoryMetric::Damage, |point| point.damage),
         (HistoryMetric::Healing, |point| point.healing),
         (HistoryMetric::DamageTaken, |point| point.taken),
@@ -1173,7 +1485,8 @@ fn empty_summary(encounter_id: i32) -> EncounterSummaryDto {
 mod tests {
     use super::*;
     use crate::database::history_codec::{
-        encode_history_chunk, HistoryDeath, HistoryHit, HistorySkillCast, HistoryStream,
+        encode_history_chunk, HistoryDeath, HistoryHit, HistoryRevive, HistorySkillCast,
+        HistoryStream,
     };
     use crate::live::projections::combat::accumulator::CombatHitFlags;
     use crate::live::projections::death::{
@@ -1456,6 +1769,163 @@ mod tests {
         assert!(right.entities[0].deaths[1].replay.is_none());
     }
 
+    #[test]
+    fn revives_stamp_only_the_latest_unrevived_death_replay() {
+        let revive = |sequence, offset_ms, revived_at_ms| HistoryEnvelope {
+            sequence,
+            offset_ms,
+            event: HistoryEvent::Revive(HistoryRevive {
+                entity_id: 1,
+                revived_at_ms,
+            }),
+        };
+        let chunk = stored_combat_chunk(
+            1,
+            0,
+            vec![
+                death(1, 100, Some(death_replay(10_100))),
+                revive(2, 400, 10_400),
+                revive(3, 500, 10_500),
+                death(4, 600, Some(death_replay(10_600))),
+            ],
+        );
+
+        let range = replay_range(0..1_000, std::slice::from_ref(&chunk));
+
+        let revived = range.entities[0]
+            .deaths
+            .iter()
+            .map(|death| {
+                death
+                    .replay
+                    .as_ref()
+                    .and_then(|replay| replay.revived_timestamp_ms.clone())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(revived, vec![Some("10400".to_string()), None]);
+    }
+
+    #[test]
+    fn range_phases_are_seeded_from_the_snapshot_and_clipped_to_the_range() {
+        let mut live = HistoryProjectionReducer::new(0..u64::MAX, 1_000)
+            .expect("live reducer")
+            .without_dynamic_series();
+        live.apply(&HistoryEnvelope {
+            sequence: 2,
+            offset_ms: 1_000,
+            event: HistoryEvent::PhaseBoundary(HistoryPhaseBoundary {
+                phase: 2,
+                trigger: HistoryPhaseTrigger::BossHp,
+                detail: Some(50),
+                boss_entity_id: Some(2),
+            }),
+        });
+        let mut snapshot = live.finish_detail(1, empty_summary(1));
+        assert!(snapshot.detail.phases.is_empty());
+        assert_eq!(snapshot.phase_boundaries.len(), 1);
+        snapshot.last_sequence = 4;
+
+        // The timeline chunk holding the boundary is not part of the replay.
+        let combat_chunk = stored_combat_chunk(
+            1,
+            0,
+            vec![
+                hit(0, 0, 5),
+                hit(1, 600, 10),
+                hit(3, 1_000, 20),
+                hit(4, 1_400, 40),
+            ],
+        );
+        let range = replay_chunks(1, 0, &snapshot, &[combat_chunk], 500, 1_500, 1_000)
+            .expect("replay range");
+
+        assert_eq!(
+            range
+                .phases
+                .iter()
+                .map(|phase| (
+                    phase.name.as_str(),
+                    phase.trigger,
+                    phase.start_ms,
+                    phase.end_ms_exclusive,
+                    phase.totals.damage.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("P1", None, 500, 1_000, "10"),
+                ("P2", Some(HistoryPhaseTrigger::BossHp), 1_000, 1_500, "60"),
+            ]
+        );
+        assert_eq!(range.totals.damage, "70");
+    }
+
+    #[test]
+    fn range_buff_uptime_clips_seeded_spans_and_weights_stacks() {
+        let mut live = HistoryProjectionReducer::new(0..u64::MAX, 1_000)
+            .expect("live reducer")
+            .without_dynamic_series();
+        for (sequence, interval) in [
+            (1, buff_span(2, Some(1), 0, 800, 1)),
+            (2, buff_span(2, Some(1), 800, 2_000, 2)),
+            (3, buff_span(1, None, 1_200, 5_000, 1)),
+        ] {
+            live.apply(&HistoryEnvelope {
+                sequence,
+                offset_ms: interval.end_ms_exclusive,
+                event: HistoryEvent::BuffInterval(interval),
+            });
+        }
+        let mut snapshot = live.finish_detail(1, empty_summary(1));
+        assert!(snapshot.detail.buff_uptimes.is_empty());
+        assert!(snapshot.buff_intervals.is_empty());
+        // The writer seeds every span here: none ends in the replayed chunk.
+        snapshot.buff_intervals = vec![
+            buff_span(2, Some(1), 0, 800, 1),
+            buff_span(2, Some(1), 800, 2_000, 2),
+            buff_span(1, None, 1_200, 5_000, 1),
+        ];
+        snapshot.last_sequence = 4;
+
+        // Every span ends outside the replayed chunk.
+        let combat_chunk = stored_combat_chunk(1, 0, vec![hit(4, 600, 10)]);
+        let range = replay_chunks(1, 0, &snapshot, &[combat_chunk], 500, 1_500, 1_000)
+            .expect("replay range");
+
+        assert_eq!(
+            range
+                .buff_uptimes
+                .iter()
+                .map(|uptime| (
+                    uptime.entity_id.as_str(),
+                    uptime.source_entity_id.as_deref(),
+                    uptime.uptime_ms,
+                    uptime.stack_ms,
+                ))
+                .collect::<Vec<_>>(),
+            vec![("1", None, 300, 300), ("2", Some("1"), 1_000, 1_700)]
+        );
+        assert!((range.buff_uptimes[0].uptime_pct - 30.0).abs() < f64::EPSILON);
+        assert!((range.buff_uptimes[1].uptime_pct - 100.0).abs() < f64::EPSILON);
+        assert!((range.buff_uptimes[1].average_stacks - 1.7).abs() < 1e-9);
+    }
+
+    fn buff_span(
+        entity_id: i64,
+        source_entity_id: Option<i64>,
+        start_ms: u64,
+        end_ms_exclusive: u64,
+        stacks: u32,
+    ) -> HistoryBuffInterval {
+        HistoryBuffInterval {
+            entity_id,
+            base_id: 77,
+            source_entity_id,
+            start_ms,
+            end_ms_exclusive,
+            stacks,
+        }
+    }
+
     fn decimal(value: &str) -> u128 {
         value.parse().expect("decimal value")
     }
@@ -1538,7 +2008,7 @@ mod tests {
 
     #[test]
     fn adjacent_half_open_ranges_are_additive_across_chunks() {
-        let events = vec![
+        let events = [
             metric_hit_at(0, 0, HistoryMetric::Damage, 5, CombatHitFlags::CRITICAL),
             metric_hit_at(1, 999, HistoryMetric::Healing, 8, 0),
             metric_hit_at(
@@ -1984,6 +2454,35 @@ mod tests {
         assert!(snapshot.detail.markers.is_empty());
     }
 
+    #[test]
+    fn stored_projection_keeps_peak_bursts_per_window() {
+        let mut reducer = HistoryProjectionReducer::new(0..60_000, 1_000).expect("reducer");
+        reducer.apply(&actor_hit(1, 0, 1, HistoryMetric::Damage, 100));
+        reducer.apply(&actor_hit(2, 10_000, 1, HistoryMetric::Damage, 400));
+        reducer.apply(&actor_hit(3, 12_000, 1, HistoryMetric::Damage, 600));
+        reducer.apply(&actor_hit(4, 12_000, 2, HistoryMetric::DamageTaken, 50));
+
+        let snapshot = reducer.finish_detail(1, empty_summary(1));
+        let encoded = encode_detail_projection(&snapshot).expect("encode projection");
+        let decoded = decode_detail_projection(&encoded.data).expect("decode projection");
+
+        let actor = &decoded.detail.entities[0];
+        assert_eq!(
+            actor
+                .bursts
+                .iter()
+                .map(|burst| (burst.window_ms, burst.damage.as_str(), burst.end_ms))
+                .collect::<Vec<_>>(),
+            vec![
+                (5_000, "1000", 12_000),
+                (15_000, "1100", 12_000),
+                (30_000, "1100", 12_000),
+            ]
+        );
+        assert_eq!(actor.bursts[0].dps, "200");
+        assert!(decoded.detail.entities[1].bursts.is_empty());
+    }
+
     #[test]
     fn context_class_spec_discriminant_resolves_spec_name() {
         let mut reducer = HistoryProjectionReducer::new(0..10, 10).expect("reducer");
@@ -1,4 +1,5 @@
 pub mod commands;
+pub mod dungeon_runs;
 pub mod event_journal;
 pub mod history_codec;
 pub mod history_query;
@@ -16,6 +17,7 @@ use serde::{Deserialize, Serialize};
 use crate::database::models as m;
 use crate::database::schema as sch;
 
+use self::dungeon_runs::{FinalizeDungeonRun, RecordingDungeonRun};
 use self::event_journal::{FinalizeEncounter, FinalizeOutcome, InsertOutcome, RecordingEncounter};
 use self::history_codec::EncodedHistoryChunk;
 use self::history_query::{
@@ -24,6 +26,7 @@ use self::history_query::{
 
 pub const MIGRATIONS: EmbeddedMigrations = diesel_migrations::embed_migrations!();
 const MAX_ENCOUNTER_HISTORY: i64 = 200;
+const MAX_DUNGEON_RUN_HISTORY: i64 = 200;
 const DATABASE_QUEUE_CAPACITY: usize = 256;
 
 type DatabaseOperation = Box<dyn FnOnce(&mut SqliteConnection) + Send + 'static>;
@@ -56,7 +59,8 @@ enum DatabaseRequest {
     Shutdown(mpsc::SyncSender<Result<(), String>>),
 }
 
-static DATABASE_SENDER: OnceLock<mpsc::SyncSender<DatabaseRequest>> = OnceLock::new();
+/// The process-wide database the app's commands and history writer use.
+static DATABASE: OnceLock<Database> = OnceLock::new();
 
 #[derive(Debug, thiserror::Error)]
 pub enum DbInitError {
@@ -64,6 +68,8 @@ pub enum DbInitError {
     Connection(String),
     #[error("DB migration error: {0}")]
     Migration(String),
+    #[error("database already open at {open}; cannot open {requested}")]
+    AlreadyOpen { open: PathBuf, requested: PathBuf },
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -92,52 +98,196 @@ pub fn ensure_parent_dir(path: &Path) -> std::io::Result<()> {
 }
 
 pub fn init_db() -> Result<(), DbInitError> {
-    if DATABASE_SENDER.get().is_some() {
-        return Ok(());
+    init_db_at(&default_db_path())
+}
+
+/// Opens and migrates the database at `db_path` as the process-wide actor.
+/// `:memory:` keeps everything in memory for the life of the process. Once
+/// the actor runs, asking for the same path again is a no-op and asking for
+/// a different one is an error.
+pub fn init_db_at(db_path: &Path) -> Result<(), DbInitError> {
+    let already_open = |open: &Database| {
+        if open.path == db_path {
+            Ok(())
+        } else {
+            Err(DbInitError::AlreadyOpen {
+                open: open.path.clone(),
+                requested: db_path.to_path_buf(),
+            })
+        }
+    };
+    if let Some(open) = DATABASE.get() {
+        return already_open(open);
+    }
+    let database = Database::open(db_path)?;
+    if let Err(database) = DATABASE.set(database) {
+        // Another thread opened one first; keep only its actor.
+        let _ = database.shutdown();
+        return already_open(DATABASE.get().expect("database set by the other thread"));
     }
-    let db_path = default_db_path();
-    log::info!(target: "app::db", "db_path={}", db_path.display());
-    ensure_parent_dir(&db_path).map_err(|error| {
-        DbInitError::Connection(format!("failed to create DB directory: {error}"))
-    })?;
-    let mut conn = SqliteConnection::establish(&db_path.to_string_lossy())
-        .map_err(|error| DbInitError::Connection(error.to_string()))?;
-    apply_sqlite_pragmas(&mut conn).map_err(DbInitError::Connection)?;
-    conn.run_pending_migrations(MIGRATIONS)
-        .map_err(|error| DbInitError::Migration(error.to_string()))?;
-
-    let (sender, receiver) = mpsc::sync_channel(DATABASE_QUEUE_CAPACITY);
-    std::thread::Builder::new()
-        .name("db-worker".to_string())
-        .spawn(move || database_actor(conn, receiver))
-        .map_err(|error| {
-            DbInitError::Connection(format!("failed to spawn database actor: {error}"))
-        })?;
-    DATABASE_SENDER
-        .set(sender)
-        .map_err(|_| DbInitError::Connection("database actor already initialized".to_string()))?;
     Ok(())
 }
 
-#[cfg(test)]
-pub(crate) fn init_in_memory_actor_for_test() -> Result<(), String> {
-    if DATABASE_SENDER.get().is_some() {
-        return Err("database actor is already initialized".to_string());
+/// One SQLite connection owned by its own actor thread. Clones share the
+/// actor; the app's is process-wide, while batch runs and tests open their own.
+#[derive(Clone)]
+pub struct Database {
+    path: PathBuf,
+    sender: mpsc::SyncSender<DatabaseRequest>,
+}
+
+impl Database {
+    /// Opens and migrates the database at `db_path` on a new actor thread.
+    pub fn open(db_path: &Path) -> Result<Self, DbInitError> {
+        log::info!(target: "app::db", "db_path={}", db_path.display());
+        ensure_parent_dir(db_path).map_err(|error| {
+            DbInitError::Connection(format!("failed to create DB directory: {error}"))
+        })?;
+        let mut conn = SqliteConnection::establish(&db_path.to_string_lossy())
+            .map_err(|error| DbInitError::Connection(error.to_string()))?;
+        apply_sqlite_pragmas(&mut conn).map_err(DbInitError::Connection)?;
+        conn.run_pending_migrations(MIGRATIONS)
+            .map_err(|error| DbInitError::Migration(error.to_string()))?;
+
+        let (sender, receiver) = mpsc::sync_channel(DATABASE_QUEUE_CAPACITY);
+        std::thread::Builder::new()
+            .name("db-worker".to_string())
+            .spawn(move || database_actor(conn, receiver))
+            .map_err(|error| {
+                DbInitError::Connection(format!("failed to spawn database actor: {error}"))
+            })?;
+        Ok(Self {
+            path: db_path.to_path_buf(),
+            sender,
+        })
     }
 
-    let mut conn = SqliteConnection::establish(":memory:").map_err(|error| error.to_string())?;
-    apply_sqlite_pragmas(&mut conn)?;
-    conn.run_pending_migrations(MIGRATIONS)
-        .map_err(|error| error.to_string())?;
+    fn request<T>(
+        &self,
+        build: impl FnOnce(mpsc::SyncSender<Result<T, String>>) -> DatabaseRequest,
+    ) -> Result<T, String> {
+        let (reply, receiver) = mpsc::sync_channel(0);
+        self.sender
+            .send(build(reply))
+            .map_err(|_| "database actor queue is closed".to_string())?;
+        receiver
+            .recv()
+            .map_err(|_| "database actor dropped the response".to_string())?
+    }
+
+    /// Runs a non-history operation on the actor-owned connection.
+    pub fn exec<T, F>(&self, operation: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut SqliteConnection) -> Result<T, String> + Send + 'static,
+    {
+        let (reply, receiver) = mpsc::sync_channel(0);
+        self.sender
+            .send(DatabaseRequest::Execute(Box::new(move |conn| {
+                let _ = reply.send(operation(conn));
+            })))
+            .map_err(|_| "database actor queue is closed".to_string())?;
+        receiver
+            .recv()
+            .map_err(|_| "database actor dropped the response".to_string())?
+    }
+
+    pub fn begin_history_recording(&self, recording: RecordingEncounter) -> Result<i32, String> {
+        self.request(|reply| DatabaseRequest::BeginRecording { recording, reply })
+    }
+
+    pub fn append_history_chunk(
+        &self,
+        chunk: EncodedHistoryChunk,
+    ) -> Result<InsertOutcome, String> {
+        self.request(|reply| DatabaseRequest::AppendChunk { chunk, reply })
+    }
+
+    pub fn finalize_history_recording(
+        &self,
+        finalize: FinalizeEncounter,
+    ) -> Result<FinalizeOutcome, String> {
+        self.request(|reply| DatabaseRequest::Finalize { finalize, reply })
+    }
+
+    pub fn begin_dungeon_run(&self, run: RecordingDungeonRun) -> Result<i32, String> {
+        self.exec(move |conn| dungeon_runs::begin_dungeon_run(conn, &run))
+    }
+
+    pub fn link_dungeon_run_encounter(&self, run_id: i32, encounter_id: i32) -> Result<(), String> {
+        self.exec(move |conn| dungeon_runs::link_dungeon_run_encounter(conn, run_id, encounter_id))
+    }
+
+    pub fn finalize_dungeon_run(
+        &self,
+        run_id: i32,
+        finalize: FinalizeDungeonRun,
+    ) -> Result<(), String> {
+        self.exec(move |conn| dungeon_runs::finalize_dungeon_run(conn, run_id, &finalize))
+    }
+
+    /// Highest encounter id so far, finished or not.
+    pub fn latest_encounter_id(&self) -> Result<Option<i32>, String> {
+        self.exec(|conn| {
+            use sch::encounters::dsl as e;
+            e::encounters
+                .select(diesel::dsl::max(e::id))
+                .first::<Option<i32>>(conn)
+                .map_err(|error| error.to_string())
+        })
+    }
+
+    /// Finished encounters recorded after `after_id`, oldest first.
+    pub fn finished_encounter_ids_after(&self, after_id: Option<i32>) -> Result<Vec<i32>, String> {
+        self.exec(move |conn| {
+            use sch::encounters::dsl as e;
+            e::encounters
+                .select(e::id)
+                .filter(e::id.gt(after_id.unwrap_or(0)))
+                .filter(e::ended_at_ms.is_not_null())
+                .order(e::id.asc())
+                .load::<i32>(conn)
+                .map_err(|error| error.to_string())
+        })
+    }
+
+    pub fn load_history_detail(
+        &self,
+        encounter_id: i32,
+        target_points: u32,
+    ) -> Result<EncounterDetailData, String> {
+        let query = self.request(|reply| DatabaseRequest::LoadDetail {
+            encounter_id,
+            reply,
+        })?;
+        history_query::project_encounter_detail(query, target_points)
+            .map_err(|error| error.to_string())
+    }
+
+    pub fn load_history_range(
+        &self,
+        encounter_id: i32,
+        start_ms: u64,
+        end_ms_exclusive: u64,
+    ) -> Result<EncounterRangeData, String> {
+        let query = self.request(|reply| DatabaseRequest::LoadRange {
+            encounter_id,
+            start_ms,
+            end_ms_exclusive,
+            reply,
+        })?;
+        history_query::project_encounter_range(query, start_ms, end_ms_exclusive)
+            .map_err(|error| error.to_string())
+    }
+
+    pub fn flush(&self) -> Result<(), String> {
+        self.request(DatabaseRequest::Barrier)
+    }
 
-    let (sender, receiver) = mpsc::sync_channel(DATABASE_QUEUE_CAPACITY);
-    std::thread::Builder::new()
-        .name("db-worker-test".to_string())
-        .spawn(move || database_actor(conn, receiver))
-        .map_err(|error| format!("failed to spawn test database actor: {error}"))?;
-    DATABASE_SENDER
-        .set(sender)
-        .map_err(|_| "database actor was initialized concurrently".to_string())
+    /// Stops the actor once every request queued before this one has run.
+    pub fn shutdown(&self) -> Result<(), String> {
+        self.request(DatabaseRequest::Shutdown)
+    }
 }
 
 fn apply_sqlite_pragmas(conn: &mut SqliteConnection) -> Result<(), String> {
@@ -216,53 +366,33 @@ fn database_actor(mut conn: SqliteConnection, receiver: mpsc::Receiver<DatabaseR
     log::info!(target: "app::db", "database_actor_exiting");
 }
 
-fn database_sender() -> Result<mpsc::SyncSender<DatabaseRequest>, String> {
-    DATABASE_SENDER
+/// The process-wide database, once [`init_db_at`] has opened it.
+pub fn database() -> Result<Database, String> {
+    DATABASE
         .get()
         .cloned()
         .ok_or_else(|| "database actor is not initialized".to_string())
 }
 
-fn request<T>(
-    build: impl FnOnce(mpsc::SyncSender<Result<T, String>>) -> DatabaseRequest,
-) -> Result<T, String> {
-    let sender = database_sender()?;
-    let (reply, receiver) = mpsc::sync_channel(0);
-    sender
-        .send(build(reply))
-        .map_err(|_| "database actor queue is closed".to_string())?;
-    receiver
-        .recv()
-        .map_err(|_| "database actor dropped the response".to_string())?
-}
-
-/// Runs a non-history operation on the actor-owned connection.
+/// Runs a non-history operation on the process-wide connection.
 pub fn db_exec<T, F>(operation: F) -> Result<T, String>
 where
     T: Send + 'static,
     F: FnOnce(&mut SqliteConnection) -> Result<T, String> + Send + 'static,
 {
-    let sender = database_sender()?;
-    let (reply, receiver) = mpsc::sync_channel(0);
-    sender
-        .send(DatabaseRequest::Execute(Box::new(move |conn| {
-            let _ = reply.send(operation(conn));
-        })))
-        .map_err(|_| "database actor queue is closed".to_string())?;
-    receiver
-        .recv()
-        .map_err(|_| "database actor dropped the response".to_string())?
+    database()?.exec(operation)
 }
 
 pub fn db_send<F>(operation: F)
 where
     F: FnOnce(&mut SqliteConnection) + Send + 'static,
 {
-    let Ok(sender) = database_sender() else {
+    let Some(database) = DATABASE.get() else {
         log::error!(target: "app::db", "database_send_failed reason=not_initialized");
         return;
     };
-    if sender
+    if database
+        .sender
         .send(DatabaseRequest::Execute(Box::new(operation)))
         .is_err()
     {
@@ -306,27 +436,11 @@ pub fn flush_playerdata(player_id: i64, last_seen_ms: i64, vdata_bytes: Vec<u8>)
     });
 }
 
-pub fn begin_history_recording(recording: RecordingEncounter) -> Result<i32, String> {
-    request(|reply| DatabaseRequest::BeginRecording { recording, reply })
-}
-
-pub fn append_history_chunk(chunk: EncodedHistoryChunk) -> Result<InsertOutcome, String> {
-    request(|reply| DatabaseRequest::AppendChunk { chunk, reply })
-}
-
-pub fn finalize_history_recording(finalize: FinalizeEncounter) -> Result<FinalizeOutcome, String> {
-    request(|reply| DatabaseRequest::Finalize { finalize, reply })
-}
-
 pub fn load_history_detail(
     encounter_id: i32,
     target_points: u32,
 ) -> Result<EncounterDetailData, String> {
-    let query = request(|reply| DatabaseRequest::LoadDetail {
-        encounter_id,
-        reply,
-    })?;
-    history_query::project_encounter_detail(query, target_points).map_err(|error| error.to_string())
+    database()?.load_history_detail(encounter_id, target_points)
 }
 
 pub fn load_history_range(
@@ -334,25 +448,18 @@ pub fn load_history_range(
     start_ms: u64,
     end_ms_exclusive: u64,
 ) -> Result<EncounterRangeData, String> {
-    let query = request(|reply| DatabaseRequest::LoadRange {
-        encounter_id,
-        start_ms,
-        end_ms_exclusive,
-        reply,
-    })?;
-    history_query::project_encounter_range(query, start_ms, end_ms_exclusive)
-        .map_err(|error| error.to_string())
+    database()?.load_history_range(encounter_id, start_ms, end_ms_exclusive)
 }
 
 pub fn flush_database() -> Result<(), String> {
-    request(DatabaseRequest::Barrier)
+    database()?.flush()
 }
 
 pub fn shutdown_database() -> Result<(), String> {
-    if DATABASE_SENDER.get().is_none() {
-        return Ok(());
+    match DATABASE.get() {
+        Some(database) => database.shutdown(),
+        None => Ok(()),
     }
-    request(DatabaseRequest::Shutdown)
 }
 
 /// Delete old non-favorite summaries. Primary keys are stable and are never
@@ -373,9 +480,35 @@ pub fn startup_maintenance() {
         if let Err(error) = prune_encounters(conn, MAX_ENCOUNTER_HISTORY) {
             log::warn!(target: "app::db", "startup_maintenance_failed error={error}");
         }
+        if let Err(error) = prune_dungeon_runs(conn, MAX_DUNGEON_RUN_HISTORY) {
+            log::warn!(target: "app::db", "startup_maintenance_runs_failed error={error}");
+        }
     });
 }
 
+/// Drops runs interrupted by a crash and keeps the newest finished ones;
+/// their encounter links go with them by foreign-key cascade.
+fn prune_dungeon_runs(conn: &mut SqliteConnection, keep: i64) -> Result<(), String> {
+    use sch::dungeon_runs::dsl as r;
+    diesel::delete(r::dungeon_runs.filter(r::ended_at_ms.is_null()))
+        .execute(conn)
+        .map_err(|error| error.to_string())?;
+    let delete_ids = r::dungeon_runs
+        .select(r::id)
+        .order((r::started_at_ms.desc(), r::id.desc()))
+        .offset(keep.max(0))
+        .load::<i32>(conn)
+        .map_err(|error| error.to_string())?;
+    if delete_ids.is_empty() {
+        return Ok(());
+    }
+    let deleted = diesel::delete(r::dungeon_runs.filter(r::id.eq_any(delete_ids)))
+        .execute(conn)
+        .map_err(|error| error.to_string())?;
+    log::info!(target: "app::db", "startup_maintenance_pruned_runs deleted={deleted} keep={keep}");
+    Ok(())
+}
+
 fn prune_encounters(conn: &mut SqliteConnection, keep: i64) -> Result<(), String> {
     use sch::encounters::dsl as e;
     let delete_ids = e::encounters
@@ -416,6 +549,17 @@ mod tests {
             .expect("load playerdata row")
     }
 
+    #[test]
+    fn the_process_wide_database_refuses_a_second_path() {
+        let memory = Path::new(":memory:");
+        init_db_at(memory).expect("open process-wide database");
+        init_db_at(memory).expect("same path again is a no-op");
+        assert!(matches!(
+            init_db_at(Path::new("elsewhere.db")),
+            Err(DbInitError::AlreadyOpen { .. })
+        ));
+    }
+
     #[test]
     fn upsert_playerdata_inserts_then_overwrites() {
         let mut conn = memory_conn();
@@ -105,6 +105,32 @@ diesel::table! {
     }
 }
 
+// Instanced dungeon visits grouping the encounters recorded inside them.
+diesel::table! {
+    dungeon_runs (id) {
+        id -> Integer,
+        scene_id -> Integer,
+        dungeon_difficulty -> Nullable<Integer>,
+        // Scene entered and left, in milliseconds since the Unix epoch.
+        started_at_ms -> BigInt,
+        ended_at_ms -> Nullable<BigInt>,
+        // Dungeon flow entered playing, and later ended or settled.
+        clear_started_at_ms -> Nullable<BigInt>,
+        cleared_at_ms -> Nullable<BigInt>,
+        wipes -> Integer,
+        deaths -> Integer,
+        // JSON-encoded array of completed objectives in completion order.
+        objective_splits -> Text,
+    }
+}
+
+diesel::table! {
+    dungeon_run_encounters (encounter_id) {
+        encounter_id -> Integer,
+        run_id -> Integer,
+    }
+}
+
 // Simple key-value config table for app settings.
 diesel::table! {
     app_config (key) {
@@ -117,11 +143,15 @@ diesel::table! {
 
 diesel::joinable!(encounter_event_chunks -> encounters (encounter_id));
 diesel::joinable!(encounter_projection -> encounters (encounter_id));
+diesel::joinable!(dungeon_run_encounters -> dungeon_runs (run_id));
+diesel::joinable!(dungeon_run_encounters -> encounters (encounter_id));
 diesel::allow_tables_to_appear_in_same_query!(
     entities,
     encounters,
     encounter_event_chunks,
     encounter_projection,
+    dungeon_runs,
+    dungeon_run_encounters,
     detailed_playerdata,
     app_config,
 );
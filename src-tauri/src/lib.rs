@@ -1,5 +1,6 @@
 mod buff_icons;
 mod build_app;
+mod capture_batch;
 mod live;
 pub mod module_optimizer;
 mod packets;
@@ -28,7 +29,6 @@ use tauri_plugin_window_state::{AppHandleExt, StateFlags, WindowExt};
 use tauri_specta::{Builder, collect_commands};
 mod database;
 mod hud_layout;
-use serde_json::json;
 
 /// The label for the live window.
 pub const WINDOW_LIVE_LABEL: &str = "live";
@@ -55,6 +55,14 @@ fn api_builder() -> Builder<tauri::Wry> {
         live::ipc::commands::set_live_pull_active,
         live::ipc::commands::get_live_status,
         live::ipc::commands::get_live_scene,
+        live::ipc::commands::get_capture_health,
+        live::ipc::commands::get_live_sessions,
+        live::ipc::commands::get_protocol_discovery,
+        live::ipc::commands::set_protocol_discovery_enabled,
+        live::ipc::commands::clear_protocol_discovery,
+        live::ipc::commands::get_local_api_status,
+        live::ipc::commands::save_and_apply_local_api_settings,
+        live::ipc::commands::reload_user_scripts,
         hud_layout::migrate_hud_layout,
         live::ipc::commands::enable_blur,
         live::ipc::commands::disable_blur,
@@ -62,13 +70,17 @@ fn api_builder() -> Builder<tauri::Wry> {
         live::ipc::commands::toggle_pause_encounter,
         live::ipc::commands::start_training_dummy,
         live::ipc::commands::stop_training_dummy,
+        live::ipc::commands::select_live_session,
         live::ipc::commands::save_and_apply_monitor_runtime_snapshot,
+        live::ipc::commands::replay_recorded_session,
         database::commands::get_unique_scene_ids,
         database::commands::get_unique_boss_monster_ids,
         database::commands::get_player_names_filtered,
         database::commands::get_recent_encounters_filtered,
         database::commands::get_encounter_detail,
         database::commands::get_encounter_range,
+        database::commands::get_dungeon_runs,
+        database::commands::get_dungeon_run_detail,
         database::commands::delete_encounter,
         database::commands::delete_encounters,
         database::commands::toggle_favorite_encounter,
@@ -134,6 +146,11 @@ pub fn export_typescript_bindings() -> Result<(), specta_typescript::ExportError
     )
 }
 
+/// Entry point of the headless `parse_captures` binary.
+pub fn run_capture_batch(args: impl IntoIterator<Item = String>) -> Result<(), String> {
+    capture_batch::run(args)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let builder = api_builder();
@@ -149,11 +166,17 @@ pub fn run() {
     let publication_cache = crate::live::ipc::publisher::LivePublicationCache::new();
     let (history_writer, history_join) = crate::live::history_writer::HistoryWriterHandle::start()
         .expect("failed to start history writer");
+    let protocol_discovery = crate::live::protocol::discovery::ProtocolDiscovery::new();
+    let schema_drift_log = crate::live::protocol::schema_drift::SchemaDriftLog::new();
+    let local_api = crate::live::local_api::LocalApiServer::new(publication_cache.clone());
 
     let tauri_builder = tauri::Builder::default()
         .manage(live_runtime)
         .manage(publication_cache.clone())
         .manage(history_writer.clone())
+        .manage(protocol_discovery.clone())
+        .manage(schema_drift_log.clone())
+        .manage(local_api.clone())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_updater::Builder::new().build())
@@ -179,12 +202,11 @@ pub fn run() {
             let _setup_guard = setup_span.enter();
 
             log::info!(target: "app::startup", "starting app v{}", app.package_info().version);
-            if let Some(hud_window) = app.get_webview_window(WINDOW_HUD_OVERLAY_LABEL) {
-                if let Err(error) =
+            if let Some(hud_window) = app.get_webview_window(WINDOW_HUD_OVERLAY_LABEL)
+                && let Err(error) =
                     hud_window.restore_state(StateFlags::POSITION | StateFlags::SIZE)
-                {
-                    warn!("failed to restore HUD geometry: {error}");
-                }
+            {
+                warn!("failed to restore HUD geometry: {error}");
             }
             stop_windivert();
             remove_windivert();
@@ -294,6 +316,12 @@ pub fn run() {
                 Err(e) => warn!(target: "app::voice", "failed to initialize VoiceService: {e}"),
             }
 
+            // Local API for overlays and stream tools; off unless enabled.
+            let local_api_settings = crate::live::local_api::load_local_api_settings(&app_handle);
+            if let Err(error) = local_api.apply(&local_api_settings) {
+                warn!(target: "app::startup", "failed to start local API: {error}");
+            }
+
             // Live Meter
             // https://v2.tauri.app/learn/splashscreen/#start-some-setup-tasks
             tauri::async_runtime::spawn(async move {
@@ -303,6 +331,8 @@ pub fn run() {
                     publication_cache,
                     history_writer,
                     history_join,
+                    protocol_discovery,
+                    schema_drift_log,
                 )
                 .await
             });
@@ -330,11 +360,36 @@ pub fn run() {
 mod packet_settings_commands {
     use super::*;
 
+    /// The `packetCapture.json` that `live_main` reads when capture starts.
+    #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, specta::Type)]
+    #[serde(rename_all = "camelCase", default)]
+    pub struct PacketCaptureSettings {
+        pub method: String,
+        pub npcap_device: String,
+        /// Capture file replayed by the `PcapFile` method.
+        pub pcap_file: String,
+        /// Where the `Remote` method listens for capture agents; empty means
+        /// loopback on the default port.
+        pub remote_listen: String,
+        /// Key shared with the remote capture agents.
+        pub remote_key: String,
+        pub session_recording: CaptureRecordingSettings,
+    }
+
+    /// Rotating recordings of the capture stream; zero limits use the
+    /// recorder's defaults.
+    #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, specta::Type)]
+    #[serde(rename_all = "camelCase", default)]
+    pub struct CaptureRecordingSettings {
+        pub enabled: bool,
+        pub max_file_mb: u32,
+        pub max_files: u32,
+    }
+
     #[tauri::command]
     #[specta::specta]
     pub fn save_packet_capture_settings(
-        method: String,
-        npcap_device: String,
+        settings: PacketCaptureSettings,
         app_handle: tauri::AppHandle,
     ) -> Result<(), String> {
         let app_data_dirs = [
@@ -350,13 +405,9 @@ mod packet_settings_commands {
                 continue;
             }
             let path = target_dir.join("packetCapture.json");
-            let payload = json!({
-                "method": method,
-                "npcapDevice": npcap_device,
-            });
             match std::fs::write(
                 &path,
-                serde_json::to_vec_pretty(&payload).map_err(|e| e.to_string())?,
+                serde_json::to_vec_pretty(&settings).map_err(|e| e.to_string())?,
             ) {
                 Ok(_) => {
                     info!("Saved packet capture config to {}", path.display());
@@ -639,7 +690,8 @@ mod debug_commands {
         Ok(())
     }
 
-    /// Creates a debug ZIP containing the most recent application log file and returns the path.
+    /// Creates a debug ZIP containing the most recent application log file and the protocol
+    /// schema drift report, and returns the path.
     ///
     /// If `destination_path` is provided, the ZIP is written there. Otherwise it is created
     /// in the app log directory.
@@ -729,7 +781,7 @@ async fn check_for_updates(app: tauri::AppHandle) -> tauri_plugin_updater::Resul
             update.version,
             locale.as_str()
         );
-        let payload = json!({
+        let payload = serde_json::json!({
             "version": update.version.to_string(),
             "body": update.body.unwrap_or_default(),
             "downloadUrl": update.download_url.to_string(),
@@ -853,7 +905,7 @@ fn cleanup_old_logs(log_dir: &Path, keep: usize) -> Result<(), String> {
     }
 
     // Newest first.
-    entries.sort_by(|a, b| b.0.cmp(&a.0));
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.0));
     for (_, path) in entries.into_iter().skip(keep) {
         let _ = std::fs::remove_file(&path);
     }
@@ -861,6 +913,8 @@ fn cleanup_old_logs(log_dir: &Path, keep: usize) -> Result<(), String> {
     Ok(())
 }
 
+const SCHEMA_DRIFT_FILE_NAME: &str = "protocol_schema_drift.json";
+
 fn create_diagnostics_bundle(
     app_handle: &tauri::AppHandle,
     destination_path: Option<String>,
@@ -913,7 +967,7 @@ fn create_diagnostics_bundle(
         let modified = meta.modified().unwrap_or(std::time::SystemTime::UNIX_EPOCH);
         files.push((modified, path));
     }
-    files.sort_by(|a, b| b.0.cmp(&a.0));
+    files.sort_by_key(|file| std::cmp::Reverse(file.0));
 
     let Some((_, path)) = files.into_iter().next() else {
         return Err("No application log file found in log directory".to_string());
@@ -941,6 +995,18 @@ fn create_diagnostics_bundle(
     zip.write_all(&bytes)
         .map_err(|e| format!("zip: write file {name}: {e}"))?;
 
+    // Protobuf schema drift seen by the latest capture streams' decoders.
+    let drift = serde_json::to_vec_pretty(
+        &app_handle
+            .state::<crate::live::protocol::schema_drift::SchemaDriftLog>()
+            .report(),
+    )
+    .map_err(|e| format!("serialize schema drift: {e}"))?;
+    zip.start_file(SCHEMA_DRIFT_FILE_NAME, opts)
+        .map_err(|e| format!("zip: start file {SCHEMA_DRIFT_FILE_NAME}: {e}"))?;
+    zip.write_all(&drift)
+        .map_err(|e| format!("zip: write file {SCHEMA_DRIFT_FILE_NAME}: {e}"))?;
+
     zip.finish().map_err(|e| format!("zip: finish: {e}"))?;
     Ok(bundle_path.display().to_string())
 }
@@ -1,6 +1,6 @@
 use crate::live::counter::engine::CounterRule;
 use crate::live::counter::season_cultivate::{FactorCounterTemplate, normalize_factor_templates};
-use crate::live::runtime::segment::TRAINING_WINDOW_MS;
+use crate::live::runtime::segment::{SegmentPolicy, TRAINING_WINDOW_MS};
 use crate::voice::models::VoiceRuntimeSnapshot;
 use log::{info, warn};
 use serde::{Deserialize, Deserializer, Serialize};
@@ -9,10 +9,12 @@ use tauri::AppHandle;
 use tauri::Manager;
 
 const SNAPSHOT_FILE_NAME: &str = "monitorRuntime.json";
+const MAX_UPTIME_BUFFS: usize = 30;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, specta::Type)]
 pub enum AppLocale {
     #[serde(rename = "zh-CN")]
+    #[default]
     ZhCn,
     #[serde(rename = "en-US")]
     EnUs,
@@ -39,12 +41,6 @@ impl AppLocale {
     }
 }
 
-impl Default for AppLocale {
-    fn default() -> Self {
-        Self::ZhCn
-    }
-}
-
 impl<'de> Deserialize<'de> for AppLocale {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -58,7 +54,7 @@ impl<'de> Deserialize<'de> for AppLocale {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, specta::Type)]
 #[serde(rename_all = "camelCase", default)]
 pub struct MonitorRuntimeSnapshot {
     #[serde(rename = "i18n")]
@@ -71,23 +67,20 @@ pub struct MonitorRuntimeSnapshot {
     pub voice: VoiceRuntimeSnapshot,
 }
 
-impl Default for MonitorRuntimeSnapshot {
-    fn default() -> Self {
-        Self {
-            i18n: I18nRuntimeSnapshot::default(),
-            live: LiveRuntimeSnapshot::default(),
-            skill: SkillRuntimeSnapshot::default(),
-            monster: MonsterRuntimeSnapshot::default(),
-            teammate: TeammateRuntimeSnapshot::default(),
-            voice: VoiceRuntimeSnapshot::default(),
-        }
-    }
-}
-
 impl MonitorRuntimeSnapshot {
     pub fn normalize(mut self) -> Result<Self, String> {
         self.live.event_update_rate_ms = self.live.event_update_rate_ms.clamp(50, 2000);
         self.live.training_window_ms = self.live.training_window_ms.clamp(30_000, 600_000);
+        let policy = &mut self.live.segment_policy;
+        policy.boundary_delay_ms = policy.boundary_delay_ms.min(30_000);
+        policy.max_segment_duration_ms = policy
+            .max_segment_duration_ms
+            .clamp(60_000, 4 * 60 * 60 * 1_000);
+        dedup_and_sort_i32(&mut policy.training_dummy_monster_ids);
+        dedup_and_sort_i32(&mut self.live.uptime_buff_ids);
+        if self.live.uptime_buff_ids.len() > MAX_UPTIME_BUFFS {
+            return Err(format!("最多统计{MAX_UPTIME_BUFFS}个Buff覆盖率"));
+        }
 
         dedup_and_sort_i32(&mut self.skill.monitored_skill_ids);
         if self.skill.monitored_skill_ids.len() > 10 {
@@ -164,25 +157,23 @@ impl MonitorRuntimeSnapshot {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, specta::Type)]
 #[serde(rename_all = "camelCase", default)]
 pub struct I18nRuntimeSnapshot {
     pub locale: AppLocale,
 }
 
-impl Default for I18nRuntimeSnapshot {
-    fn default() -> Self {
-        Self {
-            locale: AppLocale::default(),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
 #[serde(rename_all = "camelCase", default)]
 pub struct LiveRuntimeSnapshot {
     pub event_update_rate_ms: u64,
     pub training_window_ms: u64,
+    pub segment_policy: SegmentPolicy,
+    /// Adds each player's damage per boss and elite to the live payload.
+    pub target_breakdown: bool,
+    /// Buff base ids whose uptime is tracked on the local player, teammates
+    /// and bosses.
+    pub uptime_buff_ids: Vec<i32>,
 }
 
 impl Default for LiveRuntimeSnapshot {
@@ -190,6 +181,9 @@ impl Default for LiveRuntimeSnapshot {
         Self {
             event_update_rate_ms: 200,
             training_window_ms: TRAINING_WINDOW_MS,
+            segment_policy: SegmentPolicy::default(),
+            target_breakdown: false,
+            uptime_buff_ids: Vec::new(),
         }
     }
 }
@@ -394,6 +388,7 @@ fn dedup_and_sort_i32(values: &mut Vec<i32>) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::live::runtime::segment::SegmentMode;
 
     #[test]
     fn normalize_defaults_training_window_to_183s() {
@@ -432,5 +427,24 @@ mod tests {
         let live: LiveRuntimeSnapshot = serde_json::from_str(r#"{"eventUpdateRateMs":200}"#)
             .expect("legacy live snapshot deserializes");
         assert_eq!(live.training_window_ms, TRAINING_WINDOW_MS);
+        assert_eq!(live.segment_policy, SegmentPolicy::default());
+    }
+
+    #[test]
+    fn normalize_clamps_segment_policy() {
+        let mut snapshot = MonitorRuntimeSnapshot::default();
+        snapshot.live.segment_policy = serde_json::from_str(
+            r#"{"mode":"bossOnly","boundaryDelayMs":90000,"maxSegmentDurationMs":5,"trainingDummyMonsterIds":[122,115,122]}"#,
+        )
+        .expect("segment policy deserializes");
+        let policy = snapshot
+            .normalize()
+            .expect("clamped snapshot is valid")
+            .live
+            .segment_policy;
+        assert_eq!(policy.mode, SegmentMode::BossOnly);
+        assert_eq!(policy.boundary_delay_ms, 30_000);
+        assert_eq!(policy.max_segment_duration_ms, 60_000);
+        assert_eq!(policy.training_dummy_monster_ids, vec![115, 122]);
     }
 }
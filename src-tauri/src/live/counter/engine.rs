@@ -24,7 +24,7 @@ use crate::live::runtime::events::{
 use crate::live::runtime::scheduler::{DeadlineScheduler, DueTimer, TickSchedule, TimerTask};
 
 const FULL_SCALE_BASIS_POINTS: u64 = 10_000;
-const DUNGEON_FLOW_PLAYING: i32 = 3;
+pub(crate) const DUNGEON_FLOW_PLAYING: i32 = 3;
 const MAX_MOVEMENT_DELTA_METERS: f32 = 50.0;
 
 #[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
@@ -418,7 +418,7 @@ struct SlotRuntime {
     freeze: Option<FreezeState>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 struct NamespaceState {
     rule_set: u64,
     rules: Vec<RuleRuntime>,
@@ -428,19 +428,6 @@ struct NamespaceState {
     touched_once: Vec<SourceHandle>,
 }
 
-impl Default for NamespaceState {
-    fn default() -> Self {
-        Self {
-            rule_set: 0,
-            rules: Vec::new(),
-            rule_by_id: HashMap::new(),
-            indexes: EventIndexes::default(),
-            current_batch: None,
-            touched_once: Vec::new(),
-        }
-    }
-}
-
 const SEASON_NODE_BUFF_MIN_ID: i32 = 4;
 
 #[derive(Debug, Default)]
@@ -586,12 +573,10 @@ impl CounterEngine {
             DomainEvent::AttributeChanged {
                 entity,
                 attr_id,
-                current,
+                current: AttributeValue::Int(value),
                 ..
             } if self.is_local(*entity) => {
-                if let AttributeValue::Int(value) = current {
-                    changed |= self.record_attr(*attr_id, *value);
-                }
+                changed |= self.record_attr(*attr_id, *value);
             }
             DomainEvent::PositionChanged {
                 entity,
@@ -1508,9 +1493,9 @@ impl NamespaceState {
                 else {
                     return false;
                 };
-                if !slot
+                if slot
                     .freeze
-                    .is_some_and(|freeze| freeze.deadline_mono == due.scheduled_for)
+                    .is_none_or(|freeze| freeze.deadline_mono != due.scheduled_for)
                 {
                     return false;
                 }
@@ -2232,6 +2217,160 @@ fn default_basis_points_per_unit() -> u32 {
     1
 }
 
+fn index_skill_ids(
+    index: &mut HashMap<(i32, PhaseKey), Vec<SourceHandle>>,
+    skill_ids: &[i32],
+    phase: PhaseKey,
+    handle: SourceHandle,
+) {
+    let mut seen = HashSet::with_capacity(skill_ids.len());
+    for skill_id in skill_ids {
+        if seen.insert(*skill_id) {
+            index.entry((*skill_id, phase)).or_default().push(handle);
+        }
+    }
+}
+
+impl SourceRuntime {
+    fn compile(source: CounterSource) -> Self {
+        match source {
+            CounterSource::DamageBySkillKey {
+                increment,
+                hits_required,
+                required_type_flags,
+                ..
+            } => Self::Damage {
+                kind: DamageSourceKind::Outgoing,
+                increment,
+                hits_required,
+                required_type_flags,
+                hit_accumulator: 0,
+            },
+            CounterSource::DamageBySkillKeySelfTarget {
+                increment,
+                hits_required,
+                required_type_flags,
+                ..
+            } => Self::Damage {
+                kind: DamageSourceKind::OutgoingSelfTarget,
+                increment,
+                hits_required,
+                required_type_flags,
+                hit_accumulator: 0,
+            },
+            CounterSource::AnyDamage {
+                increment,
+                hits_required,
+                required_type_flags,
+            } => Self::Damage {
+                kind: DamageSourceKind::Outgoing,
+                increment,
+                hits_required,
+                required_type_flags,
+                hit_accumulator: 0,
+            },
+            CounterSource::DamageTaken {
+                increment,
+                hits_required,
+                required_type_flags,
+                ..
+            } => Self::Damage {
+                kind: DamageSourceKind::Incoming,
+                increment,
+                hits_required,
+                required_type_flags,
+                hit_accumulator: 0,
+            },
+            CounterSource::DamageBySkillKeyOnce {
+                increment,
+                required_type_flags,
+                ..
+            } => Self::DamageOnce {
+                increment,
+                required_type_flags,
+                batch_counts: HashMap::new(),
+                batch_maxima: HashMap::new(),
+                touched: false,
+            },
+            CounterSource::FightResourceSpent {
+                units_required,
+                increment,
+                ..
+            } => Self::FightResourceSpent {
+                units_required: units_required.max(1),
+                increment,
+                previous_value: None,
+                accumulated_spent: 0,
+            },
+            CounterSource::BuffAdded {
+                source_config_id,
+                increment,
+                ..
+            } => Self::BuffAdded {
+                source_config_id,
+                increment,
+            },
+            CounterSource::BuffLayerSpent {
+                units_required,
+                increment,
+                ..
+            } => Self::BuffLayerSpent {
+                units_required: units_required.max(1),
+                increment,
+                accumulated_spent: 0,
+            },
+            CounterSource::BuffDurationTick {
+                tick_interval_ms,
+                increment,
+                attr_condition,
+                ..
+            } => Self::BuffTick {
+                interval_ms: tick_interval_ms.max(1),
+                increment,
+                attr_condition,
+                active: None,
+            },
+            CounterSource::SkillCast { increment, .. } => Self::SkillCast { increment },
+            CounterSource::SkillDurationTick {
+                tick_interval_ms,
+                increment,
+                ..
+            } => Self::SkillTick {
+                interval_ms: tick_interval_ms.max(1),
+                increment,
+                next_cast_sequence: 1,
+                active: None,
+            },
+            CounterSource::SkillCastComplete { increment, .. } => Self::SkillComplete { increment },
+            CounterSource::MovementDistance {
+                meters_required,
+                increment,
+                ..
+            } => Self::Movement {
+                meters_required: normalize_meters_required(meters_required),
+                increment,
+                active_target: None,
+                last_position: None,
+                accumulated_meters: 0.0,
+                staged_position: None,
+            },
+        }
+    }
+}
+
+impl SlotRuntime {
+    fn new(config: EffectSlotConfig) -> Self {
+        Self {
+            config,
+            current_count: 0,
+            is_counting: true,
+            reset_buff_target: None,
+            condition_buff_target: None,
+            freeze: None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -4233,157 +4372,3 @@ mod tests {
         assert_eq!(count(&engine, CounterNamespace::Normal, 49), 10);
     }
 }
-
-fn index_skill_ids(
-    index: &mut HashMap<(i32, PhaseKey), Vec<SourceHandle>>,
-    skill_ids: &[i32],
-    phase: PhaseKey,
-    handle: SourceHandle,
-) {
-    let mut seen = HashSet::with_capacity(skill_ids.len());
-    for skill_id in skill_ids {
-        if seen.insert(*skill_id) {
-            index.entry((*skill_id, phase)).or_default().push(handle);
-        }
-    }
-}
-
-impl SourceRuntime {
-    fn compile(source: CounterSource) -> Self {
-        match source {
-            CounterSource::DamageBySkillKey {
-                increment,
-                hits_required,
-                required_type_flags,
-                ..
-            } => Self::Damage {
-                kind: DamageSourceKind::Outgoing,
-                increment,
-                hits_required,
-                required_type_flags,
-                hit_accumulator: 0,
-            },
-            CounterSource::DamageBySkillKeySelfTarget {
-                increment,
-                hits_required,
-                required_type_flags,
-                ..
-            } => Self::Damage {
-                kind: DamageSourceKind::OutgoingSelfTarget,
-                increment,
-                hits_required,
-                required_type_flags,
-                hit_accumulator: 0,
-            },
-            CounterSource::AnyDamage {
-                increment,
-                hits_required,
-                required_type_flags,
-            } => Self::Damage {
-                kind: DamageSourceKind::Outgoing,
-                increment,
-                hits_required,
-                required_type_flags,
-                hit_accumulator: 0,
-            },
-            CounterSource::DamageTaken {
-                increment,
-                hits_required,
-                required_type_flags,
-                ..
-            } => Self::Damage {
-                kind: DamageSourceKind::Incoming,
-                increment,
-                hits_required,
-                required_type_flags,
-                hit_accumulator: 0,
-            },
-            CounterSource::DamageBySkillKeyOnce {
-                increment,
-                required_type_flags,
-                ..
-            } => Self::DamageOnce {
-                increment,
-                required_type_flags,
-                batch_counts: HashMap::new(),
-                batch_maxima: HashMap::new(),
-                touched: false,
-            },
-            CounterSource::FightResourceSpent {
-                units_required,
-                increment,
-                ..
-            } => Self::FightResourceSpent {
-                units_required: units_required.max(1),
-                increment,
-                previous_value: None,
-                accumulated_spent: 0,
-            },
-            CounterSource::BuffAdded {
-                source_config_id,
-                increment,
-                ..
-            } => Self::BuffAdded {
-                source_config_id,
-                increment,
-            },
-            CounterSource::BuffLayerSpent {
-                units_required,
-                increment,
-                ..
-            } => Self::BuffLayerSpent {
-                units_required: units_required.max(1),
-                increment,
-                accumulated_spent: 0,
-            },
-            CounterSource::BuffDurationTick {
-                tick_interval_ms,
-                increment,
-                attr_condition,
-                ..
-            } => Self::BuffTick {
-                interval_ms: tick_interval_ms.max(1),
-                increment,
-                attr_condition,
-                active: None,
-            },
-            CounterSource::SkillCast { increment, .. } => Self::SkillCast { increment },
-            CounterSource::SkillDurationTick {
-                tick_interval_ms,
-                increment,
-                ..
-            } => Self::SkillTick {
-                interval_ms: tick_interval_ms.max(1),
-                increment,
-                next_cast_sequence: 1,
-                active: None,
-            },
-            CounterSource::SkillCastComplete { increment, .. } => Self::SkillComplete { increment },
-            CounterSource::MovementDistance {
-                meters_required,
-                increment,
-                ..
-            } => Self::Movement {
-                meters_required: normalize_meters_required(meters_required),
-                increment,
-                active_target: None,
-                last_position: None,
-                accumulated_meters: 0.0,
-                staged_position: None,
-            },
-        }
-    }
-}
-
-impl SlotRuntime {
-    fn new(config: EffectSlotConfig) -> Self {
-        Self {
-            config,
-            current_count: 0,
-            is_counting: true,
-            reset_buff_target: None,
-            condition_buff_target: None,
-            freeze: None,
-        }
-    }
-}
@@ -1,13 +1,18 @@
 //! Bounded history encoder between the live owner and the SQLite actor.
 
+use std::collections::{BTreeSet, HashMap};
 use std::sync::mpsc::{self, RecvTimeoutError, SyncSender};
 use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 
+use crate::database::Database;
+use crate::database::dungeon_runs::{FinalizeDungeonRun, RecordingDungeonRun};
 use crate::database::event_journal::{
     FinalizeEncounter, FinalizeEncounterMetadata, RecordingEncounter,
 };
-use crate::database::history_codec::{HistoryChunker, HistoryEnvelope};
+use crate::database::history_codec::{
+    HistoryBuffInterval, HistoryChunker, HistoryEnvelope, HistoryEvent,
+};
 use crate::database::history_query::{
     DetailProjectionSnapshot, encode_detail_projection, quality_flags_to_bits,
 };
@@ -26,39 +31,157 @@ pub struct HistoryFinalization {
 #[derive(Debug)]
 enum HistoryWriterCommand {
     Begin {
+        lane: u32,
         segment_id: SegmentId,
         recording: RecordingEncounter,
     },
     Event {
+        lane: u32,
         segment_id: SegmentId,
         envelope: HistoryEnvelope,
     },
-    Finalize(HistoryFinalization),
+    Finalize {
+        lane: u32,
+        finalization: Box<HistoryFinalization>,
+    },
+    BeginRun {
+        lane: u32,
+        run: RecordingDungeonRun,
+    },
+    FinalizeRun {
+        lane: u32,
+        finalize: FinalizeDungeonRun,
+    },
     Fence(SyncSender<Result<(), String>>),
     Shutdown(SyncSender<Result<(), String>>),
 }
 
+/// A command observed by [`HistoryWriterHandle::recording`].
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) enum RecordedHistory {
+    Begin {
+        lane: u32,
+        segment_id: SegmentId,
+        recording: RecordingEncounter,
+    },
+    Event {
+        lane: u32,
+        segment_id: SegmentId,
+        envelope: HistoryEnvelope,
+    },
+    Finalize {
+        lane: u32,
+        finalization: Box<HistoryFinalization>,
+    },
+    BeginRun {
+        lane: u32,
+        run: RecordingDungeonRun,
+    },
+    FinalizeRun {
+        lane: u32,
+        finalize: FinalizeDungeonRun,
+    },
+}
+
 #[derive(Debug)]
 struct ActiveHistory {
     segment_id: SegmentId,
     encounter_id: i32,
+    /// Lets a run confirmed mid-segment claim the segment already open.
+    scene_id: Option<i32>,
     chunker: HistoryChunker,
     next_flush_at: Instant,
+    /// Buff spans that start before the chunk recording them, kept to seed
+    /// range replays that do not load that chunk.
+    seed_buff_intervals: BTreeSet<HistoryBuffInterval>,
+}
+
+/// The dungeon run a lane's segments are linked to while it is open.
+#[derive(Debug, Clone, Copy)]
+struct OpenDungeonRun {
+    run_id: i32,
+    scene_id: i32,
+}
+
+/// Where a writer persists: the process-wide database, which app setup opens
+/// after the writer has started, or one the caller owns.
+enum WriterDatabase {
+    ProcessWide,
+    Owned(Database),
+}
+
+impl WriterDatabase {
+    fn get(&self) -> Result<Database, String> {
+        match self {
+            Self::ProcessWide => crate::database::database(),
+            Self::Owned(database) => Ok(database.clone()),
+        }
+    }
 }
 
+/// Segment ids are only unique within one live pipeline, so every pipeline
+/// writes through its own lane; each lane has at most one active segment.
 #[derive(Clone, Debug)]
 pub struct HistoryWriterHandle {
     sender: SyncSender<HistoryWriterCommand>,
+    lane: u32,
 }
 
 impl HistoryWriterHandle {
+    /// A writer persisting to the process-wide database.
     pub fn start() -> Result<(Self, JoinHandle<()>), String> {
+        Self::spawn(WriterDatabase::ProcessWide)
+    }
+
+    /// A writer persisting to `database` instead of the process-wide one.
+    pub fn start_with(database: Database) -> Result<(Self, JoinHandle<()>), String> {
+        Self::spawn(WriterDatabase::Owned(database))
+    }
+
+    fn spawn(database: WriterDatabase) -> Result<(Self, JoinHandle<()>), String> {
         let (sender, receiver) = mpsc::sync_channel(HISTORY_QUEUE_CAPACITY);
         let join = std::thread::Builder::new()
             .name("history-writer".to_string())
-            .spawn(move || run_writer(receiver))
+            .spawn(move || run_writer(receiver, &database))
+            .map_err(|error| format!("failed to start history writer: {error}"))?;
+        Ok((Self { sender, lane: 0 }, join))
+    }
+
+    /// A writer that accepts every segment and persists none of it, for
+    /// headless tests without a database.
+    #[cfg(test)]
+    pub(crate) fn discarding() -> Result<(Self, JoinHandle<()>), String> {
+        let (sender, receiver) = mpsc::sync_channel(HISTORY_QUEUE_CAPACITY);
+        let join = std::thread::Builder::new()
+            .name("history-discard".to_string())
+            .spawn(move || run_discarding_writer(receiver))
+            .map_err(|error| format!("failed to start history writer: {error}"))?;
+        Ok((Self { sender, lane: 0 }, join))
+    }
+
+    /// A writer that hands every segment command to the returned receiver
+    /// instead of SQLite, for tests asserting on what would be persisted.
+    #[cfg(test)]
+    pub(crate) fn recording()
+    -> Result<(Self, JoinHandle<()>, mpsc::Receiver<RecordedHistory>), String> {
+        let (sender, receiver) = mpsc::sync_channel(HISTORY_QUEUE_CAPACITY);
+        let (recorded, recorded_receiver) = mpsc::channel();
+        let join = std::thread::Builder::new()
+            .name("history-recording".to_string())
+            .spawn(move || run_recording_writer(receiver, recorded))
             .map_err(|error| format!("failed to start history writer: {error}"))?;
-        Ok((Self { sender }, join))
+        Ok((Self { sender, lane: 0 }, join, recorded_receiver))
+    }
+
+    /// A handle to the same writer whose recordings are kept apart from
+    /// every other lane's.
+    #[must_use]
+    pub fn lane(&self, lane: u32) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            lane,
+        }
     }
 
     pub fn begin(
@@ -67,6 +190,7 @@ impl HistoryWriterHandle {
         recording: RecordingEncounter,
     ) -> Result<(), String> {
         self.send(HistoryWriterCommand::Begin {
+            lane: self.lane,
             segment_id,
             recording,
         })
@@ -74,13 +198,33 @@ impl HistoryWriterHandle {
 
     pub fn record(&self, segment_id: SegmentId, envelope: HistoryEnvelope) -> Result<(), String> {
         self.send(HistoryWriterCommand::Event {
+            lane: self.lane,
             segment_id,
             envelope,
         })
     }
 
     pub fn finalize(&self, finalization: HistoryFinalization) -> Result<(), String> {
-        self.send(HistoryWriterCommand::Finalize(finalization))
+        self.send(HistoryWriterCommand::Finalize {
+            lane: self.lane,
+            finalization: Box::new(finalization),
+        })
+    }
+
+    /// Opens a dungeon run; this lane's open segment and those begun later in
+    /// the run's scene are linked to it until [`Self::finalize_run`].
+    pub fn begin_run(&self, run: RecordingDungeonRun) -> Result<(), String> {
+        self.send(HistoryWriterCommand::BeginRun {
+            lane: self.lane,
+            run,
+        })
+    }
+
+    pub fn finalize_run(&self, finalize: FinalizeDungeonRun) -> Result<(), String> {
+        self.send(HistoryWriterCommand::FinalizeRun {
+            lane: self.lane,
+            finalize,
+        })
     }
 
     /// Makes every history write queued before this call visible to readers.
@@ -109,28 +253,23 @@ impl HistoryWriterHandle {
     }
 }
 
-fn run_writer(receiver: mpsc::Receiver<HistoryWriterCommand>) {
-    let mut active: Option<ActiveHistory> = None;
+fn run_writer(receiver: mpsc::Receiver<HistoryWriterCommand>, database: &WriterDatabase) {
+    let mut active: HashMap<u32, ActiveHistory> = HashMap::new();
+    let mut runs: HashMap<u32, OpenDungeonRun> = HashMap::new();
     loop {
-        if active
-            .as_ref()
-            .is_some_and(|history| history.next_flush_at <= Instant::now())
-        {
-            if let Err(error) = flush_chunks(history_mut(&mut active)) {
+        let next_flush_at = active.values().map(|history| history.next_flush_at).min();
+        if next_flush_at.is_some_and(|at| at <= Instant::now()) {
+            if let Err(error) = flush_due_chunks(database, &mut active) {
                 log::error!(target: "app::history", "history_chunk_flush_failed error={error}");
                 break;
             }
             continue;
         }
-        let command = match active.as_ref() {
-            Some(history) => match receiver.recv_timeout(
-                history
-                    .next_flush_at
-                    .saturating_duration_since(Instant::now()),
-            ) {
+        let command = match next_flush_at {
+            Some(at) => match receiver.recv_timeout(at.saturating_duration_since(Instant::now())) {
                 Ok(command) => Some(command),
                 Err(RecvTimeoutError::Timeout) => {
-                    if let Err(error) = flush_chunks(history_mut(&mut active)) {
+                    if let Err(error) = flush_due_chunks(database, &mut active) {
                         log::error!(target: "app::history", "history_chunk_flush_failed error={error}");
                         break;
                     }
@@ -149,29 +288,46 @@ fn run_writer(receiver: mpsc::Receiver<HistoryWriterCommand>) {
 
         let result = match command {
             HistoryWriterCommand::Begin {
+                lane,
+                segment_id,
+                recording,
+            } => begin_segment(
+                database,
+                &mut active,
+                runs.get(&lane),
+                lane,
                 segment_id,
                 recording,
-            } => begin_segment(&mut active, segment_id, recording),
+            ),
             HistoryWriterCommand::Event {
+                lane,
                 segment_id,
                 envelope,
-            } => record_event(&mut active, segment_id, envelope),
-            HistoryWriterCommand::Finalize(finalization) => {
-                finalize_segment(&mut active, finalization)
+            } => record_event(database, active.get_mut(&lane), segment_id, envelope),
+            HistoryWriterCommand::Finalize { lane, finalization } => {
+                finalize_segment(database, active.remove(&lane), *finalization)
+            }
+            HistoryWriterCommand::BeginRun { lane, run } => {
+                begin_run(database, &mut runs, active.get(&lane), lane, run)
+            }
+            HistoryWriterCommand::FinalizeRun { lane, finalize } => {
+                finalize_run(database, runs.remove(&lane), finalize)
             }
             HistoryWriterCommand::Fence(reply) => {
                 let result = active
-                    .as_mut()
-                    .map_or(Ok(()), flush_chunks)
-                    .and_then(|()| crate::database::flush_database());
+                    .values_mut()
+                    .try_for_each(|history| flush_chunks(database, history))
+                    .and_then(|()| database.get()?.flush());
                 let _ = reply.send(result.clone());
                 result
             }
             HistoryWriterCommand::Shutdown(reply) => {
-                let result = if active.is_some() {
+                let result = if !active.is_empty() {
                     Err("history writer shut down with an unfinalized segment".to_string())
+                } else if !runs.is_empty() {
+                    Err("history writer shut down with an unfinished dungeon run".to_string())
                 } else {
-                    crate::database::flush_database()
+                    database.get().and_then(|database| database.flush())
                 };
                 let _ = reply.send(result);
                 break;
@@ -184,70 +340,214 @@ fn run_writer(receiver: mpsc::Receiver<HistoryWriterCommand>) {
     }
 }
 
+#[cfg(test)]
+fn run_discarding_writer(receiver: mpsc::Receiver<HistoryWriterCommand>) {
+    while let Ok(command) = receiver.recv() {
+        match command {
+            HistoryWriterCommand::Begin { .. }
+            | HistoryWriterCommand::Event { .. }
+            | HistoryWriterCommand::Finalize { .. }
+            | HistoryWriterCommand::BeginRun { .. }
+            | HistoryWriterCommand::FinalizeRun { .. } => {}
+            HistoryWriterCommand::Fence(reply) => {
+                let _ = reply.send(Ok(()));
+            }
+            HistoryWriterCommand::Shutdown(reply) => {
+                let _ = reply.send(Ok(()));
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn run_recording_writer(
+    receiver: mpsc::Receiver<HistoryWriterCommand>,
+    recorded: mpsc::Sender<RecordedHistory>,
+) {
+    while let Ok(command) = receiver.recv() {
+        let history = match command {
+            HistoryWriterCommand::Begin {
+                lane,
+                segment_id,
+                recording,
+            } => RecordedHistory::Begin {
+                lane,
+                segment_id,
+                recording,
+            },
+            HistoryWriterCommand::Event {
+                lane,
+                segment_id,
+                envelope,
+            } => RecordedHistory::Event {
+                lane,
+                segment_id,
+                envelope,
+            },
+            HistoryWriterCommand::Finalize { lane, finalization } => {
+                RecordedHistory::Finalize { lane, finalization }
+            }
+            HistoryWriterCommand::BeginRun { lane, run } => RecordedHistory::BeginRun { lane, run },
+            HistoryWriterCommand::FinalizeRun { lane, finalize } => {
+                RecordedHistory::FinalizeRun { lane, finalize }
+            }
+            HistoryWriterCommand::Fence(reply) => {
+                let _ = reply.send(Ok(()));
+                continue;
+            }
+            HistoryWriterCommand::Shutdown(reply) => {
+                let _ = reply.send(Ok(()));
+                break;
+            }
+        };
+        // The test may stop listening early; the writer keeps draining.
+        let _ = recorded.send(history);
+    }
+}
+
 fn begin_segment(
-    active: &mut Option<ActiveHistory>,
+    database: &WriterDatabase,
+    active: &mut HashMap<u32, ActiveHistory>,
+    run: Option<&OpenDungeonRun>,
+    lane: u32,
     segment_id: SegmentId,
     recording: RecordingEncounter,
 ) -> Result<(), String> {
-    if active.is_some() {
+    if active.contains_key(&lane) {
         return Err("received SegmentStarted before the previous history finalized".to_string());
     }
-    let encounter_id = crate::database::begin_history_recording(recording)?;
-    *active = Some(ActiveHistory {
-        segment_id,
-        encounter_id,
-        chunker: HistoryChunker::new(encounter_id).map_err(|error| error.to_string())?,
-        next_flush_at: Instant::now() + CHUNK_FLUSH_INTERVAL,
-    });
+    // A segment opened in the same batch as a scene change already carries
+    // the new scene and must not join the run being left.
+    let run_id = run
+        .filter(|run| recording.scene_id == Some(run.scene_id))
+        .map(|run| run.run_id);
+    let scene_id = recording.scene_id;
+    let database = database.get()?;
+    let encounter_id = database.begin_history_recording(recording)?;
+    if let Some(run_id) = run_id {
+        database.link_dungeon_run_encounter(run_id, encounter_id)?;
+    }
+    active.insert(
+        lane,
+        ActiveHistory {
+            segment_id,
+            encounter_id,
+            scene_id,
+            chunker: HistoryChunker::new(encounter_id).map_err(|error| error.to_string())?,
+            next_flush_at: Instant::now() + CHUNK_FLUSH_INTERVAL,
+            seed_buff_intervals: BTreeSet::new(),
+        },
+    );
     Ok(())
 }
 
+/// A run is confirmed after its scene was entered, usually while the lane's
+/// segment for that scene is already open; that segment joins the run too.
+fn begin_run(
+    database: &WriterDatabase,
+    runs: &mut HashMap<u32, OpenDungeonRun>,
+    active: Option<&ActiveHistory>,
+    lane: u32,
+    run: RecordingDungeonRun,
+) -> Result<(), String> {
+    if runs.contains_key(&lane) {
+        return Err("received a dungeon run before the previous run finished".to_string());
+    }
+    let scene_id = run.scene_id;
+    let database = database.get()?;
+    let run_id = database.begin_dungeon_run(run)?;
+    if let Some(history) = active.filter(|history| history.scene_id == Some(scene_id)) {
+        database.link_dungeon_run_encounter(run_id, history.encounter_id)?;
+    }
+    runs.insert(lane, OpenDungeonRun { run_id, scene_id });
+    Ok(())
+}
+
+fn finalize_run(
+    database: &WriterDatabase,
+    run: Option<OpenDungeonRun>,
+    finalize: FinalizeDungeonRun,
+) -> Result<(), String> {
+    let run = run.ok_or_else(|| "received a dungeon run end without an open run".to_string())?;
+    database.get()?.finalize_dungeon_run(run.run_id, finalize)
+}
+
 fn record_event(
-    active: &mut Option<ActiveHistory>,
+    database: &WriterDatabase,
+    history: Option<&mut ActiveHistory>,
     segment_id: SegmentId,
     envelope: HistoryEnvelope,
 ) -> Result<(), String> {
-    let history = active
-        .as_mut()
-        .ok_or_else(|| "received a history event without an active segment".to_string())?;
+    let history =
+        history.ok_or_else(|| "received a history event without an active segment".to_string())?;
     if history.segment_id != segment_id {
         return Err(format!(
             "history event segment mismatch: active={} event={}",
             history.segment_id.0, segment_id.0
         ));
     }
+    let stream_kind = envelope.event.stream_kind();
+    let interval = match &envelope.event {
+        HistoryEvent::BuffInterval(interval) => Some(*interval),
+        _ => None,
+    };
     if let Some(chunk) = history
         .chunker
         .push(envelope)
         .map_err(|error| error.to_string())?
     {
-        crate::database::append_history_chunk(chunk)?;
+        database.get()?.append_history_chunk(chunk)?;
+    }
+    if let Some(interval) = interval
+        && history
+            .chunker
+            .open_chunk_start_ms(stream_kind)
+            .is_none_or(|start_ms| interval.start_ms < start_ms)
+    {
+        history.seed_buff_intervals.insert(interval);
     }
     Ok(())
 }
 
-fn flush_chunks(history: &mut ActiveHistory) -> Result<(), String> {
-    for chunk in history.chunker.flush().map_err(|error| error.to_string())? {
-        crate::database::append_history_chunk(chunk)?;
+fn flush_chunks(database: &WriterDatabase, history: &mut ActiveHistory) -> Result<(), String> {
+    let chunks = history.chunker.flush().map_err(|error| error.to_string())?;
+    if !chunks.is_empty() {
+        let database = database.get()?;
+        for chunk in chunks {
+            database.append_history_chunk(chunk)?;
+        }
     }
     history.next_flush_at = Instant::now() + CHUNK_FLUSH_INTERVAL;
     Ok(())
 }
 
+fn flush_due_chunks(
+    database: &WriterDatabase,
+    active: &mut HashMap<u32, ActiveHistory>,
+) -> Result<(), String> {
+    let now = Instant::now();
+    active
+        .values_mut()
+        .filter(|history| history.next_flush_at <= now)
+        .try_for_each(|history| flush_chunks(database, history))
+}
+
 fn finalize_segment(
-    active: &mut Option<ActiveHistory>,
+    database: &WriterDatabase,
+    history: Option<ActiveHistory>,
     mut finalization: HistoryFinalization,
 ) -> Result<(), String> {
-    let mut history = active
-        .take()
-        .ok_or_else(|| "received SegmentEnded without active history".to_string())?;
+    let mut history =
+        history.ok_or_else(|| "received SegmentEnded without active history".to_string())?;
     if history.segment_id != finalization.segment_id {
         return Err(format!(
             "history finalize segment mismatch: active={} event={}",
             history.segment_id.0, finalization.segment_id.0
         ));
     }
-    flush_chunks(&mut history)?;
+    flush_chunks(database, &mut history)?;
+    finalization.projection.buff_intervals = history.seed_buff_intervals.into_iter().collect();
     finalization.projection.detail.encounter_id = history.encounter_id;
     finalization.projection.detail.summary.id = history.encounter_id;
     let snapshot = finalization.projection;
@@ -256,22 +556,21 @@ fn finalize_segment(
     finalization.metadata.quality_flags |= quality_flags_to_bits(&snapshot.detail.quality_flags);
     let mut projection = encode_detail_projection(&snapshot).map_err(|error| error.to_string())?;
     projection.quality_flags |= finalization.metadata.quality_flags;
-    crate::database::finalize_history_recording(FinalizeEncounter {
-        encounter_id: history.encounter_id,
-        total_damage_exact,
-        total_healing_exact,
-        metadata: finalization.metadata,
-        projection,
-    })?;
+    database
+        .get()?
+        .finalize_history_recording(FinalizeEncounter {
+            encounter_id: history.encounter_id,
+            total_damage_exact,
+            total_healing_exact,
+            metadata: finalization.metadata,
+            projection,
+        })?;
     Ok(())
 }
 
-fn history_mut(active: &mut Option<ActiveHistory>) -> &mut ActiveHistory {
-    active.as_mut().expect("caller checked active history")
-}
-
 #[cfg(test)]
 mod tests {
+    use std::path::Path;
     use std::sync::mpsc::TryRecvError;
 
     use diesel::prelude::*;
@@ -305,22 +604,115 @@ mod tests {
         projection: crate::database::event_journal::StoredProjection,
     }
 
-    /// Starts at the canonical accepted-hit boundary used by `LiveCore`.
-    /// Constructing `LiveCore` itself would require a windowed Wry `AppHandle`;
-    /// its hit gating is covered independently in `live_core` tests.
+    #[test]
+    fn a_run_confirmed_mid_segment_links_the_open_segment_of_its_scene() {
+        let database = Database::open(Path::new(":memory:")).expect("open isolated database");
+        let (writer, join) =
+            HistoryWriterHandle::start_with(database.clone()).expect("start history writer");
+        let other_scene = writer.lane(1);
+        let recording = |scene_id| RecordingEncounter {
+            started_at_ms: STARTED_AT_MS,
+            local_player_id: Some(10),
+            scene_id: Some(scene_id),
+            dungeon_difficulty: None,
+        };
+        let run = |scene_id| RecordingDungeonRun {
+            scene_id,
+            dungeon_difficulty: None,
+            started_at_ms: STARTED_AT_MS,
+        };
+
+        // The segment opens on entering the scene; the run only once the
+        // scene shows dungeon state.
+        writer
+            .begin(SEGMENT_ID, recording(7))
+            .expect("begin segment");
+        writer.begin_run(run(7)).expect("begin run");
+        other_scene
+            .begin(SEGMENT_ID, recording(8))
+            .expect("begin other segment");
+        other_scene.begin_run(run(9)).expect("begin other run");
+        writer.fence().expect("fence writer");
+
+        let links = database
+            .exec(|conn| {
+                use sch::dungeon_run_encounters::dsl as l;
+                l::dungeon_run_encounters
+                    .select((l::run_id, l::encounter_id))
+                    .load::<(i32, i32)>(conn)
+                    .map_err(|error| error.to_string())
+            })
+            .expect("load run links");
+        assert_eq!(links, vec![(1, 1)]);
+
+        // Both segments stay open, so the writer stops on disconnect rather
+        // than through a shutdown that would reject them.
+        drop((writer, other_scene));
+        join.join().expect("join history writer");
+        database.shutdown().expect("stop isolated database actor");
+    }
+
+    #[test]
+    fn only_buff_spans_starting_before_their_chunk_are_kept_as_seeds() {
+        let database = WriterDatabase::Owned(
+            Database::open(Path::new(":memory:")).expect("open isolated database"),
+        );
+        let mut history = ActiveHistory {
+            segment_id: SEGMENT_ID,
+            encounter_id: 1,
+            scene_id: None,
+            chunker: HistoryChunker::new(1).expect("chunker"),
+            next_flush_at: Instant::now() + CHUNK_FLUSH_INTERVAL,
+            seed_buff_intervals: BTreeSet::new(),
+        };
+        let span = |start_ms, end_ms_exclusive| HistoryBuffInterval {
+            entity_id: 2,
+            base_id: 77,
+            source_entity_id: None,
+            start_ms,
+            end_ms_exclusive,
+            stacks: 1,
+        };
+
+        // The first span opens the timeline chunk at 100, so it started
+        // before it; the second lies inside it; the third reaches back past it.
+        for (sequence, interval) in [(1, span(50, 100)), (2, span(150, 200)), (3, span(80, 300))] {
+            record_event(
+                &database,
+                Some(&mut history),
+                SEGMENT_ID,
+                HistoryEnvelope {
+                    sequence,
+                    offset_ms: interval.end_ms_exclusive,
+                    event: HistoryEvent::BuffInterval(interval),
+                },
+            )
+            .expect("record span");
+        }
+
+        assert_eq!(
+            history.seed_buff_intervals.into_iter().collect::<Vec<_>>(),
+            vec![span(50, 100), span(80, 300)]
+        );
+    }
+
+    /// Starts at the canonical accepted-hit boundary used by `LiveCore`, so
+    /// the measurement covers the writer and the SQLite actor only; hit
+    /// gating is covered independently in `live_core` tests.
     #[test]
     #[ignore = "sustained-load benchmark; run explicitly with --ignored --nocapture"]
     fn benchmark_accepted_hits_backpressure_through_sqlite_actor() {
-        crate::database::init_in_memory_actor_for_test().expect("start isolated database actor");
+        let database = Database::open(Path::new(":memory:")).expect("open isolated database");
+        let writer_database = WriterDatabase::Owned(database.clone());
 
         let (sender, receiver) = mpsc::sync_channel(HISTORY_QUEUE_CAPACITY);
-        let writer = HistoryWriterHandle { sender };
+        let writer = HistoryWriterHandle { sender, lane: 0 };
         let (release_writer, await_release) = mpsc::sync_channel(0);
         let writer_join = std::thread::Builder::new()
             .name("history-writer-benchmark".to_string())
             .spawn(move || {
                 await_release.recv().expect("release benchmark writer");
-                run_writer(receiver);
+                run_writer(receiver, &writer_database);
             })
             .expect("start gated history writer");
 
@@ -354,7 +746,7 @@ mod tests {
         writer_join.join().expect("history writer joins");
         let fenced_elapsed = released_at.elapsed();
 
-        let persisted = load_persisted_benchmark().expect("load persisted benchmark");
+        let persisted = load_persisted_benchmark(&database).expect("load persisted benchmark");
         let mut persisted_hits = 0_u64;
         let mut persisted_damage = 0_u128;
         let mut expected_sequence = 0_u64;
@@ -406,7 +798,7 @@ mod tests {
             hits_per_second,
         );
 
-        crate::database::shutdown_database().expect("stop isolated database actor");
+        database.shutdown().expect("stop isolated database actor");
     }
 
     fn produce_accepted_hits(
@@ -544,8 +936,8 @@ mod tests {
         }
     }
 
-    fn load_persisted_benchmark() -> Result<PersistedBenchmark, String> {
-        crate::database::db_exec(|conn| {
+    fn load_persisted_benchmark(database: &Database) -> Result<PersistedBenchmark, String> {
+        database.exec(|conn| {
             use sch::encounters::dsl as e;
             let encounter_id = e::encounters
                 .select(e::id)
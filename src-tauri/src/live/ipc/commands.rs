@@ -1,11 +1,19 @@
 use crate::WINDOW_LIVE_LABEL;
 use crate::live::bootstrap_snapshot::{MonitorRuntimeSnapshot, save_monitor_runtime_snapshot};
 use crate::live::ipc::models::{
-    HudFrame, HudFrameRequest, LivePullWindow, LiveScenePayload, LiveStatusPayload,
-    LiveWindowFrame, LiveWindowFrameRequest,
+    HudFrame, HudFrameRequest, LiveCaptureHealthPayload, LivePullWindow, LiveScenePayload,
+    LiveSessionsPayload, LiveStatusPayload, LiveWindowFrame, LiveWindowFrameRequest,
+    LocalApiStatusPayload, ProtocolDiscoveryPayload, UserScriptsPayload,
 };
 use crate::live::ipc::publisher::LivePublicationCache;
+use crate::live::local_api::{
+    LocalApiServer, LocalApiSettings, load_local_api_settings, save_local_api_settings,
+};
+use crate::live::protocol::discovery::ProtocolDiscovery;
+use crate::live::replay::{self, ReplaySpeed, ReplayStats};
 use crate::live::runtime_handle::LiveRuntimeHandle;
+use crate::live::user_scripts::{read_user_scripts, user_scripts_dir};
+use std::path::PathBuf;
 use tauri::Manager;
 use window_vibrancy::{apply_blur, clear_blur};
 
@@ -61,6 +69,87 @@ pub fn get_live_scene(
     Ok(cache.current_scene())
 }
 
+/// Capture loss and decode-failure counters for diagnosing a suspicious parse.
+#[tauri::command]
+#[specta::specta]
+pub fn get_capture_health(
+    cache: tauri::State<'_, LivePublicationCache>,
+) -> Result<LiveCaptureHealthPayload, String> {
+    Ok(cache.current_capture_health())
+}
+
+/// Captured game clients and which one the live windows currently show.
+#[tauri::command]
+#[specta::specta]
+pub fn get_live_sessions(
+    cache: tauri::State<'_, LivePublicationCache>,
+) -> Result<LiveSessionsPayload, String> {
+    Ok(cache.current_sessions())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn select_live_session(
+    runtime: tauri::State<'_, LiveRuntimeHandle>,
+    session_id: u32,
+) -> Result<(), String> {
+    runtime.select_session(session_id).await
+}
+
+/// Unrouted messages recorded while protocol discovery is enabled.
+#[tauri::command]
+#[specta::specta]
+pub fn get_protocol_discovery(
+    discovery: tauri::State<'_, ProtocolDiscovery>,
+) -> Result<ProtocolDiscoveryPayload, String> {
+    Ok(discovery.snapshot())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_protocol_discovery_enabled(
+    discovery: tauri::State<'_, ProtocolDiscovery>,
+    enabled: bool,
+) {
+    discovery.set_enabled(enabled);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn clear_protocol_discovery(discovery: tauri::State<'_, ProtocolDiscovery>) {
+    discovery.clear();
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_local_api_status(
+    app_handle: tauri::AppHandle,
+    server: tauri::State<'_, LocalApiServer>,
+) -> LocalApiStatusPayload {
+    LocalApiStatusPayload {
+        settings: load_local_api_settings(&app_handle),
+        listen_address: server.listen_address().map(|address| address.to_string()),
+    }
+}
+
+/// Persists the settings, then restarts the listener with them. A port that
+/// cannot be bound is reported after the settings are saved.
+#[tauri::command]
+#[specta::specta]
+pub fn save_and_apply_local_api_settings(
+    settings: LocalApiSettings,
+    app_handle: tauri::AppHandle,
+    server: tauri::State<'_, LocalApiServer>,
+) -> Result<LocalApiStatusPayload, String> {
+    let settings = settings.normalize()?;
+    save_local_api_settings(&app_handle, &settings)?;
+    let address = server.apply(&settings)?;
+    Ok(LocalApiStatusPayload {
+        settings,
+        listen_address: address.map(|address| address.to_string()),
+    })
+}
+
 fn pull_window_active(
     window: &tauri::WebviewWindow,
     cache: &LivePublicationCache,
@@ -133,3 +222,38 @@ pub async fn save_and_apply_monitor_runtime_snapshot(
     save_monitor_runtime_snapshot(&app_handle, &snapshot)?;
     runtime.apply_monitor_config(snapshot).await
 }
+
+/// Rereads the scripts folder and restarts the live runtime's scripts from it.
+/// Files that fail to read or compile are reported and left out.
+#[tauri::command]
+#[specta::specta]
+pub async fn reload_user_scripts(
+    app_handle: tauri::AppHandle,
+    runtime: tauri::State<'_, LiveRuntimeHandle>,
+) -> Result<UserScriptsPayload, String> {
+    let directory = user_scripts_dir(&app_handle)?;
+    let (scripts, statuses) = read_user_scripts(&directory);
+    runtime.apply_user_scripts(scripts).await?;
+    Ok(UserScriptsPayload {
+        directory: directory.display().to_string(),
+        scripts: statuses,
+    })
+}
+
+/// Replays recorded session files through a private live core and writes the
+/// resulting encounters to history. `speed` is `max`, `realtime` or `<n>x`.
+#[tauri::command]
+#[specta::specta]
+pub async fn replay_recorded_session(
+    app_handle: tauri::AppHandle,
+    paths: Vec<String>,
+    speed: String,
+) -> Result<ReplayStats, String> {
+    let speed = speed.parse::<ReplaySpeed>()?;
+    let paths = paths.into_iter().map(PathBuf::from).collect();
+    tauri::async_runtime::spawn_blocking(move || {
+        replay::replay_session_files(&app_handle, paths, speed)
+    })
+    .await
+    .map_err(|error| format!("replay worker failed: {error}"))?
+}
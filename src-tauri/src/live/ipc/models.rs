@@ -1,4 +1,9 @@
-use crate::live::projections::combat::accumulator::CombatSourceStats;
+use crate::database::history_query::{
+    EncounterBuffUptimeData, EncounterPhaseData, HistoryQualityFlag,
+};
+use crate::live::local_api::LocalApiSettings;
+use crate::live::projections::combat::accumulator::{CombatSourceStats, CombatTargetStats};
+use crate::live::projections::combat::rolling::{RollingWindow, per_second};
 use crate::live::projections::combat::stats::{CombatStats, Skill};
 use std::collections::HashMap;
 
@@ -54,6 +59,122 @@ pub struct LiveDeathsPayload {
     pub deaths: Vec<DeathRecord>,
 }
 
+/// Capture pipeline health (`live-capture-health`), 1s throttle. Stream
+/// counters are cumulative for the capture; `affected_ranges` are offsets into
+/// the active encounter, or the last one once it has ended.
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveCaptureHealthPayload {
+    pub revision: u64,
+    pub active_segment_id: Option<u64>,
+    pub streams: Vec<CaptureStreamHealth>,
+    pub affected_ranges: Vec<CaptureAffectedRange>,
+}
+
+/// Loss and decode counters for one TCP stream. `stream_id` is a string
+/// because stream ids span the full u64 range.
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureStreamHealth {
+    pub stream_id: String,
+    pub dropped_segments: u64,
+    pub lost_bytes: u64,
+    pub reassembly_resets: u64,
+    pub malformed_frames: u64,
+    pub zstd_failures: u64,
+    pub unknown_opcodes: u64,
+    pub backpressure_stalls: u64,
+    pub backpressure_stall_ms: u64,
+    pub last_issue_wall_ms: Option<i64>,
+}
+
+/// Encounter time touched by packet loss or decode failures.
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureAffectedRange {
+    pub kind: HistoryQualityFlag,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub occurrences: u32,
+}
+
+/// Messages the decoder discarded while protocol discovery was enabled, most
+/// frequent first.
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolDiscoveryPayload {
+    pub enabled: bool,
+    pub messages: Vec<DiscoveredMessage>,
+    /// Sightings of further keys once the table was full.
+    pub dropped_sightings: u64,
+}
+
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredMessage {
+    pub kind: DiscoveredMessageKind,
+    pub reason: DiscoveryReason,
+    pub service_id: Option<u32>,
+    pub method_id: Option<u32>,
+    pub count: u64,
+    pub first_seen_wall_ms: i64,
+    pub last_seen_wall_ms: i64,
+    pub min_payload_bytes: u32,
+    pub max_payload_bytes: u32,
+    /// Schemaless dump of the most recent payload.
+    pub sample: WireDump,
+}
+
+/// `Notify` is server to client, `Call` client to server.
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DiscoveredMessageKind {
+    Notify,
+    Call,
+}
+
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DiscoveryReason {
+    UnknownOpcode,
+    UnroutedService,
+    UnroutedMethod,
+}
+
+/// Protobuf wire format read without a schema. `trailing_hex` holds what
+/// followed the last field that parsed; `truncated` marks a sample cut short
+/// before dumping.
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WireDump {
+    pub payload_bytes: u32,
+    pub truncated: bool,
+    pub fields: Vec<WireField>,
+    pub trailing_hex: Option<String>,
+}
+
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WireField {
+    pub number: u32,
+    pub value: WireValue,
+}
+
+/// 64-bit integers are strings, as in [`CaptureStreamHealth`]. Length-delimited
+/// fields are guessed as text, then a nested message, then packed varints,
+/// falling back to raw bytes.
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "wireType", rename_all = "camelCase")]
+pub enum WireValue {
+    Varint { value: String, zigzag: String },
+    Fixed64 { value: String, double: f64 },
+    Fixed32 { value: u32, float: f32 },
+    Message { len: u32, fields: Vec<WireField> },
+    Text { value: String },
+    PackedVarints { values: Vec<String> },
+    Bytes { len: u32, hex: String },
+}
+
 #[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct LiveScenePayload {
@@ -62,6 +183,26 @@ pub struct LiveScenePayload {
     pub dungeon_difficulty: Option<i32>,
 }
 
+/// Game clients seen by the capture. Live windows show the selected one.
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveSessionsPayload {
+    pub revision: u64,
+    pub selected_session_id: Option<u32>,
+    pub sessions: Vec<LiveSessionSummary>,
+}
+
+/// One game client and the TCP streams it has used so far (strings, as in
+/// [`CaptureStreamHealth`]).
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveSessionSummary {
+    pub session_id: u32,
+    pub local_player_uuid: Option<String>,
+    pub local_player_name: Option<String>,
+    pub stream_ids: Vec<String>,
+}
+
 #[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub enum LivePullWindow {
@@ -167,6 +308,9 @@ pub struct LiveStatusPayload {
     pub shield_max_hp: i64,
     pub shield_entries: Vec<ShieldDetailEntry>,
     pub fight_resource: Option<FightResourceState>,
+    /// Counters and bars published by user scripts, in script then name order.
+    pub script_counters: Vec<ScriptCounterState>,
+    pub script_errors: Vec<ScriptErrorState>,
 }
 
 /// Local player buff list (`live-buffs`), 50ms throttle.
@@ -232,6 +376,10 @@ pub struct LiveDataPayload {
     pub is_paused: bool,
     pub bosses: Vec<BossHealth>,
     pub entities: Vec<RawEntityData>,
+    /// Boss phases of the segment; empty until a second phase opens.
+    pub phases: Vec<EncounterPhaseData>,
+    /// Uptime of the configured buffs over the segment so far.
+    pub buff_uptimes: Vec<EncounterBuffUptimeData>,
 }
 
 impl Default for LiveDataPayload {
@@ -250,6 +398,8 @@ impl Default for LiveDataPayload {
             is_paused: false,
             bosses: Vec::new(),
             entities: Vec::new(),
+            phases: Vec::new(),
+            buff_uptimes: Vec::new(),
         }
     }
 }
@@ -292,6 +442,41 @@ pub struct RawEntityData {
     pub heal_skills: HashMap<i64, RawSkillStats>,
     pub taken_skills: HashMap<i64, RawSkillStats>,
     pub taken_per_source: Vec<PerSourceStats>,
+    /// Outgoing rates over the trailing windows, narrowest first; empty for
+    /// entities that only took damage.
+    pub rolling: Vec<RawRollingWindow>,
+    /// Damage per boss or elite target, largest first; empty unless the
+    /// per-target breakdown is enabled.
+    pub targets: Vec<RawTargetStats>,
+}
+
+/// Damage one entity dealt to a single boss or elite.
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RawTargetStats {
+    /// The target's entity UUID, serialized as a string for JS safety.
+    pub entity_uuid: String,
+    pub monster_id: Option<i32>,
+    pub is_boss: bool,
+    pub total_value: String,
+    pub hits: String,
+    /// Damage dealt as a percentage of the target's max HP; `None` while
+    /// its max HP is unknown.
+    pub hp_share_pct: Option<f64>,
+}
+
+/// Damage and healing per second over one trailing window of combat, and
+/// the best damage rate a window of that length reached this segment.
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RawRollingWindow {
+    pub window_ms: u64,
+    pub dps: String,
+    pub hps: String,
+    pub peak_dps: String,
+    /// Segment offset at which the peak window ended; `None` before the
+    /// first damage.
+    pub peak_end_ms: Option<u64>,
 }
 
 #[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -420,12 +605,42 @@ pub fn to_raw_skill_stats(skill: &Skill) -> RawSkillStats {
     }
 }
 
+pub fn to_raw_rolling_window(window: &RollingWindow) -> RawRollingWindow {
+    RawRollingWindow {
+        window_ms: window.window_ms,
+        dps: per_second(window.damage, window.window_ms).to_string(),
+        hps: per_second(window.healing, window.window_ms).to_string(),
+        peak_dps: window
+            .peak
+            .map_or(0, |peak| per_second(peak.damage, window.window_ms))
+            .to_string(),
+        peak_end_ms: window.peak.map(|peak| peak.end_ms),
+    }
+}
+
+pub fn to_raw_target_stats(
+    target_uuid: i64,
+    target: &CombatTargetStats,
+    max_hp: Option<i64>,
+) -> RawTargetStats {
+    RawTargetStats {
+        entity_uuid: target_uuid.to_string(),
+        monster_id: target.stats.target_monster_id,
+        is_boss: target.is_boss,
+        total_value: target.stats.total_value.to_string(),
+        hits: target.stats.hits.to_string(),
+        hp_share_pct: max_hp
+            .filter(|max_hp| *max_hp > 0)
+            .map(|max_hp| target.stats.total_value as f64 * 100.0 / max_hp as f64),
+    }
+}
+
 /// Map the incrementally maintained taken-source projection to the live DTO.
 pub fn build_taken_per_source(
     sources: &HashMap<Option<i32>, CombatSourceStats>,
 ) -> Vec<PerSourceStats> {
     let mut rows = sources.iter().collect::<Vec<_>>();
-    rows.sort_unstable_by(|left, right| right.1.stats.total.cmp(&left.1.stats.total));
+    rows.sort_unstable_by_key(|(_, source)| std::cmp::Reverse(source.stats.total));
     rows.into_iter()
         .map(|(source_monster_id, source)| PerSourceStats {
             source_monster_id: *source_monster_id,
@@ -600,6 +815,9 @@ pub struct MinimapEntity {
     pub facing: Option<f32>,
     /// Whether the entity is currently in the dead actor state.
     pub is_dead: bool,
+    /// Bumped each time the entity teleports or is pulled back. Positions
+    /// from different epochs must not be joined into one trail.
+    pub position_epoch: u32,
     /// Top-level summoner/owner UUID when present.
     pub top_summoner_id: Option<String>,
 }
@@ -784,6 +1002,53 @@ pub struct DeathRecord {
     pub victim_buffs: Vec<DeathBuffSnapshot>,
     #[serde(default)]
     pub participant_buffs: Vec<DeathParticipantBuffSnapshot>,
+    /// When the victim was revived, if it has been since this death.
+    #[serde(default)]
+    pub revived_timestamp_ms: Option<String>,
+}
+
+/// Local API settings and where the listener is bound while it runs.
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalApiStatusPayload {
+    pub settings: LocalApiSettings,
+    pub listen_address: Option<String>,
+}
+
+/// A value a user script published with `counter()` or `bar()`; `max` is set
+/// only for bars.
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptCounterState {
+    pub script_id: String,
+    pub name: String,
+    pub value: f64,
+    pub max: Option<f64>,
+}
+
+/// Why a user script stopped running; it stays stopped until the scripts are
+/// reloaded.
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptErrorState {
+    pub script_id: String,
+    pub message: String,
+}
+
+/// Load result for one `.rhai` file in the scripts folder.
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UserScriptStatus {
+    pub script_id: String,
+    /// Read or compile error; `None` when the script was handed to the live runtime.
+    pub error: Option<String>,
+}
+
+#[derive(specta::Type, serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserScriptsPayload {
+    pub directory: String,
+    pub scripts: Vec<UserScriptStatus>,
 }
 
 #[cfg(test)]
@@ -4,19 +4,23 @@
 //! on its existing cadence and moves them into this cache. Tauri commands read
 //! the cache directly instead of entering the runtime control channel, whose
 //! fence intentionally pauses packet capture for state-changing commands.
+//! The local API reads the same slots and waits on its change signal.
 
 use std::collections::VecDeque;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
 
-use parking_lot::RwLock;
+use parking_lot::{Condvar, Mutex, RwLock};
+use serde::{Serialize, Serializer};
 
 use super::models::{
-    HudFrame, HudFrameRequest, LiveBuffsPayload, LiveCombatPayload, LiveDeathsPayload,
-    LiveFantasyPayload, LiveMonsterPayload, LivePullWindow, LiveScenePayload, LiveStatusPayload,
-    LiveWindowFrame, LiveWindowFrameRequest, MinimapSkillCast, MinimapSnapshot,
-    MinimapSnapshotUpdate, MinimapUpdatePayload,
+    HudFrame, HudFrameRequest, LiveBuffsPayload, LiveCaptureHealthPayload, LiveCombatPayload,
+    LiveDeathsPayload, LiveFantasyPayload, LiveMonsterPayload, LivePullWindow, LiveScenePayload,
+    LiveSessionsPayload, LiveStatusPayload, LiveWindowFrame, LiveWindowFrameRequest,
+    MinimapSkillCast, MinimapSnapshot, MinimapSnapshotUpdate, MinimapUpdatePayload,
 };
+use super::topic::Topic;
 use crate::live::projection_set::TopicPublication;
 
 const MINIMAP_CAST_RING_CAPACITY: usize = 512;
@@ -73,6 +77,8 @@ struct PublishedState {
     fantasy: Option<Arc<LiveFantasyPayload>>,
     deaths: Option<Arc<LiveDeathsPayload>>,
     scene: Option<Arc<LiveScenePayload>>,
+    capture_health: Option<Arc<LiveCaptureHealthPayload>>,
+    sessions: Option<Arc<LiveSessionsPayload>>,
     minimap: MinimapSlot,
 }
 
@@ -88,6 +94,8 @@ impl PublishedState {
             fantasy: None,
             deaths: None,
             scene: None,
+            capture_health: None,
+            sessions: None,
             minimap: MinimapSlot::default(),
         }
     }
@@ -97,6 +105,51 @@ impl PublishedState {
 pub struct LivePublicationCache {
     inner: Arc<RwLock<PublishedState>>,
     activity: Arc<PullActivity>,
+    changes: Arc<ChangeSignal>,
+}
+
+/// Wakes readers outside the WebView pull path when the cache changes.
+#[derive(Debug, Default)]
+struct ChangeSignal {
+    generation: Mutex<u64>,
+    changed: Condvar,
+}
+
+/// The latest payload of one topic, shared with the cache rather than copied.
+#[derive(Debug, Clone)]
+pub enum PublishedPayload {
+    Combat(Arc<LiveCombatPayload>),
+    Status(Arc<LiveStatusPayload>),
+    Buffs(Arc<LiveBuffsPayload>),
+    Monster(Arc<LiveMonsterPayload>),
+    Fantasy(Arc<LiveFantasyPayload>),
+    Deaths(Arc<LiveDeathsPayload>),
+    Scene(Arc<LiveScenePayload>),
+    /// `None` while the current scene has no minimap.
+    Minimap(Option<Arc<MinimapSnapshot>>),
+}
+
+impl Serialize for PublishedPayload {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Combat(payload) => payload.as_ref().serialize(serializer),
+            Self::Status(payload) => payload.as_ref().serialize(serializer),
+            Self::Buffs(payload) => payload.as_ref().serialize(serializer),
+            Self::Monster(payload) => payload.as_ref().serialize(serializer),
+            Self::Fantasy(payload) => payload.as_ref().serialize(serializer),
+            Self::Deaths(payload) => payload.as_ref().serialize(serializer),
+            Self::Scene(payload) => payload.as_ref().serialize(serializer),
+            Self::Minimap(snapshot) => snapshot.as_deref().serialize(serializer),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PublishedTopic {
+    pub epoch: u64,
+    /// The payload's own revision; for the minimap, its snapshot revision.
+    pub revision: u64,
+    pub payload: PublishedPayload,
 }
 
 #[derive(Debug)]
@@ -126,6 +179,7 @@ impl LivePublicationCache {
         Self {
             inner: Arc::new(RwLock::new(PublishedState::new())),
             activity: Arc::new(PullActivity::default()),
+            changes: Arc::new(ChangeSignal::default()),
         }
     }
 
@@ -160,12 +214,33 @@ impl LivePublicationCache {
                     scene_event = Some(payload);
                 }
                 TopicPublication::Minimap(payload) => publish_minimap(&mut state.minimap, payload),
+                TopicPublication::CaptureHealth(payload) => {
+                    state.capture_health = Some(Arc::new(payload));
+                }
             }
         }
+        drop(state);
 
+        self.notify_changed();
         scene_event
     }
 
+    /// Drops every topic payload and starts a new epoch, so pull clients
+    /// resynchronise from scratch after the windows switch to another game
+    /// client. The session list is kept.
+    pub fn reset(&self) {
+        let mut state = self.inner.write();
+        let sessions = state.sessions.take();
+        *state = PublishedState::new();
+        state.sessions = sessions;
+        drop(state);
+        self.notify_changed();
+    }
+
+    pub fn publish_sessions(&self, payload: LiveSessionsPayload) {
+        self.inner.write().sessions = Some(Arc::new(payload));
+    }
+
     #[must_use]
     pub fn pull_live_window(
         &self,
@@ -333,6 +408,83 @@ impl LivePublicationCache {
             .cloned()
             .unwrap_or_default()
     }
+
+    #[must_use]
+    pub fn current_capture_health(&self) -> LiveCaptureHealthPayload {
+        self.inner
+            .read()
+            .capture_health
+            .as_deref()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn current_sessions(&self) -> LiveSessionsPayload {
+        self.inner
+            .read()
+            .sessions
+            .as_deref()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Latest payload of `topic` and the epoch it belongs to; `None` until
+    /// the topic is first published in this epoch. Capture health carries no
+    /// revision and is only served by its own command.
+    #[must_use]
+    pub fn published_topic(&self, topic: Topic) -> Option<PublishedTopic> {
+        let state = self.inner.read();
+        let (revision, payload) = match topic {
+            Topic::Combat => revisioned(&state.combat, PublishedPayload::Combat),
+            Topic::Status => revisioned(&state.status, PublishedPayload::Status),
+            Topic::Buffs => revisioned(&state.buffs, PublishedPayload::Buffs),
+            Topic::Monster => revisioned(&state.monster, PublishedPayload::Monster),
+            Topic::Fantasy => revisioned(&state.fantasy, PublishedPayload::Fantasy),
+            Topic::Deaths => revisioned(&state.deaths, PublishedPayload::Deaths),
+            Topic::Scene => revisioned(&state.scene, PublishedPayload::Scene),
+            Topic::Minimap => state.minimap.published.then(|| {
+                (
+                    state.minimap.snapshot_revision,
+                    PublishedPayload::Minimap(state.minimap.snapshot.as_ref().map(Arc::clone)),
+                )
+            }),
+            Topic::CaptureHealth => None,
+        }?;
+        Some(PublishedTopic {
+            epoch: state.epoch,
+            revision,
+            payload,
+        })
+    }
+
+    /// Changes on every reset; revisions are only comparable within one epoch.
+    #[must_use]
+    pub fn epoch(&self) -> u64 {
+        self.inner.read().epoch
+    }
+
+    /// Counts publishes and resets since the cache was created.
+    #[must_use]
+    pub fn change_generation(&self) -> u64 {
+        *self.changes.generation.lock()
+    }
+
+    /// Blocks until the generation moves past `seen` or `timeout` elapses,
+    /// then returns the current generation.
+    pub fn wait_for_change(&self, seen: u64, timeout: Duration) -> u64 {
+        let mut generation = self.changes.generation.lock();
+        if *generation == seen {
+            let _ = self.changes.changed.wait_for(&mut generation, timeout);
+        }
+        *generation
+    }
+
+    fn notify_changed(&self) {
+        let mut generation = self.changes.generation.lock();
+        *generation = generation.wrapping_add(1);
+        self.changes.changed.notify_all();
+    }
 }
 
 impl PullActivity {
@@ -377,6 +529,14 @@ fn changed<T: Revisioned>(
         .map(Arc::clone)
 }
 
+fn revisioned<T: Revisioned>(
+    slot: &Option<Arc<T>>,
+    wrap: fn(Arc<T>) -> PublishedPayload,
+) -> Option<(u64, PublishedPayload)> {
+    slot.as_ref()
+        .map(|payload| (payload.revision(), wrap(Arc::clone(payload))))
+}
+
 fn clone_payload<T: Clone>(payload: Option<Arc<T>>) -> Option<T> {
     payload.map(|payload| (*payload).clone())
 }
@@ -406,13 +566,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn published_topics_share_the_latest_payload_and_signal_changes() {
+        let cache = LivePublicationCache::new();
+        assert!(cache.published_topic(Topic::Combat).is_none());
+        assert!(cache.published_topic(Topic::Minimap).is_none());
+
+        let seen = cache.change_generation();
+        let combat = LiveCombatPayload {
+            revision: 4,
+            ..LiveCombatPayload::default()
+        };
+        cache.publish([
+            TopicPublication::Combat(combat),
+            TopicPublication::Minimap(minimap(100, Vec::new())),
+            TopicPublication::CaptureHealth(LiveCaptureHealthPayload::default()),
+        ]);
+        assert_eq!(cache.wait_for_change(seen, Duration::ZERO), seen + 1);
+
+        let combat = cache
+            .published_topic(Topic::Combat)
+            .expect("combat published");
+        assert_eq!(combat.revision, 4);
+        assert!(matches!(combat.payload, PublishedPayload::Combat(_)));
+        let minimap = cache
+            .published_topic(Topic::Minimap)
+            .expect("minimap published");
+        assert_eq!((minimap.epoch, minimap.revision), (combat.epoch, 1));
+        assert!(cache.published_topic(Topic::CaptureHealth).is_none());
+
+        cache.reset();
+        assert_eq!(cache.change_generation(), seen + 2);
+        assert!(cache.published_topic(Topic::Combat).is_none());
+    }
+
     #[test]
     fn window_pull_only_returns_changed_domains() {
         let cache = LivePublicationCache::new();
-        let mut combat = LiveCombatPayload::default();
-        combat.revision = 3;
-        let mut fantasy = LiveFantasyPayload::default();
-        fantasy.revision = 7;
+        let combat = LiveCombatPayload {
+            revision: 3,
+            ..LiveCombatPayload::default()
+        };
+        let fantasy = LiveFantasyPayload {
+            revision: 7,
+            ..LiveFantasyPayload::default()
+        };
         cache.publish([
             TopicPublication::Combat(combat),
             TopicPublication::Fantasy(fantasy),
@@ -438,8 +636,10 @@ mod tests {
     #[test]
     fn shared_topic_has_no_server_side_acknowledgement() {
         let cache = LivePublicationCache::new();
-        let mut fantasy = LiveFantasyPayload::default();
-        fantasy.revision = 9;
+        let fantasy = LiveFantasyPayload {
+            revision: 9,
+            ..LiveFantasyPayload::default()
+        };
         cache.publish([TopicPublication::Fantasy(fantasy)]);
 
         let live = cache.pull_live_window(&LiveWindowFrameRequest::default(), true);
@@ -457,14 +657,22 @@ mod tests {
     #[test]
     fn hud_pull_respects_sparse_interests_and_revisions() {
         let cache = LivePublicationCache::new();
-        let mut status = LiveStatusPayload::default();
-        status.revision = 1;
-        let mut buffs = LiveBuffsPayload::default();
-        buffs.revision = 2;
-        let mut monster = LiveMonsterPayload::default();
-        monster.revision = 3;
-        let mut fantasy = LiveFantasyPayload::default();
-        fantasy.revision = 4;
+        let status = LiveStatusPayload {
+            revision: 1,
+            ..LiveStatusPayload::default()
+        };
+        let buffs = LiveBuffsPayload {
+            revision: 2,
+            ..LiveBuffsPayload::default()
+        };
+        let monster = LiveMonsterPayload {
+            revision: 3,
+            ..LiveMonsterPayload::default()
+        };
+        let fantasy = LiveFantasyPayload {
+            revision: 4,
+            ..LiveFantasyPayload::default()
+        };
         cache.publish([
             TopicPublication::Status(status),
             TopicPublication::Buffs(buffs),
@@ -606,8 +814,10 @@ mod tests {
     #[test]
     fn inactive_pull_returns_no_payload() {
         let cache = LivePublicationCache::new();
-        let mut status = LiveStatusPayload::default();
-        status.revision = 4;
+        let status = LiveStatusPayload {
+            revision: 4,
+            ..LiveStatusPayload::default()
+        };
         cache.publish([
             TopicPublication::Status(status),
             TopicPublication::Minimap(minimap(100, vec![cast(7)])),
@@ -632,6 +842,28 @@ mod tests {
         assert_eq!(frame.skill_cast_cursor, 1);
     }
 
+    #[test]
+    fn reset_starts_a_new_epoch_but_keeps_the_session_list() {
+        let cache = LivePublicationCache::new();
+        let combat = LiveCombatPayload {
+            revision: 4,
+            ..LiveCombatPayload::default()
+        };
+        cache.publish([TopicPublication::Combat(combat)]);
+        cache.publish_sessions(LiveSessionsPayload {
+            revision: 1,
+            selected_session_id: Some(2),
+            sessions: Vec::new(),
+        });
+        let before = cache.pull_live_window(&LiveWindowFrameRequest::default(), true);
+
+        cache.reset();
+        let after = cache.pull_live_window(&LiveWindowFrameRequest::default(), true);
+        assert_ne!(after.epoch, before.epoch);
+        assert!(after.combat.is_none());
+        assert_eq!(cache.current_sessions().selected_session_id, Some(2));
+    }
+
     #[test]
     fn pull_activity_defaults_match_window_startup_visibility() {
         let cache = LivePublicationCache::new();
@@ -4,7 +4,7 @@ use crate::WINDOW_MAIN_LABEL;
 
 /// Bitmask of dirty publication topics.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
-pub struct TopicMask(u8);
+pub struct TopicMask(u16);
 
 impl TopicMask {
     pub const EMPTY: Self = Self(0);
@@ -16,6 +16,7 @@ impl TopicMask {
     pub const MINIMAP: Self = Self(1 << 5);
     pub const DEATHS: Self = Self(1 << 6);
     pub const SCENE: Self = Self(1 << 7);
+    pub const CAPTURE_HEALTH: Self = Self(1 << 8);
 
     #[must_use]
     pub const fn is_empty(self) -> bool {
@@ -82,6 +83,7 @@ pub enum Topic {
     Minimap,
     Deaths,
     Scene,
+    CaptureHealth,
 }
 
 impl Topic {
@@ -95,9 +97,10 @@ impl Topic {
         Self::Minimap,
         Self::Deaths,
         Self::Scene,
+        Self::CaptureHealth,
     ];
 
-    pub const COUNT: usize = 8;
+    pub const COUNT: usize = 9;
 
     /// Position of this topic in [`Topic::ALL`].
     #[must_use]
@@ -111,6 +114,7 @@ impl Topic {
             Self::Minimap => 5,
             Self::Deaths => 6,
             Self::Scene => 7,
+            Self::CaptureHealth => 8,
         }
     }
 
@@ -125,9 +129,31 @@ impl Topic {
             Self::Minimap => TopicMask::MINIMAP,
             Self::Deaths => TopicMask::DEATHS,
             Self::Scene => TopicMask::SCENE,
+            Self::CaptureHealth => TopicMask::CAPTURE_HEALTH,
         }
     }
 
+    /// Stable identifier used by external clients of the local API.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Combat => "combat",
+            Self::Status => "status",
+            Self::Buffs => "buffs",
+            Self::Monster => "monster",
+            Self::Fantasy => "fantasy",
+            Self::Minimap => "minimap",
+            Self::Deaths => "deaths",
+            Self::Scene => "scene",
+            Self::CaptureHealth => "captureHealth",
+        }
+    }
+
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|topic| topic.name() == name)
+    }
+
     #[must_use]
     pub const fn event_name(self) -> Option<&'static str> {
         match self {
@@ -157,6 +183,8 @@ impl Topic {
             | Self::Minimap
             | Self::Deaths
             | Self::Scene => Some(50),
+            // Diagnostics only; counters can move on every packet under loss.
+            Self::CaptureHealth => Some(1_000),
         }
     }
 }
@@ -193,4 +221,12 @@ mod tests {
         }
         assert_eq!(Topic::Combat.throttle_ms(), None);
     }
+
+    #[test]
+    fn topic_names_round_trip() {
+        for topic in Topic::ALL {
+            assert_eq!(Topic::from_name(topic.name()), Some(topic));
+        }
+        assert_eq!(Topic::from_name("Combat"), None);
+    }
 }
@@ -3,13 +3,13 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use tauri::{AppHandle, Manager};
 use tokio::sync::oneshot;
 
 use crate::live::bootstrap_snapshot::MonitorRuntimeSnapshot;
-use crate::live::counter::engine::CounterSource;
+use crate::live::counter::engine::{CounterSource, DUNGEON_FLOW_PLAYING};
 use crate::live::dungeon_objectives::classify_objective;
 use crate::live::history_writer::HistoryWriterHandle;
+use crate::live::ipc::models::LiveSessionsPayload;
 use crate::live::ipc::topic::{Topic, TopicMask};
 use crate::live::marker_skills::KEY_SKILL_IDS;
 use crate::live::projection_set::{ProjectionSet, TopicPublication};
@@ -24,10 +24,15 @@ use crate::live::runtime::events::{
 use crate::live::runtime::scheduler::{DeadlineScheduler, TimerTask};
 use crate::live::runtime::segment::{CombatGate, SegmentController, SegmentState};
 use crate::live::runtime_handle::RuntimeCommand;
+use crate::live::sink::LiveSink;
 
 #[derive(Debug, Default)]
 pub struct Publications {
     pub topics: Vec<TopicPublication>,
+    /// The windows switched to another session: its topics replace every
+    /// cached payload rather than updating them.
+    pub reset_cache: bool,
+    pub sessions: Option<LiveSessionsPayload>,
 }
 
 /// Publication cadence bookkeeping for a single topic.
@@ -51,8 +56,23 @@ struct ClockAnchor {
     wall_ms: i64,
 }
 
+/// Source of "now" for work that is not driven by a packet: commands,
+/// publication wakeups and shutdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockMode {
+    /// Host monotonic and wall clocks.
+    Live,
+    /// Time only advances with processed batches or explicit
+    /// [`LiveCore::take_due_publications`] calls, so a recorded session
+    /// replays identically regardless of replay speed.
+    Recorded {
+        start_mono: MonoTimeMs,
+        start_wall_ms: i64,
+    },
+}
+
 pub struct LiveCore {
-    app: AppHandle,
+    sink: Arc<dyn LiveSink>,
     entities: EntityContext,
     segments: SegmentController,
     scheduler: DeadlineScheduler,
@@ -62,19 +82,27 @@ pub struct LiveCore {
     next_domain_sequence: u64,
     next_synthetic_batch: u64,
     clock_anchor: ClockAnchor,
+    clock_mode: ClockMode,
     live_publish_interval_ms: u64,
     publish: [PublishSchedule; Topic::COUNT],
     shutdown_complete: bool,
 }
 
 impl LiveCore {
-    pub fn new(
-        app: AppHandle,
+    pub fn with_clock(
+        sink: Arc<dyn LiveSink>,
         history_writer: HistoryWriterHandle,
         initial_config: MonitorRuntimeSnapshot,
+        clock_mode: ClockMode,
     ) -> Result<Self, String> {
         let config = Arc::new(initial_config.normalize()?);
-        let now = monotonic_now_ms();
+        let (now, now_wall_ms) = match clock_mode {
+            ClockMode::Live => (monotonic_now_ms(), wall_now_ms()),
+            ClockMode::Recorded {
+                start_mono,
+                start_wall_ms,
+            } => (start_mono, start_wall_ms),
+        };
         let mut scheduler = DeadlineScheduler::new();
         let mut entities = EntityContext::new();
         let mut projections = ProjectionSet::new(history_writer);
@@ -82,7 +110,7 @@ impl LiveCore {
         entities.set_watched_skill_ids(watched_skill_ids(&config));
 
         let mut core = Self {
-            app,
+            sink,
             entities,
             segments: SegmentController::new(),
             scheduler,
@@ -93,14 +121,17 @@ impl LiveCore {
             next_synthetic_batch: u64::MAX,
             clock_anchor: ClockAnchor {
                 mono: now,
-                wall_ms: wall_now_ms(),
+                wall_ms: now_wall_ms,
             },
+            clock_mode,
             live_publish_interval_ms: config.live.event_update_rate_ms,
             publish: [PublishSchedule::default(); Topic::COUNT],
             shutdown_complete: false,
         };
         core.segments
             .set_training_window_ms(config.live.training_window_ms);
+        core.segments
+            .set_policy(config.live.segment_policy.clone(), &mut core.scheduler);
         core.request_publications(now, true);
         Ok(core)
     }
@@ -128,6 +159,9 @@ impl LiveCore {
                 DomainEvent::ContainerReset => self
                     .segments
                     .container_boundary(envelope.meta, &mut self.scheduler),
+                DomainEvent::Disconnected { .. } => self
+                    .segments
+                    .disconnect_boundary(envelope.meta, &mut self.scheduler),
                 _ => Vec::new(),
             };
             transitions.extend(boundary);
@@ -228,6 +262,12 @@ impl LiveCore {
     }
 
     pub fn take_due_publications(&mut self, now: MonoTimeMs) -> Result<Publications, String> {
+        if matches!(self.clock_mode, ClockMode::Recorded { .. }) && now > self.clock_anchor.mono {
+            self.clock_anchor = ClockAnchor {
+                mono: now,
+                wall_ms: self.wall_at(now),
+            };
+        }
         self.drain_due_through(now)?;
         let due = self.due_topics(now);
         if due.is_empty() {
@@ -242,7 +282,10 @@ impl LiveCore {
             schedule.next = None;
             schedule.last = Some(now);
         }
-        Ok(Publications { topics })
+        Ok(Publications {
+            topics,
+            ..Publications::default()
+        })
     }
 
     pub fn publish_now(&mut self) -> Result<Publications, String> {
@@ -251,6 +294,18 @@ impl LiveCore {
         self.take_due_publications(now)
     }
 
+    /// Publishes every topic, not only the dirty ones, for a cache that was
+    /// just reset.
+    pub fn republish_all(&mut self) -> Result<Publications, String> {
+        self.projections.mark_all_dirty();
+        self.publish_now()
+    }
+
+    #[must_use]
+    pub fn entities(&self) -> &EntityContext {
+        &self.entities
+    }
+
     pub fn handle_command(&mut self, command: RuntimeCommand) -> Result<LiveCoreFlow, String> {
         match command {
             RuntimeCommand::ManualReset => {
@@ -262,7 +317,15 @@ impl LiveCore {
                 Ok(LiveCoreFlow::Continue)
             }
             RuntimeCommand::ApplyMonitorConfig(config) => {
-                self.apply_config(config)?;
+                self.apply_config(*config)?;
+                Ok(LiveCoreFlow::Continue)
+            }
+            RuntimeCommand::ApplyUserScripts(scripts) => {
+                let now = self.refresh_clock();
+                self.drain_due_through(now)?;
+                self.projections.apply_scripts(&scripts, self.wall_at(now));
+                self.flush_voice_cues();
+                self.request_publications(now, true);
                 Ok(LiveCoreFlow::Continue)
             }
             RuntimeCommand::StartTraining => {
@@ -277,11 +340,14 @@ impl LiveCore {
                 self.stop_training()?;
                 Ok(LiveCoreFlow::Continue)
             }
+            // A core is a single session; selection is handled by `LiveSessions`.
+            RuntimeCommand::SelectSession(_) => Ok(LiveCoreFlow::Continue),
             RuntimeCommand::Shutdown { reply } => Ok(LiveCoreFlow::ShutdownRequested { reply }),
         }
     }
 
-    /// Finalizes the active segment after capture and decode have been drained.
+    /// Finalizes the active segment and dungeon run after capture and decode
+    /// have been drained.
     /// The outer runtime owns the subsequent history-writer fence and join.
     pub fn shutdown(&mut self) -> Result<(), String> {
         if self.shutdown_complete {
@@ -292,6 +358,7 @@ impl LiveCore {
         let meta = self.synthetic_meta(now, self.wall_at(now));
         let transitions = self.segments.shutdown_boundary(meta, &mut self.scheduler);
         self.apply_transition_batch(meta, transitions)?;
+        self.projections.shutdown(meta.captured_wall_ms)?;
         self.shutdown_complete = true;
         Ok(())
     }
@@ -311,8 +378,19 @@ impl LiveCore {
                     &mut self.active_dungeon_objective,
                 )
                 .then_some(SegmentReason::AutomaticObjective),
+                DomainEvent::SceneChanged { .. } => Some(SegmentReason::SceneChanged),
+                DomainEvent::DungeonFlowChanged { previous, current } => {
+                    (*current == DUNGEON_FLOW_PLAYING && *previous != Some(DUNGEON_FLOW_PLAYING))
+                        .then_some(SegmentReason::DungeonRunStarted)
+                }
+                DomainEvent::DeathOccurred { victim, .. } => self
+                    .entities
+                    .entity(victim.uuid)
+                    .is_some_and(|entity| entity.identity.is_boss_monster())
+                    .then_some(SegmentReason::BossDefeated),
                 _ => None,
             };
+            // The policy mode decides which of these actually end the segment.
             if let Some(reason) = reason {
                 self.segments
                     .arm_automatic_boundary(envelope.meta, reason, &mut self.scheduler);
@@ -351,6 +429,8 @@ impl LiveCore {
         self.live_publish_interval_ms = config.live.event_update_rate_ms;
         self.segments
             .set_training_window_ms(config.live.training_window_ms);
+        self.segments
+            .set_policy(config.live.segment_policy.clone(), &mut self.scheduler);
         self.projections.apply_config(
             Arc::clone(&config),
             &self.entities,
@@ -359,33 +439,23 @@ impl LiveCore {
         )?;
         self.entities
             .set_watched_skill_ids(watched_skill_ids(&config));
-        // Playback settings are a service concern, not projection state: sync
-        // them here so `VoiceProjection` stays free of the Tauri handle.
-        if let Some(service) = self.app.try_state::<crate::voice::VoiceService>() {
-            service.apply_runtime_settings(
-                config.voice.enabled,
-                config.voice.volume,
-                config.voice.queue_policy,
-            );
-        }
+        self.sink.apply_voice_settings(&config.voice);
         self.flush_voice_cues();
         self.request_publications(now, true);
         Ok(())
     }
 
-    /// Hands every cue matched by [`VoiceProjection`] to the playback service.
+    /// Hands every cue matched by [`VoiceProjection`] to the sink.
     /// Called at each batch boundary so cues reach the player in the same
     /// order the domain events produced them.
     fn flush_voice_cues(&mut self) {
         let cues = self.projections.take_voice_cues();
-        if cues.is_empty() {
+        // Replayed sessions must not speak over the live game.
+        if cues.is_empty() || matches!(self.clock_mode, ClockMode::Recorded { .. }) {
             return;
         }
-        let Some(service) = self.app.try_state::<crate::voice::VoiceService>() else {
-            return;
-        };
         for cue in cues {
-            service.enqueue_cue(cue);
+            self.sink.enqueue_voice_cue(cue);
         }
     }
 
@@ -432,6 +502,8 @@ impl LiveCore {
             self.entities.reset_combat_lifecycles();
         }
         self.update_game_timer_schedule(envelope);
+        #[cfg(test)]
+        crate::live::scenario::observe_domain_event(envelope);
         self.projections
             .apply(envelope, &self.entities, &mut self.scheduler)
     }
@@ -597,6 +669,9 @@ impl LiveCore {
     }
 
     fn refresh_clock(&mut self) -> MonoTimeMs {
+        if matches!(self.clock_mode, ClockMode::Recorded { .. }) {
+            return self.clock_anchor.mono;
+        }
         let mono = monotonic_now_ms();
         self.clock_anchor = ClockAnchor {
             mono,
@@ -1,27 +1,34 @@
-//! Lifecycle owner for the single live-domain pipeline.
+//! Lifecycle owner for the live-domain pipelines, one per captured game client.
 
 use std::future::pending;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 
-use log::{debug, error, info, warn};
-use tauri::{AppHandle, Emitter, Manager};
+use log::{error, info, warn};
+use tauri::{AppHandle, Manager};
 use tokio::sync::{mpsc, oneshot};
 
 use crate::live::bootstrap_snapshot::{MonitorRuntimeSnapshot, load_monitor_runtime_snapshot};
 use crate::live::history_writer::HistoryWriterHandle;
-use crate::live::ipc::models::LiveScenePayload;
 use crate::live::ipc::publisher::LivePublicationCache;
-use crate::live::ipc::topic::Topic;
-use crate::live::live_core::{LiveCore, LiveCoreFlow, Publications};
+use crate::live::live_core::LiveCoreFlow;
+use crate::live::protocol::discovery::ProtocolDiscovery;
+use crate::live::protocol::schema_drift::SchemaDriftLog;
 use crate::live::runtime::events::{MonoTimeMs, monotonic_now_ms};
 use crate::live::runtime_handle::RuntimeCommand;
+use crate::live::sessions::LiveSessions;
+use crate::live::sink::{LiveSink, TauriSink};
+use crate::live::user_scripts::{read_user_scripts, user_scripts_dir};
 use crate::packets;
 use crate::packets::packet_capture::CaptureMethod;
 use crate::packets::packet_process::CAPTURE_PIPELINE_FENCE;
+use crate::packets::remote_ingest::SharedKey;
+use crate::packets::session_recorder::SessionRecorderConfig;
 
 const DECODE_CHANNEL_CAPACITY: usize = 4_096;
+const SESSION_RECORDING_DIR: &str = "sessions";
 
 /// Runs the only owner of live domain state.
 pub async fn start(
@@ -30,13 +37,16 @@ pub async fn start(
     publication_cache: LivePublicationCache,
     history_writer: HistoryWriterHandle,
     history_join: std::thread::JoinHandle<()>,
+    discovery: ProtocolDiscovery,
+    schema_drift: SchemaDriftLog,
 ) {
     let initial_config = load_monitor_runtime_snapshot(&app).unwrap_or_else(|| {
         info!(target: "app::live", "monitor runtime snapshot missing; using defaults");
         MonitorRuntimeSnapshot::default()
     });
-    let mut core = match LiveCore::new(app.clone(), history_writer.clone(), initial_config) {
-        Ok(core) => core,
+    let sink: Arc<dyn LiveSink> = Arc::new(TauriSink::new(app.clone(), publication_cache));
+    let mut sessions = match LiveSessions::new(sink, history_writer.clone(), initial_config) {
+        Ok(sessions) => sessions,
         Err(error) => {
             error!(target: "app::live", "live_core_start_failed error={error}");
             let _ = history_writer.shutdown();
@@ -45,30 +55,48 @@ pub async fn start(
         }
     };
 
-    let capture = packets::packet_capture::start_capture(get_capture_method(&app));
+    match user_scripts_dir(&app) {
+        Ok(dir) => {
+            let (scripts, _) = read_user_scripts(&dir);
+            if !scripts.is_empty() {
+                info!(target: "app::live", "user_scripts_loaded count={}", scripts.len());
+                if let Err(error) =
+                    sessions.handle_command(RuntimeCommand::ApplyUserScripts(scripts))
+                {
+                    warn!(target: "app::live", "user_scripts_apply_failed error={error}");
+                }
+            }
+        }
+        Err(error) => warn!(target: "app::live", "user_scripts_dir_unavailable error={error}"),
+    }
+
+    let capture_config = get_capture_config(&app);
+    let recording = session_recorder_config(&app, capture_config.recording);
+    let capture = packets::packet_capture::start_capture(capture_config.method, recording);
     let (capture_receiver, capture_worker, outstanding) = capture.into_parts();
     let (batch_sender, mut batches) = mpsc::channel(DECODE_CHANNEL_CAPACITY);
-    let decoder_worker =
-        packets::decode_worker::spawn_decode_worker(capture_receiver, batch_sender);
+    let decoder_worker = packets::decode_worker::spawn_decode_worker(
+        capture_receiver,
+        batch_sender,
+        discovery,
+        schema_drift,
+    );
 
     let mut batches_open = true;
     let mut pending_command: Option<RuntimeCommand> = None;
     let mut shutdown_reply: Option<oneshot::Sender<Result<(), String>>> = None;
     let mut failure: Option<String> = None;
 
-    match core.publish_now() {
-        Ok(publications) => publish_publications(&app, &publication_cache, publications),
-        Err(error) => {
-            warn!(target: "app::live", "initial_snapshot_publish_failed error={error}");
-        }
+    if let Err(error) = sessions.publish_snapshot() {
+        warn!(target: "app::live", "initial_snapshot_publish_failed error={error}");
     }
 
     loop {
         if pending_command.is_some() && outstanding_count(&outstanding) == 0 {
             let command = pending_command.take().expect("checked above");
-            let result = core.handle_command(command).map(|flow| match flow {
+            let result = sessions.handle_command(command).map(|flow| match flow {
                 LiveCoreFlow::Continue => {
-                    publish_due(&app, &publication_cache, &mut core, monotonic_now_ms());
+                    publish_due(&mut sessions, monotonic_now_ms());
                     flow
                 }
                 LiveCoreFlow::ShutdownRequested { .. } => flow,
@@ -89,7 +117,7 @@ pub async fn start(
             }
         }
 
-        let wakeup = core.next_wakeup();
+        let wakeup = sessions.next_wakeup();
         tokio::select! {
             biased;
 
@@ -105,13 +133,13 @@ pub async fn start(
                 match batch {
                     Some(batch) => {
                         let batch_time = batch.meta.mono_ms();
-                        let result = core.process_batch(batch);
+                        let result = sessions.process_batch(batch);
                         decrement_outstanding(&outstanding);
                         if let Err(error) = result {
                             failure = Some(error);
                             break;
                         }
-                        publish_due(&app, &publication_cache, &mut core, batch_time);
+                        publish_due(&mut sessions, batch_time);
                     }
                     None => {
                         batches_open = false;
@@ -125,7 +153,7 @@ pub async fn start(
                 }
                 let now = monotonic_now_ms();
                 outstanding.store(0, Ordering::Release);
-                publish_due(&app, &publication_cache, &mut core, now);
+                publish_due(&mut sessions, now);
             }
         }
     }
@@ -139,10 +167,10 @@ pub async fn start(
     while let Some(batch) = batches.recv().await {
         if failure.is_none() {
             let batch_time = batch.meta.mono_ms();
-            if let Err(error) = core.process_batch(batch) {
+            if let Err(error) = sessions.process_batch(batch) {
                 record_failure(&mut failure, error);
             } else {
-                publish_due(&app, &publication_cache, &mut core, batch_time);
+                publish_due(&mut sessions, batch_time);
             }
         }
         decrement_outstanding(&outstanding);
@@ -151,13 +179,11 @@ pub async fn start(
         record_failure(&mut failure, "protocol decoder worker panicked");
     }
 
-    if let Err(error) = core.shutdown() {
+    if let Err(error) = sessions
+        .shutdown()
+        .and_then(|()| sessions.publish_snapshot())
+    {
         record_failure(&mut failure, error);
-    } else {
-        match core.publish_now() {
-            Ok(publications) => publish_publications(&app, &publication_cache, publications),
-            Err(error) => record_failure(&mut failure, error),
-        }
     }
     if let Err(error) = history_writer.shutdown() {
         record_failure(&mut failure, error);
@@ -176,50 +202,9 @@ pub async fn start(
     }
 }
 
-fn publish_due(
-    app: &AppHandle,
-    publication_cache: &LivePublicationCache,
-    core: &mut LiveCore,
-    now: MonoTimeMs,
-) {
-    let Ok(publications) = core.take_due_publications(now) else {
-        return;
-    };
-    publish_publications(app, publication_cache, publications);
-}
-
-fn publish_publications(
-    app: &AppHandle,
-    publication_cache: &LivePublicationCache,
-    publications: Publications,
-) {
-    if let Some(scene) = publication_cache.publish(publications.topics) {
-        emit_scene(app, &scene);
-    }
-}
-
-/// Scene is the only live topic that remains event-driven. It is tiny and must
-/// continue reaching `main` while that window is hidden so the toolbox can
-/// control the overlay auto-hide policy.
-fn emit_scene(app: &AppHandle, payload: &LiveScenePayload) {
-    let topic = Topic::Scene;
-    let event = topic
-        .event_name()
-        .expect("scene is the only event-backed live topic");
-    for label in topic.window_labels() {
-        let Some(window) = app.get_webview_window(label) else {
-            continue;
-        };
-        if let Err(error) = window.emit(event, payload) {
-            let message = error.to_string();
-            // 0x8007139F: webview minimized / hidden / mid-transition.
-            if message.contains("0x8007139F") || message.contains("not in the correct state") {
-                debug!(target: "app::live", "emit_skipped_webview_busy event={event} label={label}");
-            } else {
-                warn!(target: "app::live", "emit_failed event={event} label={label} error={message}");
-            }
-        }
-    }
+/// A failed publication is dropped; the next one carries the same state.
+fn publish_due(sessions: &mut LiveSessions, now: MonoTimeMs) {
+    let _ = sessions.publish_due(now);
 }
 
 async fn wait_for_wakeup(deadline: Option<MonoTimeMs>) {
@@ -270,7 +255,40 @@ fn record_failure(failure: &mut Option<String>, error: impl Into<String>) {
     }
 }
 
-fn get_capture_method(app: &AppHandle) -> CaptureMethod {
+/// Packet capture settings persisted by the network settings page.
+#[derive(Debug)]
+struct CaptureConfig {
+    method: CaptureMethod,
+    recording: Option<SessionRecordingSettings>,
+}
+
+/// Opt-in session recording limits (`sessionRecording` in the capture config).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SessionRecordingSettings {
+    max_file_bytes: u64,
+    max_files: usize,
+}
+
+fn session_recorder_config(
+    app: &AppHandle,
+    settings: Option<SessionRecordingSettings>,
+) -> Option<SessionRecorderConfig> {
+    let settings = settings?;
+    let directory = match app.path().app_data_dir() {
+        Ok(dir) => dir.join(SESSION_RECORDING_DIR),
+        Err(error) => {
+            warn!(target: "app::capture", "session_recording_dir_unavailable error={error}");
+            return None;
+        }
+    };
+    Some(SessionRecorderConfig {
+        directory,
+        max_file_bytes: settings.max_file_bytes,
+        max_files: settings.max_files,
+    })
+}
+
+fn get_capture_config(app: &AppHandle) -> CaptureConfig {
     let filename_candidates = ["packetCapture.json", "packetCapture.bin", "packetCapture"];
     let mut dir_candidates = Vec::new();
     if let Ok(dir) = app.path().app_data_dir() {
@@ -285,8 +303,8 @@ fn get_capture_method(app: &AppHandle) -> CaptureMethod {
     for dir in dir_candidates {
         for file_name in filename_candidates {
             let path = dir.join(file_name);
-            if let Some(method) = read_capture_method(&path) {
-                return method;
+            if let Some(config) = read_capture_config(&path) {
+                return config;
             }
         }
         if let Ok(entries) = std::fs::read_dir(&dir) {
@@ -296,18 +314,25 @@ fn get_capture_method(app: &AppHandle) -> CaptureMethod {
                     .file_name()
                     .and_then(|name| name.to_str())
                     .is_some_and(|name| name.starts_with("packetCapture"));
-                if is_candidate && let Some(method) = read_capture_method(&path) {
-                    return method;
+                if is_candidate && let Some(config) = read_capture_config(&path) {
+                    return config;
                 }
             }
         }
     }
 
-    warn!(target: "app::capture", "packet capture config missing; using WinDivert");
-    CaptureMethod::WinDivert
+    let (method, source) = default_capture_method();
+    warn!(
+        target: "app::capture",
+        "packet capture config missing; using method={method:?} source={source}"
+    );
+    CaptureConfig {
+        method,
+        recording: None,
+    }
 }
 
-fn read_capture_method(path: &Path) -> Option<CaptureMethod> {
+fn read_capture_config(path: &Path) -> Option<CaptureConfig> {
     if !path.exists() {
         return None;
     }
@@ -325,34 +350,107 @@ fn read_capture_method(path: &Path) -> Option<CaptureMethod> {
             return None;
         }
     };
-    Some(capture_method_from_json(&json, path))
+    Some(capture_config_from_json(&json, path))
 }
 
-fn capture_method_from_json(json: &serde_json::Value, path: &Path) -> CaptureMethod {
+fn capture_config_from_json(json: &serde_json::Value, path: &Path) -> CaptureConfig {
     let method = json.get("method").and_then(serde_json::Value::as_str);
     let device = json
         .get("npcapDevice")
         .and_then(serde_json::Value::as_str)
         .unwrap_or_default();
-    let (capture_method, source) = resolve_capture_method(method, device);
+    let pcap_file = json
+        .get("pcapFile")
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or_default();
+    let (capture_method, source) = if method == Some("Remote") {
+        (remote_capture_from_json(json), "explicit")
+    } else {
+        resolve_capture_method(method, device, pcap_file)
+    };
     info!(
         target: "app::capture",
-        "capture_config_loaded path={} method={} device={} source={}",
+        "capture_config_loaded path={} method={} device={} pcap_file={} source={}",
         path.display(),
         method.unwrap_or("<missing>"),
         device,
+        pcap_file,
         source
     );
-    capture_method
+    CaptureConfig {
+        method: capture_method,
+        recording: session_recording_from_json(json),
+    }
 }
 
-fn resolve_capture_method(method: Option<&str>, device: &str) -> (CaptureMethod, &'static str) {
+/// Address the meter listens on for remote capture agents by default. Only
+/// local agents reach it; a LAN address has to be set in `remoteListen`.
+const DEFAULT_REMOTE_LISTEN: &str = "127.0.0.1:47100";
+
+/// The shared key is required; an empty one is reported when capture starts
+/// rather than silently falling back to local capture.
+fn remote_capture_from_json(json: &serde_json::Value) -> CaptureMethod {
+    let listen = json
+        .get("remoteListen")
+        .and_then(serde_json::Value::as_str)
+        .map(str::trim)
+        .filter(|listen| !listen.is_empty())
+        .unwrap_or(DEFAULT_REMOTE_LISTEN);
+    let key = json
+        .get("remoteKey")
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or_default();
+    CaptureMethod::Remote {
+        listen: listen.to_string(),
+        key: SharedKey::new(key),
+    }
+}
+
+fn session_recording_from_json(json: &serde_json::Value) -> Option<SessionRecordingSettings> {
+    let recording = json.get("sessionRecording")?;
+    if !recording
+        .get("enabled")
+        .and_then(serde_json::Value::as_bool)
+        .unwrap_or(false)
+    {
+        return None;
+    }
+    let max_file_bytes = recording
+        .get("maxFileMb")
+        .and_then(serde_json::Value::as_u64)
+        .filter(|mb| *mb > 0)
+        .map_or(SessionRecorderConfig::DEFAULT_MAX_FILE_BYTES, |mb| {
+            mb.saturating_mul(1024 * 1024)
+        });
+    let max_files = recording
+        .get("maxFiles")
+        .and_then(serde_json::Value::as_u64)
+        .and_then(|count| usize::try_from(count).ok())
+        .filter(|count| *count > 0)
+        .unwrap_or(SessionRecorderConfig::DEFAULT_MAX_FILES);
+    Some(SessionRecordingSettings {
+        max_file_bytes,
+        max_files,
+    })
+}
+
+fn resolve_capture_method(
+    method: Option<&str>,
+    device: &str,
+    pcap_file: &str,
+) -> (CaptureMethod, &'static str) {
     match method {
         Some("WinDivert") => (CaptureMethod::WinDivert, "explicit"),
         Some("Npcap") => (CaptureMethod::Npcap(device.to_string()), "explicit"),
-        Some(_) | None if device.trim().is_empty() => {
-            (CaptureMethod::WinDivert, "default_windivert")
-        }
+        Some("PcapFile") if !pcap_file.trim().is_empty() => (
+            CaptureMethod::PcapFile(PathBuf::from(pcap_file.trim())),
+            "explicit",
+        ),
+        Some("AfPacket") => (
+            CaptureMethod::AfPacket(device.trim().to_string()),
+            "explicit",
+        ),
+        Some(_) | None if device.trim().is_empty() => default_capture_method(),
         Some(_) | None => (
             CaptureMethod::Npcap(device.to_string()),
             "legacy_npcap_device",
@@ -360,27 +458,52 @@ fn resolve_capture_method(method: Option<&str>, device: &str) -> (CaptureMethod,
     }
 }
 
+#[cfg(target_os = "linux")]
+fn default_capture_method() -> (CaptureMethod, &'static str) {
+    (CaptureMethod::AfPacket(String::new()), "default_af_packet")
+}
+
+#[cfg(not(target_os = "linux"))]
+fn default_capture_method() -> (CaptureMethod, &'static str) {
+    (CaptureMethod::WinDivert, "default_windivert")
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        claim_deadline_fence, close_capture_gate, decrement_outstanding, outstanding_count,
-        resolve_capture_method,
+        SessionRecordingSettings, claim_deadline_fence, close_capture_gate, decrement_outstanding,
+        outstanding_count, remote_capture_from_json, resolve_capture_method,
+        session_recording_from_json,
     };
     use crate::packets::packet_capture::CaptureMethod;
     use crate::packets::packet_process::CAPTURE_PIPELINE_FENCE;
+    use crate::packets::remote_ingest::SharedKey;
+    use crate::packets::session_recorder::SessionRecorderConfig;
     use std::sync::atomic::{AtomicUsize, Ordering};
 
     fn assert_npcap(method: Option<&str>, device: &str) {
-        match resolve_capture_method(method, device).0 {
+        match resolve_capture_method(method, device, "").0 {
             CaptureMethod::Npcap(actual) => assert_eq!(actual, device),
-            CaptureMethod::WinDivert => panic!("expected Npcap"),
+            other => panic!("expected Npcap, got {other:?}"),
         }
     }
 
     fn assert_windivert(method: Option<&str>, device: &str) {
-        match resolve_capture_method(method, device).0 {
+        match resolve_capture_method(method, device, "").0 {
             CaptureMethod::WinDivert => {}
-            CaptureMethod::Npcap(actual) => panic!("expected WinDivert, got Npcap({actual})"),
+            other => panic!("expected WinDivert, got {other:?}"),
+        }
+    }
+
+    /// WinDivert everywhere but Linux, where the native AF_PACKET source on
+    /// all interfaces is the only default that can work.
+    fn assert_platform_default(method: Option<&str>, device: &str) {
+        match resolve_capture_method(method, device, "").0 {
+            #[cfg(target_os = "linux")]
+            CaptureMethod::AfPacket(actual) => assert_eq!(actual, ""),
+            #[cfg(not(target_os = "linux"))]
+            CaptureMethod::WinDivert => {}
+            other => panic!("expected the platform default, got {other:?}"),
         }
     }
 
@@ -400,15 +523,83 @@ mod tests {
     }
 
     #[test]
-    fn empty_or_missing_legacy_config_defaults_to_windivert() {
-        assert_windivert(None, "");
-        assert_windivert(None, "   ");
+    fn empty_or_missing_legacy_config_defaults_to_platform_source() {
+        assert_platform_default(None, "");
+        assert_platform_default(None, "   ");
     }
 
     #[test]
     fn unknown_method_falls_back_by_device_presence() {
         assert_npcap(Some("Other"), "npcap-device");
-        assert_windivert(Some("Other"), "");
+        assert_platform_default(Some("Other"), "");
+    }
+
+    #[test]
+    fn explicit_af_packet_keeps_the_interface() {
+        match resolve_capture_method(Some("AfPacket"), " eth0 ", "").0 {
+            CaptureMethod::AfPacket(device) => assert_eq!(device, "eth0"),
+            other => panic!("expected AfPacket, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn pcap_file_requires_a_path() {
+        match resolve_capture_method(Some("PcapFile"), "", " raid.pcapng ").0 {
+            CaptureMethod::PcapFile(path) => assert_eq!(path.to_str(), Some("raid.pcapng")),
+            other => panic!("expected PcapFile, got {other:?}"),
+        }
+        assert_platform_default(Some("PcapFile"), "");
+        assert_npcap(Some("PcapFile"), "npcap-device");
+    }
+
+    #[test]
+    fn remote_capture_reads_listen_address_and_key() {
+        match remote_capture_from_json(&serde_json::json!({
+            "remoteListen": " 192.168.1.20:5000 ",
+            "remoteKey": "hunter2",
+        })) {
+            CaptureMethod::Remote { listen, key } => {
+                assert_eq!(listen, "192.168.1.20:5000");
+                assert_eq!(key, SharedKey::new("hunter2"));
+            }
+            other => panic!("expected Remote, got {other:?}"),
+        }
+        match remote_capture_from_json(&serde_json::json!({})) {
+            CaptureMethod::Remote { listen, key } => {
+                assert_eq!(listen, "127.0.0.1:47100");
+                assert!(key.is_empty());
+            }
+            other => panic!("expected Remote, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn session_recording_is_opt_in_with_defaults() {
+        assert_eq!(session_recording_from_json(&serde_json::json!({})), None);
+        assert_eq!(
+            session_recording_from_json(&serde_json::json!({
+                "sessionRecording": { "enabled": false, "maxFiles": 3 }
+            })),
+            None
+        );
+        assert_eq!(
+            session_recording_from_json(&serde_json::json!({
+                "sessionRecording": { "enabled": true, "maxFileMb": 8, "maxFiles": 3 }
+            })),
+            Some(SessionRecordingSettings {
+                max_file_bytes: 8 * 1024 * 1024,
+                max_files: 3,
+            })
+        );
+        assert_eq!(
+            session_recording_from_json(&serde_json::json!({
+                "sessionRecording": { "enabled": true, "maxFileMb": 0 }
+            })),
+            Some(SessionRecordingSettings {
+                max_file_bytes: SessionRecorderConfig::DEFAULT_MAX_FILE_BYTES,
+                max_files: SessionRecorderConfig::DEFAULT_MAX_FILES,
+            })
+        );
     }
 
     #[test]
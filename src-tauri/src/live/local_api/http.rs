@@ -0,0 +1,167 @@
+//! Reading one request head per connection and writing short responses.
+//! Parsing is `httparse`, the types are `http`'s; bodies are never read and
+//! every plain response ends with `Connection: close`.
+
+use std::io::{ErrorKind, Read, Write};
+
+use tungstenite::http::header::{
+    ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL, CONNECTION, CONTENT_LENGTH, CONTENT_TYPE,
+};
+use tungstenite::http::{self, StatusCode};
+
+pub(super) type Request = http::Request<()>;
+
+/// Request line plus headers; anything larger is not a real overlay client.
+const MAX_HEAD_BYTES: usize = 8 * 1024;
+const MAX_HEADERS: usize = 32;
+const READ_CHUNK_BYTES: usize = 1024;
+
+/// A request head and whatever arrived after it, which belongs to the
+/// WebSocket if the request is an upgrade.
+pub(super) struct Incoming {
+    pub request: Request,
+    pub tail: Vec<u8>,
+}
+
+pub(super) fn read_request(stream: &mut impl Read) -> Result<Incoming, String> {
+    let mut buffer = Vec::with_capacity(READ_CHUNK_BYTES);
+    let mut chunk = [0u8; READ_CHUNK_BYTES];
+    loop {
+        if let Some((length, request)) = parse_head(&buffer)? {
+            return Ok(Incoming {
+                request,
+                tail: buffer.split_off(length),
+            });
+        }
+        if buffer.len() >= MAX_HEAD_BYTES {
+            return Err(format!("request head exceeds {MAX_HEAD_BYTES} bytes"));
+        }
+        let read = match stream.read(&mut chunk) {
+            Ok(0) => return Err("connection closed before the request head".to_string()),
+            Ok(read) => read,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(format!("read request: {error}")),
+        };
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+}
+
+/// `None` until the blank line ending the head has arrived.
+fn parse_head(buffer: &[u8]) -> Result<Option<(usize, Request)>, String> {
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    let mut head = httparse::Request::new(&mut headers);
+    let length = match head.parse(buffer) {
+        Ok(httparse::Status::Complete(length)) => length,
+        Ok(httparse::Status::Partial) => return Ok(None),
+        Err(error) => return Err(format!("malformed request: {error}")),
+    };
+    let mut request = http::Request::builder()
+        .method(head.method.unwrap_or_default())
+        .uri(head.path.unwrap_or_default())
+        .version(match head.version {
+            Some(0) => http::Version::HTTP_10,
+            _ => http::Version::HTTP_11,
+        });
+    for header in head.headers.iter() {
+        request = request.header(header.name, header.value);
+    }
+    let request = request
+        .body(())
+        .map_err(|error| format!("malformed request: {error}"))?;
+    Ok(Some((length, request)))
+}
+
+pub(super) fn header<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+    request
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+}
+
+/// The first decoded `?name=value` for `name`.
+pub(super) fn query(request: &Request, name: &str) -> Option<String> {
+    form_urlencoded::parse(request.uri().query()?.as_bytes())
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+}
+
+/// Writes a complete response. CORS allows any origin because routing has
+/// already refused browser origins outside the allowlist; only the app's
+/// own webview and the configured origins ever read a body.
+pub(super) fn write_response(
+    stream: &mut impl Write,
+    status: StatusCode,
+    extra_headers: &[(&str, &str)],
+    body: Option<&[u8]>,
+) -> std::io::Result<()> {
+    let mut response = http::Response::builder()
+        .status(status)
+        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .header(CACHE_CONTROL, "no-store")
+        .header(CONNECTION, "close");
+    for (name, value) in extra_headers {
+        response = response.header(*name, *value);
+    }
+    if let Some(body) = body {
+        response = response
+            .header(CONTENT_TYPE, "application/json; charset=utf-8")
+            .header(CONTENT_LENGTH, body.len());
+    } else if !matches!(status, StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED) {
+        response = response.header(CONTENT_LENGTH, 0);
+    }
+    let response = response.body(()).map_err(std::io::Error::other)?;
+    tungstenite::handshake::server::write_response(&mut *stream, &response)
+        .map_err(std::io::Error::other)?;
+    if let Some(body) = body {
+        stream.write_all(body)?;
+    }
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_path_query_and_headers() {
+        let mut raw: &[u8] = b"GET /api/topics/combat?since=41&token=a%2Bb+c HTTP/1.1\r\n\
+            Host: 127.0.0.1:47110\r\n\
+            Connection: keep-alive, Upgrade\r\n\r\n\x81\x80";
+        let Incoming { request, tail } = read_request(&mut raw).expect("valid request");
+        assert_eq!(request.method(), http::Method::GET);
+        assert_eq!(request.uri().path(), "/api/topics/combat");
+        assert_eq!(query(&request, "since").as_deref(), Some("41"));
+        assert_eq!(query(&request, "token").as_deref(), Some("a+b c"));
+        assert_eq!(query(&request, "epoch"), None);
+        assert_eq!(header(&request, "host"), Some("127.0.0.1:47110"));
+        assert_eq!(tail, b"\x81\x80");
+    }
+
+    #[test]
+    fn rejects_malformed_and_oversized_heads() {
+        let mut truncated: &[u8] = b"GET / HTTP/1.1\r\nHost: x\r\n";
+        assert!(read_request(&mut truncated).is_err());
+        let mut bad_line: &[u8] = b"GET /\r\n\r\n";
+        assert!(read_request(&mut bad_line).is_err());
+
+        let mut oversized = b"GET / HTTP/1.1\r\nX-Pad: ".to_vec();
+        oversized.resize(MAX_HEAD_BYTES + 16, b'a');
+        assert!(read_request(&mut oversized.as_slice()).is_err());
+    }
+
+    #[test]
+    fn writes_json_and_empty_responses() {
+        let mut output = Vec::new();
+        write_response(&mut output, StatusCode::OK, &[], Some(b"{}")).expect("write");
+        let text = String::from_utf8(output).expect("utf8");
+        assert!(text.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(text.contains("content-length: 2\r\n"));
+        assert!(text.ends_with("\r\n\r\n{}"));
+
+        let mut output = Vec::new();
+        write_response(&mut output, StatusCode::NOT_MODIFIED, &[], None).expect("write");
+        let text = String::from_utf8(output).expect("utf8");
+        assert!(text.starts_with("HTTP/1.1 304 Not Modified\r\n"));
+        assert!(!text.contains("content-length"));
+    }
+}
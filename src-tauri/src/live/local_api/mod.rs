@@ -0,0 +1,544 @@
+//! Local HTTP and WebSocket access to the live publication topics, for OBS
+//! browser sources, Stream Deck plugins and similar tools on the same machine.
+//!
+//! ```text
+//! GET /api/topics                         {"epoch", "topics": [{"topic", "revision"}]}
+//! GET /api/topics/{topic}[?since=&epoch=] {"topic", "epoch", "revision", "payload"}, or 304
+//! GET /api/ws[?topics=combat,status]      WebSocket:
+//!   client -> {"subscribe": ["buffs"]} | {"unsubscribe": ["status"]}
+//!   server -> {"type": "topic", "topic", "epoch", "revision", "payload"}
+//!           | {"type": "reset", "epoch"} | {"type": "error", "message"}
+//! ```
+//!
+//! Topics are read from the same [`LivePublicationCache`] the windows pull
+//! from, so a payload is only ever serialized for clients that asked for it.
+//! Revisions restart when the epoch changes. The listener binds to loopback
+//! and checks the `Host` header unless LAN access is enabled, which requires
+//! a token (`Authorization: Bearer <token>` or `?token=`). Browser pages are
+//! refused unless their `Origin` is the app itself or explicitly allowed.
+
+mod http;
+mod routes;
+mod settings;
+
+use std::collections::HashMap;
+use std::io::{ErrorKind, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tungstenite::handshake::server::Response as Handshake;
+use tungstenite::http::StatusCode;
+use tungstenite::protocol::{Role, WebSocketConfig};
+use tungstenite::{Error as WsError, Message, WebSocket};
+
+use self::routes::{Access, Route, TopicBody, parse_topics, route};
+use crate::live::ipc::publisher::LivePublicationCache;
+use crate::live::ipc::topic::{Topic, TopicMask};
+
+pub use self::settings::LocalApiSettings;
+pub(crate) use self::settings::{load_local_api_settings, save_local_api_settings};
+
+/// Concurrent connections; further clients get a 503 until one leaves.
+const MAX_CLIENTS: usize = 16;
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Upper bound on how long a session takes to notice shutdown or a
+/// subscription change while the cache is quiet.
+const SESSION_POLL: Duration = Duration::from_millis(250);
+/// How long a session waits for client messages before pushing updates.
+const READ_POLL: Duration = Duration::from_millis(10);
+/// Clients only send subscription changes; anything larger ends the session.
+const MAX_CLIENT_MESSAGE_BYTES: usize = 4 * 1024;
+
+/// Owns the listener; `apply` replaces it whenever the settings change.
+#[derive(Clone)]
+pub struct LocalApiServer {
+    cache: LivePublicationCache,
+    running: Arc<Mutex<Option<RunningApi>>>,
+}
+
+struct RunningApi {
+    settings: LocalApiSettings,
+    address: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    acceptor: JoinHandle<()>,
+}
+
+impl LocalApiServer {
+    pub fn new(cache: LivePublicationCache) -> Self {
+        Self {
+            cache,
+            running: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Stops the current listener unless the settings are unchanged and, when
+    /// enabled, binds a new one. Returns the address it listens on.
+    pub fn apply(&self, settings: &LocalApiSettings) -> Result<Option<SocketAddr>, String> {
+        let mut running = self.running.lock();
+        if let Some(current) = running.as_ref()
+            && current.settings == *settings
+        {
+            return Ok(Some(current.address));
+        }
+        if let Some(current) = running.take() {
+            current.stop();
+        }
+        if !settings.enabled {
+            return Ok(None);
+        }
+        let address = settings.bind_address();
+        let listener = TcpListener::bind(address)
+            .map_err(|error| format!("failed to listen on {address}: {error}"))?;
+        let started = RunningApi::start(self.cache.clone(), listener, settings.clone())?;
+        let address = started.address;
+        *running = Some(started);
+        Ok(Some(address))
+    }
+
+    #[must_use]
+    pub fn listen_address(&self) -> Option<SocketAddr> {
+        self.running.lock().as_ref().map(|running| running.address)
+    }
+}
+
+impl RunningApi {
+    fn start(
+        cache: LivePublicationCache,
+        listener: TcpListener,
+        settings: LocalApiSettings,
+    ) -> Result<Self, String> {
+        listener
+            .set_nonblocking(true)
+            .map_err(|error| format!("failed to configure local API listener: {error}"))?;
+        let address = listener
+            .local_addr()
+            .map_err(|error| format!("failed to read local API address: {error}"))?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let access = Arc::new(Access {
+            allow_lan: settings.allow_lan,
+            token: settings.token.clone(),
+            allowed_origins: settings.allowed_origins.clone(),
+        });
+        let acceptor = {
+            let shutdown = Arc::clone(&shutdown);
+            std::thread::Builder::new()
+                .name("local-api".to_string())
+                .spawn(move || accept_loop(listener, cache, access, shutdown))
+                .map_err(|error| format!("failed to spawn local API listener: {error}"))?
+        };
+        info!(
+            target: "app::live",
+            "local_api_listening address={address} allow_lan={} token={}",
+            settings.allow_lan,
+            settings.token.is_some()
+        );
+        Ok(Self {
+            settings,
+            address,
+            shutdown,
+            acceptor,
+        })
+    }
+
+    /// Open WebSocket sessions notice the flag within [`SESSION_POLL`] and
+    /// close on their own.
+    fn stop(self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if self.acceptor.join().is_err() {
+            warn!(target: "app::live", "local_api_listener_panicked address={}", self.address);
+        }
+        info!(target: "app::live", "local_api_stopped address={}", self.address);
+    }
+}
+
+fn accept_loop(
+    listener: TcpListener,
+    cache: LivePublicationCache,
+    access: Arc<Access>,
+    shutdown: Arc<AtomicBool>,
+) {
+    let clients = Arc::new(AtomicUsize::new(0));
+    while !shutdown.load(Ordering::Relaxed) {
+        let (stream, peer) = match listener.accept() {
+            Ok(accepted) => accepted,
+            Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                std::thread::sleep(ACCEPT_POLL);
+                continue;
+            }
+            Err(error) => {
+                warn!(target: "app::live", "local_api_accept_failed error={error}");
+                std::thread::sleep(ACCEPT_POLL);
+                continue;
+            }
+        };
+        let slot = ClientSlot::claim(&clients);
+        if slot.is_full() {
+            reject_busy(stream, peer);
+            continue;
+        }
+        let cache = cache.clone();
+        let access = Arc::clone(&access);
+        let shutdown = Arc::clone(&shutdown);
+        let spawned = std::thread::Builder::new()
+            .name("local-api-client".to_string())
+            .spawn(move || {
+                let _slot = slot;
+                serve_connection(stream, peer, &cache, &access, &shutdown);
+            });
+        if let Err(error) = spawned {
+            warn!(target: "app::live", "local_api_spawn_failed peer={peer} error={error}");
+        }
+    }
+}
+
+/// Counts a connection for [`MAX_CLIENTS`] until dropped; `None` when full.
+struct ClientSlot(Option<Arc<AtomicUsize>>);
+
+impl ClientSlot {
+    fn claim(clients: &Arc<AtomicUsize>) -> Self {
+        let claimed = clients
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < MAX_CLIENTS).then_some(count + 1)
+            })
+            .is_ok();
+        Self(claimed.then(|| Arc::clone(clients)))
+    }
+
+    fn is_full(&self) -> bool {
+        self.0.is_none()
+    }
+}
+
+impl Drop for ClientSlot {
+    fn drop(&mut self) {
+        if let Some(clients) = &self.0 {
+            clients.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+}
+
+fn reject_busy(mut stream: TcpStream, peer: SocketAddr) {
+    debug!(target: "app::live", "local_api_busy peer={peer} clients={MAX_CLIENTS}");
+    let rejected = stream
+        .set_nonblocking(false)
+        .and_then(|()| stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+        .and_then(|()| {
+            respond_error(
+                &mut stream,
+                StatusCode::SERVICE_UNAVAILABLE,
+                "too many clients",
+            )
+        });
+    if let Err(error) = rejected {
+        debug!(target: "app::live", "local_api_write_failed peer={peer} error={error}");
+    }
+}
+
+fn serve_connection(
+    mut stream: TcpStream,
+    peer: SocketAddr,
+    cache: &LivePublicationCache,
+    access: &Access,
+    shutdown: &AtomicBool,
+) {
+    // Accepted sockets inherit non-blocking mode on some platforms.
+    if let Err(error) = stream
+        .set_nonblocking(false)
+        .and_then(|()| stream.set_read_timeout(Some(REQUEST_TIMEOUT)))
+        .and_then(|()| stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+    {
+        debug!(target: "app::live", "local_api_socket_setup_failed peer={peer} error={error}");
+        return;
+    }
+    let http::Incoming { request, tail } = match http::read_request(&mut stream) {
+        Ok(incoming) => incoming,
+        Err(error) => {
+            debug!(target: "app::live", "local_api_bad_request peer={peer} error={error}");
+            let _ = respond_error(&mut stream, StatusCode::BAD_REQUEST, &error);
+            return;
+        }
+    };
+    let result = match route(&request, access, cache) {
+        Route::Respond {
+            status,
+            headers,
+            body,
+        } => http::write_response(&mut stream, status, &headers, body.as_deref()),
+        Route::Upgrade { handshake, topics } => {
+            run_session(stream, peer, &handshake, tail, topics, cache, shutdown);
+            return;
+        }
+    };
+    if let Err(error) = result {
+        debug!(target: "app::live", "local_api_write_failed peer={peer} error={error}");
+    }
+}
+
+fn respond_error(stream: &mut TcpStream, status: StatusCode, message: &str) -> std::io::Result<()> {
+    let body = serde_json::to_vec(&serde_json::json!({ "error": message }))
+        .map_err(std::io::Error::other)?;
+    http::write_response(stream, status, &[], Some(&body))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum ServerMessage {
+    Topic(TopicBody),
+    Reset { epoch: u64 },
+    Error { message: String },
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ClientMessage {
+    subscribe: Vec<String>,
+    unsubscribe: Vec<String>,
+}
+
+/// One WebSocket client's topics and the revisions it has already been sent.
+#[derive(Debug)]
+struct Subscription {
+    topics: TopicMask,
+    epoch: Option<u64>,
+    sent: HashMap<Topic, u64>,
+}
+
+impl Subscription {
+    fn new(topics: TopicMask) -> Self {
+        Self {
+            topics,
+            epoch: None,
+            sent: HashMap::new(),
+        }
+    }
+
+    /// Newly subscribed topics are sent with the next [`Self::pending`] even
+    /// when their revision has not moved.
+    fn apply(&mut self, text: &str) -> Result<(), String> {
+        let message = serde_json::from_str::<ClientMessage>(text)
+            .map_err(|error| format!("invalid message: {error}"))?;
+        let subscribe = parse_topics(message.subscribe.iter().map(String::as_str))?;
+        let unsubscribe = parse_topics(message.unsubscribe.iter().map(String::as_str))?;
+        for topic in subscribe.iter() {
+            self.sent.remove(&topic);
+        }
+        self.topics |= subscribe;
+        self.topics.remove(unsubscribe);
+        Ok(())
+    }
+
+    /// Messages bringing the client up to date: a reset when the epoch moved,
+    /// then every subscribed topic whose revision changed.
+    fn pending(&mut self, cache: &LivePublicationCache) -> Vec<ServerMessage> {
+        let mut messages = Vec::new();
+        let epoch = cache.epoch();
+        if self.epoch != Some(epoch) {
+            if self.epoch.is_some() {
+                messages.push(ServerMessage::Reset { epoch });
+            }
+            self.epoch = Some(epoch);
+            self.sent.clear();
+        }
+        for topic in self.topics.iter() {
+            let Some(published) = cache.published_topic(topic) else {
+                continue;
+            };
+            // A reset between the two reads; the next round sends it.
+            if published.epoch != epoch || self.sent.get(&topic) == Some(&published.revision) {
+                continue;
+            }
+            self.sent.insert(topic, published.revision);
+            messages.push(ServerMessage::Topic(TopicBody {
+                topic: topic.name(),
+                epoch,
+                revision: Some(published.revision),
+                payload: Some(published.payload),
+            }));
+        }
+        messages
+    }
+}
+
+fn run_session(
+    mut stream: TcpStream,
+    peer: SocketAddr,
+    handshake: &Handshake,
+    tail: Vec<u8>,
+    topics: TopicMask,
+    cache: &LivePublicationCache,
+    shutdown: &AtomicBool,
+) {
+    // Short read timeouts let one thread both drain client messages and push
+    // publications; writes keep the blocking WRITE_TIMEOUT.
+    let opened = write_handshake(&mut stream, handshake)
+        .and_then(|()| stream.set_read_timeout(Some(READ_POLL)));
+    if let Err(error) = opened {
+        debug!(target: "app::live", "local_api_handshake_failed peer={peer} error={error}");
+        return;
+    }
+    let config = WebSocketConfig::default()
+        .max_message_size(Some(MAX_CLIENT_MESSAGE_BYTES))
+        .max_frame_size(Some(MAX_CLIENT_MESSAGE_BYTES));
+    let mut socket = WebSocket::from_partially_read(stream, tail, Role::Server, Some(config));
+    debug!(target: "app::live", "local_api_session_opened peer={peer}");
+
+    let mut subscription = Subscription::new(topics);
+    let mut generation = cache.change_generation();
+    let result = loop {
+        if shutdown.load(Ordering::Relaxed) {
+            break socket.close(None);
+        }
+        let mut replies = Vec::new();
+        let closed = loop {
+            match socket.read() {
+                Ok(Message::Text(text)) => {
+                    if let Err(message) = subscription.apply(&text) {
+                        replies.push(ServerMessage::Error { message });
+                    }
+                }
+                // Pings are answered by the socket itself on the next write.
+                Ok(_) => {}
+                Err(WsError::Io(error))
+                    if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    break None;
+                }
+                Err(WsError::ConnectionClosed | WsError::AlreadyClosed) => break Some(Ok(())),
+                Err(error) => break Some(Err(error)),
+            }
+        };
+        if let Some(result) = closed {
+            break result;
+        }
+        replies.extend(subscription.pending(cache));
+        if let Err(error) = send_messages(&mut socket, &replies) {
+            break Err(error);
+        }
+        generation = cache.wait_for_change(generation, SESSION_POLL);
+    };
+    if let Err(error) = result {
+        debug!(target: "app::live", "local_api_session_failed peer={peer} error={error}");
+    }
+    let _ = socket.get_ref().shutdown(Shutdown::Both);
+    debug!(target: "app::live", "local_api_session_closed peer={peer}");
+}
+
+fn write_handshake(stream: &mut TcpStream, handshake: &Handshake) -> std::io::Result<()> {
+    tungstenite::handshake::server::write_response(&mut *stream, handshake)
+        .map_err(std::io::Error::other)?;
+    stream.flush()
+}
+
+fn send_messages(
+    socket: &mut WebSocket<TcpStream>,
+    messages: &[ServerMessage],
+) -> Result<(), WsError> {
+    for message in messages {
+        let text = serde_json::to_string(message)
+            .map_err(|error| WsError::Io(std::io::Error::other(error)))?;
+        socket.write(Message::text(text))?;
+    }
+    socket.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::ipc::models::{LiveCombatPayload, LiveStatusPayload};
+    use crate::live::projection_set::TopicPublication;
+    use std::io::{Read, Write};
+    use std::net::Ipv4Addr;
+
+    fn publish_combat(cache: &LivePublicationCache, revision: u64) {
+        let combat = LiveCombatPayload {
+            revision,
+            ..LiveCombatPayload::default()
+        };
+        cache.publish([TopicPublication::Combat(combat)]);
+    }
+
+    #[test]
+    fn subscriptions_send_changed_revisions_and_resets() {
+        let cache = LivePublicationCache::new();
+        let mut subscription = Subscription::new(Topic::Combat.mask());
+        assert!(subscription.pending(&cache).is_empty());
+
+        publish_combat(&cache, 1);
+        cache.publish([TopicPublication::Status(LiveStatusPayload::default())]);
+        let sent = subscription.pending(&cache);
+        assert!(matches!(sent.as_slice(), [ServerMessage::Topic(body)] if body.topic == "combat"));
+        assert!(subscription.pending(&cache).is_empty());
+
+        subscription
+            .apply(r#"{"subscribe":["status"],"unsubscribe":["combat"]}"#)
+            .expect("valid message");
+        let sent = subscription.pending(&cache);
+        assert!(matches!(sent.as_slice(), [ServerMessage::Topic(body)] if body.topic == "status"));
+        assert!(
+            subscription
+                .apply(r#"{"subscribe":["captureHealth"]}"#)
+                .is_err()
+        );
+        assert!(subscription.apply(r#"{"listen":["status"]}"#).is_err());
+
+        cache.reset();
+        let sent = subscription.pending(&cache);
+        assert!(matches!(sent.as_slice(), [ServerMessage::Reset { .. }]));
+    }
+
+    #[test]
+    fn serves_http_and_websocket_over_loopback() {
+        let cache = LivePublicationCache::new();
+        publish_combat(&cache, 3);
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).expect("bind");
+        let settings = LocalApiSettings {
+            enabled: true,
+            ..LocalApiSettings::default()
+        };
+        let running = RunningApi::start(cache.clone(), listener, settings).expect("start");
+        let address = running.address;
+
+        let mut client = TcpStream::connect(address).expect("connect");
+        client
+            .write_all(b"GET /api/topics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .expect("send");
+        let mut response = String::new();
+        client.read_to_string(&mut response).expect("response");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(r#"{"topic":"combat","revision":3}"#));
+
+        let stream = TcpStream::connect(address).expect("connect");
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .expect("timeout");
+        let (mut socket, handshake) =
+            tungstenite::client("ws://localhost/api/ws?topics=combat", stream).expect("handshake");
+        assert_eq!(handshake.status(), StatusCode::SWITCHING_PROTOCOLS);
+        let Message::Text(text) = socket.read().expect("topic message") else {
+            panic!("text message expected");
+        };
+        let message: serde_json::Value = serde_json::from_str(&text).expect("json");
+        assert_eq!(message["type"], "topic");
+        assert_eq!(message["topic"], "combat");
+        assert_eq!(message["revision"], 3);
+
+        socket
+            .send(Message::text(r#"{"subscribe":["nope"]}"#))
+            .expect("send");
+        let Message::Text(text) = socket.read().expect("error message") else {
+            panic!("text message expected");
+        };
+        let message: serde_json::Value = serde_json::from_str(&text).expect("json");
+        assert_eq!(message["type"], "error");
+
+        running.stop();
+    }
+}
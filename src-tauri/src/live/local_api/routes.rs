@@ -0,0 +1,398 @@
+//! Request routing: who may call, and the topic index and topic bodies.
+
+use serde::Serialize;
+
+use tungstenite::handshake::server::{Response as Handshake, create_response};
+use tungstenite::http::{Method, StatusCode};
+
+use super::http::{Request, header, query};
+use crate::live::ipc::publisher::{LivePublicationCache, PublishedPayload};
+use crate::live::ipc::topic::{Topic, TopicMask};
+use crate::packets::remote_ingest::constant_time_eq;
+
+/// The app's own webview: `tauri://localhost` on macOS and Linux,
+/// `http(s)://tauri.localhost` on Windows.
+const APP_ORIGINS: [&str; 3] = [
+    "tauri://localhost",
+    "http://tauri.localhost",
+    "https://tauri.localhost",
+];
+
+/// Who may talk to a running listener.
+#[derive(Debug, Clone)]
+pub(super) struct Access {
+    pub allow_lan: bool,
+    pub token: Option<String>,
+    /// Browser origins besides [`APP_ORIGINS`], already normalized.
+    pub allowed_origins: Vec<String>,
+}
+
+impl Access {
+    /// Requests without an `Origin` come from OBS, Stream Deck or a script
+    /// rather than a web page, so only browsers are held to the allowlist.
+    pub fn allows_origin(&self, origin: Option<&str>) -> bool {
+        let Some(origin) = origin else {
+            return true;
+        };
+        let origin = origin.trim_end_matches('/');
+        APP_ORIGINS
+            .into_iter()
+            .chain(self.allowed_origins.iter().map(String::as_str))
+            .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+    }
+}
+
+#[derive(Debug)]
+pub(super) enum Route {
+    Respond {
+        status: StatusCode,
+        headers: Vec<(&'static str, &'static str)>,
+        body: Option<Vec<u8>>,
+    },
+    /// The `101 Switching Protocols` to send before the session starts.
+    Upgrade {
+        handshake: Handshake,
+        topics: TopicMask,
+    },
+}
+
+impl Route {
+    fn json(status: StatusCode, body: &impl Serialize) -> Self {
+        match serde_json::to_vec(body) {
+            Ok(body) => Self::Respond {
+                status,
+                headers: Vec::new(),
+                body: Some(body),
+            },
+            Err(error) => Self::error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("failed to serialize response: {error}"),
+            ),
+        }
+    }
+
+    fn error(status: StatusCode, message: &str) -> Self {
+        Self::Respond {
+            status,
+            headers: Vec::new(),
+            body: serde_json::to_vec(&serde_json::json!({ "error": message })).ok(),
+        }
+    }
+
+    fn empty(status: StatusCode, headers: Vec<(&'static str, &'static str)>) -> Self {
+        Self::Respond {
+            status,
+            headers,
+            body: None,
+        }
+    }
+}
+
+pub(super) fn route(request: &Request, access: &Access, cache: &LivePublicationCache) -> Route {
+    if !access.allow_lan && !is_loopback_host(header(request, "host")) {
+        // A page on another origin can resolve its own hostname to 127.0.0.1;
+        // the Host header is what gives such DNS rebinding away.
+        return Route::error(StatusCode::FORBIDDEN, "host not allowed");
+    }
+    if !access.allows_origin(header(request, "origin")) {
+        // Browsers send WebSocket upgrades and simple GETs cross-origin
+        // without a preflight, so any page could otherwise read the topics.
+        return Route::error(StatusCode::FORBIDDEN, "origin not allowed");
+    }
+    if request.method() == Method::OPTIONS {
+        return Route::empty(
+            StatusCode::NO_CONTENT,
+            vec![
+                ("Access-Control-Allow-Methods", "GET, OPTIONS"),
+                ("Access-Control-Allow-Headers", "Authorization"),
+                ("Access-Control-Max-Age", "600"),
+            ],
+        );
+    }
+    if !is_authorized(request, access) {
+        return Route::error(StatusCode::UNAUTHORIZED, "missing or invalid token");
+    }
+    if request.method() != Method::GET {
+        return Route::error(StatusCode::METHOD_NOT_ALLOWED, "only GET is supported");
+    }
+
+    let path = request.uri().path().trim_end_matches('/');
+    if path == "/api/topics" {
+        return Route::json(StatusCode::OK, &topic_index(cache));
+    }
+    if let Some(name) = path.strip_prefix("/api/topics/") {
+        let Some(topic) = served_topic(name) else {
+            return Route::error(StatusCode::NOT_FOUND, &format!("unknown topic {name}"));
+        };
+        return topic_route(request, topic, cache);
+    }
+    if path == "/api/ws" {
+        let topics = match query(request, "topics").as_deref().map(parse_topic_list) {
+            Some(Ok(topics)) => topics,
+            Some(Err(error)) => return Route::error(StatusCode::BAD_REQUEST, &error),
+            None => TopicMask::EMPTY,
+        };
+        return match create_response(request) {
+            Ok(handshake) => Route::Upgrade { handshake, topics },
+            Err(error) => Route::error(StatusCode::UPGRADE_REQUIRED, &error.to_string()),
+        };
+    }
+    Route::error(StatusCode::NOT_FOUND, "not found")
+}
+
+fn is_loopback_host(host: Option<&str>) -> bool {
+    let Some(host) = host else {
+        return false;
+    };
+    let hostname = match host.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    matches!(hostname, "127.0.0.1" | "::1") || hostname.eq_ignore_ascii_case("localhost")
+}
+
+fn is_authorized(request: &Request, access: &Access) -> bool {
+    let Some(expected) = access.token.as_deref() else {
+        return true;
+    };
+    let presented = header(request, "authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string)
+        .or_else(|| query(request, "token"));
+    presented.is_some_and(|token| constant_time_eq(token.trim().as_bytes(), expected.as_bytes()))
+}
+
+/// Capture health has no revision and stays an in-app diagnostic.
+fn served_topic(name: &str) -> Option<Topic> {
+    Topic::from_name(name).filter(|topic| *topic != Topic::CaptureHealth)
+}
+
+fn served_topics() -> impl Iterator<Item = Topic> {
+    Topic::ALL
+        .into_iter()
+        .filter(|topic| *topic != Topic::CaptureHealth)
+}
+
+fn parse_topic_list(names: &str) -> Result<TopicMask, String> {
+    parse_topics(
+        names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty()),
+    )
+}
+
+pub(super) fn parse_topics<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+) -> Result<TopicMask, String> {
+    names.into_iter().try_fold(TopicMask::EMPTY, |mask, name| {
+        served_topic(name)
+            .map(|topic| mask | topic.mask())
+            .ok_or_else(|| format!("unknown topic {name}"))
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TopicIndex {
+    epoch: u64,
+    topics: Vec<TopicRevision>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TopicRevision {
+    topic: &'static str,
+    /// `None` until the topic is published in this epoch.
+    revision: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct TopicBody {
+    pub topic: &'static str,
+    pub epoch: u64,
+    pub revision: Option<u64>,
+    pub payload: Option<PublishedPayload>,
+}
+
+fn topic_index(cache: &LivePublicationCache) -> TopicIndex {
+    let epoch = cache.epoch();
+    TopicIndex {
+        epoch,
+        topics: served_topics()
+            .map(|topic| TopicRevision {
+                topic: topic.name(),
+                revision: cache
+                    .published_topic(topic)
+                    .filter(|published| published.epoch == epoch)
+                    .map(|published| published.revision),
+            })
+            .collect(),
+    }
+}
+
+/// `since` (and `epoch`, when given) matching the latest revision answers 304.
+fn topic_route(request: &Request, topic: Topic, cache: &LivePublicationCache) -> Route {
+    let Some(published) = cache.published_topic(topic) else {
+        return Route::json(
+            StatusCode::OK,
+            &TopicBody {
+                topic: topic.name(),
+                epoch: cache.epoch(),
+                revision: None,
+                payload: None,
+            },
+        );
+    };
+    let since = query(request, "since").and_then(|value| value.parse::<u64>().ok());
+    let epoch = query(request, "epoch").and_then(|value| value.parse::<u64>().ok());
+    if since == Some(published.revision) && epoch.is_none_or(|epoch| epoch == published.epoch) {
+        return Route::empty(StatusCode::NOT_MODIFIED, Vec::new());
+    }
+    Route::json(
+        StatusCode::OK,
+        &TopicBody {
+            topic: topic.name(),
+            epoch: published.epoch,
+            revision: Some(published.revision),
+            payload: Some(published.payload),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::http;
+    use super::*;
+    use crate::live::ipc::models::LiveCombatPayload;
+    use crate::live::projection_set::TopicPublication;
+
+    fn request(raw: &str) -> Request {
+        http::read_request(&mut raw.as_bytes())
+            .expect("valid request")
+            .request
+    }
+
+    fn open() -> Access {
+        Access {
+            allow_lan: false,
+            token: None,
+            allowed_origins: Vec::new(),
+        }
+    }
+
+    fn status_of(route: &Route) -> StatusCode {
+        match route {
+            Route::Respond { status, .. } => *status,
+            Route::Upgrade { handshake, .. } => handshake.status(),
+        }
+    }
+
+    fn publish_combat(cache: &LivePublicationCache, revision: u64) {
+        let combat = LiveCombatPayload {
+            revision,
+            ..LiveCombatPayload::default()
+        };
+        cache.publish([TopicPublication::Combat(combat)]);
+    }
+
+    #[test]
+    fn loopback_only_rejects_foreign_hosts_and_checks_tokens() {
+        let cache = LivePublicationCache::new();
+        let local = "GET /api/topics HTTP/1.1\r\nHost: 127.0.0.1:47110\r\n\r\n";
+        let rebound = "GET /api/topics HTTP/1.1\r\nHost: evil.example:47110\r\n\r\n";
+        assert_eq!(status_of(&route(&request(local), &open(), &cache)), 200);
+        assert_eq!(status_of(&route(&request(rebound), &open(), &cache)), 403);
+        assert!(is_loopback_host(Some("[::1]:47110")));
+        assert!(is_loopback_host(Some("LOCALHOST")));
+        assert!(!is_loopback_host(Some("127.0.0.1.evil.example")));
+        assert!(!is_loopback_host(None));
+
+        let guarded = Access {
+            allow_lan: true,
+            token: Some("secret".to_string()),
+            allowed_origins: Vec::new(),
+        };
+        let bearer =
+            "GET /api/topics HTTP/1.1\r\nHost: meter.lan\r\nAuthorization: Bearer secret\r\n\r\n";
+        let query = "GET /api/topics?token=secret HTTP/1.1\r\nHost: meter.lan\r\n\r\n";
+        let wrong = "GET /api/topics?token=guess HTTP/1.1\r\nHost: meter.lan\r\n\r\n";
+        assert_eq!(status_of(&route(&request(bearer), &guarded, &cache)), 200);
+        assert_eq!(status_of(&route(&request(query), &guarded, &cache)), 200);
+        assert_eq!(status_of(&route(&request(wrong), &guarded, &cache)), 401);
+    }
+
+    #[test]
+    fn browser_origins_must_be_the_app_or_allowed() {
+        let cache = LivePublicationCache::new();
+        let from = |origin: &str| {
+            request(&format!(
+                "GET /api/topics HTTP/1.1\r\nHost: localhost\r\nOrigin: {origin}\r\n\r\n"
+            ))
+        };
+        let overlay = Access {
+            allowed_origins: vec!["https://overlay.example".to_string()],
+            ..open()
+        };
+        assert_eq!(
+            status_of(&route(&from("tauri://localhost"), &open(), &cache)),
+            200
+        );
+        assert_eq!(
+            status_of(&route(&from("https://tauri.localhost"), &open(), &cache)),
+            200
+        );
+        assert_eq!(
+            status_of(&route(&from("https://evil.example"), &open(), &cache)),
+            403
+        );
+        assert_eq!(
+            status_of(&route(&from("https://overlay.example"), &open(), &cache)),
+            403
+        );
+        assert_eq!(
+            status_of(&route(&from("https://overlay.example/"), &overlay, &cache)),
+            200
+        );
+        assert_eq!(status_of(&route(&from("null"), &overlay, &cache)), 403);
+
+        let preflight = request(
+            "OPTIONS /api/topics HTTP/1.1\r\nHost: localhost\r\nOrigin: https://evil.example\r\n\r\n",
+        );
+        assert_eq!(status_of(&route(&preflight, &open(), &cache)), 403);
+    }
+
+    #[test]
+    fn topic_route_serves_payloads_and_not_modified() {
+        let cache = LivePublicationCache::new();
+        let get = |target: &str| {
+            route(
+                &request(&format!("GET {target} HTTP/1.1\r\nHost: localhost\r\n\r\n")),
+                &open(),
+                &cache,
+            )
+        };
+        let Route::Respond { body, .. } = get("/api/topics/combat") else {
+            panic!("plain response expected");
+        };
+        let body: serde_json::Value = serde_json::from_slice(&body.expect("body")).expect("json");
+        assert_eq!(body["revision"], serde_json::Value::Null);
+
+        publish_combat(&cache, 7);
+        let Route::Respond { status, body, .. } = get("/api/topics/combat") else {
+            panic!("plain response expected");
+        };
+        assert_eq!(status, 200);
+        let body: serde_json::Value = serde_json::from_slice(&body.expect("body")).expect("json");
+        assert_eq!(body["topic"], "combat");
+        assert_eq!(body["revision"], 7);
+        assert_eq!(body["payload"]["revision"], 7);
+
+        assert_eq!(status_of(&get("/api/topics/combat?since=7")), 304);
+        assert_eq!(status_of(&get("/api/topics/combat?since=7&epoch=0")), 200);
+        assert_eq!(status_of(&get("/api/topics/captureHealth")), 404);
+        assert_eq!(status_of(&get("/api/ws?topics=combat")), 426);
+        assert_eq!(status_of(&get("/api/ws?topics=nope")), 400);
+    }
+}
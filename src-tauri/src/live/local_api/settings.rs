@@ -0,0 +1,212 @@
+//! Local API settings and where they are stored.
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+pub const DEFAULT_PORT: u16 = 47_110;
+const SETTINGS_FILE_NAME: &str = "localApi.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LocalApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Listen on every interface instead of loopback only.
+    pub allow_lan: bool,
+    pub token: Option<String>,
+    /// Browser origins allowed besides the app's own, e.g.
+    /// `https://overlay.example`. `null` admits pages opened from a local
+    /// file, such as an OBS browser source pointed at one.
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for LocalApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+            allow_lan: false,
+            token: None,
+            allowed_origins: Vec::new(),
+        }
+    }
+}
+
+impl LocalApiSettings {
+    pub fn normalize(mut self) -> Result<Self, String> {
+        self.token = self
+            .token
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty());
+        if self.port == 0 {
+            return Err("本地接口端口必须在 1-65535 之间".to_string());
+        }
+        if self.allow_lan && self.token.is_none() {
+            return Err("允许局域网访问时必须设置访问令牌".to_string());
+        }
+        let mut origins: Vec<String> = Vec::with_capacity(self.allowed_origins.len());
+        for origin in &self.allowed_origins {
+            let origin = origin.trim().trim_end_matches('/');
+            if origin.is_empty() {
+                continue;
+            }
+            if origin != "null" && !origin.contains("://") {
+                return Err(format!("允许的来源格式无效: {origin}"));
+            }
+            if !origins
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(origin))
+            {
+                origins.push(origin.to_string());
+            }
+        }
+        self.allowed_origins = origins;
+        Ok(self)
+    }
+
+    pub(super) fn bind_address(&self) -> SocketAddr {
+        let ip = if self.allow_lan {
+            Ipv4Addr::UNSPECIFIED
+        } else {
+            Ipv4Addr::LOCALHOST
+        };
+        SocketAddr::from((ip, self.port))
+    }
+}
+
+pub(crate) fn save_local_api_settings(
+    app_handle: &AppHandle,
+    settings: &LocalApiSettings,
+) -> Result<(), String> {
+    let app_data_dirs = [
+        app_handle.path().app_data_dir(),
+        app_handle.path().app_local_data_dir(),
+    ];
+    let mut last_err = None;
+    let bytes = serde_json::to_vec_pretty(settings).map_err(|error| error.to_string())?;
+
+    for dir in app_data_dirs.into_iter().flatten() {
+        let target_dir = dir.join("stores");
+        if let Err(error) = std::fs::create_dir_all(&target_dir) {
+            last_err = Some(format!("create_dir_all {}: {error}", target_dir.display()));
+            continue;
+        }
+        let path = target_dir.join(SETTINGS_FILE_NAME);
+        let temporary = target_dir.join(format!("{SETTINGS_FILE_NAME}.tmp.{}", std::process::id()));
+        let written =
+            std::fs::write(&temporary, &bytes).and_then(|()| std::fs::rename(&temporary, &path));
+        match written {
+            Ok(()) => {
+                info!(
+                    target: "app::startup",
+                    "saved local API settings to {} (enabled={} port={} allow_lan={})",
+                    path.display(),
+                    settings.enabled,
+                    settings.port,
+                    settings.allow_lan
+                );
+                return Ok(());
+            }
+            Err(error) => {
+                let _ = std::fs::remove_file(&temporary);
+                last_err = Some(format!("write {}: {error}", path.display()));
+            }
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| "failed to save local API settings".to_string()))
+}
+
+/// The first readable settings file, or the defaults (disabled).
+pub(crate) fn load_local_api_settings(app_handle: &AppHandle) -> LocalApiSettings {
+    for path in settings_path_candidates(app_handle) {
+        if !path.exists() {
+            continue;
+        }
+        let parsed = std::fs::read(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|bytes| {
+                serde_json::from_slice::<LocalApiSettings>(&bytes)
+                    .map_err(|error| error.to_string())
+            })
+            .and_then(LocalApiSettings::normalize);
+        match parsed {
+            Ok(settings) => return settings,
+            Err(error) => warn!(
+                target: "app::startup",
+                "invalid local API settings {}: {error}",
+                path.display()
+            ),
+        }
+    }
+    LocalApiSettings::default()
+}
+
+fn settings_path_candidates(app_handle: &AppHandle) -> Vec<PathBuf> {
+    [
+        app_handle.path().app_data_dir(),
+        app_handle.path().app_local_data_dir(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|dir| dir.join("stores").join(SETTINGS_FILE_NAME))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_require_a_token_for_lan_access() {
+        let lan = LocalApiSettings {
+            enabled: true,
+            allow_lan: true,
+            token: Some("  ".to_string()),
+            ..LocalApiSettings::default()
+        };
+        assert!(lan.clone().normalize().is_err());
+
+        let lan = LocalApiSettings {
+            token: Some(" secret ".to_string()),
+            ..lan
+        }
+        .normalize()
+        .expect("token given");
+        assert_eq!(lan.token.as_deref(), Some("secret"));
+        assert_eq!(lan.bind_address().ip(), Ipv4Addr::UNSPECIFIED);
+        assert_eq!(
+            LocalApiSettings::default().bind_address(),
+            SocketAddr::from((Ipv4Addr::LOCALHOST, DEFAULT_PORT))
+        );
+    }
+
+    #[test]
+    fn allowed_origins_are_trimmed_deduplicated_and_validated() {
+        let settings = LocalApiSettings {
+            allowed_origins: vec![
+                " https://overlay.example/ ".to_string(),
+                "HTTPS://OVERLAY.EXAMPLE".to_string(),
+                String::new(),
+                "null".to_string(),
+            ],
+            ..LocalApiSettings::default()
+        }
+        .normalize()
+        .expect("valid origins");
+        assert_eq!(
+            settings.allowed_origins,
+            ["https://overlay.example", "null"]
+        );
+
+        let bare_host = LocalApiSettings {
+            allowed_origins: vec!["overlay.example".to_string()],
+            ..LocalApiSettings::default()
+        };
+        assert!(bare_host.normalize().is_err());
+    }
+}
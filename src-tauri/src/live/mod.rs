@@ -8,10 +8,17 @@ pub mod history_writer;
 pub mod ipc;
 pub mod live_core;
 pub mod live_main;
+pub mod local_api;
 pub mod marker_skills;
 pub mod monster_registry;
 pub mod projection_set;
 pub mod projections;
 pub mod protocol;
+pub mod replay;
 pub mod runtime;
 pub mod runtime_handle;
+#[cfg(test)]
+mod scenario;
+pub mod sessions;
+pub mod sink;
+pub mod user_scripts;
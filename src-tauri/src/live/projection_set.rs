@@ -2,26 +2,33 @@
 
 use crate::database::commands::{EncounterSummaryDto, PlayerSummaryDto};
 use crate::database::event_journal::{FinalizeEncounterMetadata, RecordingEncounter};
+use crate::database::history_codec::{HistoryBuffInterval, HistoryPhaseBoundary};
 use crate::live::bootstrap_snapshot::MonitorRuntimeSnapshot;
 use crate::live::counter::engine::{CounterEngine, CounterNamespace};
 use crate::live::history_writer::HistoryWriterHandle;
 use crate::live::ipc::models::{
-    LiveBuffsPayload, LiveCombatPayload, LiveDataPayload, LiveDeathsPayload, LiveFantasyPayload,
-    LiveMonsterPayload, LiveScenePayload, LiveStatusPayload, MinimapUpdatePayload,
+    LiveBuffsPayload, LiveCaptureHealthPayload, LiveCombatPayload, LiveDataPayload,
+    LiveDeathsPayload, LiveFantasyPayload, LiveMonsterPayload, LiveScenePayload, LiveStatusPayload,
+    MinimapUpdatePayload,
 };
 use crate::live::ipc::topic::{Topic, TopicMask};
+use crate::live::projections::buff_uptime::BuffUptimeProjection;
+use crate::live::projections::capture_health::CaptureHealthProjection;
 use crate::live::projections::combat::accumulator::CombatHitFact;
 use crate::live::projections::combat::projection::CombatProjection;
 use crate::live::projections::death::DeathProjection;
+use crate::live::projections::dungeon_run::DungeonRunProjection;
 use crate::live::projections::entity_monitor::EntityMonitorProjection;
 use crate::live::projections::history::HistoryProjection;
 use crate::live::projections::minimap::MinimapProjection;
+use crate::live::projections::phase::PhaseProjection;
 use crate::live::projections::presentation::{ActiveCombat, PresentationProjection};
+use crate::live::projections::script::{ScriptProjection, UserScript};
 use crate::live::projections::timeline::TimelineProjection;
 use crate::live::projections::voice::VoiceProjection;
 use crate::live::runtime::entity_context::EntityContext;
 use crate::live::runtime::events::{
-    AttributeValue, DomainEnvelope, DomainEvent, MonoTimeMs, SegmentReason,
+    AttributeValue, DomainEnvelope, DomainEvent, EntityKind, MonoTimeMs, SegmentReason,
 };
 use crate::live::runtime::scheduler::{DeadlineScheduler, DueTimer};
 use crate::live::runtime::segment::SegmentState;
@@ -36,11 +43,13 @@ const MONITORED_TOPICS: TopicMask = TopicMask::STATUS
 const SEGMENT_TOPICS: TopicMask = MONITORED_TOPICS
     .union(TopicMask::COMBAT)
     .union(TopicMask::DEATHS);
-/// The scene is persistent `EntityContext` state, not segment-scoped, so it
-/// sits outside `SEGMENT_TOPICS` next to the minimap.
+/// The scene is persistent `EntityContext` state and capture health describes
+/// the transport, so neither is segment-scoped; both sit outside
+/// `SEGMENT_TOPICS` next to the minimap.
 const ALL_TOPICS: TopicMask = SEGMENT_TOPICS
     .union(TopicMask::MINIMAP)
-    .union(TopicMask::SCENE);
+    .union(TopicMask::SCENE)
+    .union(TopicMask::CAPTURE_HEALTH);
 
 /// One replace-only payload for a single dirty topic.
 #[derive(Debug)]
@@ -53,6 +62,7 @@ pub enum TopicPublication {
     Minimap(MinimapUpdatePayload),
     Deaths(LiveDeathsPayload),
     Scene(LiveScenePayload),
+    CaptureHealth(LiveCaptureHealthPayload),
 }
 
 impl TopicPublication {
@@ -67,6 +77,7 @@ impl TopicPublication {
             Self::Minimap(_) => Topic::Minimap,
             Self::Deaths(_) => Topic::Deaths,
             Self::Scene(_) => Topic::Scene,
+            Self::CaptureHealth(_) => Topic::CaptureHealth,
         }
     }
 }
@@ -79,9 +90,14 @@ pub struct ProjectionSet {
     death: DeathProjection,
     minimap: MinimapProjection,
     timeline: TimelineProjection,
+    phase: PhaseProjection,
+    buff_uptime: BuffUptimeProjection,
     voice: VoiceProjection,
+    script: ScriptProjection,
     history: HistoryProjection,
+    dungeon_run: DungeonRunProjection,
     presentation: PresentationProjection,
+    capture_health: CaptureHealthProjection,
     counter_side_effect_dirty: bool,
     dirty: TopicMask,
 }
@@ -95,9 +111,14 @@ impl ProjectionSet {
             death: DeathProjection::default(),
             minimap: MinimapProjection::default(),
             timeline: TimelineProjection::default(),
+            phase: PhaseProjection::default(),
+            buff_uptime: BuffUptimeProjection::default(),
             voice: VoiceProjection::default(),
-            history: HistoryProjection::new(history_writer),
+            script: ScriptProjection::default(),
+            history: HistoryProjection::new(history_writer.clone()),
+            dungeon_run: DungeonRunProjection::new(history_writer),
             presentation: PresentationProjection::default(),
+            capture_health: CaptureHealthProjection::default(),
             counter_side_effect_dirty: false,
             dirty: ALL_TOPICS,
         }
@@ -123,6 +144,13 @@ impl ProjectionSet {
                 scheduler,
             )
             .map_err(|error| error.to_string())?;
+        if self
+            .combat
+            .set_target_breakdown(config.live.target_breakdown)
+        {
+            self.dirty |= TopicMask::COMBAT;
+        }
+        self.buff_uptime.apply_config(&config.live.uptime_buff_ids);
         self.entity_monitor
             .apply_config(std::sync::Arc::clone(&config), entities);
         self.voice
@@ -132,6 +160,13 @@ impl ProjectionSet {
         Ok(())
     }
 
+    /// Replaces the user scripts; their `init()` output is published with the
+    /// next status payload.
+    pub fn apply_scripts(&mut self, scripts: &[UserScript], now_wall_ms: i64) {
+        self.script.apply_scripts(scripts, now_wall_ms);
+        self.dirty |= TopicMask::STATUS;
+    }
+
     pub fn begin_batch(&mut self, batch_id: crate::live::runtime::events::BatchId) {
         let changed = self.counter.begin_batch(batch_id);
         self.mark_counter_change(changed);
@@ -143,6 +178,9 @@ impl ProjectionSet {
         entities: &EntityContext,
         scheduler: &mut DeadlineScheduler,
     ) -> Result<(), String> {
+        if self.script.apply(envelope, entities) {
+            self.dirty |= TopicMask::STATUS;
+        }
         match &envelope.event {
             DomainEvent::SegmentStarted {
                 segment_id,
@@ -164,7 +202,13 @@ impl ProjectionSet {
                 ended_at_wall_ms,
                 ended_at_mono_ms,
             } => {
-                self.end_segment(*segment_id, *reason, *ended_at_wall_ms, *ended_at_mono_ms)?;
+                self.end_segment(
+                    *segment_id,
+                    *reason,
+                    *ended_at_wall_ms,
+                    *ended_at_mono_ms,
+                    entities,
+                )?;
                 return Ok(());
             }
             _ => {}
@@ -187,6 +231,7 @@ impl ProjectionSet {
         match &envelope.event {
             DomainEvent::ContainerReset => {
                 self.reset_runtime(scheduler);
+                self.script.reset_runtime(envelope.occurred_at_ms);
                 return Ok(());
             }
             DomainEvent::EntityAppeared { .. } => {
@@ -196,6 +241,12 @@ impl ProjectionSet {
             }
             DomainEvent::EntityDisappeared { entity } => {
                 combat_changed |= self.combat.remove_entity(*entity);
+                self.phase.observe_boss_gone(entity.uuid.0);
+                let intervals = self.buff_uptime.observe_entity_gone(
+                    entity.uuid.0,
+                    self.combat.segment_offset_ms(envelope.meta.mono_ms()),
+                );
+                combat_changed |= self.record_buff_intervals(intervals, entities)?;
                 reported |= self.entity_monitor.apply(envelope, entities, scheduler);
                 self.death.apply(envelope);
                 self.voice.apply(envelope, entities, scheduler);
@@ -223,6 +274,15 @@ impl ProjectionSet {
             } => {
                 if let AttributeValue::Int(value) = current {
                     combat_changed |= self.combat.observe_attribute(*entity, *attr_id, *value);
+                    let offset_ms = self.combat.segment_offset_ms(envelope.meta.mono_ms());
+                    let boundary = self.phase.observe_attribute(
+                        entity.uuid.0,
+                        *attr_id,
+                        *value,
+                        offset_ms,
+                        entities,
+                    );
+                    combat_changed |= self.record_phase(envelope, boundary, offset_ms)?;
                 }
                 reported |= self.entity_monitor.apply(envelope, entities, scheduler);
                 self.history.apply(
@@ -253,6 +313,17 @@ impl ProjectionSet {
                 if let DomainEvent::LocalPlayerChanged { current, .. } = &envelope.event {
                     combat_changed |= self.combat.set_local_player(*current);
                 }
+                if let DomainEvent::BuffChanged(event) = &envelope.event {
+                    let target_is_boss = entities
+                        .entity(event.state.target.uuid)
+                        .is_some_and(|state| state.identity.is_boss_monster());
+                    let intervals = self.buff_uptime.observe_buff(
+                        event,
+                        target_is_boss,
+                        self.combat.segment_offset_ms(envelope.meta.mono_ms()),
+                    );
+                    combat_changed |= self.record_buff_intervals(intervals, entities)?;
+                }
             }
             DomainEvent::HateListUpdated { .. }
             | DomainEvent::SkillCooldownUpdated { .. }
@@ -268,12 +339,40 @@ impl ProjectionSet {
                     minimap_changed |= self.minimap.apply(envelope);
                 }
             }
-            DomainEvent::BossMechanicStarted(_) => {
+            DomainEvent::BossMechanicStarted(mechanic) => {
                 reported |= self.entity_monitor.apply(envelope, entities, scheduler);
                 self.voice.apply(envelope, entities, scheduler);
+                let offset_ms = self.combat.segment_offset_ms(envelope.meta.mono_ms());
+                let boundary = self
+                    .phase
+                    .observe_mechanic(mechanic.base_skill_id, offset_ms);
+                combat_changed |= self.record_phase(envelope, boundary, offset_ms)?;
+            }
+            DomainEvent::DungeonObjectiveChanged {
+                target_id,
+                count,
+                complete,
+            } => {
+                let offset_ms = self.combat.segment_offset_ms(envelope.meta.mono_ms());
+                let boundary = self
+                    .phase
+                    .observe_objective(*target_id, *count, *complete, offset_ms);
+                combat_changed |= self.record_phase(envelope, boundary, offset_ms)?;
+                self.dungeon_run.observe_objective(
+                    *target_id,
+                    *complete,
+                    envelope.occurred_at_ms,
+                )?;
             }
             DomainEvent::DeathOccurred { victim, .. } => {
+                if entities
+                    .entity(victim.uuid)
+                    .is_some_and(|entity| entity.identity.kind == EntityKind::Character)
+                {
+                    self.dungeon_run.observe_player_death();
+                }
                 combat_changed |= self.combat.observe_death(*victim);
+                self.phase.observe_boss_gone(victim.uuid.0);
                 reported |= self.entity_monitor.apply(envelope, entities, scheduler);
                 self.voice.apply(envelope, entities, scheduler);
                 let replay = self.death.apply(envelope);
@@ -288,7 +387,19 @@ impl ProjectionSet {
                     replay.as_ref(),
                 )?;
             }
-            DomainEvent::Revived { .. } => {
+            DomainEvent::Revived { entity } => {
+                if self.death.revive(*entity, envelope.occurred_at_ms) {
+                    reported |= TopicMask::DEATHS;
+                }
+                minimap_changed |= self.minimap.apply(envelope);
+                self.history.apply(
+                    envelope,
+                    entities,
+                    self.combat.segment_offset_ms(envelope.meta.mono_ms()),
+                    None,
+                )?;
+            }
+            DomainEvent::Teleported { .. } => {
                 minimap_changed |= self.minimap.apply(envelope);
             }
             DomainEvent::CombatHitAccepted(hit) => {
@@ -302,12 +413,13 @@ impl ProjectionSet {
                 );
                 combat_changed |= outcome.had_combat;
                 self.death.apply_hit(envelope, hit, fact.as_ref());
-                self.history.apply_hit(
-                    envelope,
-                    fact.as_ref(),
-                    entities,
-                    self.combat.segment_offset_ms(envelope.meta.mono_ms()),
-                )?;
+                let offset_ms = self.combat.segment_offset_ms(envelope.meta.mono_ms());
+                if let Some(fact) = &fact {
+                    let boundary = self.phase.observe_hit(fact, offset_ms, entities);
+                    combat_changed |= self.record_phase(envelope, boundary, offset_ms)?;
+                }
+                self.history
+                    .apply_hit(envelope, fact.as_ref(), entities, offset_ms)?;
             }
             DomainEvent::SceneChanged {
                 scene_id,
@@ -318,6 +430,8 @@ impl ProjectionSet {
                 reported |= self.entity_monitor.apply(envelope, entities, scheduler);
                 self.voice.apply(envelope, entities, scheduler);
                 minimap_changed |= self.minimap.apply(envelope);
+                self.dungeon_run
+                    .enter_scene(*scene_id, *difficulty, envelope.occurred_at_ms)?;
                 reported |= TopicMask::SCENE;
             }
             DomainEvent::PauseChanged { is_paused } => {
@@ -335,11 +449,31 @@ impl ProjectionSet {
                 reported |= self.entity_monitor.apply(envelope, entities, scheduler);
                 self.voice.apply(envelope, entities, scheduler);
             }
+            DomainEvent::CaptureIssue(issue) => {
+                let segment_offset_ms = self
+                    .combat
+                    .segment_id()
+                    .map(|_| self.combat.segment_offset_ms(envelope.meta.mono_ms()));
+                if let Some(flag) = self.capture_health.record(
+                    envelope.meta.stream_id,
+                    *issue,
+                    envelope.meta.captured_wall_ms,
+                    segment_offset_ms,
+                ) {
+                    self.history.add_quality(flag);
+                }
+                reported |= TopicMask::CAPTURE_HEALTH;
+            }
+            DomainEvent::WipeDetected { .. } => self.dungeon_run.observe_wipe(),
+            DomainEvent::DungeonFlowChanged { current, .. } => {
+                self.dungeon_run
+                    .observe_flow(*current, envelope.occurred_at_ms)?;
+            }
+            // Slot use only reaches user scripts, which saw it above.
             DomainEvent::HitResolved(_)
-            | DomainEvent::WipeDetected { .. }
-            | DomainEvent::DungeonFlowChanged { .. }
-            | DomainEvent::DungeonObjectiveChanged { .. }
-            | DomainEvent::SeasonCultivateChanged { .. } => {}
+            | DomainEvent::SeasonCultivateChanged { .. }
+            | DomainEvent::Disconnected { .. }
+            | DomainEvent::LocalSlotUsed { .. } => {}
             DomainEvent::SegmentStarted { .. } | DomainEvent::SegmentEnded { .. } => {
                 unreachable!("segment events returned above")
             }
@@ -373,6 +507,34 @@ impl ProjectionSet {
         Ok(())
     }
 
+    /// Persists a newly opened phase; returns whether one opened.
+    fn record_phase(
+        &mut self,
+        envelope: &DomainEnvelope,
+        boundary: Option<HistoryPhaseBoundary>,
+        segment_offset_ms: u64,
+    ) -> Result<bool, String> {
+        let Some(boundary) = boundary else {
+            return Ok(false);
+        };
+        self.history
+            .apply_phase(envelope, boundary, segment_offset_ms)?;
+        Ok(true)
+    }
+
+    /// Persists closed buff spans; returns whether any closed.
+    fn record_buff_intervals(
+        &mut self,
+        intervals: Vec<HistoryBuffInterval>,
+        entities: &EntityContext,
+    ) -> Result<bool, String> {
+        let changed = !intervals.is_empty();
+        for interval in intervals {
+            self.history.apply_buff_interval(interval, entities)?;
+        }
+        Ok(changed)
+    }
+
     pub fn on_due(
         &mut self,
         due: DueTimer,
@@ -398,13 +560,21 @@ impl ProjectionSet {
     /// hand to `VoiceService`. Keeping playback out of the projections is what
     /// lets every projection stay a pure function of the domain events.
     pub fn take_voice_cues(&mut self) -> Vec<VoiceCueIntent> {
-        self.voice.take_cues()
+        let mut cues = self.voice.take_cues();
+        cues.extend(self.script.take_cues());
+        cues
+    }
+
+    /// Finishes the open dungeon run so the writer can shut down cleanly.
+    pub fn shutdown(&mut self, now_wall_ms: i64) -> Result<(), String> {
+        self.dungeon_run.finish(now_wall_ms)
     }
 
     pub fn clear_display(&mut self) {
         self.presentation.clear_display();
         self.entity_monitor.clear_segment_display();
         self.combat.clear_segment();
+        self.phase.end_segment();
         self.death.start_segment();
         self.counter_side_effect_dirty = false;
         self.dirty |= SEGMENT_TOPICS;
@@ -417,6 +587,10 @@ impl ProjectionSet {
         self.dirty |= mask;
     }
 
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty = ALL_TOPICS;
+    }
+
     fn reset_runtime(&mut self, scheduler: &mut DeadlineScheduler) {
         self.presentation.hold_runtime_display(
             self.death.snapshot(),
@@ -428,6 +602,8 @@ impl ProjectionSet {
         self.death.start_segment();
         self.minimap.reset_runtime();
         self.timeline.reset_runtime();
+        self.phase.reset_runtime();
+        self.buff_uptime.reset_runtime();
         self.voice.reset_runtime(scheduler);
         self.counter_side_effect_dirty = true;
         self.dirty = ALL_TOPICS;
@@ -440,11 +616,21 @@ impl ProjectionSet {
     fn active_combat(&self) -> Option<ActiveCombat> {
         self.combat.segment_id().map(|segment_id| ActiveCombat {
             segment_id,
-            payload: self.combat.payload(),
+            payload: self.combat_payload(self.combat.observed_duration_ms()),
             clock: self.combat.display_clock(),
         })
     }
 
+    /// The combat projection's payload plus the phase split and buff
+    /// uptime, with the last phase and open spans ending at `end_offset_ms`.
+    fn combat_payload(&self, end_offset_ms: u128) -> LiveDataPayload {
+        let end_offset_ms = u64::try_from(end_offset_ms).unwrap_or(u64::MAX);
+        let mut payload = self.combat.payload();
+        payload.phases = self.phase.payload(end_offset_ms);
+        payload.buff_uptimes = self.buff_uptime.payload(end_offset_ms);
+        payload
+    }
+
     #[must_use]
     #[cfg(test)]
     pub fn peek_combat(&self, segment_state: &SegmentState) -> LiveCombatPayload {
@@ -502,10 +688,11 @@ impl ProjectionSet {
                             .take_combat_payload(active_combat, segment_state),
                     )
                 }
-                Topic::Status => TopicPublication::Status(
-                    self.presentation
-                        .take_status_payload(monitored(), self.counter.snapshot()),
-                ),
+                Topic::Status => TopicPublication::Status(self.presentation.take_status_payload(
+                    monitored(),
+                    self.counter.snapshot(),
+                    self.script.snapshot(),
+                )),
                 Topic::Buffs => {
                     TopicPublication::Buffs(self.presentation.take_buffs_payload(monitored()))
                 }
@@ -525,6 +712,9 @@ impl ProjectionSet {
                     entities.current_scene_id(),
                     entities.current_difficulty(),
                 )),
+                Topic::CaptureHealth => {
+                    TopicPublication::CaptureHealth(self.capture_health.take_payload())
+                }
             });
         }
 
@@ -559,6 +749,8 @@ impl ProjectionSet {
         }
         self.entity_monitor.start_segment(started_at_wall_ms);
         self.death.start_segment();
+        self.phase.start_segment();
+        self.buff_uptime.start_segment();
         self.history.start_segment(
             segment_id,
             RecordingEncounter {
@@ -569,8 +761,9 @@ impl ProjectionSet {
             },
         )?;
         self.presentation.segment_started(segment_id);
+        self.capture_health.start_segment(segment_id);
         self.counter_side_effect_dirty = true;
-        self.dirty |= SEGMENT_TOPICS;
+        self.dirty |= SEGMENT_TOPICS | TopicMask::CAPTURE_HEALTH;
         Ok(())
     }
 
@@ -580,6 +773,7 @@ impl ProjectionSet {
         reason: SegmentReason,
         ended_at_wall_ms: i64,
         ended_at_mono_ms: MonoTimeMs,
+        entities: &EntityContext,
     ) -> Result<(), String> {
         let observed_ms = self.combat.observed_duration_ms();
         let scheduled_window_ms = u128::from(
@@ -595,7 +789,7 @@ impl ProjectionSet {
             clock.freeze(ended_at_wall_ms);
             self.presentation.freeze_segment(
                 segment_id,
-                payload_for_end(self.combat.payload(), reason, duration_ms),
+                payload_for_end(self.combat_payload(duration_ms), reason, duration_ms),
                 clock,
             );
         }
@@ -630,6 +824,10 @@ impl ProjectionSet {
                 .map_err(|error| error.to_string())?,
             quality_flags: 0,
         };
+        let intervals = self
+            .buff_uptime
+            .end_segment(u64::try_from(duration_ms).unwrap_or(u64::MAX));
+        self.record_buff_intervals(intervals, entities)?;
         self.history.finalize(
             segment_id,
             self.combat.accumulator(),
@@ -655,8 +853,10 @@ impl ProjectionSet {
         )?;
 
         self.combat.clear_segment();
+        self.phase.end_segment();
+        self.capture_health.end_segment();
         self.counter_side_effect_dirty = false;
-        self.dirty |= TopicMask::COMBAT | TopicMask::STATUS;
+        self.dirty |= TopicMask::COMBAT | TopicMask::STATUS | TopicMask::CAPTURE_HEALTH;
         Ok(())
     }
 
@@ -845,14 +1045,20 @@ mod tests {
         ));
         assert_eq!(
             projections.dirty_mask(),
-            SEGMENT_TOPICS.union(TopicMask::SCENE)
+            SEGMENT_TOPICS
+                .union(TopicMask::SCENE)
+                .union(TopicMask::CAPTURE_HEALTH)
         );
 
         assert_eq!(
             projections
                 .take_publications(&entities, &state, ALL_TOPICS)
                 .len(),
-            SEGMENT_TOPICS.union(TopicMask::SCENE).iter().count()
+            SEGMENT_TOPICS
+                .union(TopicMask::SCENE)
+                .union(TopicMask::CAPTURE_HEALTH)
+                .iter()
+                .count()
         );
         assert!(projections.dirty_mask().is_empty());
 
@@ -1295,4 +1501,55 @@ mod tests {
         drop(projections);
         join.join().expect("history writer stops after disconnect");
     }
+
+    #[test]
+    fn capture_issues_dirty_only_capture_health() {
+        use crate::live::runtime::events::{BatchId, CaptureIssue, EventMeta};
+
+        let (writer, join) = HistoryWriterHandle::start().expect("history writer starts");
+        let mut projections = ProjectionSet::new(writer);
+        let entities = EntityContext::new();
+        let mut scheduler = DeadlineScheduler::new();
+        let mut dirty = projections.dirty_mask();
+        dirty.remove(ALL_TOPICS);
+        projections.dirty = dirty;
+
+        let meta = EventMeta {
+            batch_id: BatchId(1),
+            capture_sequence: 1,
+            stream_id: 42,
+            stream_epoch: 1,
+            captured_wall_ms: 1_000,
+            captured_mono_ns: 1_000_000,
+            source_time_ms: None,
+        };
+        let envelope = DomainEnvelope {
+            sequence: 1,
+            batch_id: meta.batch_id,
+            occurred_at_ms: 1_000,
+            meta,
+            event_index: 0,
+            segment_id: None,
+            event: DomainEvent::CaptureIssue(CaptureIssue::SegmentsDropped { lost_bytes: 100 }),
+        };
+        projections
+            .apply(&envelope, &entities, &mut scheduler)
+            .unwrap();
+        assert_eq!(projections.dirty_mask(), TopicMask::CAPTURE_HEALTH);
+
+        let publications = projections.take_publications(
+            &entities,
+            &SegmentState::default(),
+            TopicMask::CAPTURE_HEALTH,
+        );
+        let [TopicPublication::CaptureHealth(payload)] = publications.as_slice() else {
+            panic!("expected one capture health publication");
+        };
+        assert_eq!(payload.streams[0].stream_id, "42");
+        assert_eq!(payload.streams[0].lost_bytes, 100);
+        assert!(payload.affected_ranges.is_empty());
+
+        drop(projections);
+        join.join().expect("history writer stops after disconnect");
+    }
 }
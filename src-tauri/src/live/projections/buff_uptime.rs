@@ -0,0 +1,363 @@
+//! Segment-scoped uptime of the configured buffs on the local player,
+//! teammates and bosses.
+//!
+//! Uptime is kept per (entity, buff base id, source). A span closes whenever
+//! the summed stacks of that key change; each closed span is returned as a
+//! [`HistoryBuffInterval`] for the timeline stream and added to the totals
+//! published live. Buff instances are tracked between segments too, so a
+//! segment opens with the buffs that are already up.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::database::history_codec::HistoryBuffInterval;
+use crate::database::history_query::EncounterBuffUptimeData;
+use crate::live::runtime::events::{BuffEvent, BuffTransition};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct UptimeKey {
+    entity_id: i64,
+    base_id: i32,
+    source_entity_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Uptime {
+    stacks: u32,
+    /// Segment offset at which `stacks` took its current value.
+    since_ms: u64,
+    uptime_ms: u64,
+    stack_ms: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct BuffUptimeProjection {
+    buff_ids: HashSet<i32>,
+    /// Tracked instances by (entity, instance id), with their key and stacks.
+    instances: HashMap<(i64, i64), (UptimeKey, u32)>,
+    /// Keys with stacks up, plus every key seen during the segment.
+    keys: HashMap<UptimeKey, Uptime>,
+    in_segment: bool,
+}
+
+impl BuffUptimeProjection {
+    /// Buffs tracked before a config change keep being tracked until they
+    /// drop; the new list applies to buffs gained from now on.
+    pub fn apply_config(&mut self, buff_ids: &[i32]) {
+        self.buff_ids = buff_ids.iter().copied().collect();
+    }
+
+    pub fn start_segment(&mut self) {
+        self.in_segment = true;
+        self.keys.retain(|_, uptime| uptime.stacks > 0);
+        for uptime in self.keys.values_mut() {
+            *uptime = Uptime {
+                stacks: uptime.stacks,
+                ..Uptime::default()
+            };
+        }
+    }
+
+    /// Closes every open span at `end_ms` and returns them for history.
+    pub fn end_segment(&mut self, end_ms: u64) -> Vec<HistoryBuffInterval> {
+        let mut closed = Vec::new();
+        if self.in_segment {
+            for (key, uptime) in &mut self.keys {
+                closed.extend(close_span(*key, uptime, end_ms));
+            }
+        }
+        self.in_segment = false;
+        self.keys.retain(|_, uptime| uptime.stacks > 0);
+        closed.sort_unstable();
+        closed
+    }
+
+    pub fn reset_runtime(&mut self) {
+        self.instances.clear();
+        self.keys.clear();
+        self.in_segment = false;
+    }
+
+    /// `target_is_boss` is resolved by the caller, which holds the entity
+    /// context; teammates and the local player come from the event roles.
+    pub fn observe_buff(
+        &mut self,
+        event: &BuffEvent,
+        target_is_boss: bool,
+        offset_ms: u64,
+    ) -> Vec<HistoryBuffInterval> {
+        let state = &event.state;
+        let instance = (state.target.uuid.0, state.instance_id);
+        let roles = event.target_roles;
+        let tracked = event.transition != BuffTransition::Removed
+            && (self.instances.contains_key(&instance)
+                || (self.buff_ids.contains(&state.base_id)
+                    && (roles.is_local_player || roles.is_team_member || target_is_boss)));
+        let previous = if tracked {
+            let key = UptimeKey {
+                entity_id: state.target.uuid.0,
+                base_id: state.base_id,
+                source_entity_id: state
+                    .resolved_owner
+                    .or(state.source)
+                    .map(|source| source.uuid.0),
+            };
+            self.instances.insert(
+                instance,
+                (key, u32::try_from(state.layer.max(1)).unwrap_or(1)),
+            )
+        } else {
+            self.instances.remove(&instance)
+        };
+        let next = self.instances.get(&instance).copied();
+
+        let mut keys = previous
+            .into_iter()
+            .chain(next)
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        keys.dedup();
+        keys.into_iter()
+            .filter_map(|key| self.restack(key, offset_ms))
+            .collect()
+    }
+
+    /// A despawned entity drops its buffs without removal events.
+    pub fn observe_entity_gone(
+        &mut self,
+        entity_id: i64,
+        offset_ms: u64,
+    ) -> Vec<HistoryBuffInterval> {
+        let mut keys = Vec::new();
+        self.instances.retain(|(instance_entity_id, _), (key, _)| {
+            let keep = *instance_entity_id != entity_id;
+            if !keep {
+                keys.push(*key);
+            }
+            keep
+        });
+        keys.sort_unstable();
+        keys.dedup();
+        keys.into_iter()
+            .filter_map(|key| self.restack(key, offset_ms))
+            .collect()
+    }
+
+    /// Uptime with open spans running to `now_offset_ms`, over a window of
+    /// the same length.
+    #[must_use]
+    pub fn payload(&self, now_offset_ms: u64) -> Vec<EncounterBuffUptimeData> {
+        if !self.in_segment {
+            return Vec::new();
+        }
+        let mut keys = self.keys.iter().collect::<Vec<_>>();
+        keys.sort_unstable_by_key(|(key, _)| **key);
+        keys.into_iter()
+            .filter_map(|(key, uptime)| {
+                let mut uptime = *uptime;
+                close_span(*key, &mut uptime, now_offset_ms);
+                (uptime.uptime_ms > 0).then(|| {
+                    EncounterBuffUptimeData::new(
+                        key.entity_id,
+                        key.base_id,
+                        key.source_entity_id,
+                        uptime.uptime_ms,
+                        uptime.stack_ms,
+                        now_offset_ms,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Recounts the stacks of `key` from its instances, closing the current
+    /// span when the count changed.
+    fn restack(&mut self, key: UptimeKey, offset_ms: u64) -> Option<HistoryBuffInterval> {
+        let stacks = self
+            .instances
+            .values()
+            .filter(|(instance_key, _)| *instance_key == key)
+            .fold(0_u32, |total, (_, stacks)| total.saturating_add(*stacks));
+        let uptime = self.keys.entry(key).or_default();
+        if uptime.stacks == stacks {
+            return None;
+        }
+        let closed = if self.in_segment {
+            close_span(key, uptime, offset_ms)
+        } else {
+            None
+        };
+        uptime.stacks = stacks;
+        uptime.since_ms = offset_ms;
+        if stacks == 0 && !self.in_segment {
+            self.keys.remove(&key);
+        }
+        closed
+    }
+}
+
+/// Adds the span since `uptime.since_ms` to the totals and restarts it at
+/// `end_ms`.
+fn close_span(key: UptimeKey, uptime: &mut Uptime, end_ms: u64) -> Option<HistoryBuffInterval> {
+    if uptime.stacks == 0 || end_ms <= uptime.since_ms {
+        return None;
+    }
+    let span_ms = end_ms - uptime.since_ms;
+    uptime.uptime_ms = uptime.uptime_ms.saturating_add(span_ms);
+    uptime.stack_ms = uptime
+        .stack_ms
+        .saturating_add(span_ms.saturating_mul(u64::from(uptime.stacks)));
+    let interval = HistoryBuffInterval {
+        entity_id: key.entity_id,
+        base_id: key.base_id,
+        source_entity_id: key.source_entity_id,
+        start_ms: uptime.since_ms,
+        end_ms_exclusive: end_ms,
+        stacks: uptime.stacks,
+    };
+    uptime.since_ms = end_ms;
+    Some(interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::live::runtime::events::{
+        BuffState, BuffWireKind, EntityRef, EntityRoles, EntityUuid,
+    };
+
+    fn entity(uuid: i64) -> EntityRef {
+        EntityRef {
+            uuid: EntityUuid(uuid),
+            generation: 1,
+        }
+    }
+
+    fn buff(
+        transition: BuffTransition,
+        target: i64,
+        base_id: i32,
+        layer: i32,
+        roles: EntityRoles,
+    ) -> BuffEvent {
+        BuffEvent {
+            transition,
+            wire_kind: BuffWireKind::Add,
+            duration_updated: false,
+            previous_layer: None,
+            state: BuffState {
+                target: entity(target),
+                instance_id: i64::from(base_id) * 100 + target,
+                base_id,
+                layer,
+                source: Some(entity(1)),
+                resolved_owner: None,
+                source_config_id: None,
+                duration_ms: None,
+                started_wall_ms: None,
+                expires_wall_ms: None,
+                started_mono_ms: None,
+                expires_mono_ms: None,
+                effect_ids: Arc::from([]),
+            },
+            target_roles: roles,
+        }
+    }
+
+    fn span(
+        entity_id: i64,
+        start_ms: u64,
+        end_ms_exclusive: u64,
+        stacks: u32,
+    ) -> HistoryBuffInterval {
+        HistoryBuffInterval {
+            entity_id,
+            base_id: 77,
+            source_entity_id: Some(1),
+            start_ms,
+            end_ms_exclusive,
+            stacks,
+        }
+    }
+
+    #[test]
+    fn spans_close_on_stack_changes_and_segment_end() {
+        let boss = EntityRoles {
+            is_local_player: false,
+            is_team_member: false,
+            is_current_target: false,
+        };
+        let teammate = EntityRoles {
+            is_team_member: true,
+            ..boss
+        };
+        let mut projection = BuffUptimeProjection::default();
+        projection.apply_config(&[77]);
+
+        // Up before the segment: the segment opens with it.
+        assert!(
+            projection
+                .observe_buff(&buff(BuffTransition::Applied, 20, 77, 1, boss), true, 0)
+                .is_empty()
+        );
+        projection.start_segment();
+        assert_eq!(
+            projection.observe_buff(
+                &buff(BuffTransition::LayerChanged, 20, 77, 2, boss),
+                true,
+                1_000
+            ),
+            vec![span(20, 0, 1_000, 1)]
+        );
+        // Unconfigured buffs and untracked entities are ignored.
+        assert!(
+            projection
+                .observe_buff(
+                    &buff(BuffTransition::Applied, 2, 88, 1, teammate),
+                    false,
+                    1_500
+                )
+                .is_empty()
+        );
+        assert!(
+            projection
+                .observe_buff(
+                    &buff(BuffTransition::Applied, 30, 77, 1, boss),
+                    false,
+                    1_500
+                )
+                .is_empty()
+        );
+        assert_eq!(
+            projection.observe_buff(&buff(BuffTransition::Removed, 20, 77, 2, boss), true, 3_000),
+            vec![span(20, 1_000, 3_000, 2)]
+        );
+        assert!(
+            projection
+                .observe_buff(
+                    &buff(BuffTransition::Applied, 2, 77, 1, teammate),
+                    false,
+                    3_500
+                )
+                .is_empty()
+        );
+
+        let uptimes = projection.payload(4_000);
+        assert_eq!(uptimes.len(), 2);
+        assert_eq!(
+            (uptimes[0].entity_id.as_str(), uptimes[0].uptime_ms),
+            ("2", 500)
+        );
+        assert_eq!(uptimes[1].uptime_ms, 3_000);
+        assert_eq!(uptimes[1].stack_ms, 5_000);
+        assert!((uptimes[1].uptime_pct - 75.0).abs() < f64::EPSILON);
+        assert!((uptimes[1].average_stacks - 1.25).abs() < f64::EPSILON);
+
+        assert_eq!(
+            projection.end_segment(4_000),
+            vec![span(2, 3_500, 4_000, 1)]
+        );
+        assert!(projection.payload(5_000).is_empty());
+    }
+}
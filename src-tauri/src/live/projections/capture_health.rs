@@ -0,0 +1,208 @@
+//! Capture pipeline health: transport losses and decode failures per stream.
+//!
+//! Separates "the packets never reached us" from "we misread them" when a
+//! parse looks wrong. Counters are cumulative for the capture; affected time
+//! ranges are kept for the current (or last) encounter only.
+
+use std::collections::BTreeMap;
+
+use crate::database::history_query::HistoryQualityFlag;
+use crate::live::ipc::models::{
+    CaptureAffectedRange, CaptureStreamHealth, LiveCaptureHealthPayload,
+};
+use crate::live::runtime::events::{CaptureIssue, SegmentId};
+
+/// Issues closer together than this extend the same affected range.
+const RANGE_MERGE_GAP_MS: u64 = 1_000;
+/// Bounds the payload when a stream degrades for a whole encounter.
+const MAX_AFFECTED_RANGES: usize = 256;
+
+#[derive(Debug, Default)]
+pub struct CaptureHealthProjection {
+    streams: BTreeMap<u64, CaptureStreamHealth>,
+    segment_id: Option<SegmentId>,
+    ranges: Vec<CaptureAffectedRange>,
+    revision: u64,
+}
+
+impl CaptureHealthProjection {
+    /// Counts `issue` against its stream. `segment_offset_ms` is set while an
+    /// encounter is recording; the returned flag is what the encounter's
+    /// history should carry for it.
+    pub fn record(
+        &mut self,
+        stream_id: u64,
+        issue: CaptureIssue,
+        wall_ms: i64,
+        segment_offset_ms: Option<u64>,
+    ) -> Option<HistoryQualityFlag> {
+        let stream = self
+            .streams
+            .entry(stream_id)
+            .or_insert_with(|| CaptureStreamHealth {
+                stream_id: stream_id.to_string(),
+                ..CaptureStreamHealth::default()
+            });
+        stream.last_issue_wall_ms = Some(wall_ms);
+        let flag = match issue {
+            CaptureIssue::SegmentsDropped { lost_bytes } => {
+                stream.dropped_segments += 1;
+                stream.lost_bytes += u64::from(lost_bytes);
+                Some(HistoryQualityFlag::PacketLoss)
+            }
+            // Ordinary reconnects reset reassembly too; bytes actually lost
+            // across one arrive as `SegmentsDropped`.
+            CaptureIssue::ReassemblyReset => {
+                stream.reassembly_resets += 1;
+                None
+            }
+            CaptureIssue::MalformedFrame => {
+                stream.malformed_frames += 1;
+                Some(HistoryQualityFlag::DecodeFailure)
+            }
+            CaptureIssue::DecompressionFailed => {
+                stream.zstd_failures += 1;
+                Some(HistoryQualityFlag::DecodeFailure)
+            }
+            CaptureIssue::UnknownOpcode { .. } => {
+                stream.unknown_opcodes += 1;
+                None
+            }
+            CaptureIssue::BackpressureStall { stalled_ms } => {
+                stream.backpressure_stalls += 1;
+                stream.backpressure_stall_ms += u64::from(stalled_ms);
+                None
+            }
+        };
+
+        let (Some(kind), Some(offset_ms)) = (flag, segment_offset_ms) else {
+            return None;
+        };
+        self.stamp_range(kind, offset_ms);
+        Some(kind)
+    }
+
+    pub fn start_segment(&mut self, segment_id: SegmentId) {
+        self.segment_id = Some(segment_id);
+        self.ranges.clear();
+    }
+
+    /// Keeps the finished encounter's ranges visible until the next one starts.
+    pub fn end_segment(&mut self) {
+        self.segment_id = None;
+    }
+
+    pub fn take_payload(&mut self) -> LiveCaptureHealthPayload {
+        self.revision = self.revision.saturating_add(1);
+        LiveCaptureHealthPayload {
+            revision: self.revision,
+            active_segment_id: self.segment_id.map(|segment_id| segment_id.0),
+            streams: self.streams.values().cloned().collect(),
+            affected_ranges: self.ranges.clone(),
+        }
+    }
+
+    fn stamp_range(&mut self, kind: HistoryQualityFlag, offset_ms: u64) {
+        if let Some(range) = self
+            .ranges
+            .iter_mut()
+            .rev()
+            .find(|range| range.kind == kind)
+            && offset_ms <= range.end_ms.saturating_add(RANGE_MERGE_GAP_MS)
+        {
+            range.start_ms = range.start_ms.min(offset_ms);
+            range.end_ms = range.end_ms.max(offset_ms);
+            range.occurrences = range.occurrences.saturating_add(1);
+            return;
+        }
+        if self.ranges.len() >= MAX_AFFECTED_RANGES {
+            self.ranges.remove(0);
+        }
+        self.ranges.push(CaptureAffectedRange {
+            kind,
+            start_ms: offset_ms,
+            end_ms: offset_ms,
+            occurrences: 1,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_issues_per_stream_and_flags_only_losses_and_decode_failures() {
+        let mut health = CaptureHealthProjection::default();
+        let issues = [
+            (1, CaptureIssue::SegmentsDropped { lost_bytes: 1_460 }),
+            (1, CaptureIssue::SegmentsDropped { lost_bytes: 40 }),
+            (1, CaptureIssue::BackpressureStall { stalled_ms: 30 }),
+            (1, CaptureIssue::ReassemblyReset),
+            (2, CaptureIssue::DecompressionFailed),
+            (2, CaptureIssue::UnknownOpcode { opcode: 0x99 }),
+        ];
+        let flags: Vec<_> = issues
+            .into_iter()
+            .map(|(stream, issue)| health.record(stream, issue, 10, Some(0)))
+            .collect();
+        assert_eq!(
+            flags,
+            vec![
+                Some(HistoryQualityFlag::PacketLoss),
+                Some(HistoryQualityFlag::PacketLoss),
+                None,
+                None,
+                Some(HistoryQualityFlag::DecodeFailure),
+                None,
+            ]
+        );
+
+        let payload = health.take_payload();
+        assert_eq!(payload.streams.len(), 2);
+        let first = &payload.streams[0];
+        assert_eq!(first.stream_id, "1");
+        assert_eq!((first.dropped_segments, first.lost_bytes), (2, 1_500));
+        assert_eq!(
+            (first.backpressure_stalls, first.backpressure_stall_ms),
+            (1, 30)
+        );
+        assert_eq!(first.reassembly_resets, 1);
+        let second = &payload.streams[1];
+        assert_eq!((second.zstd_failures, second.unknown_opcodes), (1, 1));
+    }
+
+    #[test]
+    fn affected_ranges_merge_nearby_issues_and_reset_per_segment() {
+        let mut health = CaptureHealthProjection::default();
+        let drop = CaptureIssue::SegmentsDropped { lost_bytes: 100 };
+        health.start_segment(SegmentId(4));
+        for offset in [1_000, 1_500, 2_400] {
+            health.record(1, drop, 0, Some(offset));
+        }
+        health.record(1, CaptureIssue::MalformedFrame, 0, Some(2_000));
+        health.record(1, drop, 0, Some(9_000));
+
+        let payload = health.take_payload();
+        assert_eq!(payload.active_segment_id, Some(4));
+        let spans: Vec<_> = payload
+            .affected_ranges
+            .iter()
+            .map(|range| (range.kind, range.start_ms, range.end_ms, range.occurrences))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (HistoryQualityFlag::PacketLoss, 1_000, 2_400, 3),
+                (HistoryQualityFlag::DecodeFailure, 2_000, 2_000, 1),
+                (HistoryQualityFlag::PacketLoss, 9_000, 9_000, 1),
+            ]
+        );
+
+        health.end_segment();
+        assert_eq!(health.record(1, drop, 0, None), None);
+        assert_eq!(health.take_payload().affected_ranges.len(), 3);
+        health.start_segment(SegmentId(5));
+        assert!(health.take_payload().affected_ranges.is_empty());
+    }
+}
@@ -195,6 +195,27 @@ pub struct CombatTotals {
     pub damage_taken: u128,
 }
 
+impl CombatTotals {
+    /// Adds one hit to the encounter-wide totals. Returns whether a total
+    /// saturated.
+    pub fn apply(&mut self, hit: &CombatHitFact) -> bool {
+        match hit.metric {
+            CombatMetric::Damage => {
+                let mut saturated = add_saturating(&mut self.damage, hit.amount);
+                if hit.target_is_boss {
+                    saturated |= add_saturating(&mut self.boss_damage, hit.amount);
+                }
+                saturated
+            }
+            CombatMetric::Healing => {
+                add_saturating(&mut self.healing, hit.amount)
+                    | add_saturating(&mut self.effective_healing, hit.effective_amount)
+            }
+            CombatMetric::DamageTaken => add_saturating(&mut self.damage_taken, hit.amount),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct CombatantStats {
     pub class_spec: ClassSpec,
@@ -250,16 +271,11 @@ impl CombatAccumulator {
 
     /// Apply one canonical fact. Returns whether this hit saturated any field.
     pub fn apply(&mut self, hit: &CombatHitFact) -> bool {
-        let mut saturated = false;
+        let mut saturated = self.totals.apply(hit);
         let delta = hit.stat_delta();
 
         match hit.metric {
             CombatMetric::Damage => {
-                saturated |= add_saturating(&mut self.totals.damage, hit.amount);
-                if hit.target_is_boss {
-                    saturated |= add_saturating(&mut self.totals.boss_damage, hit.amount);
-                }
-
                 let combatant = self.entities.entry(hit.actor_entity_id).or_default();
                 combatant.observe_base_skill(hit.base_skill_id);
                 saturated |= apply_combat_stats_saturating(&mut combatant.damage, &delta);
@@ -283,10 +299,6 @@ impl CombatAccumulator {
                 );
             }
             CombatMetric::Healing => {
-                saturated |= add_saturating(&mut self.totals.healing, hit.amount);
-                saturated |=
-                    add_saturating(&mut self.totals.effective_healing, hit.effective_amount);
-
                 let combatant = self.entities.entry(hit.actor_entity_id).or_default();
                 combatant.observe_base_skill(hit.base_skill_id);
                 saturated |= apply_combat_stats_saturating(&mut combatant.healing, &delta);
@@ -306,8 +318,6 @@ impl CombatAccumulator {
                 );
             }
             CombatMetric::DamageTaken => {
-                saturated |= add_saturating(&mut self.totals.damage_taken, hit.amount);
-
                 let combatant = self.entities.entry(hit.actor_entity_id).or_default();
                 saturated |= apply_combat_stats_saturating(&mut combatant.taken, &delta);
                 saturated |= apply_skill_saturating(
@@ -1,4 +1,5 @@
 pub mod accumulator;
 pub mod hit_event;
 pub mod projection;
+pub mod rolling;
 pub mod stats;
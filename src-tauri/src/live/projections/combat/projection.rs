@@ -3,12 +3,15 @@
 use std::collections::{HashMap, HashSet};
 
 use crate::live::ipc::models::{
-    BossHealth, LiveDataPayload, LiveDisplayClock, RawEntityData, build_taken_per_source,
-    to_raw_combat_stats, to_raw_skill_stats,
+    BossHealth, LiveDataPayload, LiveDisplayClock, RawEntityData, RawRollingWindow, RawTargetStats,
+    build_taken_per_source, to_raw_combat_stats, to_raw_rolling_window, to_raw_skill_stats,
+    to_raw_target_stats,
 };
+use crate::live::monster_registry::{MonsterType, monster_type};
 use crate::live::projections::combat::accumulator::{
     CombatAccumulator, CombatHitFact, CombatMetric, CombatantStats,
 };
+use crate::live::projections::combat::rolling::RollingRates;
 use crate::live::projections::combat::stats::class::{
     ClassSpec, get_class_id_from_spec, get_class_name, get_class_spec,
 };
@@ -63,7 +66,12 @@ impl CombatantProjection {
         }
     }
 
-    fn dto(&self, stats: &CombatantStats) -> RawEntityData {
+    fn dto(
+        &self,
+        stats: &CombatantStats,
+        rolling: Vec<RawRollingWindow>,
+        targets: Vec<RawTargetStats>,
+    ) -> RawEntityData {
         let class_spec = stats.class_spec;
         let class_id = if class_spec == ClassSpec::Unknown {
             self.class_id
@@ -100,6 +108,8 @@ impl CombatantProjection {
                 .map(|(skill, stats)| (*skill, to_raw_skill_stats(stats)))
                 .collect(),
             taken_per_source: build_taken_per_source(&stats.taken_sources),
+            rolling,
+            targets,
         }
     }
 }
@@ -113,6 +123,8 @@ pub struct CombatProjection {
     active_combat_time_ms: u128,
     last_player_damage_wall_ms: Option<i64>,
     accumulator: CombatAccumulator,
+    /// Outgoing damage and healing windows per actor, on the segment clock.
+    rolling: HashMap<i64, RollingRates>,
     local_player: Option<EntityUuid>,
     scene_id: Option<i32>,
     dungeon_difficulty: Option<i32>,
@@ -128,6 +140,11 @@ pub struct CombatProjection {
     /// monsters in the entity table for finalization), but they are hidden
     /// from the live payload.
     departed_bosses: HashSet<EntityUuid>,
+    /// Whether entity rows carry their per-target breakdown.
+    target_breakdown: bool,
+    /// Max HP of every boss or elite damaged this segment, `None` until it
+    /// is known. Only these targets appear in the breakdown.
+    breakdown_targets: HashMap<EntityUuid, Option<i64>>,
 }
 
 impl CombatProjection {
@@ -146,6 +163,7 @@ impl CombatProjection {
         let scene_id = self.scene_id;
         let dungeon_difficulty = self.dungeon_difficulty;
         let is_paused = self.is_paused;
+        let target_breakdown = self.target_breakdown;
         *self = Self {
             segment_id: Some(segment_id),
             started_at_mono_ms,
@@ -154,6 +172,7 @@ impl CombatProjection {
             scene_id,
             dungeon_difficulty,
             is_paused,
+            target_breakdown,
             paused_at_mono_ms: is_paused.then_some(started_at_mono_ms),
             paused_at_wall_ms: is_paused.then_some(started_at_wall_ms),
             ..Self::default()
@@ -165,11 +184,13 @@ impl CombatProjection {
         let scene_id = self.scene_id;
         let dungeon_difficulty = self.dungeon_difficulty;
         let is_paused = self.is_paused;
+        let target_breakdown = self.target_breakdown;
         *self = Self {
             local_player,
             scene_id,
             dungeon_difficulty,
             is_paused,
+            target_breakdown,
             ..Self::default()
         };
     }
@@ -214,6 +235,13 @@ impl CombatProjection {
         true
     }
 
+    /// Returns whether the payload-visible breakdown setting changed.
+    pub fn set_target_breakdown(&mut self, enabled: bool) -> bool {
+        let changed = self.target_breakdown != enabled;
+        self.target_breakdown = enabled;
+        changed
+    }
+
     /// Returns whether the payload-visible scene or difficulty changed.
     pub fn set_scene(&mut self, scene_id: i32, difficulty: Option<i32>) -> bool {
         let changed = self.scene_id != Some(scene_id) || self.dungeon_difficulty != difficulty;
@@ -279,6 +307,13 @@ impl CombatProjection {
                 _ => {}
             }
         }
+        if attr_id == attr_type::ATTR_MAX_HP
+            && let Some(max_hp) = self.breakdown_targets.get_mut(&entity.uuid)
+            && *max_hp != Some(value)
+        {
+            *max_hp = Some(value);
+            changed |= self.target_breakdown;
+        }
         changed
     }
 
@@ -338,6 +373,22 @@ impl CombatProjection {
             let mut fact = *fact;
             fact.target_is_boss = target_is_boss;
             self.accumulator.apply(&fact);
+            if fact.metric == CombatMetric::Damage
+                && (target_is_boss || is_elite(fact.target_monster_id))
+            {
+                let max_hp = entities
+                    .entity(hit.target.uuid)
+                    .and_then(|state| state.int_attr(attr_type::ATTR_MAX_HP));
+                let known = self.breakdown_targets.entry(hit.target.uuid).or_default();
+                *known = max_hp.or(*known);
+            }
+            if fact.metric != CombatMetric::DamageTaken {
+                let offset_ms = self.segment_offset_ms(occurred_at_mono_ms);
+                self.rolling
+                    .entry(fact.actor_entity_id)
+                    .or_default()
+                    .observe(&fact, offset_ms);
+            }
         } else if hit.source_is_player {
             outcome.had_player_damage = false;
         }
@@ -356,17 +407,32 @@ impl CombatProjection {
         outcome
     }
 
+    /// Rolling windows end at the latest combat in the segment, so a lull
+    /// with no combat at all holds them rather than draining them.
     #[must_use]
     pub fn payload(&self) -> LiveDataPayload {
+        let now_ms = self.last_combat_offset_ms.unwrap_or_default();
         let mut entities = self
             .accumulator
             .entities
             .iter()
             .map(|(entity_id, stats)| {
+                let rolling = self.rolling.get(entity_id).map_or_else(Vec::new, |rates| {
+                    rates
+                        .windows(now_ms)
+                        .iter()
+                        .map(to_raw_rolling_window)
+                        .collect()
+                });
+                let targets = if self.target_breakdown {
+                    self.breakdown_rows(stats)
+                } else {
+                    Vec::new()
+                };
                 self.combatants
                     .get(entity_id)
                     .expect("combat accumulator metadata exists")
-                    .dto(stats)
+                    .dto(stats, rolling, targets)
             })
             .collect::<Vec<_>>();
         entities.sort_unstable_by(|left, right| left.entity_uuid.cmp(&right.entity_uuid));
@@ -402,6 +468,10 @@ impl CombatProjection {
             is_paused: self.is_paused,
             bosses,
             entities,
+            // Filled in by `ProjectionSet`, which owns the phase and buff
+            // uptime projections.
+            phases: Vec::new(),
+            buff_uptimes: Vec::new(),
         }
     }
 
@@ -542,6 +612,29 @@ impl CombatProjection {
         summaries
     }
 
+    fn breakdown_rows(&self, stats: &CombatantStats) -> Vec<RawTargetStats> {
+        let mut targets = stats
+            .damage_targets
+            .iter()
+            .filter_map(|(target_uuid, target)| {
+                let max_hp = self.breakdown_targets.get(&EntityUuid(*target_uuid))?;
+                Some((target_uuid, target, *max_hp))
+            })
+            .collect::<Vec<_>>();
+        targets.sort_unstable_by(|left, right| {
+            right
+                .1
+                .stats
+                .total_value
+                .cmp(&left.1.stats.total_value)
+                .then(left.0.cmp(right.0))
+        });
+        targets
+            .into_iter()
+            .map(|(target_uuid, target, max_hp)| to_raw_target_stats(*target_uuid, target, max_hp))
+            .collect()
+    }
+
     fn ensure_combatant(
         &mut self,
         entity: EntityRef,
@@ -598,6 +691,12 @@ impl CombatProjection {
     }
 }
 
+fn is_elite(monster_id: Option<i32>) -> bool {
+    monster_id
+        .and_then(monster_type)
+        .is_some_and(|kind| kind == MonsterType::Elite)
+}
+
 fn active_increment(previous: Option<i64>, current: i64) -> u128 {
     let Some(previous) = previous else {
         return HIT_GRACE_MS;
@@ -876,6 +975,52 @@ mod tests {
         assert_eq!(projection.boss_monster_ids(), vec![900]);
     }
 
+    #[test]
+    fn target_breakdown_lists_boss_damage_with_hp_share_only_when_enabled() {
+        let mut entities = EntityContext::new();
+        entities.apply_batch(identity_batch(1, 900, FieldPatch::Set(true)));
+        let mut projection = CombatProjection::default();
+        projection.start_segment(SegmentId(1), MonoTimeMs(0), 0);
+
+        apply_hit(
+            &mut projection,
+            hit(true, HitKind::Damage),
+            1,
+            MonoTimeMs(1),
+            &entities,
+        );
+        let damage_rows = |projection: &CombatProjection| {
+            projection
+                .payload()
+                .entities
+                .into_iter()
+                .find(|row| row.entity_uuid == "10")
+                .expect("attacker row")
+                .targets
+        };
+        assert!(damage_rows(&projection).is_empty());
+
+        // The setting survives the segment boundary.
+        assert!(projection.set_target_breakdown(true));
+        projection.start_segment(SegmentId(2), MonoTimeMs(10), 10);
+        apply_hit(
+            &mut projection,
+            hit(true, HitKind::Damage),
+            11,
+            MonoTimeMs(11),
+            &entities,
+        );
+        let targets = damage_rows(&projection);
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].entity_uuid, "20");
+        assert!(targets[0].is_boss);
+        assert_eq!(targets[0].total_value, "100");
+        assert_eq!(targets[0].hp_share_pct, None);
+
+        assert!(projection.observe_attribute(entity(20), attr_type::ATTR_MAX_HP, 1_000));
+        assert_eq!(damage_rows(&projection)[0].hp_share_pct, Some(10.0));
+    }
+
     fn dummy_boss_batch(sequence: u64, monster_id: i32) -> ProtocolBatch {
         ProtocolBatch {
             meta: EventMeta {
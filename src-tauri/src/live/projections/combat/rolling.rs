@@ -0,0 +1,205 @@
+//! Trailing-window damage and healing sums with their per-segment peaks,
+//! shared by live combat and history replay.
+//!
+//! Hits are kept only as long as the widest window needs them, and every
+//! window's sum is moved forward as hits age out, so each hit costs O(1)
+//! amortized. The damage peak of a window is exact: a sliding sum over hits
+//! is largest for some window ending on a hit, and every such window is seen.
+
+use std::collections::VecDeque;
+
+use crate::live::projections::combat::accumulator::{CombatHitFact, CombatMetric};
+
+/// Trailing windows, narrowest first.
+pub const ROLLING_WINDOWS_MS: [u64; 3] = [5_000, 15_000, 30_000];
+
+/// The most damage dealt within one window length during the segment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RollingPeak {
+    pub damage: u128,
+    /// Segment offset of the last hit inside the peak window.
+    pub end_ms: u64,
+}
+
+/// One window as seen at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RollingWindow {
+    pub window_ms: u64,
+    pub damage: u128,
+    pub healing: u128,
+    pub peak: Option<RollingPeak>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    offset_ms: u64,
+    damage: u128,
+    healing: u128,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct WindowSum {
+    /// Index of the oldest sample still inside the window.
+    start: usize,
+    damage: u128,
+    healing: u128,
+    peak: Option<RollingPeak>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RollingRates {
+    samples: VecDeque<Sample>,
+    windows: [WindowSum; ROLLING_WINDOWS_MS.len()],
+}
+
+impl RollingRates {
+    /// Adds an outgoing damage or healing hit at `offset_ms`. Hits must
+    /// arrive in offset order; a late one is counted at the newest offset.
+    pub fn observe(&mut self, fact: &CombatHitFact, offset_ms: u64) {
+        let (damage, healing) = match fact.metric {
+            CombatMetric::Damage => (fact.amount, 0),
+            CombatMetric::Healing => (0, fact.amount),
+            CombatMetric::DamageTaken => return,
+        };
+        let offset_ms = self
+            .samples
+            .back()
+            .map_or(offset_ms, |sample| sample.offset_ms.max(offset_ms));
+        match self.samples.back_mut() {
+            Some(sample) if sample.offset_ms == offset_ms => {
+                sample.damage = sample.damage.saturating_add(damage);
+                sample.healing = sample.healing.saturating_add(healing);
+            }
+            _ => self.samples.push_back(Sample {
+                offset_ms,
+                damage,
+                healing,
+            }),
+        }
+
+        for (window, window_ms) in self.windows.iter_mut().zip(ROLLING_WINDOWS_MS) {
+            window.damage = window.damage.saturating_add(damage);
+            window.healing = window.healing.saturating_add(healing);
+            while let Some(sample) = self.samples.get(window.start)
+                && sample.offset_ms.saturating_add(window_ms) <= offset_ms
+            {
+                window.damage = window.damage.saturating_sub(sample.damage);
+                window.healing = window.healing.saturating_sub(sample.healing);
+                window.start += 1;
+            }
+            if damage > 0 && window.peak.is_none_or(|peak| window.damage > peak.damage) {
+                window.peak = Some(RollingPeak {
+                    damage: window.damage,
+                    end_ms: offset_ms,
+                });
+            }
+        }
+
+        // The widest window holds the oldest sample anyone still needs.
+        let expired = self.windows.last().map_or(0, |window| window.start);
+        self.samples.drain(..expired);
+        for window in &mut self.windows {
+            window.start -= expired;
+        }
+    }
+
+    /// Every window ending at `now_ms`. Hits that aged out since the last one
+    /// are discounted here without being dropped.
+    #[must_use]
+    pub fn windows(&self, now_ms: u64) -> [RollingWindow; ROLLING_WINDOWS_MS.len()] {
+        std::array::from_fn(|index| {
+            let window = &self.windows[index];
+            let window_ms = ROLLING_WINDOWS_MS[index];
+            let mut damage = window.damage;
+            let mut healing = window.healing;
+            for sample in self
+                .samples
+                .range(window.start..)
+                .take_while(|sample| sample.offset_ms.saturating_add(window_ms) <= now_ms)
+            {
+                damage = damage.saturating_sub(sample.damage);
+                healing = healing.saturating_sub(sample.healing);
+            }
+            RollingWindow {
+                window_ms,
+                damage,
+                healing,
+                peak: window.peak,
+            }
+        })
+    }
+
+    /// The damage peak of every window that has seen damage.
+    pub fn peaks(&self) -> impl Iterator<Item = (u64, RollingPeak)> + '_ {
+        ROLLING_WINDOWS_MS
+            .into_iter()
+            .zip(&self.windows)
+            .filter_map(|(window_ms, window)| Some((window_ms, window.peak?)))
+    }
+}
+
+/// `amount` spread over `window_ms`, per second.
+#[must_use]
+pub fn per_second(amount: u128, window_ms: u64) -> u128 {
+    amount.saturating_mul(1_000) / u128::from(window_ms.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::projections::combat::accumulator::CombatHitFlags;
+
+    fn fact(metric: CombatMetric, amount: u128) -> CombatHitFact {
+        CombatHitFact {
+            metric,
+            actor_entity_id: 1,
+            source_entity_id: Some(1),
+            target_entity_id: 2,
+            source_monster_id: None,
+            target_monster_id: None,
+            target_is_boss: false,
+            skill_key: 10,
+            base_skill_id: Some(10),
+            amount,
+            effective_amount: amount,
+            has_loss_breakdown: false,
+            hp_loss: 0,
+            shield_loss: 0,
+            flags: CombatHitFlags::from_bits(0),
+            property: None,
+            damage_mode: None,
+        }
+    }
+
+    #[test]
+    fn windows_trail_the_clock_and_keep_the_best_burst() {
+        let mut rates = RollingRates::default();
+        rates.observe(&fact(CombatMetric::Damage, 100), 0);
+        rates.observe(&fact(CombatMetric::Damage, 400), 2_000);
+        rates.observe(&fact(CombatMetric::Healing, 50), 2_000);
+        rates.observe(&fact(CombatMetric::DamageTaken, 999), 3_000);
+        rates.observe(&fact(CombatMetric::Damage, 300), 6_000);
+        rates.observe(&fact(CombatMetric::Damage, 10), 20_000);
+
+        let [five, fifteen, thirty] = rates.windows(20_000);
+        assert_eq!((five.damage, five.healing), (10, 0));
+        assert_eq!(fifteen.damage, 310);
+        assert_eq!((thirty.damage, thirty.healing), (810, 50));
+        // 400 at 2s and 300 at 6s share a 5s window; the opener does not.
+        assert_eq!(
+            five.peak,
+            Some(RollingPeak {
+                damage: 700,
+                end_ms: 6_000,
+            })
+        );
+        assert_eq!(fifteen.peak.map(|peak| peak.damage), Some(800));
+
+        // Nothing new: the 5s window drains while the peaks stay.
+        let [five, fifteen, _] = rates.windows(26_000);
+        assert_eq!(five.damage, 0);
+        assert_eq!(fifteen.damage, 10);
+        assert_eq!(five.peak.map(|peak| peak.damage), Some(700));
+        assert_eq!(per_second(700, five.window_ms), 140);
+    }
+}
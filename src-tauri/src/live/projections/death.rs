@@ -87,6 +87,9 @@ impl From<&DeathReplaySnapshot> for DeathRecord {
                     buffs: participant.buffs.iter().map(ipc_buff_snapshot).collect(),
                 })
                 .collect(),
+            // Stamped when the victim gets back up, which is after the death
+            // was recorded; see `DeathProjection::revive`.
+            revived_timestamp_ms: None,
         }
     }
 }
@@ -161,7 +164,7 @@ impl DeathProjection {
         hit: &DomainHit,
         fact: Option<&CombatHitFact>,
     ) {
-        if !fact.is_some_and(|fact| fact.metric == CombatMetric::DamageTaken) {
+        if fact.is_none_or(|fact| fact.metric != CombatMetric::DamageTaken) {
             return;
         }
         let queue = self.recent_by_target.entry(hit.target).or_default();
@@ -175,6 +178,25 @@ impl DeathProjection {
         });
     }
 
+    /// Stamps the victim's latest unrevived death with the revive time and
+    /// returns whether a record changed.
+    pub fn revive(&mut self, entity: EntityRef, revived_at_ms: i64) -> bool {
+        let victim = entity.uuid.0.to_string();
+        let Some(record) = self
+            .records
+            .iter_mut()
+            .rev()
+            .find(|record| record.victim_entity_uuid == victim)
+        else {
+            return false;
+        };
+        if record.revived_timestamp_ms.is_some() {
+            return false;
+        }
+        record.revived_timestamp_ms = Some(revived_at_ms.max(0).to_string());
+        true
+    }
+
     #[must_use]
     pub fn snapshot(&self) -> Vec<DeathRecord> {
         self.records.clone()
@@ -388,6 +410,7 @@ mod tests {
                         expires_mono_ms: None,
                         effect_ids: Arc::from([]),
                     }],
+                    complete: false,
                 },
                 ProtocolObservation::BuffSnapshot {
                     target_uuid: ATTACKER.uuid,
@@ -404,6 +427,7 @@ mod tests {
                         expires_mono_ms: None,
                         effect_ids: Arc::from([]),
                     }],
+                    complete: false,
                 },
                 ProtocolObservation::AttributeUpdated {
                     uuid: VICTIM.uuid,
@@ -515,6 +539,31 @@ mod tests {
         assert_eq!(projection.records.len(), 1);
     }
 
+    #[test]
+    fn revive_stamps_only_the_latest_unrevived_death() {
+        let mut projection = DeathProjection::default();
+        assert!(!projection.revive(VICTIM, 1_500));
+
+        apply_hit(&mut projection, 1_000, damage(false));
+        projection.apply(&death_at(1_100, DeathBuffCheckpoint::default()));
+        assert!(projection.revive(VICTIM, 4_000));
+        assert!(!projection.revive(VICTIM, 4_100));
+
+        apply_hit(&mut projection, 5_000, damage(false));
+        projection.apply(&death_at(5_100, DeathBuffCheckpoint::default()));
+        assert!(projection.revive(VICTIM, 9_000));
+
+        let revived = projection
+            .snapshot()
+            .into_iter()
+            .map(|record| record.revived_timestamp_ms)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            revived,
+            vec![Some("4000".to_string()), Some("9000".to_string())]
+        );
+    }
+
     #[test]
     fn ipc_conversion_is_exact_for_wide_damage_and_numeric_ids() {
         let snapshot = DeathReplaySnapshot {
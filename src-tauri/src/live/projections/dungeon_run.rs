@@ -0,0 +1,217 @@
+//! Groups the segments recorded in one instanced scene into a dungeon run.
+//!
+//! Entering a scene only arms a run: it is confirmed as a dungeon by the first
+//! dungeon flow or objective update, and opened at the time the scene was
+//! entered. Leaving the scene (or shutting down) finishes it with its clear
+//! time, objective splits, wipes and player deaths.
+
+use std::collections::HashSet;
+
+use crate::database::dungeon_runs::{DungeonRunSplit, FinalizeDungeonRun, RecordingDungeonRun};
+use crate::live::counter::engine::DUNGEON_FLOW_PLAYING;
+use crate::live::history_writer::HistoryWriterHandle;
+
+/// `EDungeonState::End` and `EDungeonState::Settlement`: the dungeon is done.
+const DUNGEON_FLOW_END: i32 = 4;
+const DUNGEON_FLOW_SETTLEMENT: i32 = 5;
+
+#[derive(Debug, Clone, Copy)]
+struct EnteredScene {
+    scene_id: i32,
+    difficulty: Option<i32>,
+    entered_at_ms: i64,
+}
+
+#[derive(Debug, Default)]
+struct ActiveRun {
+    clear_started_at_ms: Option<i64>,
+    cleared_at_ms: Option<i64>,
+    wipes: u32,
+    deaths: u32,
+    completed_objectives: HashSet<i32>,
+    splits: Vec<DungeonRunSplit>,
+}
+
+#[derive(Debug)]
+pub struct DungeonRunProjection {
+    writer: HistoryWriterHandle,
+    /// The scene entered last; `None` before the first scene change.
+    scene: Option<EnteredScene>,
+    /// Open once the current scene has shown dungeon state.
+    active: Option<ActiveRun>,
+}
+
+impl DungeonRunProjection {
+    pub fn new(writer: HistoryWriterHandle) -> Self {
+        Self {
+            writer,
+            scene: None,
+            active: None,
+        }
+    }
+
+    pub fn enter_scene(
+        &mut self,
+        scene_id: i32,
+        difficulty: Option<i32>,
+        at_ms: i64,
+    ) -> Result<(), String> {
+        self.finish(at_ms)?;
+        self.scene = Some(EnteredScene {
+            scene_id,
+            difficulty,
+            entered_at_ms: at_ms,
+        });
+        Ok(())
+    }
+
+    pub fn observe_flow(&mut self, current: i32, at_ms: i64) -> Result<(), String> {
+        let Some(run) = self.confirm()? else {
+            return Ok(());
+        };
+        match current {
+            DUNGEON_FLOW_PLAYING if run.clear_started_at_ms.is_none() => {
+                run.clear_started_at_ms = Some(at_ms);
+            }
+            DUNGEON_FLOW_END | DUNGEON_FLOW_SETTLEMENT
+                if run.clear_started_at_ms.is_some() && run.cleared_at_ms.is_none() =>
+            {
+                run.cleared_at_ms = Some(at_ms);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Objective snapshots repeat, so each objective splits once, when it is
+    /// first seen complete.
+    pub fn observe_objective(
+        &mut self,
+        target_id: i32,
+        complete: bool,
+        at_ms: i64,
+    ) -> Result<(), String> {
+        let Some(run) = self.confirm()? else {
+            return Ok(());
+        };
+        if complete && run.completed_objectives.insert(target_id) {
+            run.splits.push(DungeonRunSplit {
+                target_id,
+                completed_at_ms: at_ms,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn observe_wipe(&mut self) {
+        if let Some(run) = &mut self.active {
+            run.wipes = run.wipes.saturating_add(1);
+        }
+    }
+
+    pub fn observe_player_death(&mut self) {
+        if let Some(run) = &mut self.active {
+            run.deaths = run.deaths.saturating_add(1);
+        }
+    }
+
+    /// Finishes the open run, if any, and disarms the scene.
+    pub fn finish(&mut self, at_ms: i64) -> Result<(), String> {
+        self.scene = None;
+        let Some(run) = self.active.take() else {
+            return Ok(());
+        };
+        self.writer.finalize_run(FinalizeDungeonRun {
+            ended_at_ms: at_ms,
+            clear_started_at_ms: run.clear_started_at_ms,
+            cleared_at_ms: run.cleared_at_ms,
+            wipes: run.wipes,
+            deaths: run.deaths,
+            objective_splits: run.splits,
+        })
+    }
+
+    /// The open run, opening it first when the entered scene has just shown
+    /// dungeon state. The writer links the segment already open in that
+    /// scene, so it need not be restarted.
+    fn confirm(&mut self) -> Result<Option<&mut ActiveRun>, String> {
+        if self.active.is_none() {
+            let Some(scene) = self.scene else {
+                return Ok(None);
+            };
+            self.writer.begin_run(RecordingDungeonRun {
+                scene_id: scene.scene_id,
+                dungeon_difficulty: scene.difficulty,
+                started_at_ms: scene.entered_at_ms,
+            })?;
+            self.active = Some(ActiveRun::default());
+        }
+        Ok(self.active.as_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::history_writer::RecordedHistory;
+
+    #[test]
+    fn instanced_scene_becomes_a_run_with_clear_time_and_splits() {
+        let (writer, join, recorded) = HistoryWriterHandle::recording().unwrap();
+        let mut projection = DungeonRunProjection::new(writer.clone());
+
+        // A town scene never shows dungeon state and records no run.
+        projection.enter_scene(1, None, 0).unwrap();
+        projection.observe_wipe();
+        projection.enter_scene(9, Some(2), 1_000).unwrap();
+        projection.observe_flow(2, 1_500).unwrap();
+        projection
+            .observe_flow(DUNGEON_FLOW_PLAYING, 3_000)
+            .unwrap();
+        projection.observe_objective(41, false, 4_000).unwrap();
+        projection.observe_objective(41, true, 9_000).unwrap();
+        projection.observe_objective(41, true, 9_500).unwrap();
+        projection.observe_wipe();
+        projection.observe_player_death();
+        projection.observe_player_death();
+        projection.observe_flow(DUNGEON_FLOW_END, 20_000).unwrap();
+        projection
+            .observe_flow(DUNGEON_FLOW_SETTLEMENT, 21_000)
+            .unwrap();
+        projection.enter_scene(1, None, 25_000).unwrap();
+        projection.finish(30_000).unwrap();
+
+        writer.shutdown().unwrap();
+        join.join().unwrap();
+        let recorded = recorded.try_iter().collect::<Vec<_>>();
+        assert_eq!(recorded.len(), 2);
+        let RecordedHistory::BeginRun { run, .. } = &recorded[0] else {
+            panic!("expected the run to begin, got {:?}", recorded[0]);
+        };
+        assert_eq!(
+            run,
+            &RecordingDungeonRun {
+                scene_id: 9,
+                dungeon_difficulty: Some(2),
+                started_at_ms: 1_000,
+            }
+        );
+        let RecordedHistory::FinalizeRun { finalize, .. } = &recorded[1] else {
+            panic!("expected the run to finish, got {:?}", recorded[1]);
+        };
+        assert_eq!(
+            finalize,
+            &FinalizeDungeonRun {
+                ended_at_ms: 25_000,
+                clear_started_at_ms: Some(3_000),
+                cleared_at_ms: Some(20_000),
+                wipes: 1,
+                deaths: 2,
+                objective_splits: vec![DungeonRunSplit {
+                    target_id: 41,
+                    completed_at_ms: 9_000,
+                }],
+            }
+        );
+    }
+}
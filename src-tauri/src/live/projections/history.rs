@@ -4,7 +4,8 @@ use std::collections::HashSet;
 
 use crate::database::event_journal::RecordingEncounter;
 use crate::database::history_codec::{
-    HistoryDeath, HistoryEntityContext, HistoryEnvelope, HistoryEvent, HistoryHit, HistorySkillCast,
+    HistoryBuffInterval, HistoryDeath, HistoryEntityContext, HistoryEnvelope, HistoryEvent,
+    HistoryHit, HistoryPhaseBoundary, HistoryRevive, HistorySkillCast,
 };
 use crate::database::history_query::{quality_flags_to_bits, HistoryQualityFlag};
 use crate::live::history_writer::{HistoryFinalization, HistoryWriterHandle};
@@ -113,6 +114,22 @@ impl HistoryProjection {
                     true,
                 )?;
             }
+            // Only entities with a recorded context can have died here.
+            DomainEvent::Revived { entity }
+                if self
+                    .active
+                    .as_ref()
+                    .is_some_and(|active| active.recorded_contexts.contains(&entity.uuid.0)) =>
+            {
+                self.persist(
+                    segment_offset_ms,
+                    HistoryEvent::Revive(HistoryRevive {
+                        entity_id: entity.uuid.0,
+                        revived_at_ms: envelope.occurred_at_ms,
+                    }),
+                    true,
+                )?;
+            }
             DomainEvent::IdentityChanged { entity, .. }
                 if self
                     .active
@@ -161,6 +178,40 @@ impl HistoryProjection {
         )
     }
 
+    pub fn apply_phase(
+        &mut self,
+        envelope: &DomainEnvelope,
+        boundary: HistoryPhaseBoundary,
+        segment_offset_ms: u64,
+    ) -> Result<(), String> {
+        if !self.accepts(envelope) {
+            return Ok(());
+        }
+        self.persist(
+            segment_offset_ms,
+            HistoryEvent::PhaseBoundary(boundary),
+            true,
+        )
+    }
+
+    /// Persists a closed buff span at its end offset; a no-op between
+    /// segments.
+    pub fn apply_buff_interval(
+        &mut self,
+        interval: HistoryBuffInterval,
+        entities: &EntityContext,
+    ) -> Result<(), String> {
+        if self.active.is_none() {
+            return Ok(());
+        }
+        let offset_ms = interval.end_ms_exclusive;
+        self.ensure_context_id(interval.entity_id, entities, offset_ms)?;
+        if let Some(source_entity_id) = interval.source_entity_id {
+            self.ensure_context_id(source_entity_id, entities, offset_ms)?;
+        }
+        self.persist(offset_ms, HistoryEvent::BuffInterval(interval), true)
+    }
+
     pub fn finalize(
         &mut self,
         segment_id: SegmentId,
@@ -264,7 +315,8 @@ impl HistoryProjection {
             .is_some_and(|active| active.segment_id == segment_id)
     }
 
-    fn add_quality(&mut self, flag: HistoryQualityFlag) {
+    /// Marks the active segment's history; a no-op between segments.
+    pub fn add_quality(&mut self, flag: HistoryQualityFlag) {
         if let Some(active) = &mut self.active {
             active.reducer.add_quality_flags([flag]);
         }
@@ -27,6 +27,7 @@ struct MinimapEntityState {
     monster_id: Option<i32>,
     facing: Option<f32>,
     is_dead: bool,
+    position_epoch: u32,
     owner: Option<EntityUuid>,
     is_boss: bool,
 }
@@ -42,6 +43,7 @@ impl MinimapEntityState {
             monster_id: None,
             facing: None,
             is_dead: false,
+            position_epoch: 0,
             owner: None,
             is_boss: false,
         }
@@ -168,6 +170,19 @@ impl MinimapProjection {
                 }
                 false
             }
+            DomainEvent::Teleported {
+                entity, position, ..
+            } => {
+                let state = self
+                    .entities
+                    .entry(entity.uuid)
+                    .or_insert_with(|| MinimapEntityState::new(*entity, EntityKind::Unknown));
+                if let Some(position) = position {
+                    state.position = Some((position.x, position.y, position.z));
+                }
+                state.position_epoch = state.position_epoch.wrapping_add(1);
+                true
+            }
             DomainEvent::LocalPlayerChanged { previous, current } => {
                 let mut changed = self.local_player != *current;
                 self.local_player = *current;
@@ -223,9 +238,9 @@ impl MinimapProjection {
             DomainEvent::SkillLifecycleChanged {
                 caster,
                 skill_id,
-                phase,
+                phase: SkillPhase::CastStarted | SkillPhase::Observed,
                 ..
-            } if matches!(phase, SkillPhase::CastStarted | SkillPhase::Observed) => {
+            } => {
                 if !self.visible_entities.contains(&caster.uuid) {
                     return false;
                 }
@@ -301,6 +316,7 @@ impl MinimapProjection {
                             monster_id: state.monster_id,
                             facing: state.facing,
                             is_dead: state.is_dead,
+                            position_epoch: state.position_epoch,
                             top_summoner_id: state.owner.map(|owner| owner.0.to_string()),
                         })
                     })
@@ -910,6 +926,70 @@ mod tests {
         assert!(!is_dead(&mut projection));
     }
 
+    #[test]
+    fn teleport_moves_entity_and_starts_a_new_position_epoch() {
+        let mut projection = MinimapProjection::default();
+        projection.apply(&scene(1, 6563));
+        add_positioned_entity(
+            &mut projection,
+            10,
+            TEAMMATE,
+            EntityKind::Character,
+            None,
+            false,
+        );
+        projection.apply(&envelope(
+            20,
+            10_020,
+            DomainEvent::TeamMembershipChanged {
+                entity: TEAMMATE,
+                is_member: true,
+            },
+        ));
+        let teammate = |projection: &mut MinimapProjection| {
+            projection
+                .take_payload()
+                .snapshot
+                .expect("registered scene should publish a snapshot")
+                .entities
+                .into_iter()
+                .find(|entity| entity.entity_uuid == "2")
+                .expect("teammate stays visible")
+        };
+        assert_eq!(teammate(&mut projection).position_epoch, 0);
+
+        assert!(projection.apply(&envelope(
+            21,
+            10_021,
+            DomainEvent::Teleported {
+                entity: TEAMMATE,
+                position: Some(Position {
+                    x: 500.0,
+                    y: 1.0,
+                    z: -500.0,
+                }),
+                forced: false,
+            },
+        )));
+        let moved = teammate(&mut projection);
+        assert_eq!((moved.x, moved.z), (500.0, -500.0));
+        assert_eq!(moved.position_epoch, 1);
+
+        // A pull-back without a position still breaks the trail.
+        projection.apply(&envelope(
+            22,
+            10_022,
+            DomainEvent::Teleported {
+                entity: TEAMMATE,
+                position: None,
+                forced: true,
+            },
+        ));
+        let pulled = teammate(&mut projection);
+        assert_eq!((pulled.x, pulled.z), (500.0, -500.0));
+        assert_eq!(pulled.position_epoch, 2);
+    }
+
     #[test]
     fn emits_skill_cast_nodes_once_and_only_for_visible_casters() {
         let mut projection = MinimapProjection::default();
@@ -1,9 +1,14 @@
+pub mod buff_uptime;
+pub mod capture_health;
 pub mod combat;
 pub mod death;
+pub mod dungeon_run;
 pub mod entity_monitor;
 pub mod history;
 pub mod minimap;
+pub mod phase;
 pub mod presentation;
+pub mod script;
 pub mod skill_cd;
 pub mod timeline;
 pub mod voice;
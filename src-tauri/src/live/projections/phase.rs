@@ -0,0 +1,362 @@
+//! Segment-scoped boss phase segmentation.
+//!
+//! Phase 1 opens with the segment. Later phases open when the tracked boss
+//! crosses an HP threshold, starts a mechanic not seen before, a new dungeon
+//! objective appears, or a different boss takes over after the tracked one is
+//! gone. Each opened phase is returned as a [`HistoryPhaseBoundary`] for the
+//! timeline stream; per-phase totals are kept for the live payload.
+
+use std::collections::HashSet;
+
+use crate::database::history_codec::{HistoryPhaseBoundary, HistoryPhaseTrigger};
+use crate::database::history_query::{EncounterPhaseData, EncounterTotalsData};
+use crate::live::projections::combat::accumulator::{CombatHitFact, CombatMetric, CombatTotals};
+use crate::live::protocol::attrs as attr_type;
+use crate::live::runtime::entity_context::EntityContext;
+use crate::live::runtime::events::EntityUuid;
+
+/// Boss HP percentages that open a phase, highest first.
+const HP_THRESHOLDS_PCT: [i64; 3] = [75, 50, 25];
+/// A new mechanic inside a shorter phase is folded into it, so an opening
+/// burst of mechanics does not produce a row of empty phases.
+const MIN_MECHANIC_PHASE_MS: u64 = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TrackedBoss {
+    entity_id: i64,
+    gone: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Phase {
+    start_ms: u64,
+    trigger: Option<HistoryPhaseTrigger>,
+    detail: Option<i64>,
+    totals: CombatTotals,
+}
+
+impl Phase {
+    fn new(start_ms: u64, trigger: Option<HistoryPhaseTrigger>, detail: Option<i64>) -> Self {
+        Self {
+            start_ms,
+            trigger,
+            detail,
+            totals: CombatTotals::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PhaseProjection {
+    /// Empty outside a segment.
+    phases: Vec<Phase>,
+    boss: Option<TrackedBoss>,
+    /// Index into [`HP_THRESHOLDS_PCT`] of the next threshold to cross.
+    next_hp_threshold: usize,
+    seen_mechanics: HashSet<i32>,
+    /// Dungeon objectives outlive segments, so this is kept across them.
+    objective: Option<i32>,
+}
+
+impl PhaseProjection {
+    pub fn start_segment(&mut self) {
+        self.phases = vec![Phase::new(0, None, None)];
+        self.boss = None;
+        self.next_hp_threshold = 0;
+        self.seen_mechanics.clear();
+    }
+
+    pub fn end_segment(&mut self) {
+        self.phases.clear();
+        self.boss = None;
+    }
+
+    pub fn reset_runtime(&mut self) {
+        self.end_segment();
+        self.objective = None;
+    }
+
+    /// Adds the hit to the current phase after checking for a boss swap.
+    pub fn observe_hit(
+        &mut self,
+        hit: &CombatHitFact,
+        offset_ms: u64,
+        entities: &EntityContext,
+    ) -> Option<HistoryPhaseBoundary> {
+        if self.phases.is_empty() {
+            return None;
+        }
+        let mut boundary = None;
+        if hit.metric == CombatMetric::Damage && hit.target_is_boss {
+            match self.boss {
+                None => self.track_boss(hit.target_entity_id, entities),
+                Some(boss) if boss.gone && boss.entity_id != hit.target_entity_id => {
+                    self.track_boss(hit.target_entity_id, entities);
+                    self.seen_mechanics.clear();
+                    boundary = self.open_phase(
+                        offset_ms,
+                        HistoryPhaseTrigger::BossChanged,
+                        hit.target_monster_id.map(i64::from),
+                    );
+                }
+                Some(_) => {}
+            }
+        }
+        if let Some(phase) = self.phases.last_mut() {
+            phase.totals.apply(hit);
+        }
+        boundary
+    }
+
+    pub fn observe_attribute(
+        &mut self,
+        entity_id: i64,
+        attr_id: i32,
+        value: i64,
+        offset_ms: u64,
+        entities: &EntityContext,
+    ) -> Option<HistoryPhaseBoundary> {
+        if attr_id != attr_type::ATTR_CURRENT_HP
+            || self.boss.is_none_or(|boss| boss.entity_id != entity_id)
+        {
+            return None;
+        }
+        let max_hp = entities
+            .entity(EntityUuid(entity_id))
+            .and_then(|entity| entity.int_attr(attr_type::ATTR_MAX_HP))?;
+        let pct = hp_pct(value, max_hp)?;
+        // Several thresholds crossed in one update open a single phase at the
+        // lowest of them.
+        let mut crossed = None;
+        while let Some(threshold) = HP_THRESHOLDS_PCT.get(self.next_hp_threshold)
+            && pct < *threshold
+        {
+            crossed = Some(*threshold);
+            self.next_hp_threshold += 1;
+        }
+        self.open_phase(offset_ms, HistoryPhaseTrigger::BossHp, Some(crossed?))
+    }
+
+    pub fn observe_boss_gone(&mut self, entity_id: i64) {
+        if let Some(boss) = &mut self.boss
+            && boss.entity_id == entity_id
+        {
+            boss.gone = true;
+        }
+    }
+
+    pub fn observe_mechanic(
+        &mut self,
+        base_skill_id: i32,
+        offset_ms: u64,
+    ) -> Option<HistoryPhaseBoundary> {
+        if self.phases.is_empty() || !self.seen_mechanics.insert(base_skill_id) {
+            return None;
+        }
+        let phase_start_ms = self.phases.last()?.start_ms;
+        if offset_ms.saturating_sub(phase_start_ms) < MIN_MECHANIC_PHASE_MS {
+            return None;
+        }
+        self.open_phase(
+            offset_ms,
+            HistoryPhaseTrigger::BossMechanic,
+            Some(i64::from(base_skill_id)),
+        )
+    }
+
+    /// Only a freshly started objective opens a phase; progress updates and
+    /// the first objective observed are recorded without one.
+    pub fn observe_objective(
+        &mut self,
+        target_id: i32,
+        count: i32,
+        complete: bool,
+        offset_ms: u64,
+    ) -> Option<HistoryPhaseBoundary> {
+        if complete || count != 0 || self.objective == Some(target_id) {
+            return None;
+        }
+        self.objective.replace(target_id)?;
+        self.open_phase(
+            offset_ms,
+            HistoryPhaseTrigger::Objective,
+            Some(i64::from(target_id)),
+        )
+    }
+
+    /// Phases with the last one ending at `now_offset_ms`. Empty while the
+    /// segment is still a single phase.
+    #[must_use]
+    pub fn payload(&self, now_offset_ms: u64) -> Vec<EncounterPhaseData> {
+        if self.phases.len() < 2 {
+            return Vec::new();
+        }
+        let ends = self
+            .phases
+            .iter()
+            .skip(1)
+            .map(|phase| phase.start_ms)
+            .chain(std::iter::once(now_offset_ms));
+        self.phases
+            .iter()
+            .zip(ends)
+            .enumerate()
+            .map(|(index, (phase, end_ms))| {
+                let number = u32::try_from(index + 1).unwrap_or(u32::MAX);
+                EncounterPhaseData {
+                    phase: number,
+                    name: EncounterPhaseData::phase_name(number),
+                    trigger: phase.trigger,
+                    detail: phase.detail,
+                    start_ms: phase.start_ms,
+                    end_ms_exclusive: end_ms.max(phase.start_ms),
+                    totals: EncounterTotalsData::from(&phase.totals),
+                }
+            })
+            .collect()
+    }
+
+    fn track_boss(&mut self, entity_id: i64, entities: &EntityContext) {
+        self.boss = Some(TrackedBoss {
+            entity_id,
+            gone: false,
+        });
+        // A boss first seen below a threshold (late join, swap to a damaged
+        // boss) must not open phases for HP it lost before we saw it.
+        let pct = entities.entity(EntityUuid(entity_id)).and_then(|entity| {
+            hp_pct(
+                entity.int_attr(attr_type::ATTR_CURRENT_HP)?,
+                entity.int_attr(attr_type::ATTR_MAX_HP)?,
+            )
+        });
+        self.next_hp_threshold = pct.map_or(0, |pct| {
+            HP_THRESHOLDS_PCT
+                .iter()
+                .take_while(|threshold| pct < **threshold)
+                .count()
+        });
+    }
+
+    fn open_phase(
+        &mut self,
+        offset_ms: u64,
+        trigger: HistoryPhaseTrigger,
+        detail: Option<i64>,
+    ) -> Option<HistoryPhaseBoundary> {
+        let current = self.phases.last()?;
+        // Two triggers at the same instant describe one transition.
+        if offset_ms <= current.start_ms {
+            return None;
+        }
+        self.phases
+            .push(Phase::new(offset_ms, Some(trigger), detail));
+        Some(HistoryPhaseBoundary {
+            phase: u32::try_from(self.phases.len()).unwrap_or(u32::MAX),
+            trigger,
+            detail,
+            boss_entity_id: self.boss.map(|boss| boss.entity_id),
+        })
+    }
+}
+
+fn hp_pct(current: i64, max: i64) -> Option<i64> {
+    (max > 0).then(|| current.clamp(0, max).saturating_mul(100) / max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::projections::combat::accumulator::CombatHitFlags;
+
+    fn boss_hit(target_entity_id: i64, amount: u128) -> CombatHitFact {
+        CombatHitFact {
+            metric: CombatMetric::Damage,
+            actor_entity_id: 1,
+            source_entity_id: Some(1),
+            target_entity_id,
+            source_monster_id: None,
+            target_monster_id: Some(9_000 + i32::try_from(target_entity_id).unwrap()),
+            target_is_boss: true,
+            skill_key: 10,
+            base_skill_id: Some(10),
+            amount,
+            effective_amount: amount,
+            has_loss_breakdown: false,
+            hp_loss: 0,
+            shield_loss: 0,
+            flags: CombatHitFlags::from_bits(0),
+            property: None,
+            damage_mode: None,
+        }
+    }
+
+    #[test]
+    fn boss_swap_and_new_mechanics_open_phases_with_their_own_totals() {
+        let entities = EntityContext::new();
+        let mut projection = PhaseProjection::default();
+        projection.start_segment();
+
+        assert_eq!(
+            projection.observe_hit(&boss_hit(7, 100), 0, &entities),
+            None
+        );
+        // Too early after the phase start: folded into phase 1.
+        assert_eq!(projection.observe_mechanic(501, 1_000), None);
+        assert_eq!(projection.observe_mechanic(501, 9_000), None);
+        let mechanic = projection
+            .observe_mechanic(502, 9_000)
+            .expect("new mechanic opens phase 2");
+        assert_eq!(mechanic.phase, 2);
+        assert_eq!(mechanic.detail, Some(502));
+        assert_eq!(mechanic.boss_entity_id, Some(7));
+
+        projection.observe_hit(&boss_hit(7, 50), 10_000, &entities);
+        // Another boss is only a swap once the tracked one is gone.
+        assert_eq!(
+            projection.observe_hit(&boss_hit(8, 1), 11_000, &entities),
+            None
+        );
+        projection.observe_boss_gone(7);
+        let swap = projection
+            .observe_hit(&boss_hit(8, 30), 12_000, &entities)
+            .expect("boss swap opens phase 3");
+        assert_eq!(swap.trigger, HistoryPhaseTrigger::BossChanged);
+        assert_eq!(swap.detail, Some(9_008));
+
+        let phases = projection.payload(20_000);
+        assert_eq!(
+            phases
+                .iter()
+                .map(|phase| (
+                    phase.name.as_str(),
+                    phase.start_ms,
+                    phase.end_ms_exclusive,
+                    phase.totals.damage.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("P1", 0, 9_000, "100"),
+                ("P2", 9_000, 12_000, "51"),
+                ("P3", 12_000, 20_000, "30"),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_freshly_started_objectives_open_phases() {
+        let mut projection = PhaseProjection::default();
+        assert_eq!(projection.observe_objective(1, 0, false, 0), None);
+        projection.start_segment();
+        assert_eq!(projection.observe_objective(1, 3, false, 1_000), None);
+        assert_eq!(projection.observe_objective(1, 5, true, 2_000), None);
+        let boundary = projection
+            .observe_objective(2, 0, false, 3_000)
+            .expect("new objective opens phase 2");
+        assert_eq!(boundary.trigger, HistoryPhaseTrigger::Objective);
+        assert_eq!(boundary.detail, Some(2));
+        assert_eq!(projection.payload(3_500).len(), 2);
+
+        projection.end_segment();
+        assert!(projection.payload(4_000).is_empty());
+    }
+}
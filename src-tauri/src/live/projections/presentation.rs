@@ -7,6 +7,7 @@ use crate::live::ipc::models::{
     TeammateFantasyState, TrainingDummyPhase, TrainingDummyState,
 };
 use crate::live::projections::entity_monitor::EntityMonitorSnapshot;
+use crate::live::projections::script::ScriptSnapshot;
 use crate::live::runtime::events::SegmentId;
 use crate::live::runtime::segment::{IdleMode, RecordingMode, SegmentState};
 
@@ -114,9 +115,10 @@ impl PresentationProjection {
         &mut self,
         monitored: &EntityMonitorSnapshot,
         counters: CounterSnapshot,
+        scripts: ScriptSnapshot,
     ) -> LiveStatusPayload {
         self.status_revision = self.status_revision.saturating_add(1);
-        self.status_payload(monitored, counters)
+        self.status_payload(monitored, counters, scripts)
     }
 
     #[must_use]
@@ -125,8 +127,9 @@ impl PresentationProjection {
         &self,
         monitored: &EntityMonitorSnapshot,
         counters: CounterSnapshot,
+        scripts: ScriptSnapshot,
     ) -> LiveStatusPayload {
-        self.status_payload(monitored, counters)
+        self.status_payload(monitored, counters, scripts)
     }
 
     pub fn take_buffs_payload(&mut self, monitored: &EntityMonitorSnapshot) -> LiveBuffsPayload {
@@ -229,6 +232,7 @@ impl PresentationProjection {
         &self,
         monitored: &EntityMonitorSnapshot,
         counters: CounterSnapshot,
+        scripts: ScriptSnapshot,
     ) -> LiveStatusPayload {
         LiveStatusPayload {
             revision: self.status_revision,
@@ -244,6 +248,8 @@ impl PresentationProjection {
             shield_max_hp: monitored.shield_max_hp,
             shield_entries: monitored.shield_entries.clone(),
             fight_resource: monitored.fight_resource.clone(),
+            script_counters: scripts.counters,
+            script_errors: scripts.errors,
         }
     }
 
@@ -353,11 +359,23 @@ mod tests {
     fn peek_does_not_advance_revision() {
         let mut presentation = PresentationProjection::default();
         let monitored = EntityMonitorSnapshot::default();
-        let first = presentation.take_status_payload(&monitored, CounterSnapshot::default());
+        let first = presentation.take_status_payload(
+            &monitored,
+            CounterSnapshot::default(),
+            ScriptSnapshot::default(),
+        );
         assert_eq!(first.revision, 1);
-        let peeked = presentation.peek_status_payload(&monitored, CounterSnapshot::default());
+        let peeked = presentation.peek_status_payload(
+            &monitored,
+            CounterSnapshot::default(),
+            ScriptSnapshot::default(),
+        );
         assert_eq!(peeked.revision, 1);
-        let second = presentation.take_status_payload(&monitored, CounterSnapshot::default());
+        let second = presentation.take_status_payload(
+            &monitored,
+            CounterSnapshot::default(),
+            ScriptSnapshot::default(),
+        );
         assert_eq!(second.revision, 2);
     }
 
@@ -565,6 +583,7 @@ mod tests {
             recent_damages: Vec::new(),
             victim_buffs: Vec::new(),
             participant_buffs: Vec::new(),
+            revived_timestamp_ms: None,
         }
     }
 
@@ -0,0 +1,879 @@
+//! User-supplied Rhai scripts fed with domain events.
+//!
+//! Each script runs in its own sandboxed engine with no module, file or clock
+//! access. Every call is bounded by an operation count and a wall-clock
+//! budget, and all script data by size limits, so a broken script stops with
+//! an error instead of stalling the live owner thread. The scripts also share
+//! a per-second budget, since a script that stays under the per-call limits
+//! can still hog the thread through event volume; when it runs out, the
+//! script that spent the most is stopped. A script that fails once stays
+//! stopped until the scripts are reloaded.
+//!
+//! A script may define `fn init()` (run on load and after every container
+//! reset) and `fn on_event(event)`. Both share the persistent `this` map, and
+//! can call:
+//!
+//! - `counter(name, value)` / `bar(name, value, max)` to publish a HUD value
+//! - `clear(name)` to remove one
+//! - `voice(phrase_id)` / `voice(phrase_id, priority)` to queue a voice cue
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+use parking_lot::Mutex;
+use rhai::{AST, CallFnOptions, Dynamic, Engine, EvalAltResult, ImmutableString, Map, Scope};
+
+use crate::live::ipc::models::{ScriptCounterState, ScriptErrorState};
+use crate::live::runtime::entity_context::EntityContext;
+use crate::live::runtime::events::{
+    BuffTransition, DomainEnvelope, DomainEvent, EntityRef, HitKind, SkillPhase,
+};
+use crate::voice::models::VoiceCueIntent;
+
+pub const MAX_SCRIPTS: usize = 16;
+pub const MAX_SOURCE_BYTES: usize = 64 * 1024;
+const MAX_OPERATIONS_PER_CALL: u64 = 10_000;
+/// Backstop for operations that are individually expensive; the operation
+/// count is what normally stops a runaway script.
+const CALL_TIME_BUDGET: Duration = Duration::from_millis(20);
+const PROGRESS_CHECK_INTERVAL: u64 = 256;
+const SHARED_BUDGET_WINDOW: Duration = Duration::from_secs(1);
+const SHARED_OPERATIONS_PER_WINDOW: u64 = 200_000;
+const SHARED_TIME_PER_WINDOW: Duration = Duration::from_millis(50);
+const MAX_CALL_LEVELS: usize = 16;
+const MAX_EXPR_DEPTH: usize = 64;
+const MAX_FUNCTION_EXPR_DEPTH: usize = 32;
+const MAX_STRING_BYTES: usize = 4 * 1024;
+const MAX_ARRAY_LEN: usize = 1_024;
+const MAX_MAP_LEN: usize = 256;
+const MAX_OUTPUTS_PER_SCRIPT: usize = 32;
+const MAX_OUTPUT_NAME_CHARS: usize = 64;
+const MAX_CUES_PER_CALL: usize = 4;
+const DEFAULT_CUE_PRIORITY: u8 = 1;
+const INIT_FN: &str = "init";
+const EVENT_FN: &str = "on_event";
+
+/// One `.rhai` file; `id` is its file stem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserScript {
+    pub id: String,
+    pub source: String,
+}
+
+/// Script output folded into the status topic.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptSnapshot {
+    pub counters: Vec<ScriptCounterState>,
+    pub errors: Vec<ScriptErrorState>,
+}
+
+/// Compiles `source` in the same sandbox the live runtime uses, for reporting
+/// syntax errors when the scripts are loaded.
+pub fn check_script(source: &str) -> Result<(), String> {
+    if source.len() > MAX_SOURCE_BYTES {
+        return Err(format!("脚本超过 {MAX_SOURCE_BYTES} 字节"));
+    }
+    let engine = sandboxed_engine("check", &ScriptHost::default());
+    engine
+        .compile(source)
+        .map(|_| ())
+        .map_err(|error| error.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ScriptOutput {
+    value: f64,
+    max: Option<f64>,
+}
+
+#[derive(Debug, Default)]
+struct ScriptEffects {
+    outputs: BTreeMap<String, ScriptOutput>,
+    cues: Vec<(String, u8)>,
+    outputs_changed: bool,
+}
+
+/// State shared between a script's engine callbacks and its owner.
+#[derive(Debug, Default, Clone)]
+struct ScriptHost {
+    effects: Arc<Mutex<ScriptEffects>>,
+    deadline: Arc<Mutex<Option<Instant>>>,
+    /// Operations used by the current or last call.
+    operations: Arc<AtomicU64>,
+}
+
+struct ScriptInstance {
+    id: String,
+    engine: Engine,
+    /// `None` once the script failed to compile or stopped on an error.
+    ast: Option<AST>,
+    has_init: bool,
+    has_on_event: bool,
+    this: Dynamic,
+    host: ScriptHost,
+    error: Option<String>,
+    /// Spent in the current shared budget window.
+    window_operations: u64,
+    window_time: Duration,
+}
+
+impl ScriptInstance {
+    fn load(script: &UserScript) -> Self {
+        let host = ScriptHost::default();
+        let engine = sandboxed_engine(&script.id, &host);
+        let mut instance = Self {
+            id: script.id.clone(),
+            engine,
+            ast: None,
+            has_init: false,
+            has_on_event: false,
+            this: Dynamic::from_map(Map::new()),
+            host,
+            error: None,
+            window_operations: 0,
+            window_time: Duration::ZERO,
+        };
+        if script.source.len() > MAX_SOURCE_BYTES {
+            instance.error = Some(format!("脚本超过 {MAX_SOURCE_BYTES} 字节"));
+            return instance;
+        }
+        match instance.engine.compile(&script.source) {
+            Ok(ast) => {
+                let has_fn = |name: &str, arity: usize| {
+                    ast.iter_functions()
+                        .any(|function| function.name == name && function.params.len() == arity)
+                };
+                instance.has_init = has_fn(INIT_FN, 0);
+                instance.has_on_event = has_fn(EVENT_FN, 1);
+                instance.ast = Some(ast);
+            }
+            Err(error) => instance.error = Some(error.to_string()),
+        }
+        instance
+    }
+
+    /// Clears state and outputs, then runs `init()`. Returns the cues it queued.
+    fn init(&mut self) -> Vec<(String, u8)> {
+        self.this = Dynamic::from_map(Map::new());
+        self.host.effects.lock().outputs.clear();
+        if self.has_init {
+            self.call(INIT_FN, ())
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn call(&mut self, name: &str, args: impl rhai::FuncArgs) -> Vec<(String, u8)> {
+        let Some(ast) = self.ast.as_ref() else {
+            return Vec::new();
+        };
+        let started = Instant::now();
+        *self.host.deadline.lock() = Some(started + CALL_TIME_BUDGET);
+        self.host.operations.store(0, Ordering::Relaxed);
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .rewind_scope(true)
+            .bind_this_ptr(&mut self.this);
+        let result = self.engine.call_fn_with_options::<Dynamic>(
+            options,
+            &mut Scope::new(),
+            ast,
+            name,
+            args,
+        );
+        *self.host.deadline.lock() = None;
+        self.window_time += started.elapsed();
+        self.window_operations += self.host.operations.load(Ordering::Relaxed);
+        let cues = std::mem::take(&mut self.host.effects.lock().cues);
+        match result {
+            Ok(_) => cues,
+            Err(error) => {
+                self.stop(&error);
+                Vec::new()
+            }
+        }
+    }
+
+    fn stop(&mut self, error: impl std::fmt::Display) {
+        warn!(
+            target: "app::live",
+            "user_script_stopped script_id={} error={error}",
+            self.id
+        );
+        self.ast = None;
+        self.error = Some(error.to_string());
+        let mut effects = self.host.effects.lock();
+        effects.outputs.clear();
+        effects.outputs_changed = true;
+    }
+
+    fn take_outputs_changed(&self) -> bool {
+        std::mem::take(&mut self.host.effects.lock().outputs_changed)
+    }
+}
+
+/// Runs the loaded user scripts and collects what they publish.
+#[derive(Default)]
+pub struct ScriptProjection {
+    scripts: Vec<ScriptInstance>,
+    pending_cues: Vec<VoiceCueIntent>,
+    window_started: Option<Instant>,
+}
+
+impl std::fmt::Debug for ScriptProjection {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("ScriptProjection")
+            .field(
+                "scripts",
+                &self
+                    .scripts
+                    .iter()
+                    .map(|script| script.id.as_str())
+                    .collect::<Vec<_>>(),
+            )
+            .field("pending_cues", &self.pending_cues)
+            .finish()
+    }
+}
+
+impl ScriptProjection {
+    /// Replaces every loaded script and runs each `init()`. Scripts beyond
+    /// [`MAX_SCRIPTS`] are ignored.
+    pub fn apply_scripts(&mut self, scripts: &[UserScript], now_wall_ms: i64) {
+        self.scripts = scripts
+            .iter()
+            .take(MAX_SCRIPTS)
+            .map(ScriptInstance::load)
+            .collect();
+        self.pending_cues.clear();
+        self.window_started = None;
+        self.init_all(now_wall_ms);
+    }
+
+    /// Feeds one event to every running script. Returns whether the status
+    /// topic changed.
+    pub fn apply(&mut self, envelope: &DomainEnvelope, entities: &EntityContext) -> bool {
+        if !self
+            .scripts
+            .iter()
+            .any(|script| script.ast.is_some() && script.has_on_event)
+        {
+            return false;
+        }
+        let Some(event) = event_map(envelope, entities) else {
+            return false;
+        };
+        self.roll_budget_window(Instant::now());
+        let mut changed = false;
+        for script in &mut self.scripts {
+            if script.ast.is_none() || !script.has_on_event {
+                continue;
+            }
+            let cues = script.call(EVENT_FN, (Dynamic::from_map(event.clone()),));
+            push_cues(
+                &mut self.pending_cues,
+                &script.id,
+                cues,
+                envelope.occurred_at_ms,
+            );
+            changed |= script.take_outputs_changed();
+        }
+        changed | self.enforce_shared_budget()
+    }
+
+    /// Starts every running script over, as after a fresh load.
+    pub fn reset_runtime(&mut self, now_wall_ms: i64) {
+        self.init_all(now_wall_ms);
+    }
+
+    pub fn take_cues(&mut self) -> Vec<VoiceCueIntent> {
+        std::mem::take(&mut self.pending_cues)
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> ScriptSnapshot {
+        let mut snapshot = ScriptSnapshot::default();
+        for script in &self.scripts {
+            if let Some(message) = &script.error {
+                snapshot.errors.push(ScriptErrorState {
+                    script_id: script.id.clone(),
+                    message: message.clone(),
+                });
+            }
+            let effects = script.host.effects.lock();
+            snapshot
+                .counters
+                .extend(
+                    effects
+                        .outputs
+                        .iter()
+                        .map(|(name, output)| ScriptCounterState {
+                            script_id: script.id.clone(),
+                            name: name.clone(),
+                            value: output.value,
+                            max: output.max,
+                        }),
+                );
+        }
+        snapshot
+    }
+
+    fn roll_budget_window(&mut self, now: Instant) {
+        if self
+            .window_started
+            .is_some_and(|started| now.duration_since(started) < SHARED_BUDGET_WINDOW)
+        {
+            return;
+        }
+        self.window_started = Some(now);
+        for script in &mut self.scripts {
+            script.window_operations = 0;
+            script.window_time = Duration::ZERO;
+        }
+    }
+
+    /// Stops the heaviest scripts until the window is back under budget.
+    /// Returns whether any script was stopped.
+    fn enforce_shared_budget(&mut self) -> bool {
+        let mut stopped = false;
+        loop {
+            let running = || self.scripts.iter().filter(|script| script.ast.is_some());
+            let operations: u64 = running().map(|script| script.window_operations).sum();
+            let time: Duration = running().map(|script| script.window_time).sum();
+            let running = self
+                .scripts
+                .iter_mut()
+                .filter(|script| script.ast.is_some());
+            let offender = if operations > SHARED_OPERATIONS_PER_WINDOW {
+                running.max_by_key(|script| script.window_operations)
+            } else if time > SHARED_TIME_PER_WINDOW {
+                running.max_by_key(|script| script.window_time)
+            } else {
+                return stopped;
+            };
+            let Some(offender) = offender else {
+                return stopped;
+            };
+            offender.stop(format!(
+                "used the most of the shared script budget ({} operations, {} ms) in one second",
+                offender.window_operations,
+                offender.window_time.as_millis()
+            ));
+            offender.take_outputs_changed();
+            stopped = true;
+        }
+    }
+
+    fn init_all(&mut self, now_wall_ms: i64) {
+        for script in &mut self.scripts {
+            let cues = script.init();
+            push_cues(&mut self.pending_cues, &script.id, cues, now_wall_ms);
+            script.take_outputs_changed();
+        }
+    }
+}
+
+fn push_cues(
+    pending: &mut Vec<VoiceCueIntent>,
+    script_id: &str,
+    cues: Vec<(String, u8)>,
+    triggered_at_ms: i64,
+) {
+    pending.extend(
+        cues.into_iter()
+            .map(|(phrase_id, priority)| VoiceCueIntent {
+                rule_id: format!("script:{script_id}"),
+                phrase_id,
+                priority,
+                triggered_at_ms,
+            }),
+    );
+}
+
+fn sandboxed_engine(script_id: &str, host: &ScriptHost) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS_PER_CALL);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.set_max_expr_depths(MAX_EXPR_DEPTH, MAX_FUNCTION_EXPR_DEPTH);
+    engine.set_max_string_size(MAX_STRING_BYTES);
+    engine.set_max_array_size(MAX_ARRAY_LEN);
+    engine.set_max_map_size(MAX_MAP_LEN);
+
+    let deadline = Arc::clone(&host.deadline);
+    let used = Arc::clone(&host.operations);
+    engine.on_progress(move |operations| {
+        used.store(operations, Ordering::Relaxed);
+        if !operations.is_multiple_of(PROGRESS_CHECK_INTERVAL) {
+            return None;
+        }
+        deadline
+            .lock()
+            .is_some_and(|deadline| Instant::now() >= deadline)
+            .then(|| Dynamic::from("time budget exceeded"))
+    });
+    let print_id = script_id.to_string();
+    engine.on_print(move |text| {
+        debug!(target: "app::live", "user_script_print script_id={print_id} text={text}");
+    });
+    let debug_id = script_id.to_string();
+    engine.on_debug(move |text, _, position| {
+        debug!(
+            target: "app::live",
+            "user_script_debug script_id={debug_id} position={position} text={text}"
+        );
+    });
+
+    let effects = Arc::clone(&host.effects);
+    engine.register_fn(
+        "counter",
+        move |name: ImmutableString, value: Dynamic| -> Result<(), Box<EvalAltResult>> {
+            set_output(&effects, &name, number(&value)?, None)
+        },
+    );
+    let effects = Arc::clone(&host.effects);
+    engine.register_fn(
+        "bar",
+        move |name: ImmutableString,
+              value: Dynamic,
+              max: Dynamic|
+              -> Result<(), Box<EvalAltResult>> {
+            set_output(&effects, &name, number(&value)?, Some(number(&max)?))
+        },
+    );
+    let effects = Arc::clone(&host.effects);
+    engine.register_fn("clear", move |name: ImmutableString| {
+        let mut effects = effects.lock();
+        if effects.outputs.remove(name.as_str()).is_some() {
+            effects.outputs_changed = true;
+        }
+    });
+    let effects = Arc::clone(&host.effects);
+    engine.register_fn(
+        "voice",
+        move |phrase_id: ImmutableString| -> Result<(), Box<EvalAltResult>> {
+            queue_cue(&effects, &phrase_id, DEFAULT_CUE_PRIORITY)
+        },
+    );
+    let effects = Arc::clone(&host.effects);
+    engine.register_fn(
+        "voice",
+        move |phrase_id: ImmutableString, priority: i64| -> Result<(), Box<EvalAltResult>> {
+            queue_cue(
+                &effects,
+                &phrase_id,
+                priority.clamp(0, i64::from(u8::MAX)) as u8,
+            )
+        },
+    );
+    engine
+}
+
+fn number(value: &Dynamic) -> Result<f64, Box<EvalAltResult>> {
+    value
+        .as_float()
+        .or_else(|_| value.as_int().map(|value| value as f64))
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| format!("expected a finite number, got {}", value.type_name()).into())
+}
+
+fn set_output(
+    effects: &Mutex<ScriptEffects>,
+    name: &str,
+    value: f64,
+    max: Option<f64>,
+) -> Result<(), Box<EvalAltResult>> {
+    if name.is_empty() || name.chars().count() > MAX_OUTPUT_NAME_CHARS {
+        return Err(format!("output name must be 1-{MAX_OUTPUT_NAME_CHARS} characters").into());
+    }
+    let mut effects = effects.lock();
+    if !effects.outputs.contains_key(name) && effects.outputs.len() >= MAX_OUTPUTS_PER_SCRIPT {
+        return Err(
+            format!("a script can publish at most {MAX_OUTPUTS_PER_SCRIPT} outputs").into(),
+        );
+    }
+    let output = ScriptOutput { value, max };
+    if effects.outputs.get(name) != Some(&output) {
+        effects.outputs.insert(name.to_string(), output);
+        effects.outputs_changed = true;
+    }
+    Ok(())
+}
+
+fn queue_cue(
+    effects: &Mutex<ScriptEffects>,
+    phrase_id: &str,
+    priority: u8,
+) -> Result<(), Box<EvalAltResult>> {
+    let mut effects = effects.lock();
+    if effects.cues.len() >= MAX_CUES_PER_CALL {
+        return Err(format!("at most {MAX_CUES_PER_CALL} voice cues per call").into());
+    }
+    effects.cues.push((phrase_id.to_string(), priority));
+    Ok(())
+}
+
+/// The script-facing view of an event, or `None` for events scripts do not see.
+/// Entities are identified by their UUID.
+fn event_map(envelope: &DomainEnvelope, entities: &EntityContext) -> Option<Map> {
+    let local_player = entities.local_player().map(|entity| entity.uuid);
+    let is_local =
+        |entity: Option<EntityRef>| entity.is_some_and(|entity| Some(entity.uuid) == local_player);
+    let mut map = Map::new();
+    let kind = match &envelope.event {
+        DomainEvent::CombatHitAccepted(hit) => {
+            insert_entity(&mut map, "source", hit.source);
+            insert_entity(&mut map, "target", Some(hit.target));
+            insert(&mut map, "skill_id", optional(hit.skill_id.map(i64::from)));
+            insert(&mut map, "skill_key", hit.skill_key);
+            insert(&mut map, "heal", hit.kind == HitKind::Healing);
+            insert(&mut map, "amount", hit.amount.min(i64::MAX as u128) as i64);
+            insert(&mut map, "target_is_boss", hit.target_is_boss);
+            insert(
+                &mut map,
+                "target_monster_id",
+                optional(hit.target_monster_id.map(i64::from)),
+            );
+            insert(&mut map, "by_local_player", hit.source_is_local_player);
+            insert(&mut map, "on_local_player", is_local(Some(hit.target)));
+            "hit"
+        }
+        DomainEvent::BuffChanged(buff) => {
+            let transition = match buff.transition {
+                BuffTransition::Baseline => "baseline",
+                BuffTransition::Applied => "applied",
+                BuffTransition::Refreshed => "refreshed",
+                BuffTransition::LayerChanged => "layer_changed",
+                BuffTransition::Removed => "removed",
+            };
+            insert(&mut map, "transition", transition);
+            insert(&mut map, "buff_id", i64::from(buff.state.base_id));
+            insert(&mut map, "layer", i64::from(buff.state.layer));
+            insert_entity(&mut map, "target", Some(buff.state.target));
+            insert_entity(&mut map, "source", buff.state.source);
+            insert(
+                &mut map,
+                "duration_ms",
+                optional(
+                    buff.state
+                        .duration_ms
+                        .map(|duration| duration.min(i64::MAX as u64) as i64),
+                ),
+            );
+            insert(
+                &mut map,
+                "on_local_player",
+                buff.target_roles.is_local_player,
+            );
+            insert(&mut map, "by_local_player", is_local(buff.state.source));
+            "buff"
+        }
+        DomainEvent::SkillLifecycleChanged {
+            caster,
+            skill_id,
+            phase,
+            target,
+        } => {
+            let phase = match phase {
+                SkillPhase::CastStarted => "cast_started",
+                SkillPhase::DurationStarted => "duration_started",
+                SkillPhase::DurationEnded => "duration_ended",
+                SkillPhase::Completed => "completed",
+                SkillPhase::Observed => "observed",
+            };
+            insert(&mut map, "phase", phase);
+            insert(&mut map, "skill_id", i64::from(*skill_id));
+            insert_entity(&mut map, "caster", Some(*caster));
+            insert_entity(&mut map, "target", *target);
+            insert(&mut map, "by_local_player", is_local(Some(*caster)));
+            "skill"
+        }
+        DomainEvent::DeathOccurred {
+            victim,
+            killer,
+            skill_key,
+            ..
+        } => {
+            insert_entity(&mut map, "victim", Some(*victim));
+            insert_entity(&mut map, "killer", *killer);
+            insert(&mut map, "skill_key", optional(*skill_key));
+            insert(&mut map, "on_local_player", is_local(Some(*victim)));
+            "death"
+        }
+        DomainEvent::BossMechanicStarted(mechanic) => {
+            insert(&mut map, "skill_id", i64::from(mechanic.base_skill_id));
+            insert(&mut map, "effect_id", i64::from(mechanic.skill_effect_id));
+            insert(
+                &mut map,
+                "duration_ms",
+                mechanic.duration_ms.min(i64::MAX as u64) as i64,
+            );
+            "boss_mechanic"
+        }
+        // Any non-skill slot. The id is the bar position rather than the
+        // item, so usage stats need the script to know its own bar layout.
+        DomainEvent::LocalSlotUsed { user, slot_id } => {
+            insert_entity(&mut map, "user", Some(*user));
+            insert(&mut map, "slot_id", i64::from(*slot_id));
+            "slot_used"
+        }
+        DomainEvent::SegmentStarted { segment_id, .. } => {
+            insert(&mut map, "segment_id", segment_id.0 as i64);
+            "segment_started"
+        }
+        DomainEvent::SegmentEnded { segment_id, .. } => {
+            insert(&mut map, "segment_id", segment_id.0 as i64);
+            "segment_ended"
+        }
+        _ => return None,
+    };
+    insert(&mut map, "kind", kind);
+    insert(&mut map, "at_ms", envelope.occurred_at_ms);
+    Some(map)
+}
+
+fn insert(map: &mut Map, key: &str, value: impl Into<Dynamic>) {
+    map.insert(key.into(), value.into());
+}
+
+fn insert_entity(map: &mut Map, key: &str, entity: Option<EntityRef>) {
+    insert(map, key, optional(entity.map(|entity| entity.uuid.0)));
+}
+
+/// Missing values reach scripts as `()`.
+fn optional(value: Option<i64>) -> Dynamic {
+    value.map_or(Dynamic::UNIT, Dynamic::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::runtime::events::{
+        BatchId, EntityUuid, EventMeta, MonoTimeMs, ProtocolBatch, ProtocolObservation, SegmentId,
+        SegmentReason,
+    };
+
+    fn script(id: &str, source: &str) -> UserScript {
+        UserScript {
+            id: id.to_string(),
+            source: source.to_string(),
+        }
+    }
+
+    fn loaded(source: &str) -> ScriptProjection {
+        let mut projection = ScriptProjection::default();
+        projection.apply_scripts(&[script("test", source)], 0);
+        projection
+    }
+
+    #[test]
+    fn init_publishes_counters_and_bars() {
+        let projection = loaded(
+            r#"
+            fn init() {
+                this.stacks = 2;
+                counter("stacks", this.stacks);
+                bar("gauge", 1.5, 4);
+            }
+            "#,
+        );
+        let snapshot = projection.snapshot();
+        assert!(snapshot.errors.is_empty(), "{:?}", snapshot.errors);
+        assert_eq!(
+            snapshot.counters,
+            vec![
+                ScriptCounterState {
+                    script_id: "test".to_string(),
+                    name: "gauge".to_string(),
+                    value: 1.5,
+                    max: Some(4.0),
+                },
+                ScriptCounterState {
+                    script_id: "test".to_string(),
+                    name: "stacks".to_string(),
+                    value: 2.0,
+                    max: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn state_persists_between_calls_and_resets_on_init() {
+        let mut projection = loaded(
+            r#"
+            fn init() { this.calls = 0; }
+            fn on_event(event) {
+                this.calls += 1;
+                counter("calls", this.calls);
+                if this.calls == 2 { voice("twice", 3); }
+            }
+            "#,
+        );
+        let script = &mut projection.scripts[0];
+        assert!(
+            script
+                .call(EVENT_FN, (Dynamic::from_map(Map::new()),))
+                .is_empty()
+        );
+        assert_eq!(
+            script.call(EVENT_FN, (Dynamic::from_map(Map::new()),)),
+            vec![("twice".to_string(), 3)]
+        );
+        assert!(script.take_outputs_changed());
+        assert_eq!(projection.snapshot().counters[0].value, 2.0);
+
+        projection.reset_runtime(0);
+        assert!(projection.snapshot().counters.is_empty());
+    }
+
+    #[test]
+    fn slot_use_reaches_scripts() {
+        let mut projection = loaded(
+            r#"
+            fn init() { this.used = 0; }
+            fn on_event(event) {
+                if event.kind == "slot_used" && event.slot_id == 11 {
+                    this.used += 1;
+                    counter("potions", this.used);
+                }
+            }
+            "#,
+        );
+        let meta = EventMeta {
+            batch_id: BatchId(1),
+            capture_sequence: 1,
+            stream_id: 1,
+            stream_epoch: 1,
+            captured_wall_ms: 10_000,
+            captured_mono_ns: 1_000_000,
+            source_time_ms: None,
+        };
+        let mut entities = EntityContext::new();
+        let used = entities.apply_batch(ProtocolBatch {
+            meta,
+            observations: vec![
+                ProtocolObservation::LocalPlayerChanged {
+                    uuid: Some(EntityUuid(7)),
+                },
+                ProtocolObservation::LocalSlotUsed { slot_id: 11 },
+            ],
+        });
+        let changed = used.iter().fold(false, |changed, envelope| {
+            projection.apply(envelope, &entities) | changed
+        });
+        assert!(changed);
+        assert_eq!(projection.snapshot().counters[0].value, 1.0);
+    }
+
+    #[test]
+    fn runaway_scripts_stop_with_an_error() {
+        let mut projection = loaded("fn on_event(event) { loop { } }");
+        let script = &mut projection.scripts[0];
+        script.call(EVENT_FN, (Dynamic::from_map(Map::new()),));
+        assert!(script.ast.is_none());
+
+        let snapshot = projection.snapshot();
+        assert_eq!(snapshot.errors.len(), 1);
+        assert_eq!(snapshot.errors[0].script_id, "test");
+    }
+
+    #[test]
+    fn the_busiest_script_stops_when_the_shared_budget_runs_out() {
+        let mut projection = ScriptProjection::default();
+        projection.apply_scripts(
+            &[
+                // An empty loop body would be optimized away.
+                script(
+                    "busy",
+                    "fn on_event(event) { let total = 0; for i in 0..2000 { total += i; } total }",
+                ),
+                script(
+                    "light",
+                    r#"fn on_event(event) { counter("kind", event.kind.len()); }"#,
+                ),
+            ],
+            0,
+        );
+        let envelope = DomainEnvelope {
+            sequence: 1,
+            batch_id: BatchId(1),
+            occurred_at_ms: 10_000,
+            meta: EventMeta {
+                batch_id: BatchId(1),
+                capture_sequence: 1,
+                stream_id: 1,
+                stream_epoch: 1,
+                captured_wall_ms: 10_000,
+                captured_mono_ns: 1_000_000,
+                source_time_ms: None,
+            },
+            event_index: 0,
+            segment_id: Some(SegmentId(1)),
+            event: DomainEvent::SegmentStarted {
+                segment_id: SegmentId(1),
+                reason: SegmentReason::Manual,
+                started_at_mono_ms: MonoTimeMs(1),
+                started_at_wall_ms: 10_000,
+            },
+        };
+        let entities = EntityContext::new();
+        // Each call stays far below the per-call limits; only the shared
+        // budget can stop it.
+        for _ in 0..1_000 {
+            projection.apply(&envelope, &entities);
+            if projection.scripts[0].ast.is_none() {
+                break;
+            }
+        }
+        assert!(projection.scripts[0].ast.is_none());
+        assert!(projection.scripts[1].ast.is_some());
+
+        let snapshot = projection.snapshot();
+        assert_eq!(snapshot.errors.len(), 1);
+        assert_eq!(snapshot.errors[0].script_id, "busy");
+        assert_eq!(snapshot.counters.len(), 1);
+    }
+
+    #[test]
+    fn oversized_data_and_output_floods_are_rejected() {
+        let mut projection = loaded(
+            r#"
+            fn on_event(event) {
+                let text = "x";
+                for i in 0..20 { text += text; }
+            }
+            "#,
+        );
+        projection.scripts[0].call(EVENT_FN, (Dynamic::from_map(Map::new()),));
+        assert!(projection.scripts[0].ast.is_none());
+
+        let projection = loaded(r#"fn init() { for i in 0..100 { counter(`c${i}`, i); } }"#);
+        let snapshot = projection.snapshot();
+        assert!(snapshot.counters.is_empty());
+        assert_eq!(snapshot.errors.len(), 1);
+    }
+
+    #[test]
+    fn compile_errors_are_reported_per_script() {
+        assert!(check_script("fn on_event(event) { counter(\"a\", 1) ").is_err());
+        assert!(check_script("fn on_event(event) { counter(\"a\", 1); }").is_ok());
+        assert!(check_script("import \"fs\" as fs;").is_err());
+
+        let mut projection = ScriptProjection::default();
+        projection.apply_scripts(
+            &[
+                script("broken", "fn init( {"),
+                script("fine", "fn init() {}"),
+            ],
+            0,
+        );
+        let snapshot = projection.snapshot();
+        assert_eq!(snapshot.errors.len(), 1);
+        assert_eq!(snapshot.errors[0].script_id, "broken");
+    }
+}
@@ -62,9 +62,9 @@ pub fn classify_marker(
         DomainEvent::SkillLifecycleChanged {
             caster,
             skill_id,
-            phase,
+            phase: SkillPhase::CastStarted | SkillPhase::Observed,
             ..
-        } if matches!(phase, SkillPhase::CastStarted | SkillPhase::Observed) => {
+        } => {
             let key_skill = is_key_skill_marker(*skill_id);
             let boss_skill = entities
                 .entity(caster.uuid)
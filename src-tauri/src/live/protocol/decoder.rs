@@ -7,27 +7,30 @@
 //!
 //! The decoder is deliberately close to stateless: it only keeps what cannot
 //! be reconstructed from a single packet (the season-cultivate baseline the
-//! dirty-wire deltas patch, and the server clock offset used for buff times).
-//! Entity, buff, passive and team tables live in `EntityContext`.
+//! dirty-wire deltas patch, and the server clock offset used for buff times)
+//! plus its stream's schema drift counters. Entity, buff, passive and team
+//! tables live in `EntityContext`.
 
 use crate::live::damage_id;
 use crate::live::entity_id::{canonical_player_uuid, entity_type_bits};
 use crate::live::monster_registry::{self, MonsterType};
 use crate::live::protocol::MARKER_SKILL_ID_BASE;
 use crate::live::protocol::attrs as attr_type;
+use crate::live::protocol::schema_drift::{SchemaDrift, SchemaDriftLog};
 use crate::live::runtime::events::{
-    AttributeValue, BatchId, BossMechanicObservation, CaptureEnvelope, EntityIdentityPatch,
-    EntityKind, EntityUuid, FieldPatch, GameTimerKey, GameTimerState, HateEntry, HitChannel,
-    HitKind, LOCAL_PLAYER, MonoTimeMs, ObservationOrigin, ObservedBuff, ObservedBuffChange,
-    ObservedHit, PacketDirection, PassiveSkillObservation, Position, ProtocolBatch,
-    ProtocolObservation, ShieldDetail, SkillCooldownState, SkillPhase,
+    AttributeValue, BatchId, BossMechanicObservation, CaptureEnvelope, CaptureIssue,
+    EntityIdentityPatch, EntityKind, EntityUuid, FieldPatch, GameTimerKey, GameTimerState,
+    HateEntry, HitChannel, HitKind, LOCAL_PLAYER, MonoTimeMs, ObservationOrigin, ObservedBuff,
+    ObservedBuffChange, ObservedHit, PacketDirection, PassiveSkillObservation, Position,
+    ProtocolBatch, ProtocolObservation, ShieldDetail, SkillCooldownState, SkillPhase,
 };
 use crate::packets::opcodes::{
     GRPC_TEAM_NTF_SERVICE_ID, Pkt, WORLD_CALL_SERVICE_ID, WORLD_NTF_SERVICE_ID, grpc_team_method,
     world_call_method,
 };
 use crate::packets::packet_process::{
-    SYNTHETIC_DECODE_ISSUE_OPCODE, SYNTHETIC_REASSEMBLY_RESET_OPCODE, SYNTHETIC_STREAM_GAP_OPCODE,
+    CaptureDecodeIssueCategory, SYNTHETIC_BACKPRESSURE_STALL_OPCODE, SYNTHETIC_DECODE_ISSUE_OPCODE,
+    SYNTHETIC_REASSEMBLY_RESET_OPCODE, SYNTHETIC_STREAM_GAP_OPCODE,
 };
 use blueprotobuf_lib::blueprotobuf;
 use bytes::Buf;
@@ -79,6 +82,77 @@ pub struct ProtocolDecoder {
     season_data: Option<blueprotobuf::SeasonCultivateLineData>,
     /// `captured_wall_ms - server_ms`, used to project buff create times.
     server_clock_offset_ms: Option<i64>,
+    /// Fields of this stream's payloads the generated schema does not know.
+    schema_drift: SchemaDrift,
+}
+
+/// Decoders for each capture stream kept at once; the least recently used
+/// one is dropped past this (a game reconnect abandons its old stream).
+const MAX_STREAM_DECODERS: usize = 32;
+
+/// One [`ProtocolDecoder`] per capture stream, so the per-connection state of
+/// one game client never patches another client's packets.
+#[derive(Default)]
+pub struct StreamDecoders {
+    decoders: HashMap<u64, (ProtocolDecoder, u64)>,
+    /// Where each stream's schema drift report goes, when anyone reads it.
+    schema_drift_log: Option<SchemaDriftLog>,
+}
+
+impl StreamDecoders {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decoders that publish their schema drift reports to `log`.
+    #[must_use]
+    pub fn with_schema_drift_log(log: SchemaDriftLog) -> Self {
+        Self {
+            decoders: HashMap::new(),
+            schema_drift_log: Some(log),
+        }
+    }
+
+    #[must_use]
+    pub fn decode(&mut self, envelope: CaptureEnvelope) -> ProtocolBatch {
+        if !self.decoders.contains_key(&envelope.stream_id)
+            && self.decoders.len() >= MAX_STREAM_DECODERS
+            && let Some(stale) = self
+                .decoders
+                .iter()
+                .min_by_key(|(_, (_, last_sequence))| *last_sequence)
+                .map(|(stream_id, _)| *stream_id)
+            && let Some((decoder, _)) = self.decoders.remove(&stale)
+            && let Some(log) = &self.schema_drift_log
+        {
+            log.publish(stale, decoder.schema_drift.report());
+        }
+        let stream_id = envelope.stream_id;
+        let (decoder, last_sequence) = self
+            .decoders
+            .entry(stream_id)
+            .or_insert_with(|| (ProtocolDecoder::new(), 0));
+        *last_sequence = envelope.capture_sequence;
+        let batch = decoder.decode(envelope);
+        if let Some(log) = &self.schema_drift_log
+            && let Some(report) = decoder.schema_drift.take_due_report()
+        {
+            log.publish(stream_id, report);
+        }
+        batch
+    }
+}
+
+impl Drop for StreamDecoders {
+    fn drop(&mut self) {
+        let Some(log) = &self.schema_drift_log else {
+            return;
+        };
+        for (stream_id, (decoder, _)) in &self.decoders {
+            log.publish(*stream_id, decoder.schema_drift.report());
+        }
+    }
 }
 
 impl ProtocolDecoder {
@@ -101,33 +175,25 @@ impl ProtocolDecoder {
         envelope: &CaptureEnvelope,
         source_time_ms: &mut Option<i64>,
     ) -> Vec<ProtocolObservation> {
-        if is_synthetic_opcode(envelope.key.opcode) {
-            return Vec::new();
-        }
-
-        let service_id = envelope.key.service_id.map(u64::from);
-        if service_id == Some(WORLD_NTF_SERVICE_ID)
-            && envelope.direction == PacketDirection::ServerToClient
-        {
-            let Ok(opcode) = Pkt::try_from(envelope.key.opcode) else {
-                return Vec::new();
-            };
-            return self.decode_world_notify(opcode, envelope, source_time_ms);
-        }
-
-        if service_id == Some(WORLD_CALL_SERVICE_ID)
-            && envelope.direction == PacketDirection::ClientToServer
-        {
-            return decode_world_call(envelope);
-        }
-
-        if service_id == Some(GRPC_TEAM_NTF_SERVICE_ID)
-            && envelope.direction == PacketDirection::ServerToClient
-        {
-            return decode_team(envelope);
+        match route(envelope) {
+            Route::Synthetic => capture_issue(envelope)
+                .map(ProtocolObservation::CaptureIssue)
+                .into_iter()
+                .collect(),
+            Route::WorldNotify(opcode) => {
+                self.decode_world_notify(opcode, envelope, source_time_ms)
+            }
+            Route::WorldCall => decode_world_call(&mut self.schema_drift, envelope),
+            Route::Team => decode_team(&mut self.schema_drift, envelope),
+            Route::Unrouted(Unrouted::UnknownOpcode) => {
+                vec![ProtocolObservation::CaptureIssue(
+                    CaptureIssue::UnknownOpcode {
+                        opcode: envelope.key.opcode,
+                    },
+                )]
+            }
+            Route::Unrouted(_) => Vec::new(),
         }
-
-        Vec::new()
     }
 
     fn decode_world_notify(
@@ -138,7 +204,7 @@ impl ProtocolDecoder {
     ) -> Vec<ProtocolObservation> {
         macro_rules! decoded {
             ($message:ty, $handler:expr) => {
-                match decode_message::<$message>(&envelope.payload) {
+                match decode_message::<$message>(&mut self.schema_drift, &envelope.payload) {
                     Ok(message) => ($handler)(message),
                     Err(_) => Vec::new(),
                 }
@@ -244,9 +310,77 @@ impl ProtocolDecoder {
                         .collect()
                 }
             ),
-            // Standalone BuffInfoSync packets are ignored like the legacy
-            // pipeline did: the wire behavior is unverified.
-            Pkt::BuffInfoSync => Vec::new(),
+            Pkt::NotifyReviveUser => decoded!(
+                blueprotobuf::NotifyReviveUser,
+                |message: blueprotobuf::NotifyReviveUser| {
+                    message
+                        .v_actor_uuid
+                        .filter(|uuid| *uuid != 0)
+                        .map(|uuid| ProtocolObservation::ReviveNotified {
+                            uuid: EntityUuid(uuid),
+                        })
+                        .into_iter()
+                        .collect()
+                }
+            ),
+            Pkt::Teleport => decoded!(blueprotobuf::Teleport, |message: blueprotobuf::Teleport| {
+                // Scene changes arrive separately through EnterScene.
+                let Some(transfer) = message
+                    .teleport_params
+                    .and_then(|params| params.transfer_params)
+                else {
+                    return Vec::new();
+                };
+                let position = transfer
+                    .position_param
+                    .and_then(|param| param.scene_point_info)
+                    .and_then(|point| point.position)
+                    .and_then(|position| proto_position(&position));
+                vec![ProtocolObservation::Teleported {
+                    uuid: LOCAL_PLAYER,
+                    position,
+                    forced: false,
+                }]
+            }),
+            Pkt::ForcedPullBack => decoded!(
+                blueprotobuf::ForcedPullBack,
+                |message: blueprotobuf::ForcedPullBack| {
+                    vec![ProtocolObservation::Teleported {
+                        uuid: LOCAL_PLAYER,
+                        position: message.cur_pos.as_ref().and_then(proto_position),
+                        forced: true,
+                    }]
+                }
+            ),
+            Pkt::NotifyClientKickOff => decoded!(
+                blueprotobuf::NotifyClientKickOff,
+                |message: blueprotobuf::NotifyClientKickOff| {
+                    vec![ProtocolObservation::ClientKickedOff {
+                        err_code: message.err_code,
+                    }]
+                }
+            ),
+            // A standalone sync carries the target's full buff set, so a meter
+            // started mid-fight picks up long-running buffs and one resuming
+            // after a reassembly reset drops those removed in the gap.
+            Pkt::BuffInfoSync => decoded!(
+                blueprotobuf::BuffInfoSync,
+                |message: blueprotobuf::BuffInfoSync| {
+                    let Some(target_uuid) = message.uuid.filter(|uuid| *uuid != 0).map(EntityUuid)
+                    else {
+                        return Vec::new();
+                    };
+                    let mut observations = Vec::new();
+                    self.decode_buff_snapshot(
+                        target_uuid,
+                        &message,
+                        true,
+                        envelope,
+                        &mut observations,
+                    );
+                    observations
+                }
+            ),
             _ => Vec::new(),
         }
     }
@@ -322,7 +456,14 @@ impl ProtocolDecoder {
         // Attributes carry owner/monster identity that `EntityContext` needs
         // before it can resolve the fantasy marker emitted below.
         if let Some(attrs) = entity.attrs.as_ref() {
-            Self::decode_attributes(uuid, kind, attrs, origin, observations);
+            Self::decode_attributes(
+                &mut self.schema_drift,
+                uuid,
+                kind,
+                attrs,
+                origin,
+                observations,
+            );
         }
         if let Some(temp_attrs) = entity.temp_attrs.as_ref() {
             decode_temp_attributes(uuid, temp_attrs, origin, observations);
@@ -331,7 +472,7 @@ impl ProtocolDecoder {
             Self::decode_passive_starts(uuid, passives, observations);
         }
         if let Some(snapshot) = entity.buff_infos.as_ref() {
-            self.decode_buff_snapshot(uuid, snapshot, envelope, observations);
+            self.decode_buff_snapshot(uuid, snapshot, false, envelope, observations);
             Self::detect_fantasy(uuid, kind, snapshot, observations);
         }
         // `entity.buff_effect` on appear entities is intentionally not
@@ -400,7 +541,14 @@ impl ProtocolDecoder {
         observations.push(ProtocolObservation::EntityAppeared { uuid, kind });
 
         if let Some(attrs) = delta.attrs.as_ref() {
-            Self::decode_attributes(uuid, kind, attrs, ObservationOrigin::Delta, observations);
+            Self::decode_attributes(
+                &mut self.schema_drift,
+                uuid,
+                kind,
+                attrs,
+                ObservationOrigin::Delta,
+                observations,
+            );
         }
         if let Some(temp_attrs) = delta.temp_attrs.as_ref() {
             decode_temp_attributes(uuid, temp_attrs, ObservationOrigin::Delta, observations);
@@ -465,7 +613,8 @@ impl ProtocolDecoder {
         // delta. Batch-aware `DamageBySkillKeyOnce` commits before the first
         // following non-hit counter event.
         if let Some(raw) = delta.buff_effect.as_deref()
-            && let Ok(effects) = decode_message::<blueprotobuf::BuffEffectSync>(raw)
+            && let Ok(effects) =
+                decode_message::<blueprotobuf::BuffEffectSync>(&mut self.schema_drift, raw)
         {
             self.decode_buff_effect_sync(uuid, &effects, envelope, observations);
         }
@@ -474,6 +623,7 @@ impl ProtocolDecoder {
     /// Wire attributes are self-contained, so this is a pure fan-out from one
     /// `AttrCollection` to observations; no decoder state is consulted.
     fn decode_attributes(
+        schema_drift: &mut SchemaDrift,
         uuid: EntityUuid,
         kind: EntityKind,
         collection: &blueprotobuf::AttrCollection,
@@ -490,7 +640,7 @@ impl ProtocolDecoder {
             let raw = attr.raw_data.as_deref();
             match attr_id {
                 attr_type::ATTR_POS => {
-                    if let Some(position) = raw.and_then(decode_position) {
+                    if let Some(position) = raw.and_then(|raw| decode_position(schema_drift, raw)) {
                         observations.push(ProtocolObservation::PositionUpdated {
                             uuid,
                             attr_id,
@@ -711,6 +861,7 @@ impl ProtocolDecoder {
         &mut self,
         target_uuid: EntityUuid,
         snapshot: &blueprotobuf::BuffInfoSync,
+        complete: bool,
         envelope: &CaptureEnvelope,
         observations: &mut Vec<ProtocolObservation>,
     ) {
@@ -726,6 +877,7 @@ impl ProtocolDecoder {
         observations.push(ProtocolObservation::BuffSnapshot {
             target_uuid,
             buffs: normalized,
+            complete,
         });
     }
 
@@ -742,7 +894,7 @@ impl ProtocolDecoder {
             let Some(instance_id) = effect.buff_uuid.map(i64::from) else {
                 continue;
             };
-            let effect_ids = decode_play_effect_ids(&effect.logic_effect);
+            let effect_ids = decode_play_effect_ids(&mut self.schema_drift, &effect.logic_effect);
             let effect_ids =
                 (!effect_ids.is_empty()).then(|| Arc::<[i32]>::from(effect_ids.into_boxed_slice()));
 
@@ -754,7 +906,8 @@ impl ProtocolDecoder {
                     continue;
                 };
                 if effect_type == blueprotobuf::EBuffEffectLogicPbType::BuffEffectAddBuff as i32 {
-                    if let Ok(info) = decode_message::<blueprotobuf::BuffInfo>(raw)
+                    if let Ok(info) =
+                        decode_message::<blueprotobuf::BuffInfo>(&mut self.schema_drift, raw)
                         && let Some(mut buff) = self.observed_buff(instance_id, &info, envelope)
                     {
                         if let Some(effect_ids) = &effect_ids {
@@ -767,7 +920,8 @@ impl ProtocolDecoder {
                     }
                 } else if effect_type
                     == blueprotobuf::EBuffEffectLogicPbType::BuffEffectBuffChange as i32
-                    && let Ok(change) = decode_message::<blueprotobuf::BuffChange>(raw)
+                    && let Ok(change) =
+                        decode_message::<blueprotobuf::BuffChange>(&mut self.schema_drift, raw)
                 {
                     observations.push(ProtocolObservation::BuffChanged {
                         target_uuid,
@@ -794,7 +948,7 @@ impl ProtocolDecoder {
     }
 
     fn observed_buff(
-        &self,
+        &mut self,
         instance_id: i64,
         info: &blueprotobuf::BuffInfo,
         envelope: &CaptureEnvelope,
@@ -814,7 +968,10 @@ impl ProtocolDecoder {
             expires_wall_ms: None,
             started_mono_ms: None,
             expires_mono_ms: None,
-            effect_ids: Arc::from(decode_play_effect_ids(&info.logic_effect).into_boxed_slice()),
+            effect_ids: Arc::from(
+                decode_play_effect_ids(&mut self.schema_drift, &info.logic_effect)
+                    .into_boxed_slice(),
+            ),
         };
         update_buff_times(
             &mut buff,
@@ -959,11 +1116,21 @@ impl ProtocolDecoder {
     }
 }
 
-fn decode_world_call(envelope: &CaptureEnvelope) -> Vec<ProtocolObservation> {
-    if envelope.key.method_id != Some(world_call_method::USE_SLOT) {
-        return Vec::new();
+fn decode_world_call(
+    schema_drift: &mut SchemaDrift,
+    envelope: &CaptureEnvelope,
+) -> Vec<ProtocolObservation> {
+    match envelope.key.method_id {
+        Some(world_call_method::USE_SLOT) => decode_use_slot(schema_drift, &envelope.payload),
+        _ => Vec::new(),
     }
-    let use_slot = match decode_message::<blueprotobuf::UseSlot>(&envelope.payload) {
+}
+
+/// Skill slots report the requested skill; every other slot only reports
+/// which slot was pressed. The slot id is a bar position, not an item: which
+/// consumable, mount or interaction sits in it is not part of the call.
+fn decode_use_slot(schema_drift: &mut SchemaDrift, payload: &[u8]) -> Vec<ProtocolObservation> {
+    let use_slot = match decode_message::<blueprotobuf::UseSlot>(schema_drift, payload) {
         Ok(message) => message,
         Err(_) => return Vec::new(),
     };
@@ -971,12 +1138,16 @@ fn decode_world_call(envelope: &CaptureEnvelope) -> Vec<ProtocolObservation> {
         return Vec::new();
     };
     if request.use_type != Some(blueprotobuf::EUseSlotType::UseSlotTypeSkill as i32) {
-        return Vec::new();
+        return request
+            .slot_id
+            .map(|slot_id| ProtocolObservation::LocalSlotUsed { slot_id })
+            .into_iter()
+            .collect();
     }
     let Some(extra) = request.extra_data else {
         return Vec::new();
     };
-    let param = match decode_message::<blueprotobuf::UseSkillParam>(&extra) {
+    let param = match decode_message::<blueprotobuf::UseSkillParam>(schema_drift, &extra) {
         Ok(message) => message,
         Err(_) => return Vec::new(),
     };
@@ -989,11 +1160,14 @@ fn decode_world_call(envelope: &CaptureEnvelope) -> Vec<ProtocolObservation> {
     }]
 }
 
-fn decode_team(envelope: &CaptureEnvelope) -> Vec<ProtocolObservation> {
+fn decode_team(
+    schema_drift: &mut SchemaDrift,
+    envelope: &CaptureEnvelope,
+) -> Vec<ProtocolObservation> {
     let Some(method_id) = envelope.key.method_id else {
         return Vec::new();
     };
-    let Some(event) = decode_team_event(method_id, &envelope.payload) else {
+    let Some(event) = decode_team_event(schema_drift, method_id, &envelope.payload) else {
         return Vec::new();
     };
     match event {
@@ -1055,10 +1229,14 @@ fn parse_hit_core(info: &blueprotobuf::SyncDamageInfo) -> Option<HitCore> {
     })
 }
 
-fn decode_team_event(method_id: u32, payload: &[u8]) -> Option<TeamWireEvent> {
+fn decode_team_event(
+    schema_drift: &mut SchemaDrift,
+    method_id: u32,
+    payload: &[u8],
+) -> Option<TeamWireEvent> {
     match method_id {
         grpc_team_method::NOTICE_UPDATE_TEAM_INFO => {
-            match decode_message::<blueprotobuf::NoticeUpdateTeamInfo>(payload) {
+            match decode_message::<blueprotobuf::NoticeUpdateTeamInfo>(schema_drift, payload) {
                 Ok(message) => {
                     message
                         .v_request
@@ -1077,7 +1255,8 @@ fn decode_team_event(method_id: u32, payload: &[u8]) -> Option<TeamWireEvent> {
             }
         }
         grpc_team_method::NOTICE_UPDATE_TEAM_MEMBER_INFO => {
-            match decode_message::<blueprotobuf::NoticeUpdateTeamMemberInfo>(payload) {
+            match decode_message::<blueprotobuf::NoticeUpdateTeamMemberInfo>(schema_drift, payload)
+            {
                 Ok(message) => message.v_request.map(|request| {
                     let mut members = Vec::new();
                     for member in request.team_member_social_datas {
@@ -1095,7 +1274,7 @@ fn decode_team_event(method_id: u32, payload: &[u8]) -> Option<TeamWireEvent> {
             }
         }
         grpc_team_method::NOTIFY_JOIN_TEAM => {
-            match decode_message::<blueprotobuf::NotifyJoinTeam>(payload) {
+            match decode_message::<blueprotobuf::NotifyJoinTeam>(schema_drift, payload) {
                 Ok(message) => message.v_request.and_then(|request| {
                     let base_info = request.base_info?;
                     let mut members = Vec::new();
@@ -1121,7 +1300,7 @@ fn decode_team_event(method_id: u32, payload: &[u8]) -> Option<TeamWireEvent> {
             }
         }
         grpc_team_method::NOTIFY_LEAVE_TEAM => {
-            match decode_message::<blueprotobuf::NotifyLeaveTeam>(payload) {
+            match decode_message::<blueprotobuf::NotifyLeaveTeam>(schema_drift, payload) {
                 Ok(message) => message.v_request.map(|request| TeamWireEvent::Left {
                     member_uuid: canonical_player_uuid(request.char_id.unwrap_or_default()),
                 }),
@@ -1609,8 +1788,11 @@ fn decode_attribute_value(raw: &[u8]) -> Option<AttributeValue> {
         .or_else(|| decode_prefixed_string(raw).map(AttributeValue::Text))
 }
 
-fn decode_position(raw: &[u8]) -> Option<Position> {
-    let position = decode_message::<blueprotobuf::Position>(raw).ok()?;
+fn decode_position(schema_drift: &mut SchemaDrift, raw: &[u8]) -> Option<Position> {
+    proto_position(&decode_message::<blueprotobuf::Position>(schema_drift, raw).ok()?)
+}
+
+fn proto_position(position: &blueprotobuf::Position) -> Option<Position> {
     Some(Position {
         x: position.x?,
         y: position.y?,
@@ -1732,7 +1914,10 @@ fn decode_shield_details(raw: &[u8]) -> Vec<ShieldDetail> {
     entries
 }
 
-fn decode_play_effect_ids(logic_effects: &[blueprotobuf::BuffEffectLogicInfo]) -> Vec<i32> {
+fn decode_play_effect_ids(
+    schema_drift: &mut SchemaDrift,
+    logic_effects: &[blueprotobuf::BuffEffectLogicInfo],
+) -> Vec<i32> {
     logic_effects
         .iter()
         .filter(|logic| {
@@ -1745,7 +1930,10 @@ fn decode_play_effect_ids(logic_effects: &[blueprotobuf::BuffEffectLogicInfo]) -
             logic
                 .raw_data
                 .as_deref()
-                .and_then(|raw| decode_message::<blueprotobuf::BuffEffectLogicPlayEffect>(raw).ok())
+                .and_then(|raw| {
+                    decode_message::<blueprotobuf::BuffEffectLogicPlayEffect>(schema_drift, raw)
+                        .ok()
+                })
                 .and_then(|effect| effect.effect_id)
         })
         .collect()
@@ -1874,11 +2062,87 @@ fn decode_dungeon_delta(message: blueprotobuf::SyncDungeonDirtyData) -> Vec<Prot
     observations
 }
 
-fn decode_message<M>(payload: &[u8]) -> Result<M, prost::DecodeError>
+fn decode_message<M>(
+    schema_drift: &mut SchemaDrift,
+    payload: &[u8],
+) -> Result<M, prost::DecodeError>
 where
     M: Message + Default,
 {
-    M::decode(payload)
+    let result = M::decode(payload);
+    schema_drift.observe::<M>(payload, result.as_ref());
+    result
+}
+
+/// Which handler an envelope goes to, decided from its key and direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Route {
+    Synthetic,
+    WorldNotify(Pkt),
+    WorldCall,
+    Team,
+    Unrouted(Unrouted),
+}
+
+/// Why an envelope reached no handler and was discarded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unrouted {
+    /// World notify opcode that `Pkt` does not name.
+    UnknownOpcode,
+    /// Service id (or direction) no handler listens on.
+    Service,
+    /// Method id of a routed service that no handler decodes.
+    Method,
+}
+
+/// Reports envelopes the decoder discards without looking at the payload.
+#[must_use]
+pub fn unrouted(envelope: &CaptureEnvelope) -> Option<Unrouted> {
+    match route(envelope) {
+        Route::Unrouted(reason) => Some(reason),
+        _ => None,
+    }
+}
+
+fn route(envelope: &CaptureEnvelope) -> Route {
+    if is_synthetic_opcode(envelope.key.opcode) {
+        return Route::Synthetic;
+    }
+    let service_id = envelope.key.service_id.map(u64::from);
+    let method_id = envelope.key.method_id;
+    match (service_id, envelope.direction) {
+        (Some(WORLD_NTF_SERVICE_ID), PacketDirection::ServerToClient) => {
+            Pkt::try_from(envelope.key.opcode)
+                .map_or(Route::Unrouted(Unrouted::UnknownOpcode), Route::WorldNotify)
+        }
+        (Some(WORLD_CALL_SERVICE_ID), PacketDirection::ClientToServer) => {
+            if method_id == Some(world_call_method::USE_SLOT) {
+                Route::WorldCall
+            } else {
+                Route::Unrouted(Unrouted::Method)
+            }
+        }
+        (Some(GRPC_TEAM_NTF_SERVICE_ID), PacketDirection::ServerToClient) => {
+            if method_id.is_some_and(is_team_method) {
+                Route::Team
+            } else {
+                Route::Unrouted(Unrouted::Method)
+            }
+        }
+        _ => Route::Unrouted(Unrouted::Service),
+    }
+}
+
+fn is_team_method(method_id: u32) -> bool {
+    matches!(
+        method_id,
+        grpc_team_method::NOTICE_UPDATE_TEAM_INFO
+            | grpc_team_method::NOTICE_UPDATE_TEAM_MEMBER_INFO
+            | grpc_team_method::NOTIFY_JOIN_TEAM
+            | grpc_team_method::NOTIFY_LEAVE_TEAM
+            | grpc_team_method::NOTIFY_BE_TRANSFER_LEADER
+            | grpc_team_method::NOTICE_TEAM_DISSOLVE
+    )
 }
 
 fn is_synthetic_opcode(opcode: u32) -> bool {
@@ -1887,9 +2151,40 @@ fn is_synthetic_opcode(opcode: u32) -> bool {
         SYNTHETIC_STREAM_GAP_OPCODE
             | SYNTHETIC_REASSEMBLY_RESET_OPCODE
             | SYNTHETIC_DECODE_ISSUE_OPCODE
+            | SYNTHETIC_BACKPRESSURE_STALL_OPCODE
     )
 }
 
+/// Reads the transport fact a synthetic capture envelope carries. The payload
+/// layouts are written by `CaptureEmitter`.
+fn capture_issue(envelope: &CaptureEnvelope) -> Option<CaptureIssue> {
+    let payload = envelope.payload.as_ref();
+    let be_u32 = |offset: usize| {
+        payload
+            .get(offset..offset + 4)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u32::from_be_bytes)
+    };
+    match envelope.key.opcode {
+        SYNTHETIC_STREAM_GAP_OPCODE => Some(CaptureIssue::SegmentsDropped {
+            lost_bytes: be_u32(4)?.wrapping_sub(be_u32(0)?),
+        }),
+        SYNTHETIC_REASSEMBLY_RESET_OPCODE => Some(CaptureIssue::ReassemblyReset),
+        SYNTHETIC_DECODE_ISSUE_OPCODE => {
+            let category = *payload.first()?;
+            if category == CaptureDecodeIssueCategory::Decompression as u8 {
+                Some(CaptureIssue::DecompressionFailed)
+            } else {
+                Some(CaptureIssue::MalformedFrame)
+            }
+        }
+        SYNTHETIC_BACKPRESSURE_STALL_OPCODE => Some(CaptureIssue::BackpressureStall {
+            stalled_ms: be_u32(0)?,
+        }),
+        _ => None,
+    }
+}
+
 fn timer_state(info: blueprotobuf::TimerInfo) -> Option<GameTimerState> {
     Some(GameTimerState {
         key: GameTimerKey {
@@ -1950,6 +2245,156 @@ mod tests {
         canonical_player_uuid(char_id)
     }
 
+    fn synthetic(sequence: u64, opcode: u32, payload: Vec<u8>) -> CaptureEnvelope {
+        CaptureEnvelope {
+            capture_sequence: sequence,
+            stream_id: 7,
+            stream_epoch: 2,
+            captured_wall_ms: 50_000,
+            captured_mono_ns: sequence * 1_000_000,
+            direction: PacketDirection::ServerToClient,
+            key: crate::live::runtime::events::PacketKey {
+                opcode,
+                service_id: None,
+                method_id: None,
+            },
+            payload: payload.into(),
+        }
+    }
+
+    #[test]
+    fn stream_decoders_drop_the_least_recently_used_stream() {
+        let mut decoders = StreamDecoders::new();
+        for stream_id in 0..=MAX_STREAM_DECODERS as u64 {
+            let mut envelope =
+                synthetic(stream_id + 1, SYNTHETIC_REASSEMBLY_RESET_OPCODE, Vec::new());
+            envelope.stream_id = stream_id;
+            let batch = decoders.decode(envelope);
+            assert_eq!(batch.meta.stream_id, stream_id);
+        }
+        assert_eq!(decoders.decoders.len(), MAX_STREAM_DECODERS);
+        assert!(!decoders.decoders.contains_key(&0));
+        assert!(
+            decoders
+                .decoders
+                .contains_key(&(MAX_STREAM_DECODERS as u64))
+        );
+    }
+
+    #[test]
+    fn stream_decoders_report_schema_drift_per_stream() {
+        let log = SchemaDriftLog::new();
+        let mut decoders = StreamDecoders::with_schema_drift_log(log.clone());
+        let use_slot = blueprotobuf::UseSlot {
+            v_request: Some(blueprotobuf::UseSlotRequest {
+                slot_id: Some(3),
+                ..Default::default()
+            }),
+        };
+        let mut drifted = world_call(1, world_call_method::USE_SLOT, &use_slot);
+        let mut payload = use_slot.encode_to_vec();
+        // Field 9, varint 5: not part of UseSlot.
+        payload.extend_from_slice(&[0x48, 0x05]);
+        drifted.payload = payload.into();
+        drifted.stream_id = 1;
+        let mut clean = world_call(2, world_call_method::USE_SLOT, &use_slot);
+        clean.stream_id = 2;
+
+        let _ = decoders.decode(drifted);
+        let _ = decoders.decode(clean);
+        drop(decoders);
+
+        let streams = log.report();
+        assert_eq!(
+            streams
+                .iter()
+                .map(|stream| (stream.stream_id, stream.messages[0].unknown_fields.len()))
+                .collect::<Vec<_>>(),
+            vec![(1, 1), (2, 0)]
+        );
+    }
+
+    #[test]
+    fn synthetic_envelopes_become_capture_issues() {
+        let mut decoder = ProtocolDecoder::new();
+        let mut gap = 100u32.to_be_bytes().to_vec();
+        gap.extend_from_slice(&1_560u32.to_be_bytes());
+        let cases = [
+            (
+                synthetic(1, SYNTHETIC_STREAM_GAP_OPCODE, gap),
+                CaptureIssue::SegmentsDropped { lost_bytes: 1_460 },
+            ),
+            (
+                synthetic(2, SYNTHETIC_REASSEMBLY_RESET_OPCODE, Vec::new()),
+                CaptureIssue::ReassemblyReset,
+            ),
+            (
+                synthetic(3, SYNTHETIC_DECODE_ISSUE_OPCODE, vec![2, 0, 0, 0, 0]),
+                CaptureIssue::DecompressionFailed,
+            ),
+            (
+                synthetic(4, SYNTHETIC_DECODE_ISSUE_OPCODE, vec![1, 0, 0, 0, 0]),
+                CaptureIssue::MalformedFrame,
+            ),
+            (
+                synthetic(
+                    5,
+                    SYNTHETIC_BACKPRESSURE_STALL_OPCODE,
+                    250u32.to_be_bytes().to_vec(),
+                ),
+                CaptureIssue::BackpressureStall { stalled_ms: 250 },
+            ),
+        ];
+        for (envelope, expected) in cases {
+            assert_eq!(
+                decoder.decode(envelope).observations,
+                vec![ProtocolObservation::CaptureIssue(expected)]
+            );
+        }
+
+        let truncated = synthetic(6, SYNTHETIC_STREAM_GAP_OPCODE, vec![0; 3]);
+        assert!(decoder.decode(truncated).observations.is_empty());
+    }
+
+    #[test]
+    fn unknown_world_notify_opcode_is_reported() {
+        let mut envelope = synthetic(1, u32::MAX - 100, vec![1, 2, 3]);
+        envelope.key.service_id = Some(u32::try_from(WORLD_NTF_SERVICE_ID).unwrap());
+
+        assert_eq!(
+            ProtocolDecoder::new().decode(envelope).observations,
+            vec![ProtocolObservation::CaptureIssue(
+                CaptureIssue::UnknownOpcode {
+                    opcode: u32::MAX - 100
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn unrouted_reports_why_an_envelope_is_discarded() {
+        let empty = blueprotobuf::SyncServerTime::default();
+        assert_eq!(unrouted(&notify(1, Pkt::SyncServerTime, &empty)), None);
+        assert_eq!(
+            unrouted(&world_call(2, world_call_method::USE_SLOT, &empty)),
+            None
+        );
+        assert_eq!(
+            unrouted(&world_call(3, 0x7777, &empty)),
+            Some(Unrouted::Method)
+        );
+
+        let mut unknown = notify(4, Pkt::SyncServerTime, &empty);
+        unknown.key.opcode = u32::MAX - 100;
+        assert_eq!(unrouted(&unknown), Some(Unrouted::UnknownOpcode));
+        unknown.key.service_id = Some(42);
+        assert_eq!(unrouted(&unknown), Some(Unrouted::Service));
+        assert_eq!(
+            unrouted(&synthetic(5, SYNTHETIC_REASSEMBLY_RESET_OPCODE, Vec::new())),
+            None
+        );
+    }
+
     #[test]
     fn negative_reported_hit_values_are_rejected() {
         let info = blueprotobuf::SyncDamageInfo {
@@ -2016,6 +2461,7 @@ mod tests {
         };
 
         let event = decode_team_event(
+            &mut SchemaDrift::default(),
             grpc_team_method::NOTICE_UPDATE_TEAM_MEMBER_INFO,
             &message.encode_to_vec(),
         );
@@ -2061,7 +2507,11 @@ mod tests {
             }),
         };
 
-        let event = decode_team_event(grpc_team_method::NOTIFY_JOIN_TEAM, &message.encode_to_vec());
+        let event = decode_team_event(
+            &mut SchemaDrift::default(),
+            grpc_team_method::NOTIFY_JOIN_TEAM,
+            &message.encode_to_vec(),
+        );
 
         assert_eq!(
             event,
@@ -2189,6 +2639,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn non_skill_slot_use_reports_the_slot() {
+        let call = |use_type: blueprotobuf::EUseSlotType| blueprotobuf::UseSlot {
+            v_request: Some(blueprotobuf::UseSlotRequest {
+                slot_id: Some(11),
+                use_type: Some(use_type as i32),
+                extra_data: None,
+            }),
+        };
+        let mut decoder = ProtocolDecoder::new();
+
+        let other = call(blueprotobuf::EUseSlotType::UseSlotTypeOther);
+        assert_eq!(
+            decoder
+                .decode(world_call(1, world_call_method::USE_SLOT, &other))
+                .observations,
+            vec![ProtocolObservation::LocalSlotUsed { slot_id: 11 }]
+        );
+
+        // A skill slot without its UseSkillParam carries nothing to report.
+        let skill = call(blueprotobuf::EUseSlotType::UseSlotTypeSkill);
+        assert!(
+            decoder
+                .decode(world_call(2, world_call_method::USE_SLOT, &skill))
+                .observations
+                .is_empty()
+        );
+    }
+
     #[test]
     fn server_skill_end_reports_local_completion_without_a_caster() {
         let message = blueprotobuf::SyncServerSkillEnd {
@@ -2204,6 +2683,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn session_notifications_decode_to_observations() {
+        let mut decoder = ProtocolDecoder::new();
+        let revive = blueprotobuf::NotifyReviveUser {
+            v_actor_uuid: Some(4_242),
+        };
+        assert_eq!(
+            decoder
+                .decode(notify(1, Pkt::NotifyReviveUser, &revive))
+                .observations,
+            vec![ProtocolObservation::ReviveNotified {
+                uuid: EntityUuid(4_242)
+            }]
+        );
+
+        let destination = blueprotobuf::Position {
+            x: Some(1.0),
+            y: Some(2.0),
+            z: Some(3.0),
+            ..Default::default()
+        };
+        let teleport = blueprotobuf::Teleport {
+            teleport_params: Some(blueprotobuf::TeleportParams {
+                transfer_params: Some(blueprotobuf::TransferParam {
+                    scene_id: Some(6_563),
+                    position_param: Some(blueprotobuf::PositionParam {
+                        scene_point_info: Some(blueprotobuf::ScenePointInfo {
+                            position: Some(destination),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+            }),
+        };
+        let expected_position = Some(Position {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+        });
+        assert_eq!(
+            decoder
+                .decode(notify(2, Pkt::Teleport, &teleport))
+                .observations,
+            vec![ProtocolObservation::Teleported {
+                uuid: LOCAL_PLAYER,
+                position: expected_position,
+                forced: false,
+            }]
+        );
+
+        let pull_back = blueprotobuf::ForcedPullBack {
+            cur_pos: Some(destination),
+            force: Some(true),
+        };
+        assert_eq!(
+            decoder
+                .decode(notify(3, Pkt::ForcedPullBack, &pull_back))
+                .observations,
+            vec![ProtocolObservation::Teleported {
+                uuid: LOCAL_PLAYER,
+                position: expected_position,
+                forced: true,
+            }]
+        );
+
+        let kick = blueprotobuf::NotifyClientKickOff { err_code: Some(7) };
+        assert_eq!(
+            decoder
+                .decode(notify(4, Pkt::NotifyClientKickOff, &kick))
+                .observations,
+            vec![ProtocolObservation::ClientKickedOff { err_code: Some(7) }]
+        );
+    }
+
     #[test]
     fn to_me_delta_without_uuid_addresses_the_local_player_sentinel() {
         let message = blueprotobuf::SyncToMeDeltaInfo {
@@ -2345,12 +2900,19 @@ mod tests {
         let mut decoder = ProtocolDecoder::new();
         let batch = decoder.decode(notify(1, Pkt::SyncNearEntities, &message));
 
-        let [.., ProtocolObservation::BuffSnapshot { target_uuid, buffs }] =
-            batch.observations.as_slice()
+        let [
+            ..,
+            ProtocolObservation::BuffSnapshot {
+                target_uuid,
+                buffs,
+                complete,
+            },
+        ] = batch.observations.as_slice()
         else {
             panic!("buff snapshot observation expected last");
         };
         assert_eq!(*target_uuid, EntityUuid(20));
+        assert!(!complete, "appearances only upsert buffs");
         assert_eq!(buffs[0].instance_id, 7);
         assert_eq!(buffs[0].base_id, 8);
         assert_eq!(buffs[0].duration_ms, Some(3_000));
@@ -2365,6 +2927,52 @@ mod tests {
         assert_eq!(buffs[1].expires_mono_ms, None);
     }
 
+    #[test]
+    fn standalone_buff_info_sync_resynchronizes_buffs() {
+        let info = |buff_uuid, base_id| blueprotobuf::BuffInfo {
+            buff_uuid: Some(buff_uuid),
+            base_id: Some(base_id),
+            layer: Some(2),
+            fire_uuid: Some(30),
+            ..Default::default()
+        };
+        let mut decoder = ProtocolDecoder::new();
+
+        let targeted = blueprotobuf::BuffInfoSync {
+            uuid: Some(20),
+            buff_infos: vec![info(7, 8), info(9, 10)],
+        };
+        let batch = decoder.decode(notify(1, Pkt::BuffInfoSync, &targeted));
+        let [
+            ProtocolObservation::BuffSnapshot {
+                target_uuid,
+                buffs,
+                complete,
+            },
+        ] = batch.observations.as_slice()
+        else {
+            panic!("one buff snapshot expected");
+        };
+        assert_eq!(*target_uuid, EntityUuid(20));
+        assert!(complete);
+        assert_eq!(
+            buffs
+                .iter()
+                .map(|buff| (buff.instance_id, buff.base_id, buff.layer))
+                .collect::<Vec<_>>(),
+            vec![(7, 8, 2), (9, 10, 2)]
+        );
+        assert_eq!(buffs[0].source_uuid, Some(EntityUuid(30)));
+
+        // Without a uuid there is no telling whose buff set it replaces.
+        let untargeted = blueprotobuf::BuffInfoSync {
+            uuid: None,
+            buff_infos: vec![info(11, 12)],
+        };
+        let batch = decoder.decode(notify(2, Pkt::BuffInfoSync, &untargeted));
+        assert!(batch.observations.is_empty());
+    }
+
     #[test]
     fn buff_effect_uses_outer_id_and_preserves_logic_order() {
         let add = blueprotobuf::BuffEffectLogicInfo {
@@ -2868,6 +3476,7 @@ mod tests {
         };
         let mut observations = Vec::new();
         ProtocolDecoder::decode_attributes(
+            &mut SchemaDrift::default(),
             EntityUuid(1),
             kind,
             &collection,
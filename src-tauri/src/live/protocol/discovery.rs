@@ -0,0 +1,524 @@
+//! Opt-in log of the messages the decoder discards.
+//!
+//! After a game patch the data we need usually moves to an opcode the decoder
+//! does not route yet. While discovery is enabled every unrouted notify or
+//! call is counted per key, and its latest payload is kept for a schemaless
+//! protobuf dump, so new opcodes can be identified without leaving the tool.
+
+use crate::live::ipc::models::{
+    DiscoveredMessage, DiscoveredMessageKind, DiscoveryReason, ProtocolDiscoveryPayload, WireDump,
+    WireField, WireValue,
+};
+use crate::live::protocol::decoder::{self, Unrouted};
+use crate::live::runtime::events::{CaptureEnvelope, PacketDirection};
+use log::info;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Distinct keys tracked at once; later keys are only counted as dropped.
+const MAX_DISCOVERED_KEYS: usize = 512;
+/// Payload prefix kept per key for dumping.
+const MAX_SAMPLE_BYTES: usize = 16 * 1024;
+const MAX_DUMP_DEPTH: usize = 8;
+const MAX_FIELDS_PER_MESSAGE: usize = 512;
+const MAX_HEX_BYTES: usize = 256;
+/// Largest valid protobuf field number (2^29 - 1).
+const MAX_FIELD_NUMBER: u64 = 0x1fff_ffff;
+
+/// Shared between the decode worker, which records, and the commands that
+/// toggle and read the log.
+#[derive(Clone, Default)]
+pub struct ProtocolDiscovery {
+    inner: Arc<DiscoveryInner>,
+}
+
+#[derive(Default)]
+struct DiscoveryInner {
+    enabled: AtomicBool,
+    log: Mutex<DiscoveryLog>,
+}
+
+#[derive(Default)]
+struct DiscoveryLog {
+    entries: HashMap<DiscoveryKey, DiscoveryEntry>,
+    dropped_sightings: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct DiscoveryKey {
+    direction: PacketDirection,
+    service_id: Option<u32>,
+    method_id: Option<u32>,
+}
+
+struct DiscoveryEntry {
+    reason: Unrouted,
+    count: u64,
+    first_seen_wall_ms: i64,
+    last_seen_wall_ms: i64,
+    min_payload_bytes: usize,
+    max_payload_bytes: usize,
+    sample: Vec<u8>,
+    sample_payload_bytes: usize,
+}
+
+impl ProtocolDiscovery {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.inner.enabled.store(enabled, Ordering::Relaxed);
+        info!(target: "app::live", "protocol_discovery enabled={enabled}");
+    }
+
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.inner.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Called for every envelope before it is decoded; costs one atomic load
+    /// while discovery is off.
+    pub fn observe(&self, envelope: &CaptureEnvelope) {
+        if !self.is_enabled() {
+            return;
+        }
+        let Some(reason) = decoder::unrouted(envelope) else {
+            return;
+        };
+        self.inner.log.lock().record(reason, envelope);
+    }
+
+    pub fn clear(&self) {
+        *self.inner.log.lock() = DiscoveryLog::default();
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> ProtocolDiscoveryPayload {
+        let log = self.inner.log.lock();
+        let mut messages: Vec<_> = log
+            .entries
+            .iter()
+            .map(|(key, entry)| discovered_message(*key, entry))
+            .collect();
+        messages.sort_by(|left, right| {
+            right
+                .count
+                .cmp(&left.count)
+                .then(left.service_id.cmp(&right.service_id))
+                .then(left.method_id.cmp(&right.method_id))
+        });
+        ProtocolDiscoveryPayload {
+            enabled: self.is_enabled(),
+            messages,
+            dropped_sightings: log.dropped_sightings,
+        }
+    }
+}
+
+impl DiscoveryLog {
+    fn record(&mut self, reason: Unrouted, envelope: &CaptureEnvelope) {
+        let key = DiscoveryKey {
+            direction: envelope.direction,
+            service_id: envelope.key.service_id,
+            method_id: envelope.key.method_id,
+        };
+        let payload = envelope.payload.as_ref();
+        let sample = &payload[..payload.len().min(MAX_SAMPLE_BYTES)];
+        let wall_ms = envelope.captured_wall_ms;
+
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.count = entry.count.saturating_add(1);
+            entry.last_seen_wall_ms = wall_ms;
+            entry.min_payload_bytes = entry.min_payload_bytes.min(payload.len());
+            entry.max_payload_bytes = entry.max_payload_bytes.max(payload.len());
+            entry.sample.clear();
+            entry.sample.extend_from_slice(sample);
+            entry.sample_payload_bytes = payload.len();
+            return;
+        }
+        if self.entries.len() >= MAX_DISCOVERED_KEYS {
+            self.dropped_sightings = self.dropped_sightings.saturating_add(1);
+            return;
+        }
+        info!(
+            target: "app::live",
+            "protocol_discovery_new_key direction={:?} service_id={:?} method_id={:?} reason={:?}",
+            key.direction,
+            key.service_id,
+            key.method_id,
+            reason
+        );
+        self.entries.insert(
+            key,
+            DiscoveryEntry {
+                reason,
+                count: 1,
+                first_seen_wall_ms: wall_ms,
+                last_seen_wall_ms: wall_ms,
+                min_payload_bytes: payload.len(),
+                max_payload_bytes: payload.len(),
+                sample: sample.to_vec(),
+                sample_payload_bytes: payload.len(),
+            },
+        );
+    }
+}
+
+fn discovered_message(key: DiscoveryKey, entry: &DiscoveryEntry) -> DiscoveredMessage {
+    DiscoveredMessage {
+        kind: match key.direction {
+            PacketDirection::ServerToClient => DiscoveredMessageKind::Notify,
+            PacketDirection::ClientToServer => DiscoveredMessageKind::Call,
+        },
+        reason: match entry.reason {
+            Unrouted::UnknownOpcode => DiscoveryReason::UnknownOpcode,
+            Unrouted::Service => DiscoveryReason::UnroutedService,
+            Unrouted::Method => DiscoveryReason::UnroutedMethod,
+        },
+        service_id: key.service_id,
+        method_id: key.method_id,
+        count: entry.count,
+        first_seen_wall_ms: entry.first_seen_wall_ms,
+        last_seen_wall_ms: entry.last_seen_wall_ms,
+        min_payload_bytes: len_u32(entry.min_payload_bytes),
+        max_payload_bytes: len_u32(entry.max_payload_bytes),
+        sample: dump_sample(&entry.sample, entry.sample_payload_bytes),
+    }
+}
+
+/// Reads `payload` as protobuf wire format without a schema. Parsing stops at
+/// the first byte that cannot start a field; the rest is reported as hex.
+#[cfg(test)]
+fn dump_wire(payload: &[u8]) -> WireDump {
+    dump_sample(
+        &payload[..payload.len().min(MAX_SAMPLE_BYTES)],
+        payload.len(),
+    )
+}
+
+fn dump_sample(sample: &[u8], payload_bytes: usize) -> WireDump {
+    let (fields, consumed) = read_fields(sample, 0);
+    WireDump {
+        payload_bytes: len_u32(payload_bytes),
+        truncated: payload_bytes > sample.len(),
+        fields,
+        trailing_hex: (consumed < sample.len()).then(|| hex_preview(&sample[consumed..])),
+    }
+}
+
+/// Returns the fields read and how many bytes they covered.
+fn read_fields(bytes: &[u8], depth: usize) -> (Vec<WireField>, usize) {
+    let mut fields = Vec::new();
+    let mut position = 0;
+    while position < bytes.len() && fields.len() < MAX_FIELDS_PER_MESSAGE {
+        let Some((field, next)) = read_field(bytes, position, depth) else {
+            break;
+        };
+        fields.push(field);
+        position = next;
+    }
+    (fields, position)
+}
+
+fn read_field(bytes: &[u8], position: usize, depth: usize) -> Option<(WireField, usize)> {
+    let (tag, mut position) = read_varint(bytes, position)?;
+    let number = tag >> 3;
+    if number == 0 || number > MAX_FIELD_NUMBER {
+        return None;
+    }
+    let value = match tag & 0x7 {
+        0 => {
+            let (value, next) = read_varint(bytes, position)?;
+            position = next;
+            let zigzag = ((value >> 1) as i64) ^ -((value & 1) as i64);
+            WireValue::Varint {
+                value: value.to_string(),
+                zigzag: zigzag.to_string(),
+            }
+        }
+        1 => {
+            let raw: [u8; 8] = bytes.get(position..position + 8)?.try_into().ok()?;
+            position += 8;
+            let value = u64::from_le_bytes(raw);
+            WireValue::Fixed64 {
+                value: value.to_string(),
+                double: f64::from_bits(value),
+            }
+        }
+        2 => {
+            let (length, next) = read_varint(bytes, position)?;
+            let end = next.checked_add(usize::try_from(length).ok()?)?;
+            let body = bytes.get(next..end)?;
+            position = end;
+            guess_length_delimited(body, depth)
+        }
+        5 => {
+            let raw: [u8; 4] = bytes.get(position..position + 4)?.try_into().ok()?;
+            position += 4;
+            let value = u32::from_le_bytes(raw);
+            WireValue::Fixed32 {
+                value,
+                float: f32::from_bits(value),
+            }
+        }
+        // Groups (3, 4) are deprecated and unused by the game; anything else
+        // is not a wire type at all.
+        _ => return None,
+    };
+    Some((
+        WireField {
+            number: number as u32,
+            value,
+        },
+        position,
+    ))
+}
+
+/// Length-delimited fields carry strings, bytes, nested messages and packed
+/// repeated scalars alike; the most specific reading that fits wins.
+fn guess_length_delimited(body: &[u8], depth: usize) -> WireValue {
+    if let Some(text) = printable_text(body) {
+        return WireValue::Text { value: text };
+    }
+    if depth < MAX_DUMP_DEPTH {
+        let (fields, consumed) = read_fields(body, depth + 1);
+        if !fields.is_empty() && consumed == body.len() {
+            return WireValue::Message {
+                len: len_u32(body.len()),
+                fields,
+            };
+        }
+    }
+    if let Some(values) = packed_varints(body) {
+        return WireValue::PackedVarints { values };
+    }
+    WireValue::Bytes {
+        len: len_u32(body.len()),
+        hex: hex_preview(body),
+    }
+}
+
+fn printable_text(body: &[u8]) -> Option<String> {
+    let text = std::str::from_utf8(body).ok()?;
+    (!text.is_empty()
+        && text
+            .chars()
+            .all(|ch| !ch.is_control() || matches!(ch, '\t' | '\n' | '\r')))
+    .then(|| text.to_string())
+}
+
+fn packed_varints(body: &[u8]) -> Option<Vec<String>> {
+    let mut values = Vec::new();
+    let mut position = 0;
+    while position < body.len() {
+        let (value, next) = read_varint(body, position)?;
+        values.push(value.to_string());
+        position = next;
+    }
+    (!values.is_empty()).then_some(values)
+}
+
+pub(super) fn read_varint(bytes: &[u8], mut position: usize) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(position)?;
+        position += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some((value, position));
+        }
+    }
+    None
+}
+
+fn hex_preview(bytes: &[u8]) -> String {
+    let shown = &bytes[..bytes.len().min(MAX_HEX_BYTES)];
+    let mut hex = hex::encode(shown);
+    if shown.len() < bytes.len() {
+        hex.push_str("...");
+    }
+    hex
+}
+
+fn len_u32(len: usize) -> u32 {
+    u32::try_from(len).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::runtime::events::PacketKey;
+    use crate::packets::opcodes::{WORLD_CALL_SERVICE_ID, WORLD_NTF_SERVICE_ID};
+
+    fn envelope(service_id: u64, direction: PacketDirection, method_id: u32) -> CaptureEnvelope {
+        CaptureEnvelope {
+            capture_sequence: 1,
+            stream_id: 1,
+            stream_epoch: 1,
+            captured_wall_ms: 1_000,
+            captured_mono_ns: 0,
+            direction,
+            key: PacketKey {
+                opcode: method_id,
+                service_id: Some(u32::try_from(service_id).unwrap()),
+                method_id: Some(method_id),
+            },
+            payload: bytes::Bytes::from_static(&[0x08, 0x2a]),
+        }
+    }
+
+    fn varint(value: &str) -> WireValue {
+        WireValue::Varint {
+            value: value.to_string(),
+            zigzag: String::new(),
+        }
+    }
+
+    /// Compares varints by value only; zigzag is checked separately.
+    fn strip_zigzag(fields: &mut [WireField]) {
+        for field in fields {
+            match &mut field.value {
+                WireValue::Varint { zigzag, .. } => zigzag.clear(),
+                WireValue::Message { fields, .. } => strip_zigzag(fields),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn dumps_scalars_text_nested_messages_and_packed_values() {
+        let mut payload = vec![0x08, 0x96, 0x01];
+        payload.extend_from_slice(&[0x12, 0x07]);
+        payload.extend_from_slice(b"testing");
+        payload.extend_from_slice(&[0x1a, 0x04, 0x08, 0x01, 0x10, 0x03]);
+        payload.extend_from_slice(&[0x25, 0x00, 0x00, 0x80, 0x3f]);
+        payload.extend_from_slice(&[0x32, 0x03, 0x03, 0x8e, 0x02]);
+        payload.extend_from_slice(&[0x38, 0x03]);
+
+        let mut dump = dump_wire(&payload);
+        assert_eq!(dump.payload_bytes, payload.len() as u32);
+        assert_eq!(dump.trailing_hex, None);
+        let WireValue::Varint { zigzag, .. } = &dump.fields[5].value else {
+            panic!("expected a varint");
+        };
+        assert_eq!(zigzag, "-2");
+        strip_zigzag(&mut dump.fields);
+        assert_eq!(
+            dump.fields,
+            vec![
+                WireField {
+                    number: 1,
+                    value: varint("150"),
+                },
+                WireField {
+                    number: 2,
+                    value: WireValue::Text {
+                        value: "testing".to_string(),
+                    },
+                },
+                WireField {
+                    number: 3,
+                    value: WireValue::Message {
+                        len: 4,
+                        fields: vec![
+                            WireField {
+                                number: 1,
+                                value: varint("1"),
+                            },
+                            WireField {
+                                number: 2,
+                                value: varint("3"),
+                            },
+                        ],
+                    },
+                },
+                WireField {
+                    number: 4,
+                    value: WireValue::Fixed32 {
+                        value: 0x3f80_0000,
+                        float: 1.0,
+                    },
+                },
+                WireField {
+                    number: 6,
+                    value: WireValue::PackedVarints {
+                        values: vec!["3".to_string(), "270".to_string()],
+                    },
+                },
+                WireField {
+                    number: 7,
+                    value: varint("3"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unparseable_tail_is_reported_as_hex() {
+        let dump = dump_wire(&[0x08, 0x01, 0x0f, 0xff]);
+        assert_eq!(dump.fields.len(), 1);
+        assert_eq!(dump.trailing_hex.as_deref(), Some("0fff"));
+    }
+
+    #[test]
+    fn records_only_unrouted_messages_while_enabled() {
+        let discovery = ProtocolDiscovery::new();
+        let unknown_notify = envelope(
+            WORLD_NTF_SERVICE_ID,
+            PacketDirection::ServerToClient,
+            0xfff0,
+        );
+        discovery.observe(&unknown_notify);
+        assert!(discovery.snapshot().messages.is_empty());
+
+        discovery.set_enabled(true);
+        discovery.observe(&unknown_notify);
+        let mut later = unknown_notify.clone();
+        later.captured_wall_ms = 4_000;
+        later.payload = bytes::Bytes::from_static(&[0x08, 0x2a, 0x10, 0x01]);
+        discovery.observe(&later);
+        discovery.observe(&envelope(
+            WORLD_CALL_SERVICE_ID,
+            PacketDirection::ClientToServer,
+            0x1234,
+        ));
+        // `SyncServerTime` is routed and never recorded.
+        discovery.observe(&envelope(
+            WORLD_NTF_SERVICE_ID,
+            PacketDirection::ServerToClient,
+            0x2b,
+        ));
+
+        let payload = discovery.snapshot();
+        assert!(payload.enabled);
+        assert_eq!(payload.messages.len(), 2);
+        let notify = &payload.messages[0];
+        assert_eq!(
+            (notify.kind, notify.reason, notify.method_id),
+            (
+                DiscoveredMessageKind::Notify,
+                DiscoveryReason::UnknownOpcode,
+                Some(0xfff0)
+            )
+        );
+        assert_eq!(notify.count, 2);
+        assert_eq!(
+            (notify.first_seen_wall_ms, notify.last_seen_wall_ms),
+            (1_000, 4_000)
+        );
+        assert_eq!((notify.min_payload_bytes, notify.max_payload_bytes), (2, 4));
+        assert_eq!(notify.sample.fields.len(), 2);
+        let call = &payload.messages[1];
+        assert_eq!(
+            (call.kind, call.reason),
+            (DiscoveredMessageKind::Call, DiscoveryReason::UnroutedMethod)
+        );
+
+        discovery.clear();
+        assert!(discovery.snapshot().messages.is_empty());
+    }
+}
@@ -1,5 +1,7 @@
 pub mod attrs;
 pub mod decoder;
+pub mod discovery;
+pub mod schema_drift;
 
 /// Passive-skill skill ids 1101..=1106 are the in-game player markers;
 /// the displayed marker number is `skill_id - MARKER_SKILL_ID_BASE`.
@@ -0,0 +1,600 @@
+//! Early warning that the generated protobuf schema is falling behind the game.
+//!
+//! prost silently skips field numbers a message does not declare, at any
+//! depth, so after a patch the decoder keeps running on whatever fields it
+//! still recognizes. Each stream's decoder owns a [`SchemaDrift`] that sees
+//! every `decode_message` call. The first payloads of each message type, and
+//! then one in [`SAMPLE_EVERY`], are inspected: the decoded message is
+//! encoded again, and field paths on the wire that the re-encoding lacks are
+//! fields the schema does not know, nested ones included. When a decode
+//! fails, its top-level fields are decoded one at a time to find the one the
+//! schema can no longer read. Stream decoders publish their reports to the
+//! shared [`SchemaDriftLog`], which ships in the diagnostics bundle.
+
+use log::warn;
+use parking_lot::Mutex;
+use prost::Message;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use crate::live::protocol::discovery::read_varint;
+
+/// Payloads of each message type always inspected before sampling starts.
+const SAMPLE_FIRST: u64 = 16;
+/// Past [`SAMPLE_FIRST`], one payload in this many is inspected.
+const SAMPLE_EVERY: u64 = 64;
+/// Distinct drifted fields remembered per message type; garbage payloads
+/// must not grow the report without bound.
+const MAX_FIELDS_PER_MESSAGE: usize = 256;
+/// Nested messages deeper than this are not walked.
+const MAX_NESTING: usize = 8;
+/// Largest valid protobuf field number (2^29 - 1).
+const MAX_FIELD_NUMBER: u64 = 0x1fff_ffff;
+/// Decodes a stream's report may lag behind when no new drift shows up.
+const PUBLISH_EVERY: u64 = 512;
+/// Stream reports the log keeps; the lowest (oldest) stream ids go first.
+const MAX_LOGGED_STREAMS: usize = 16;
+
+/// Drift seen by one stream's decoder.
+#[derive(Default)]
+pub struct SchemaDrift {
+    messages: HashMap<&'static str, MessageDrift>,
+    /// Decodes since the last report was taken.
+    unpublished: u64,
+    /// Whether drift was found for the first time since the last report.
+    news: bool,
+}
+
+#[derive(Default)]
+struct MessageDrift {
+    decoded: u64,
+    failed: u64,
+    inspected: u64,
+    last_error: Option<String>,
+    /// Top-level fields of failed decodes, classified once each.
+    classes: HashMap<(u32, u8), FieldClass>,
+    unknown: BTreeMap<FieldKey, u64>,
+    changed: BTreeMap<FieldKey, u64>,
+}
+
+/// Field numbers from the top-level message in, and the last one's wire type.
+type FieldKey = (Vec<u32>, u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldClass {
+    Known,
+    /// The schema has no such field number; prost skipped it.
+    Unknown,
+    /// The schema declares the field but cannot decode this value, usually
+    /// because its wire type changed.
+    Changed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaDriftReport {
+    /// Message types with drift first, then by name.
+    pub messages: Vec<MessageDriftReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageDriftReport {
+    pub message: String,
+    pub decoded: u64,
+    pub failed: u64,
+    pub failure_rate: f64,
+    /// Payloads whose fields were checked; field counts cover only these.
+    pub inspected: u64,
+    pub last_error: Option<String>,
+    pub unknown_fields: Vec<FieldDriftReport>,
+    pub changed_fields: Vec<FieldDriftReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldDriftReport {
+    /// Field numbers from the top-level message in.
+    pub field_path: Vec<u32>,
+    pub wire_type: &'static str,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamDriftReport {
+    pub stream_id: u64,
+    pub messages: Vec<MessageDriftReport>,
+}
+
+/// One field, with `raw` spanning its key and value and `value` without the
+/// key (and without the length prefix of a length-delimited field).
+struct RawField<'a> {
+    number: u32,
+    wire_type: u8,
+    raw: &'a [u8],
+    value: &'a [u8],
+}
+
+impl SchemaDrift {
+    /// Records one decode of `M` from `payload`.
+    pub fn observe<M>(&mut self, payload: &[u8], result: Result<&M, &prost::DecodeError>)
+    where
+        M: Message + Default,
+    {
+        self.unpublished += 1;
+        let name = message_name::<M>();
+        let drift = self.messages.entry(name).or_default();
+        drift.decoded += 1;
+        if let Err(error) = result {
+            self.news |= drift.failed == 0;
+            drift.failed += 1;
+            drift.last_error = Some(error.to_string());
+        }
+        if drift.decoded > SAMPLE_FIRST && !drift.decoded.is_multiple_of(SAMPLE_EVERY) {
+            return;
+        }
+        drift.inspected += 1;
+        self.news |= match result {
+            Ok(message) => drift.inspect_decoded(name, payload, &message.encode_to_vec()),
+            Err(_) => drift.inspect_failed::<M>(name, payload),
+        };
+    }
+
+    /// The current report, once new drift or enough decodes have piled up
+    /// since the last one.
+    pub fn take_due_report(&mut self) -> Option<SchemaDriftReport> {
+        if !self.news && self.unpublished < PUBLISH_EVERY {
+            return None;
+        }
+        self.news = false;
+        self.unpublished = 0;
+        Some(self.report())
+    }
+
+    #[must_use]
+    pub fn report(&self) -> SchemaDriftReport {
+        let mut reports = self
+            .messages
+            .iter()
+            .map(|(name, drift)| MessageDriftReport {
+                message: (*name).to_string(),
+                decoded: drift.decoded,
+                failed: drift.failed,
+                failure_rate: if drift.decoded == 0 {
+                    0.0
+                } else {
+                    drift.failed as f64 / drift.decoded as f64
+                },
+                inspected: drift.inspected,
+                last_error: drift.last_error.clone(),
+                unknown_fields: field_reports(&drift.unknown),
+                changed_fields: field_reports(&drift.changed),
+            })
+            .collect::<Vec<_>>();
+        reports.sort_by(|left, right| {
+            let drifted = |report: &MessageDriftReport| {
+                report.failed > 0
+                    || !report.unknown_fields.is_empty()
+                    || !report.changed_fields.is_empty()
+            };
+            drifted(right)
+                .cmp(&drifted(left))
+                .then_with(|| left.message.cmp(&right.message))
+        });
+        SchemaDriftReport { messages: reports }
+    }
+}
+
+impl MessageDrift {
+    /// Counts the fields prost dropped while decoding `payload`, reporting a
+    /// dropped message field once rather than once per field inside it.
+    /// Returns whether any of them is new.
+    fn inspect_decoded(&mut self, name: &str, payload: &[u8], reencoded: &[u8]) -> bool {
+        if payload == reencoded {
+            return false;
+        }
+        let mut on_wire = BTreeMap::new();
+        collect_paths(payload, &mut Vec::new(), &mut on_wire);
+        let mut kept = BTreeMap::new();
+        collect_paths(reencoded, &mut Vec::new(), &mut kept);
+
+        let mut dropped = BTreeSet::<Vec<u32>>::new();
+        let mut found = false;
+        for (path, wire_type) in on_wire {
+            if kept.contains_key(&path)
+                || (1..path.len()).any(|depth| dropped.contains(&path[..depth]))
+            {
+                continue;
+            }
+            found |= self.record(name, FieldClass::Unknown, (path.clone(), wire_type));
+            dropped.insert(path);
+        }
+        found
+    }
+
+    /// Finds the top-level fields of a payload that failed to decode.
+    /// Returns whether any of them is new.
+    fn inspect_failed<M>(&mut self, name: &str, payload: &[u8]) -> bool
+    where
+        M: Message + Default,
+    {
+        let mut found = false;
+        for_each_field(payload, |field| {
+            let key = (field.number, field.wire_type);
+            let class = match self.classes.get(&key) {
+                Some(class) => *class,
+                None if self.classes.len() >= MAX_FIELDS_PER_MESSAGE => return,
+                None => {
+                    let Some(class) = classify::<M>(&field) else {
+                        return;
+                    };
+                    self.classes.insert(key, class);
+                    class
+                }
+            };
+            found |= self.record(name, class, (vec![field.number], field.wire_type));
+        });
+        found
+    }
+
+    /// Counts one drifted field. Returns whether it was seen for the first time.
+    fn record(&mut self, name: &str, class: FieldClass, key: FieldKey) -> bool {
+        let fields = self.unknown.len() + self.changed.len();
+        let counts = match class {
+            FieldClass::Known => return false,
+            FieldClass::Unknown => &mut self.unknown,
+            FieldClass::Changed => &mut self.changed,
+        };
+        if let Some(count) = counts.get_mut(&key) {
+            *count += 1;
+            return false;
+        }
+        if fields >= MAX_FIELDS_PER_MESSAGE {
+            return false;
+        }
+        warn!(
+            target: "app::live",
+            "protocol_schema_drift message={name} field={} wire_type={} kind={class:?}",
+            field_path(&key.0),
+            wire_type_name(key.1)
+        );
+        counts.insert(key, 1);
+        true
+    }
+}
+
+/// Latest report from each capture stream's decoder. Shared between the
+/// decode worker, which publishes, and the diagnostics bundle, which reads.
+#[derive(Clone, Default)]
+pub struct SchemaDriftLog {
+    streams: Arc<Mutex<BTreeMap<u64, SchemaDriftReport>>>,
+}
+
+impl SchemaDriftLog {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn publish(&self, stream_id: u64, report: SchemaDriftReport) {
+        let mut streams = self.streams.lock();
+        streams.insert(stream_id, report);
+        while streams.len() > MAX_LOGGED_STREAMS {
+            streams.pop_first();
+        }
+    }
+
+    #[must_use]
+    pub fn report(&self) -> Vec<StreamDriftReport> {
+        self.streams
+            .lock()
+            .iter()
+            .map(|(&stream_id, report)| StreamDriftReport {
+                stream_id,
+                messages: report.messages.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Decodes the field on its own: a declared field survives re-encoding, an
+/// undeclared one is skipped. An empty length-delimited value re-encodes to
+/// nothing even when declared (an empty packed list), so it stays unclassified.
+fn classify<M>(field: &RawField<'_>) -> Option<FieldClass>
+where
+    M: Message + Default,
+{
+    match M::decode(field.raw) {
+        Err(_) => Some(FieldClass::Changed),
+        Ok(message) if message.encoded_len() > 0 => Some(FieldClass::Known),
+        Ok(_) if field.wire_type == 2 && field.value.is_empty() => None,
+        Ok(_) => Some(FieldClass::Unknown),
+    }
+}
+
+/// Adds the path of every field in `bytes`, walking into length-delimited
+/// values that parse as messages (a string that happens to parse is walked
+/// too, but reads the same before and after re-encoding). Zero and empty
+/// values are left out, since re-encoding may drop them (map entries do).
+/// Adds nothing and returns false when `bytes` is not a non-empty,
+/// well-formed message.
+fn collect_paths(bytes: &[u8], path: &mut Vec<u32>, paths: &mut BTreeMap<Vec<u32>, u8>) -> bool {
+    let mut fields = Vec::new();
+    if !for_each_field(bytes, |field| fields.push(field)) || fields.is_empty() {
+        return false;
+    }
+    for field in fields {
+        if field.value.iter().all(|byte| *byte == 0) {
+            continue;
+        }
+        path.push(field.number);
+        paths.entry(path.clone()).or_insert(field.wire_type);
+        if field.wire_type == 2 && path.len() < MAX_NESTING {
+            collect_paths(field.value, path, paths);
+        }
+        path.pop();
+    }
+    true
+}
+
+/// Walks the fields of one message, stopping at the first malformed one.
+/// Returns whether every byte was read.
+fn for_each_field<'a>(payload: &'a [u8], mut visit: impl FnMut(RawField<'a>)) -> bool {
+    let mut position = 0;
+    while position < payload.len() {
+        let start = position;
+        let Some((tag, next)) = read_varint(payload, position) else {
+            return false;
+        };
+        let number = tag >> 3;
+        if number == 0 || number > MAX_FIELD_NUMBER {
+            return false;
+        }
+        let wire_type = (tag & 0x7) as u8;
+        let mut value_start = next;
+        let end = match wire_type {
+            0 => match read_varint(payload, next) {
+                Some((_, end)) => end,
+                None => return false,
+            },
+            1 => next + 8,
+            2 => {
+                let Some((length, body_start)) = read_varint(payload, next) else {
+                    return false;
+                };
+                value_start = body_start;
+                match usize::try_from(length)
+                    .ok()
+                    .and_then(|length| body_start.checked_add(length))
+                {
+                    Some(end) => end,
+                    None => return false,
+                }
+            }
+            5 => next + 4,
+            _ => return false,
+        };
+        let (Some(raw), Some(value)) = (payload.get(start..end), payload.get(value_start..end))
+        else {
+            return false;
+        };
+        visit(RawField {
+            number: number as u32,
+            wire_type,
+            raw,
+            value,
+        });
+        position = end;
+    }
+    true
+}
+
+fn field_reports(counts: &BTreeMap<FieldKey, u64>) -> Vec<FieldDriftReport> {
+    counts
+        .iter()
+        .map(|((path, wire_type), &count)| FieldDriftReport {
+            field_path: path.clone(),
+            wire_type: wire_type_name(*wire_type),
+            count,
+        })
+        .collect()
+}
+
+fn field_path(path: &[u32]) -> String {
+    path.iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn wire_type_name(wire_type: u8) -> &'static str {
+    match wire_type {
+        0 => "varint",
+        1 => "fixed64",
+        2 => "len",
+        5 => "fixed32",
+        _ => "invalid",
+    }
+}
+
+fn message_name<M>() -> &'static str {
+    let path = std::any::type_name::<M>();
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blueprotobuf_lib::blueprotobuf;
+
+    fn kick_off_with(extra: &[u8]) -> Vec<u8> {
+        let mut payload = blueprotobuf::NotifyClientKickOff { err_code: Some(7) }.encode_to_vec();
+        payload.extend_from_slice(extra);
+        payload
+    }
+
+    fn observe<M>(drift: &mut SchemaDrift, payload: &[u8])
+    where
+        M: Message + Default,
+    {
+        let result = M::decode(payload);
+        drift.observe::<M>(payload, result.as_ref());
+    }
+
+    #[test]
+    fn unknown_fields_are_counted_without_failing_the_decode() {
+        let mut drift = SchemaDrift::default();
+        // Field 9, varint 5: not part of NotifyClientKickOff.
+        let payload = kick_off_with(&[0x48, 0x05]);
+        observe::<blueprotobuf::NotifyClientKickOff>(&mut drift, &payload);
+        observe::<blueprotobuf::NotifyClientKickOff>(&mut drift, &payload);
+
+        let report = drift.report();
+        let [message] = report.messages.as_slice() else {
+            panic!("one message type expected");
+        };
+        assert_eq!(message.message, "NotifyClientKickOff");
+        assert_eq!((message.decoded, message.failed), (2, 0));
+        assert_eq!(
+            message.unknown_fields,
+            vec![FieldDriftReport {
+                field_path: vec![9],
+                wire_type: "varint",
+                count: 2,
+            }]
+        );
+        assert!(message.changed_fields.is_empty());
+    }
+
+    #[test]
+    fn unknown_fields_inside_nested_messages_report_their_path() {
+        let mut drift = SchemaDrift::default();
+        let mut request = blueprotobuf::UseSlotRequest {
+            slot_id: Some(3),
+            ..Default::default()
+        }
+        .encode_to_vec();
+        // Field 15, varint 1: not part of UseSlotRequest.
+        request.extend_from_slice(&[0x78, 0x01]);
+        let mut payload = vec![0x0a, u8::try_from(request.len()).expect("short request")];
+        payload.extend_from_slice(&request);
+        observe::<blueprotobuf::UseSlot>(&mut drift, &payload);
+
+        let report = drift.report();
+        assert_eq!(
+            report.messages[0].unknown_fields,
+            vec![FieldDriftReport {
+                field_path: vec![1, 15],
+                wire_type: "varint",
+                count: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn an_unknown_message_field_is_reported_once_not_per_inner_field() {
+        let mut drift = SchemaDrift::default();
+        // Field 9 holding a message with fields 1 and 2.
+        let payload = kick_off_with(&[0x4a, 0x04, 0x08, 0x01, 0x10, 0x02]);
+        observe::<blueprotobuf::NotifyClientKickOff>(&mut drift, &payload);
+
+        let report = drift.report();
+        assert_eq!(
+            report.messages[0].unknown_fields,
+            vec![FieldDriftReport {
+                field_path: vec![9],
+                wire_type: "len",
+                count: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn declared_field_with_a_new_wire_type_is_a_change_and_a_failure() {
+        let mut drift = SchemaDrift::default();
+        // Field 1 (err_code, an int32) arriving length-delimited.
+        observe::<blueprotobuf::NotifyClientKickOff>(&mut drift, &[0x0a, 0x01, 0x07]);
+        observe::<blueprotobuf::NotifyClientKickOff>(&mut drift, &kick_off_with(&[]));
+
+        let report = drift.report();
+        let message = &report.messages[0];
+        assert_eq!((message.decoded, message.failed), (2, 1));
+        assert_eq!(message.failure_rate, 0.5);
+        assert!(message.last_error.is_some());
+        assert_eq!(
+            message.changed_fields,
+            vec![FieldDriftReport {
+                field_path: vec![1],
+                wire_type: "len",
+                count: 1,
+            }]
+        );
+        assert!(message.unknown_fields.is_empty());
+    }
+
+    #[test]
+    fn payloads_past_the_first_few_are_sampled() {
+        let mut drift = SchemaDrift::default();
+        let payload = kick_off_with(&[0x48, 0x05]);
+        for _ in 0..100 {
+            observe::<blueprotobuf::NotifyClientKickOff>(&mut drift, &payload);
+        }
+
+        let report = drift.report();
+        let message = &report.messages[0];
+        // The first 16, then the 64th.
+        assert_eq!((message.decoded, message.inspected), (100, 17));
+        assert_eq!(message.unknown_fields[0].count, 17);
+    }
+
+    #[test]
+    fn reports_are_due_on_new_drift_or_after_enough_decodes() {
+        let mut drift = SchemaDrift::default();
+        let clean = kick_off_with(&[]);
+        observe::<blueprotobuf::NotifyClientKickOff>(&mut drift, &clean);
+        assert!(drift.take_due_report().is_none());
+
+        observe::<blueprotobuf::NotifyClientKickOff>(&mut drift, &kick_off_with(&[0x48, 0x05]));
+        assert!(drift.take_due_report().is_some());
+        assert!(drift.take_due_report().is_none());
+
+        for _ in 0..PUBLISH_EVERY {
+            observe::<blueprotobuf::NotifyClientKickOff>(&mut drift, &clean);
+        }
+        assert!(drift.take_due_report().is_some());
+    }
+
+    #[test]
+    fn clean_messages_sort_after_drifted_ones() {
+        let mut drift = SchemaDrift::default();
+        observe::<blueprotobuf::NotifyClientKickOff>(&mut drift, &kick_off_with(&[0x48, 0x05]));
+        let revive = blueprotobuf::NotifyReviveUser {
+            v_actor_uuid: Some(1),
+        }
+        .encode_to_vec();
+        observe::<blueprotobuf::NotifyReviveUser>(&mut drift, &revive);
+
+        let names = drift
+            .report()
+            .messages
+            .into_iter()
+            .map(|message| message.message)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["NotifyClientKickOff", "NotifyReviveUser"]);
+    }
+
+    #[test]
+    fn the_log_keeps_the_newest_streams() {
+        let log = SchemaDriftLog::new();
+        let count = u64::try_from(MAX_LOGGED_STREAMS).expect("small") + 2;
+        for stream_id in 0..count {
+            log.publish(stream_id, SchemaDrift::default().report());
+        }
+
+        let streams = log.report();
+        assert_eq!(streams.len(), MAX_LOGGED_STREAMS);
+        assert_eq!(streams[0].stream_id, 2);
+    }
+}
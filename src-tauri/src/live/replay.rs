@@ -0,0 +1,363 @@
+//! Deterministic replay of recorded capture sessions.
+//!
+//! A recorded envelope stream is decoded and applied exactly like the live
+//! pipeline does it, but on a single thread and with every session's
+//! [`LiveCore`] running in [`ClockMode::Recorded`]: deadlines, publication
+//! cadence and segment boundaries all advance on recorded capture time, so the
+//! produced encounters and publications do not depend on the replay speed.
+//!
+//! [`LiveCore`]: crate::live::live_core::LiveCore
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::info;
+use tauri::AppHandle;
+
+use crate::live::bootstrap_snapshot::load_monitor_runtime_snapshot;
+use crate::live::history_writer::HistoryWriterHandle;
+use crate::live::live_core::{ClockMode, Publications};
+use crate::live::protocol::decoder::StreamDecoders;
+use crate::live::runtime::events::{CaptureEnvelope, MonoTimeMs};
+use crate::live::sessions::LiveSessions;
+use crate::live::sink::NullSink;
+use crate::packets::session_recorder::SessionReader;
+
+/// How fast recorded time is replayed relative to the host clock.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// Apply batches as fast as they decode.
+    Unthrottled,
+    /// Preserve the recorded inter-packet spacing.
+    RealTime,
+    /// Replay `n` recorded milliseconds per host millisecond.
+    Factor(f64),
+}
+
+impl ReplaySpeed {
+    fn factor(self) -> Option<f64> {
+        match self {
+            Self::Unthrottled => None,
+            Self::RealTime => Some(1.0),
+            Self::Factor(factor) => Some(factor),
+        }
+    }
+}
+
+impl FromStr for ReplaySpeed {
+    type Err = String;
+
+    /// Accepts `max`, `realtime` or a positive multiplier such as `4x`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_ascii_lowercase();
+        match value.as_str() {
+            "max" | "unthrottled" => return Ok(Self::Unthrottled),
+            "realtime" | "real-time" => return Ok(Self::RealTime),
+            _ => {}
+        }
+        let factor = value
+            .strip_suffix('x')
+            .unwrap_or(&value)
+            .parse::<f64>()
+            .map_err(|_| format!("invalid replay speed {value:?}"))?;
+        if !factor.is_finite() || factor <= 0.0 {
+            return Err(format!("replay speed must be positive, got {value:?}"));
+        }
+        Ok(Self::Factor(factor))
+    }
+}
+
+#[derive(specta::Type, serde::Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayStats {
+    pub envelopes: u64,
+    pub publications: u64,
+    /// Recorded time between the first and last envelope.
+    pub recorded_duration_ms: u64,
+}
+
+/// Host time at which `recorded_elapsed_ms` should be applied.
+fn pacing_target(recorded_elapsed_ms: u64, factor: f64) -> Duration {
+    Duration::from_secs_f64(recorded_elapsed_ms as f64 / 1_000.0 / factor)
+}
+
+/// Sleeps so recorded time advances at the selected speed.
+struct Pacer {
+    factor: Option<f64>,
+    origin: MonoTimeMs,
+    started: Instant,
+}
+
+impl Pacer {
+    fn new(speed: ReplaySpeed, origin: MonoTimeMs) -> Self {
+        Self {
+            factor: speed.factor(),
+            origin,
+            started: Instant::now(),
+        }
+    }
+
+    fn wait_until(&self, recorded: MonoTimeMs) {
+        let Some(factor) = self.factor else {
+            return;
+        };
+        let target = pacing_target(recorded.0.saturating_sub(self.origin.0), factor);
+        if let Some(delay) = target.checked_sub(self.started.elapsed()) {
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+/// Keeps capture sequence and monotonic time strictly ordered across
+/// concatenated recordings. A single app run is replayed unchanged; files from
+/// a later run restart both counters, so they are shifted to continue where the
+/// previous run stopped.
+#[derive(Debug, Default)]
+struct RecordedTimeline {
+    last_sequence: Option<u64>,
+    last_mono_ns: u64,
+    sequence_offset: u64,
+    mono_offset_ns: u64,
+}
+
+impl RecordedTimeline {
+    fn rebase(&mut self, envelope: &mut CaptureEnvelope) {
+        let mut sequence = envelope
+            .capture_sequence
+            .saturating_add(self.sequence_offset);
+        if let Some(last) = self.last_sequence
+            && sequence <= last
+        {
+            self.sequence_offset = last.saturating_add(1) - envelope.capture_sequence;
+            sequence = last.saturating_add(1);
+        }
+        let mut mono_ns = envelope
+            .captured_mono_ns
+            .saturating_add(self.mono_offset_ns);
+        if mono_ns < self.last_mono_ns {
+            self.mono_offset_ns = self.last_mono_ns - envelope.captured_mono_ns;
+            mono_ns = self.last_mono_ns;
+        }
+        envelope.capture_sequence = sequence;
+        envelope.captured_mono_ns = mono_ns;
+        self.last_sequence = Some(sequence);
+        self.last_mono_ns = mono_ns;
+    }
+}
+
+/// Envelopes from a list of session files, read in order.
+pub struct SessionEnvelopes {
+    paths: VecDeque<PathBuf>,
+    current: Option<SessionReader<BufReader<File>>>,
+}
+
+impl SessionEnvelopes {
+    pub fn new(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        Self {
+            paths: paths.into_iter().collect(),
+            current: None,
+        }
+    }
+}
+
+impl Iterator for SessionEnvelopes {
+    type Item = Result<CaptureEnvelope, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(reader) = self.current.as_mut() {
+                match reader.next_envelope() {
+                    Ok(Some(envelope)) => return Some(Ok(envelope)),
+                    Ok(None) => self.current = None,
+                    Err(error) => {
+                        self.current = None;
+                        return Some(Err(error));
+                    }
+                }
+            }
+            let path = self.paths.pop_front()?;
+            match SessionReader::open(&path) {
+                Ok(reader) => self.current = Some(reader),
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
+
+/// Drives a recorded envelope stream through decode and the live sessions.
+///
+/// `make_sessions` receives the recorded clock derived from the first
+/// envelope. Every publication of the selected session is handed to `sink`,
+/// including those released by deadlines between packets and the final
+/// shutdown snapshot.
+pub fn replay<I, F, S>(
+    envelopes: I,
+    speed: ReplaySpeed,
+    make_sessions: F,
+    mut sink: S,
+) -> Result<ReplayStats, String>
+where
+    I: IntoIterator<Item = Result<CaptureEnvelope, String>>,
+    F: FnOnce(ClockMode) -> Result<LiveSessions, String>,
+    S: FnMut(Publications),
+{
+    let mut envelopes = envelopes.into_iter().peekable();
+    let Some(first) = envelopes.peek() else {
+        return Ok(ReplayStats::default());
+    };
+    let first = first.as_ref().map_err(Clone::clone)?;
+    let origin = MonoTimeMs(first.captured_mono_ns / 1_000_000);
+    let mut sessions = make_sessions(ClockMode::Recorded {
+        start_mono: origin,
+        start_wall_ms: first.captured_wall_ms,
+    })?;
+
+    let pacer = Pacer::new(speed, origin);
+    let mut timeline = RecordedTimeline::default();
+    let mut decoders = StreamDecoders::new();
+    let mut stats = ReplayStats::default();
+    let mut last_time = origin;
+    let mut emit = |publications: Publications, stats: &mut ReplayStats| {
+        stats.publications += publications.topics.len() as u64;
+        sink(publications);
+    };
+
+    emit(sessions.publish_now()?, &mut stats);
+    for envelope in envelopes {
+        let mut envelope = envelope?;
+        timeline.rebase(&mut envelope);
+        let batch_time = MonoTimeMs(envelope.captured_mono_ns / 1_000_000);
+
+        // Release cadence publications that the live loop would have sent
+        // from its wakeup timer before this packet arrived.
+        let mut previous_wakeup = None;
+        while let Some(wakeup) = sessions.next_wakeup()
+            && wakeup < batch_time
+            && previous_wakeup != Some(wakeup)
+        {
+            pacer.wait_until(wakeup);
+            emit(sessions.take_due_publications(wakeup)?, &mut stats);
+            previous_wakeup = Some(wakeup);
+        }
+
+        pacer.wait_until(batch_time);
+        sessions.process_batch(decoders.decode(envelope))?;
+        emit(sessions.take_due_publications(batch_time)?, &mut stats);
+        stats.envelopes += 1;
+        last_time = batch_time;
+    }
+
+    sessions.shutdown()?;
+    emit(sessions.publish_now()?, &mut stats);
+    stats.recorded_duration_ms = last_time.0.saturating_sub(origin.0);
+    Ok(stats)
+}
+
+/// Replays session files into a dedicated history writer so the resulting
+/// encounters are written as new history rows. Live windows are not updated
+/// and nothing is spoken.
+pub fn replay_session_files(
+    app: &AppHandle,
+    paths: Vec<PathBuf>,
+    speed: ReplaySpeed,
+) -> Result<ReplayStats, String> {
+    let config = load_monitor_runtime_snapshot(app).unwrap_or_default();
+    let (history_writer, history_join) = HistoryWriterHandle::start()?;
+    let file_count = paths.len();
+    let result = replay(
+        SessionEnvelopes::new(paths),
+        speed,
+        |clock| LiveSessions::with_clock(Arc::new(NullSink), history_writer.clone(), config, clock),
+        |_| {},
+    );
+    let shutdown = history_writer.shutdown();
+    if history_join.join().is_err() {
+        return Err("replay history writer panicked".to_string());
+    }
+    let stats = result?;
+    shutdown?;
+    info!(
+        target: "app::live",
+        "session replay finished files={file_count} envelopes={} publications={} recorded_ms={}",
+        stats.envelopes,
+        stats.publications,
+        stats.recorded_duration_ms
+    );
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::runtime::events::{PacketDirection, PacketKey};
+    use bytes::Bytes;
+
+    fn envelope(capture_sequence: u64, captured_mono_ns: u64) -> CaptureEnvelope {
+        CaptureEnvelope {
+            capture_sequence,
+            stream_id: 1,
+            stream_epoch: 0,
+            captured_wall_ms: 0,
+            captured_mono_ns,
+            direction: PacketDirection::ServerToClient,
+            key: PacketKey {
+                opcode: 0,
+                service_id: None,
+                method_id: None,
+            },
+            payload: Bytes::new(),
+        }
+    }
+
+    #[test]
+    fn parses_replay_speeds() {
+        assert_eq!("max".parse(), Ok(ReplaySpeed::Unthrottled));
+        assert_eq!("RealTime".parse(), Ok(ReplaySpeed::RealTime));
+        assert_eq!("4x".parse(), Ok(ReplaySpeed::Factor(4.0)));
+        assert_eq!("0.5".parse(), Ok(ReplaySpeed::Factor(0.5)));
+        assert!("0x".parse::<ReplaySpeed>().is_err());
+        assert!("fast".parse::<ReplaySpeed>().is_err());
+    }
+
+    #[test]
+    fn pacing_scales_recorded_time() {
+        assert_eq!(pacing_target(1_000, 1.0), Duration::from_secs(1));
+        assert_eq!(pacing_target(1_000, 4.0), Duration::from_millis(250));
+        assert_eq!(pacing_target(0, 2.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn timeline_keeps_single_run_unchanged() {
+        let mut timeline = RecordedTimeline::default();
+        for (sequence, mono) in [(1, 10), (2, 10), (5, 30)] {
+            let mut item = envelope(sequence, mono);
+            timeline.rebase(&mut item);
+            assert_eq!(
+                (item.capture_sequence, item.captured_mono_ns),
+                (sequence, mono)
+            );
+        }
+    }
+
+    #[test]
+    fn timeline_continues_after_a_restarted_recording() {
+        let mut timeline = RecordedTimeline::default();
+        let mut first = envelope(100, 5_000);
+        timeline.rebase(&mut first);
+
+        let mut restarted = envelope(1, 200);
+        timeline.rebase(&mut restarted);
+        assert_eq!(restarted.capture_sequence, 101);
+        assert_eq!(restarted.captured_mono_ns, 5_000);
+
+        let mut next = envelope(2, 700);
+        timeline.rebase(&mut next);
+        assert_eq!(next.capture_sequence, 102);
+        assert_eq!(next.captured_mono_ns, 5_500);
+    }
+}
@@ -225,12 +225,10 @@ impl EntityContext {
         self.entities.get(&uuid).map(|entity| entity.entity)
     }
 
-    #[must_use]
     pub(crate) fn entities(&self) -> impl Iterator<Item = &EntityState> {
         self.entities.values()
     }
 
-    #[must_use]
     pub(crate) fn active_buffs(&self, uuid: EntityUuid) -> impl Iterator<Item = &BuffState> {
         self.entities
             .get(&uuid)
@@ -497,11 +495,15 @@ impl EntityContext {
                     out,
                 );
             }
-            ProtocolObservation::BuffSnapshot { target_uuid, buffs } => {
+            ProtocolObservation::BuffSnapshot {
+                target_uuid,
+                buffs,
+                complete,
+            } => {
                 let Some(target_uuid) = self.resolve_observation_uuid(target_uuid) else {
                     return;
                 };
-                self.apply_buff_snapshot(meta, target_uuid, buffs, out);
+                self.apply_buff_snapshot(meta, target_uuid, buffs, complete, out);
             }
             ProtocolObservation::BuffChanged {
                 target_uuid,
@@ -545,6 +547,13 @@ impl EntityContext {
                     out,
                 );
             }
+            ProtocolObservation::LocalSlotUsed { slot_id } => {
+                let Some(local_uuid) = self.local_player else {
+                    return;
+                };
+                let user = self.ensure_ref(local_uuid);
+                self.emit(meta, DomainEvent::LocalSlotUsed { user, slot_id }, out);
+            }
             ProtocolObservation::LocalSkillCompleted { skill_id } => {
                 let Some(local_uuid) = self.local_player else {
                     return;
@@ -1039,6 +1048,51 @@ impl EntityContext {
                     self.emit(meta, DomainEvent::PauseChanged { is_paused }, out);
                 }
             }
+            ProtocolObservation::ReviveNotified { uuid } => {
+                // Only a known corpse can revive; the ActorState flip that
+                // usually follows then sees a living entity and stays silent.
+                let Some(entity) = self.entities.get_mut(&uuid) else {
+                    return;
+                };
+                if !std::mem::replace(&mut entity.is_dead, false) {
+                    return;
+                }
+                let entity_ref = entity.entity;
+                self.cancel_pending_death(uuid);
+                self.emit(meta, DomainEvent::Revived { entity: entity_ref }, out);
+            }
+            ProtocolObservation::Teleported {
+                uuid,
+                position,
+                forced,
+            } => {
+                let Some(uuid) = self.resolve_observation_uuid(uuid) else {
+                    return;
+                };
+                let entity = self.ensure_ref(uuid);
+                if let Some(position) = position {
+                    self.entities
+                        .get_mut(&uuid)
+                        .expect("ensured above")
+                        .positions
+                        .insert(attr_type::ATTR_POS, position);
+                }
+                self.emit(
+                    meta,
+                    DomainEvent::Teleported {
+                        entity,
+                        position,
+                        forced,
+                    },
+                    out,
+                );
+            }
+            ProtocolObservation::ClientKickedOff { err_code } => {
+                self.emit(meta, DomainEvent::Disconnected { err_code }, out);
+            }
+            ProtocolObservation::CaptureIssue(issue) => {
+                self.emit(meta, DomainEvent::CaptureIssue(issue), out);
+            }
         }
     }
 
@@ -1112,12 +1166,32 @@ impl EntityContext {
         meta: EventMeta,
         target_uuid: EntityUuid,
         buffs: Vec<ObservedBuff>,
+        complete: bool,
         out: &mut Vec<DomainEnvelope>,
     ) {
         if !self.accepts_buff_upsert(target_uuid) {
             return;
         }
         let target = self.ensure_ref(target_uuid);
+        if complete {
+            let mut stale: Vec<i64> = self.entities[&target_uuid]
+                .active_buffs
+                .keys()
+                .copied()
+                .filter(|instance_id| !buffs.iter().any(|buff| buff.instance_id == *instance_id))
+                .collect();
+            stale.sort_unstable();
+            // The removes were lost (typically across a reassembly reset), so
+            // report them as the removes they stand in for.
+            for instance_id in stale {
+                self.apply_buff_change(
+                    meta,
+                    target_uuid,
+                    ObservedBuffChange::Remove { instance_id },
+                    out,
+                );
+            }
+        }
         for observed in buffs {
             let previous_layer = self.entities[&target_uuid]
                 .active_buffs
@@ -1300,6 +1374,7 @@ impl EntityContext {
         state
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn apply_fantasy(
         &mut self,
         meta: EventMeta,
@@ -1781,13 +1856,12 @@ impl EntityContext {
         let buffs = self
             .entities
             .values()
-            .filter_map(|entity| {
-                (!entity.active_buffs.is_empty()).then(|| {
-                    (
-                        entity.entity,
-                        entity.active_buffs.values().cloned().collect(),
-                    )
-                })
+            .filter(|&entity| !entity.active_buffs.is_empty())
+            .map(|entity| {
+                (
+                    entity.entity,
+                    entity.active_buffs.values().cloned().collect(),
+                )
             })
             .collect();
         DeathBuffCheckpoint::new(buffs)
@@ -2185,6 +2259,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn slot_use_is_attributed_to_the_local_player_once_known() {
+        let mut context = EntityContext::new();
+        let local = EntityUuid(10);
+        let before = context.reduce_batch(batch(
+            1,
+            vec![ProtocolObservation::LocalSlotUsed { slot_id: 11 }],
+        ));
+        assert!(before.is_empty());
+
+        context.reduce_batch(batch(
+            2,
+            vec![ProtocolObservation::LocalPlayerChanged { uuid: Some(local) }],
+        ));
+        let used = context.reduce_batch(batch(
+            3,
+            vec![ProtocolObservation::LocalSlotUsed { slot_id: 11 }],
+        ));
+        assert!(matches!(
+            used.as_slice(),
+            [DomainEnvelope {
+                event: DomainEvent::LocalSlotUsed { user, slot_id: 11 },
+                ..
+            }] if user.uuid == local
+        ));
+    }
+
     #[test]
     fn observations_addressed_to_an_unknown_local_player_are_dropped() {
         let mut context = EntityContext::new();
@@ -2231,6 +2332,7 @@ mod tests {
             vec![ProtocolObservation::BuffSnapshot {
                 target_uuid: EntityUuid(10),
                 buffs: vec![buff(1, 42)],
+                complete: false,
             }],
         ));
         let DomainEvent::BuffChanged(event) = &events[0].event else {
@@ -2250,6 +2352,7 @@ mod tests {
             vec![ProtocolObservation::BuffSnapshot {
                 target_uuid: EntityUuid(10),
                 buffs: Vec::new(),
+                complete: false,
             }],
         ));
         assert!(empty.is_empty());
@@ -2258,6 +2361,7 @@ mod tests {
             vec![ProtocolObservation::BuffSnapshot {
                 target_uuid: EntityUuid(10),
                 buffs: vec![buff(2, 43)],
+                complete: false,
             }],
         ));
         assert_eq!(partial.len(), 1);
@@ -2280,6 +2384,35 @@ mod tests {
         assert!(remaining.contains_key(&2));
     }
 
+    #[test]
+    fn complete_buff_snapshot_removes_instances_missing_from_it() {
+        let mut context = EntityContext::new();
+        let snapshot = |buffs, complete| ProtocolObservation::BuffSnapshot {
+            target_uuid: EntityUuid(10),
+            buffs,
+            complete,
+        };
+        context.reduce_batch(batch(
+            1,
+            vec![snapshot(vec![buff(1, 41), buff(2, 42), buff(3, 43)], false)],
+        ));
+
+        let events = context.reduce_batch(batch(2, vec![snapshot(vec![buff(2, 42)], true)]));
+        let removed: Vec<_> = events
+            .iter()
+            .filter_map(|envelope| match &envelope.event {
+                DomainEvent::BuffChanged(event) if event.transition == BuffTransition::Removed => {
+                    assert_eq!(event.wire_kind, BuffWireKind::Remove);
+                    Some(event.state.instance_id)
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(removed, vec![1, 3]);
+        let remaining = &context.entity(EntityUuid(10)).unwrap().active_buffs;
+        assert_eq!(remaining.keys().copied().collect::<Vec<_>>(), vec![2]);
+    }
+
     #[test]
     fn entity_disappearance_clears_all_buffs_without_synthetic_removes() {
         let mut context = EntityContext::new();
@@ -2351,6 +2484,7 @@ mod tests {
             vec![ProtocolObservation::BuffSnapshot {
                 target_uuid: target,
                 buffs: vec![buff(3, 43)],
+                complete: false,
             }],
         ));
 
@@ -2397,6 +2531,7 @@ mod tests {
                 ProtocolObservation::BuffSnapshot {
                     target_uuid: target,
                     buffs: vec![buff(1, 41)],
+                    complete: false,
                 },
                 ProtocolObservation::BuffChanged {
                     target_uuid: target,
@@ -2534,6 +2669,7 @@ mod tests {
             vec![ProtocolObservation::BuffSnapshot {
                 target_uuid: EntityUuid(10),
                 buffs: vec![buff(7, WIPE_BUFF_BASE_ID)],
+                complete: false,
             }],
         ));
         assert_eq!(baseline.len(), 1);
@@ -2967,6 +3103,7 @@ mod tests {
             vec![ProtocolObservation::BuffSnapshot {
                 target_uuid: EntityUuid(1),
                 buffs: vec![buff(1, 42)],
+                complete: false,
             }],
         ));
         let DomainEvent::BuffChanged(snapshot) = &snapshot[0].event else {
@@ -4019,6 +4156,100 @@ mod tests {
         assert!(death_events(&events).is_empty());
     }
 
+    #[test]
+    fn revive_notification_revives_once_ahead_of_actor_state() {
+        let mut context = EntityContext::new();
+        let victim = EntityUuid(72);
+        context.reduce_batch(batch(
+            1,
+            vec![
+                ProtocolObservation::EntityAppeared {
+                    uuid: victim,
+                    kind: EntityKind::Character,
+                },
+                actor_state(victim, 9, ObservationOrigin::Delta),
+            ],
+        ));
+
+        let events = context.reduce_batch(batch(
+            2,
+            vec![ProtocolObservation::ReviveNotified { uuid: victim }],
+        ));
+        assert!(matches!(
+            revived_events(&events).as_slice(),
+            [DomainEvent::Revived { entity }] if entity.uuid == victim
+        ));
+        assert!(!context.entities[&victim].is_dead);
+
+        // The ActorState flip that trails the notification is not a second revive.
+        let events = context.reduce_batch(batch(
+            3,
+            vec![actor_state(victim, 0, ObservationOrigin::Delta)],
+        ));
+        assert!(revived_events(&events).is_empty());
+
+        // A living or unknown actor has nothing to revive.
+        let events = context.reduce_batch(batch(
+            4,
+            vec![
+                ProtocolObservation::ReviveNotified { uuid: victim },
+                ProtocolObservation::ReviveNotified {
+                    uuid: EntityUuid(999),
+                },
+            ],
+        ));
+        assert!(revived_events(&events).is_empty());
+    }
+
+    #[test]
+    fn teleport_resolves_the_local_player_and_moves_it() {
+        let mut context = EntityContext::new();
+        let local = EntityUuid(10);
+        let destination = Position {
+            x: 10.0,
+            y: 2.0,
+            z: -4.0,
+        };
+        // Before the local player is known there is nobody to move.
+        let events = context.reduce_batch(batch(
+            1,
+            vec![ProtocolObservation::Teleported {
+                uuid: LOCAL_PLAYER,
+                position: Some(destination),
+                forced: false,
+            }],
+        ));
+        assert!(events.is_empty());
+
+        context.reduce_batch(batch(
+            2,
+            vec![ProtocolObservation::LocalPlayerChanged { uuid: Some(local) }],
+        ));
+        let events = context.reduce_batch(batch(
+            3,
+            vec![ProtocolObservation::Teleported {
+                uuid: LOCAL_PLAYER,
+                position: Some(destination),
+                forced: true,
+            }],
+        ));
+        assert!(matches!(
+            events.as_slice(),
+            [DomainEnvelope {
+                event: DomainEvent::Teleported {
+                    entity,
+                    position: Some(_),
+                    forced: true,
+                },
+                ..
+            }] if entity.uuid == local
+        ));
+        assert_eq!(
+            context.entities[&local].positions.get(&attr_type::ATTR_POS),
+            Some(&destination)
+        );
+    }
+
     #[test]
     fn dead_entity_is_not_the_current_attack_target() {
         let mut context = EntityContext::new();
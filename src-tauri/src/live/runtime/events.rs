@@ -38,6 +38,7 @@ pub fn wall_now_ms() -> i64 {
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 #[repr(transparent)]
 pub struct MonoTimeMs(pub u64);
 
@@ -62,6 +63,7 @@ pub struct BatchId(pub u64);
 pub struct SegmentId(pub u64);
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 #[repr(transparent)]
 pub struct EntityUuid(pub i64);
 
@@ -72,7 +74,7 @@ pub struct EntityRef {
     pub generation: u64,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PacketDirection {
     ClientToServer,
     ServerToClient,
@@ -137,6 +139,7 @@ impl EventMeta {
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub enum ObservationOrigin {
     Snapshot,
     #[default]
@@ -150,6 +153,7 @@ pub struct ProtocolBatch {
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub enum EntityKind {
     #[default]
     Unknown,
@@ -162,6 +166,7 @@ pub enum EntityKind {
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub struct Position {
     pub x: f32,
     pub y: f32,
@@ -169,12 +174,14 @@ pub struct Position {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub enum AttributeValue {
     Int(i64),
     Text(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub enum FieldPatch<T> {
     #[default]
     Unchanged,
@@ -183,6 +190,8 @@ pub enum FieldPatch<T> {
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+#[cfg_attr(test, serde(default))]
 pub struct EntityIdentityPatch {
     pub kind: FieldPatch<EntityKind>,
     pub name: FieldPatch<String>,
@@ -212,6 +221,7 @@ impl EntityIdentity {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub struct ObservedBuff {
     pub instance_id: i64,
     pub base_id: i32,
@@ -223,12 +233,14 @@ pub struct ObservedBuff {
     pub expires_wall_ms: Option<i64>,
     pub started_mono_ms: Option<MonoTimeMs>,
     pub expires_mono_ms: Option<MonoTimeMs>,
+    #[cfg_attr(test, serde(deserialize_with = "fixture::effect_ids"))]
     pub effect_ids: Arc<[i32]>,
 }
 
 /// Wire-level buff delta. The decoder emits raw observations; [`EntityContext`]
 /// owns the authoritative buff table and merges `Delta` onto existing instances.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub enum ObservedBuffChange {
     Applied {
         buff: ObservedBuff,
@@ -238,6 +250,10 @@ pub enum ObservedBuffChange {
         layer: Option<i32>,
         duration_ms: Option<u64>,
         create_time: Option<i64>,
+        #[cfg_attr(
+            test,
+            serde(default, deserialize_with = "fixture::optional_effect_ids")
+        )]
         effect_ids: Option<Arc<[i32]>>,
     },
     Remove {
@@ -246,6 +262,7 @@ pub enum ObservedBuffChange {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub enum HitKind {
     Damage,
     Healing,
@@ -254,12 +271,14 @@ pub enum HitKind {
 /// Which packet channel carried a hit. Taken-damage counters only trust
 /// `ToMe` (old pipeline parity: near deltas never fed `DamageTaken`).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub enum HitChannel {
     ToMe,
     Near,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub struct ObservedHit {
     pub channel: HitChannel,
     pub source_uuid: Option<EntityUuid>,
@@ -267,14 +286,19 @@ pub struct ObservedHit {
     pub target_uuid: EntityUuid,
     pub skill_key: i64,
     pub skill_id: Option<i32>,
+    #[cfg_attr(test, serde(default))]
     pub type_flags: i32,
     pub kind: HitKind,
     pub amount: u128,
     /// Whether the packet supplied an authoritative HP/shield split.
+    #[cfg_attr(test, serde(default))]
     pub has_loss_breakdown: bool,
+    #[cfg_attr(test, serde(default))]
     pub hp_loss: u128,
+    #[cfg_attr(test, serde(default))]
     pub shield_loss: u128,
     /// True when `amount` is only the bonus component from a lucky hit.
+    #[cfg_attr(test, serde(default))]
     pub is_lucky_bonus_only: bool,
     pub property: Option<i32>,
     pub damage_mode: Option<i32>,
@@ -284,6 +308,7 @@ pub struct ObservedHit {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub enum SkillPhase {
     CastStarted,
     DurationStarted,
@@ -293,6 +318,7 @@ pub enum SkillPhase {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub struct SkillCooldownState {
     pub skill_level_id: i32,
     pub begin_time: Option<i64>,
@@ -302,6 +328,7 @@ pub struct SkillCooldownState {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub struct ShieldDetail {
     pub buff_instance_id: i64,
     pub display_type: i32,
@@ -318,6 +345,7 @@ pub struct ResolvedShieldDetail {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub struct PassiveSkillObservation {
     pub entity_uuid: EntityUuid,
     pub passive_instance_id: i32,
@@ -327,6 +355,7 @@ pub struct PassiveSkillObservation {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub struct BossMechanicObservation {
     pub base_skill_id: i32,
     pub skill_effect_id: i32,
@@ -358,21 +387,32 @@ pub enum SegmentReason {
     FirstCombat,
     AutomaticObjective,
     Wipe,
+    /// The scene changed ([`super::segment::SegmentMode::DungeonRun`] and
+    /// [`super::segment::SegmentMode::SceneChange`]).
+    SceneChanged,
+    /// The dungeon flow entered its playing state.
+    DungeonRunStarted,
+    /// A boss died ([`super::segment::SegmentMode::BossOnly`]).
+    BossDefeated,
     Manual,
     ContainerResync,
     TrainingStarted,
     TrainingElapsed,
     MaxDurationElapsed,
     Shutdown,
+    /// The server kicked the client off (NotifyClientKickOff).
+    Disconnected,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub struct GameTimerKey {
     pub cfg_id: i32,
     pub timer_type: i32,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub struct GameTimerState {
     pub key: GameTimerKey,
     pub execution_type: i32,
@@ -461,13 +501,34 @@ pub enum TimerScope {
     Segment(SegmentId),
 }
 
+/// Capture-side degradation reported in order with the packets it affected.
+/// It describes the transport, not the game, and never touches entity state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub enum CaptureIssue {
+    /// TCP bytes that never arrived and were skipped by reassembly.
+    SegmentsDropped { lost_bytes: u32 },
+    /// Reassembly restarted (SYN, FIN/RST, sequence regression).
+    ReassemblyReset,
+    /// A frame was cut short or carried an impossible length.
+    MalformedFrame,
+    /// A compressed frame failed to decompress.
+    DecompressionFailed,
+    /// A world notify opcode the decoder does not know.
+    UnknownOpcode { opcode: u32 },
+    /// Capture waited for the decode queue instead of reading packets.
+    BackpressureStall { stalled_ms: u32 },
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub struct HateEntry {
     pub entity_uuid: EntityUuid,
     pub value: u32,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(serde::Deserialize))]
 pub enum ProtocolObservation {
     ContainerReset,
     EntityAppeared {
@@ -500,6 +561,9 @@ pub enum ProtocolObservation {
     BuffSnapshot {
         target_uuid: EntityUuid,
         buffs: Vec<ObservedBuff>,
+        /// The target's whole buff set: instances missing from it are gone.
+        /// Entity appearances only upsert.
+        complete: bool,
     },
     BuffChanged {
         target_uuid: EntityUuid,
@@ -513,6 +577,11 @@ pub enum ProtocolObservation {
         skill_id: i32,
         target_uuid: Option<EntityUuid>,
     },
+    /// Local player pressed a non-skill slot via client→server UseSlot.
+    /// `slot_id` is the bar position, not the item in it.
+    LocalSlotUsed {
+        slot_id: i32,
+    },
     /// Server acknowledged local skill completion (SyncServerSkillEnd).
     LocalSkillCompleted {
         skill_id: i32,
@@ -604,6 +673,22 @@ pub enum ProtocolObservation {
     PauseChanged {
         is_paused: bool,
     },
+    /// Server-side revive of an actor (NotifyReviveUser).
+    ReviveNotified {
+        uuid: EntityUuid,
+    },
+    /// The server moved the local player discontinuously: a teleport, or a
+    /// forced pull-back to a server-validated position.
+    Teleported {
+        uuid: EntityUuid,
+        position: Option<Position>,
+        forced: bool,
+    },
+    /// The server ended the session (NotifyClientKickOff).
+    ClientKickedOff {
+        err_code: Option<i32>,
+    },
+    CaptureIssue(CaptureIssue),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -776,11 +861,24 @@ pub enum DomainEvent {
         skill_key: Option<i64>,
         buff_checkpoint: DeathBuffCheckpoint,
     },
-    /// A previously dead entity returned to life (ActorState flipped back).
+    /// A previously dead entity returned to life (ActorState flipped back, or
+    /// an explicit NotifyReviveUser).
     /// The dual of `DeathOccurred`; carries no replay payload.
     Revived {
         entity: EntityRef,
     },
+    /// The entity jumped to a new position; consumers that connect
+    /// consecutive positions must start over instead of drawing the jump.
+    Teleported {
+        entity: EntityRef,
+        position: Option<Position>,
+        forced: bool,
+    },
+    /// The server kicked the client; the active segment ends with
+    /// [`SegmentReason::Disconnected`].
+    Disconnected {
+        err_code: Option<i32>,
+    },
     FantasyChanged {
         transition: FantasyTransition,
         fantasy: FantasyState,
@@ -789,6 +887,13 @@ pub enum DomainEvent {
         entity: Option<EntityRef>,
         buff_instance_id: Option<i64>,
     },
+    /// The local player used a non-skill slot. Uplink-only: the server's
+    /// effect (a buff, a heal) arrives separately. `slot_id` does not say
+    /// which item the slot holds.
+    LocalSlotUsed {
+        user: EntityRef,
+        slot_id: i32,
+    },
     SkillLifecycleChanged {
         caster: EntityRef,
         skill_id: i32,
@@ -857,6 +962,7 @@ pub enum DomainEvent {
     PauseChanged {
         is_paused: bool,
     },
+    CaptureIssue(CaptureIssue),
     SegmentStarted {
         segment_id: SegmentId,
         reason: SegmentReason,
@@ -892,3 +998,28 @@ pub struct DomainEnvelope {
     pub segment_id: Option<SegmentId>,
     pub event: DomainEvent,
 }
+
+/// Readers for the shared slices of observations loaded from scenario
+/// fixtures (`live::scenario`); serde's `rc` feature is not enabled.
+#[cfg(test)]
+mod fixture {
+    use std::sync::Arc;
+
+    use serde::{Deserialize, Deserializer};
+
+    pub(super) fn effect_ids<'de, D>(deserializer: D) -> Result<Arc<[i32]>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<i32>::deserialize(deserializer).map(Arc::from)
+    }
+
+    pub(super) fn optional_effect_ids<'de, D>(
+        deserializer: D,
+    ) -> Result<Option<Arc<[i32]>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<Vec<i32>>::deserialize(deserializer).map(|ids| ids.map(Arc::from))
+    }
+}
@@ -1,36 +1,100 @@
 //! Encounter segment policy and packet-level combat routing.
 
+use serde::{Deserialize, Serialize};
+
 use super::events::{
-    DomainEnvelope, DomainEvent, DomainHit, EntityRef, EventMeta, HitKind, MonoTimeMs, SegmentId,
-    SegmentReason, TimerKey, TimerScope,
+    DomainEnvelope, DomainEvent, DomainHit, EntityKind, EntityRef, EventMeta, HitKind, MonoTimeMs,
+    SegmentId, SegmentReason, TimerKey, TimerScope,
 };
 use super::scheduler::{DeadlineScheduler, DueTimer, TimerTask};
 
-pub const AUTOMATIC_BOUNDARY_DELAY_MS: u64 = 3_000;
+pub const DEFAULT_BOUNDARY_DELAY_MS: u64 = 3_000;
 pub const TRAINING_WINDOW_MS: u64 = 183_000;
-/// Hard cap on one standard segment's lifetime, so field combat cannot keep
-/// a single segment (and its history event chunks) growing for hours.
-pub const MAX_SEGMENT_DURATION_MS: u64 = 20 * 60 * 1_000;
-pub const TRAINING_DUMMY_MONSTER_IDS: [i32; 2] = [115, 122];
+/// Default cap on one standard segment's lifetime, so field combat cannot
+/// keep a single segment (and its history event chunks) growing for hours.
+pub const DEFAULT_MAX_SEGMENT_DURATION_MS: u64 = 20 * 60 * 1_000;
+pub const DEFAULT_TRAINING_DUMMY_MONSTER_IDS: [i32; 2] = [115, 122];
+
+/// What ends a standard segment besides manual resets, resyncs and the
+/// lifetime cap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SegmentMode {
+    /// Split on new dungeon objectives and wipes (raid heuristics).
+    #[default]
+    ObjectiveAndWipe,
+    /// One segment per dungeon run: split when a new run starts or the scene
+    /// changes, never on objectives or wipes.
+    DungeonRun,
+    /// Split only when the scene changes.
+    SceneChange,
+    /// Only damage to bosses opens and feeds a segment; it ends on a wipe, a
+    /// new objective or a boss death.
+    BossOnly,
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum CombatGate {
-    AllowAll,
-    Only(EntityRef),
-    BlockAll,
+impl SegmentMode {
+    /// Whether an automatic boundary for `reason` ends the segment.
+    #[must_use]
+    pub const fn splits_on(self, reason: SegmentReason) -> bool {
+        match self {
+            Self::ObjectiveAndWipe => matches!(
+                reason,
+                SegmentReason::AutomaticObjective | SegmentReason::Wipe
+            ),
+            Self::DungeonRun => matches!(
+                reason,
+                SegmentReason::DungeonRunStarted | SegmentReason::SceneChanged
+            ),
+            Self::SceneChange => matches!(reason, SegmentReason::SceneChanged),
+            Self::BossOnly => matches!(
+                reason,
+                SegmentReason::AutomaticObjective
+                    | SegmentReason::Wipe
+                    | SegmentReason::BossDefeated
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SegmentPolicy {
+    pub mode: SegmentMode,
+    /// Grace period between an automatic boundary and the segment end.
+    pub boundary_delay_ms: u64,
+    pub max_segment_duration_ms: u64,
+    /// Monsters whose first local hit opens an armed training segment.
+    pub training_dummy_monster_ids: Vec<i32>,
 }
 
-impl Default for CombatGate {
+impl Default for SegmentPolicy {
     fn default() -> Self {
-        Self::AllowAll
+        Self {
+            mode: SegmentMode::default(),
+            boundary_delay_ms: DEFAULT_BOUNDARY_DELAY_MS,
+            max_segment_duration_ms: DEFAULT_MAX_SEGMENT_DURATION_MS,
+            training_dummy_monster_ids: DEFAULT_TRAINING_DUMMY_MONSTER_IDS.to_vec(),
+        }
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CombatGate {
+    #[default]
+    AllowAll,
+    /// Drops damage to non-boss monsters ([`SegmentMode::BossOnly`]).
+    BossesOnly,
+    Only(EntityRef),
+    BlockAll,
+}
+
 impl CombatGate {
     #[must_use]
     pub fn accepts(self, hit: &DomainHit) -> bool {
         match self {
             Self::AllowAll => true,
+            Self::BossesOnly => !is_trash_damage(hit),
             Self::Only(target) => hit.target == target,
             Self::BlockAll => false,
         }
@@ -115,6 +179,7 @@ pub struct SegmentController {
     next_segment_id: u64,
     opener_buffer: Vec<DomainEnvelope>,
     training_window_ms: u64,
+    policy: SegmentPolicy,
 }
 
 impl Default for SegmentController {
@@ -124,6 +189,7 @@ impl Default for SegmentController {
             next_segment_id: 0,
             opener_buffer: Vec::new(),
             training_window_ms: TRAINING_WINDOW_MS,
+            policy: SegmentPolicy::default(),
         }
     }
 }
@@ -138,6 +204,19 @@ impl SegmentController {
         self.training_window_ms = window_ms;
     }
 
+    /// Applies a new policy live. An armed boundary keeps its deadline; the
+    /// lifetime cap of a recording standard segment moves to the new limit.
+    pub fn set_policy(&mut self, policy: SegmentPolicy, scheduler: &mut DeadlineScheduler) {
+        self.policy = policy;
+        if let SegmentState::Recording {
+            segment,
+            mode: RecordingMode::Standard { .. },
+        } = self.state
+        {
+            self.schedule_max_duration(segment, scheduler);
+        }
+    }
+
     #[must_use]
     pub const fn state(&self) -> &SegmentState {
         &self.state
@@ -159,7 +238,13 @@ impl SegmentController {
             | SegmentState::Recording {
                 mode: RecordingMode::Standard { .. },
                 ..
-            } => CombatGate::AllowAll,
+            } => {
+                if self.policy.mode == SegmentMode::BossOnly {
+                    CombatGate::BossesOnly
+                } else {
+                    CombatGate::AllowAll
+                }
+            }
             SegmentState::Recording {
                 mode: RecordingMode::Training { target, .. },
                 ..
@@ -194,10 +279,10 @@ impl SegmentController {
             match &envelope.event {
                 DomainEvent::PauseChanged { is_paused } => paused = *is_paused,
                 DomainEvent::HitResolved(hit) if !paused => {
-                    if eligible_hit.is_none() && is_eligible_player_damage(hit) {
+                    if eligible_hit.is_none() && self.is_standard_opener(hit) {
                         eligible_hit = Some((hit, envelope.event_index));
                     }
-                    if training_hit.is_none() && is_training_opener(hit) {
+                    if training_hit.is_none() && self.is_training_opener(hit) {
                         training_hit = Some((hit, envelope.event_index));
                     }
                 }
@@ -268,14 +353,18 @@ impl SegmentController {
         }
     }
 
-    /// Arms an automatic boundary. Its deadline ends the current segment and
-    /// enters idle even when no further packet arrives.
+    /// Arms an automatic boundary when the policy mode splits on `reason`.
+    /// Its deadline ends the current segment and enters idle even when no
+    /// further packet arrives.
     pub fn arm_automatic_boundary(
         &mut self,
         meta: EventMeta,
         reason: SegmentReason,
         scheduler: &mut DeadlineScheduler,
     ) -> bool {
+        if !self.policy.mode.splits_on(reason) {
+            return false;
+        }
         let SegmentState::Recording {
             segment,
             mode: RecordingMode::Standard { boundary, .. },
@@ -283,7 +372,7 @@ impl SegmentController {
         else {
             return false;
         };
-        let deadline = meta.mono_ms().saturating_add(AUTOMATIC_BOUNDARY_DELAY_MS);
+        let deadline = meta.mono_ms().saturating_add(self.policy.boundary_delay_ms);
         if let BoundaryState::BoundaryDelay {
             deadline: current, ..
         } = boundary
@@ -352,7 +441,7 @@ impl SegmentController {
                 };
                 let expected_deadline = segment
                     .started_at_mono_ms
-                    .saturating_add(MAX_SEGMENT_DURATION_MS);
+                    .saturating_add(self.policy.max_segment_duration_ms);
                 if segment.id != segment_id || expected_deadline != due.scheduled_for {
                     return Vec::new();
                 }
@@ -449,6 +538,14 @@ impl SegmentController {
         self.immediate_boundary(meta, SegmentReason::Shutdown, false, scheduler)
     }
 
+    pub fn disconnect_boundary(
+        &mut self,
+        meta: EventMeta,
+        scheduler: &mut DeadlineScheduler,
+    ) -> Vec<DomainEvent> {
+        self.immediate_boundary(meta, SegmentReason::Disconnected, false, scheduler)
+    }
+
     pub fn stop_training(
         &mut self,
         meta: EventMeta,
@@ -479,6 +576,19 @@ impl SegmentController {
         }
     }
 
+    fn is_standard_opener(&self, hit: &DomainHit) -> bool {
+        is_eligible_player_damage(hit)
+            && (self.policy.mode != SegmentMode::BossOnly || hit.target_is_boss)
+    }
+
+    fn is_training_opener(&self, hit: &DomainHit) -> bool {
+        is_eligible_player_damage(hit)
+            && hit.source_is_local_player
+            && hit
+                .target_monster_id
+                .is_some_and(|id| self.policy.training_dummy_monster_ids.contains(&id))
+    }
+
     fn is_training_armed(&self) -> bool {
         matches!(
             self.state,
@@ -550,8 +660,13 @@ impl SegmentController {
                 training_armed: false,
             },
         };
-        // Hard lifetime cap for standard segments. Training segments are
-        // already bounded by the configured training window and never schedule this.
+        self.schedule_max_duration(segment, scheduler);
+        out.push(started_event(segment, reason));
+    }
+
+    /// Hard lifetime cap for standard segments. Training segments are
+    /// already bounded by the configured training window and never schedule this.
+    fn schedule_max_duration(&self, segment: ActiveSegment, scheduler: &mut DeadlineScheduler) {
         scheduler.schedule(
             TimerKey::SegmentMaxDuration {
                 segment_id: segment.id,
@@ -559,12 +674,11 @@ impl SegmentController {
             TimerScope::Segment(segment.id),
             segment
                 .started_at_mono_ms
-                .saturating_add(MAX_SEGMENT_DURATION_MS),
+                .saturating_add(self.policy.max_segment_duration_ms),
             TimerTask::SegmentMaxDuration {
                 segment_id: segment.id,
             },
         );
-        out.push(started_event(segment, reason));
     }
 
     fn start_training(
@@ -685,12 +799,8 @@ fn is_eligible_player_damage(hit: &DomainHit) -> bool {
     hit.kind == HitKind::Damage && hit.source_is_player && hit.amount > 0
 }
 
-fn is_training_opener(hit: &DomainHit) -> bool {
-    is_eligible_player_damage(hit)
-        && hit.source_is_local_player
-        && hit
-            .target_monster_id
-            .is_some_and(|id| TRAINING_DUMMY_MONSTER_IDS.contains(&id))
+fn is_trash_damage(hit: &DomainHit) -> bool {
+    hit.kind == HitKind::Damage && hit.target_kind == EntityKind::Monster && !hit.target_is_boss
 }
 
 fn started_event(segment: ActiveSegment, reason: SegmentReason) -> DomainEvent {
@@ -1017,6 +1127,33 @@ mod tests {
         assert!(matches!(controller.state(), SegmentState::Idle { .. }));
     }
 
+    #[test]
+    fn disconnect_finalizes_the_active_segment_with_its_own_reason() {
+        let mut controller = SegmentController::new();
+        let mut scheduler = DeadlineScheduler::new();
+        let packet = [hit_event(1, 1_000, target(20), None, false)];
+        controller.preflight_batch(&packet, false, &mut scheduler);
+
+        let ended = controller.disconnect_boundary(meta(2, 1_100), &mut scheduler);
+        assert!(matches!(
+            ended.as_slice(),
+            [DomainEvent::SegmentEnded {
+                segment_id: SegmentId(1),
+                reason: SegmentReason::Disconnected,
+                ..
+            }]
+        ));
+        assert!(matches!(controller.state(), SegmentState::Idle { .. }));
+        assert!(scheduler.is_empty());
+
+        // Idle already: a second kick has nothing to end.
+        assert!(
+            controller
+                .disconnect_boundary(meta(3, 1_200), &mut scheduler)
+                .is_empty()
+        );
+    }
+
     #[test]
     fn max_duration_timer_ends_segment_and_next_hit_starts_new_one() {
         let mut controller = SegmentController::new();
@@ -1027,7 +1164,7 @@ mod tests {
         assert_eq!(decision.segment_id, Some(SegmentId(1)));
 
         // The lifetime cap is the only timer a fresh standard segment has.
-        let deadline = MonoTimeMs(1_000 + MAX_SEGMENT_DURATION_MS);
+        let deadline = MonoTimeMs(1_000 + DEFAULT_MAX_SEGMENT_DURATION_MS);
         assert_eq!(scheduler.len(), 1);
         assert_eq!(scheduler.next_deadline(), Some(deadline));
 
@@ -1055,7 +1192,9 @@ mod tests {
         ));
         assert_eq!(
             scheduler.next_deadline(),
-            Some(MonoTimeMs(deadline.0 + 10 + MAX_SEGMENT_DURATION_MS))
+            Some(MonoTimeMs(
+                deadline.0 + 10 + DEFAULT_MAX_SEGMENT_DURATION_MS
+            ))
         );
     }
 
@@ -1093,7 +1232,7 @@ mod tests {
             },
             kind: TimerKind::SegmentMaxDuration,
             scope: TimerScope::Segment(SegmentId(1)),
-            scheduled_for: MonoTimeMs(1_000 + MAX_SEGMENT_DURATION_MS),
+            scheduled_for: MonoTimeMs(1_000 + DEFAULT_MAX_SEGMENT_DURATION_MS),
             generation: 0,
             task: TimerTask::SegmentMaxDuration {
                 segment_id: SegmentId(1),
@@ -1152,4 +1291,99 @@ mod tests {
             Some(MonoTimeMs(2_000 + TRAINING_WINDOW_MS))
         );
     }
+
+    #[test]
+    fn policy_mode_decides_which_boundaries_split() {
+        let mut controller = SegmentController::new();
+        let mut scheduler = DeadlineScheduler::new();
+        controller.set_policy(
+            SegmentPolicy {
+                mode: SegmentMode::DungeonRun,
+                boundary_delay_ms: 500,
+                ..SegmentPolicy::default()
+            },
+            &mut scheduler,
+        );
+        let packet = [hit_event(1, 1_000, target(20), None, false)];
+        controller.preflight_batch(&packet, false, &mut scheduler);
+
+        assert!(!controller.arm_automatic_boundary(
+            meta(2, 1_100),
+            SegmentReason::Wipe,
+            &mut scheduler,
+        ));
+        assert!(!controller.arm_automatic_boundary(
+            meta(2, 1_100),
+            SegmentReason::AutomaticObjective,
+            &mut scheduler,
+        ));
+        assert!(controller.arm_automatic_boundary(
+            meta(3, 1_200),
+            SegmentReason::SceneChanged,
+            &mut scheduler,
+        ));
+        assert_eq!(scheduler.next_deadline(), Some(MonoTimeMs(1_700)));
+    }
+
+    #[test]
+    fn set_policy_moves_the_max_duration_of_the_active_segment() {
+        let mut controller = SegmentController::new();
+        let mut scheduler = DeadlineScheduler::new();
+        let packet = [hit_event(1, 1_000, target(20), None, false)];
+        controller.preflight_batch(&packet, false, &mut scheduler);
+
+        controller.set_policy(
+            SegmentPolicy {
+                max_segment_duration_ms: 60_000,
+                ..SegmentPolicy::default()
+            },
+            &mut scheduler,
+        );
+        assert_eq!(scheduler.len(), 1);
+        assert_eq!(scheduler.next_deadline(), Some(MonoTimeMs(61_000)));
+
+        let due = scheduler.drain_due(MonoTimeMs(61_000)).remove(0);
+        let ended = controller.on_timer(due, 1_061_000, &mut scheduler);
+        assert!(matches!(
+            ended.as_slice(),
+            [DomainEvent::SegmentEnded {
+                reason: SegmentReason::MaxDurationElapsed,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn boss_only_mode_ignores_trash_damage() {
+        let mut controller = SegmentController::new();
+        let mut scheduler = DeadlineScheduler::new();
+        controller.set_policy(
+            SegmentPolicy {
+                mode: SegmentMode::BossOnly,
+                ..SegmentPolicy::default()
+            },
+            &mut scheduler,
+        );
+        let trash = [hit_event(1, 1_000, target(20), None, false)];
+        let decision = controller.preflight_batch(&trash, false, &mut scheduler);
+        assert_eq!(decision.segment_id, None);
+        assert_eq!(decision.combat_gate, CombatGate::BossesOnly);
+        let DomainEvent::HitResolved(trash_hit) = &trash[0].event else {
+            unreachable!("hit_event builds a hit");
+        };
+        assert!(!CombatGate::BossesOnly.accepts(trash_hit));
+
+        let mut boss = hit_event(2, 2_000, target(30), None, false);
+        if let DomainEvent::HitResolved(hit) = &mut boss.event {
+            hit.target_is_boss = true;
+        }
+        let decision = controller.preflight_batch(&[boss], false, &mut scheduler);
+        assert_eq!(decision.segment_id, Some(SegmentId(1)));
+        assert_eq!(decision.combat_gate, CombatGate::BossesOnly);
+        assert!(controller.arm_automatic_boundary(
+            meta(3, 3_000),
+            SegmentReason::BossDefeated,
+            &mut scheduler,
+        ));
+    }
 }
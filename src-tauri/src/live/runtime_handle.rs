@@ -5,6 +5,7 @@ use std::time::Duration;
 use tokio::sync::{mpsc, oneshot};
 
 use crate::live::bootstrap_snapshot::MonitorRuntimeSnapshot;
+use crate::live::projections::script::UserScript;
 const CONTROL_CAPACITY: usize = 64;
 const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
 
@@ -12,9 +13,13 @@ const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
 pub enum RuntimeCommand {
     ManualReset,
     TogglePause,
-    ApplyMonitorConfig(MonitorRuntimeSnapshot),
+    ApplyMonitorConfig(Box<MonitorRuntimeSnapshot>),
+    /// Replaces every user script, restarting each from `init()`.
+    ApplyUserScripts(Vec<UserScript>),
     StartTraining,
     StopTraining,
+    /// Points the live windows at another captured game client.
+    SelectSession(u32),
     Shutdown {
         reply: oneshot::Sender<Result<(), String>>,
     },
@@ -43,10 +48,14 @@ impl LiveRuntimeHandle {
         &self,
         snapshot: MonitorRuntimeSnapshot,
     ) -> Result<(), String> {
-        self.send(RuntimeCommand::ApplyMonitorConfig(snapshot))
+        self.send(RuntimeCommand::ApplyMonitorConfig(Box::new(snapshot)))
             .await
     }
 
+    pub async fn apply_user_scripts(&self, scripts: Vec<UserScript>) -> Result<(), String> {
+        self.send(RuntimeCommand::ApplyUserScripts(scripts)).await
+    }
+
     pub async fn start_training(&self) -> Result<(), String> {
         self.send(RuntimeCommand::StartTraining).await
     }
@@ -55,6 +64,10 @@ impl LiveRuntimeHandle {
         self.send(RuntimeCommand::StopTraining).await
     }
 
+    pub async fn select_session(&self, session_id: u32) -> Result<(), String> {
+        self.send(RuntimeCommand::SelectSession(session_id)).await
+    }
+
     /// Synchronous Tauri exit hook adapter. The runtime replies only after
     /// capture/decode drain, active-segment finalize, and the DB actor fence.
     pub fn shutdown_blocking(&self) -> Result<(), String> {
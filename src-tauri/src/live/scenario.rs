@@ -0,0 +1,767 @@
+//! Golden scenario tests for the live domain.
+//!
+//! A scenario is a JSON fixture under `tests/scenarios/`: an optional runtime
+//! config and timed steps carrying `ProtocolObservation`s (serde's externally
+//! tagged form, e.g. `{"LocalPlayerChanged": {"uuid": 7}}`) and runtime
+//! commands. It runs through a headless `LiveCore` on a recorded clock, and
+//! the domain events, publications and history writes it produced are
+//! compared with the `<name>.golden` transcript next to the fixture.
+//!
+//! Recorded session files go through the same harness via `replay`, the path
+//! the replay command takes, so a capture replays to one transcript however
+//! often it is run.
+//!
+//! A fixture without a transcript fails. Run with `UPDATE_GOLDEN=1` to write
+//! missing transcripts or rewrite them after an intended behaviour change,
+//! and commit the result.
+
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::live::bootstrap_snapshot::MonitorRuntimeSnapshot;
+use crate::live::history_writer::{HistoryWriterHandle, RecordedHistory};
+use crate::live::live_core::{ClockMode, LiveCore, Publications};
+use crate::live::projection_set::TopicPublication;
+use crate::live::replay::{ReplaySpeed, SessionEnvelopes, replay};
+use crate::live::runtime::events::{
+    BatchId, DomainEnvelope, DomainEvent, EventMeta, MonoTimeMs, ProtocolBatch, ProtocolObservation,
+};
+use crate::live::runtime_handle::RuntimeCommand;
+use crate::live::sessions::LiveSessions;
+use crate::live::sink::NullSink;
+
+const START_MONO_MS: u64 = 1_000_000;
+const START_WALL_MS: i64 = 1_700_000_000_000;
+const UPDATE_GOLDEN_ENV: &str = "UPDATE_GOLDEN";
+const FIXTURE_EXTENSION: &str = "json";
+const GOLDEN_EXTENSION: &str = "golden";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Scenario {
+    #[serde(default)]
+    description: String,
+    /// Partial configs are completed with defaults.
+    #[serde(default)]
+    config: MonitorRuntimeSnapshot,
+    steps: Vec<ScenarioStep>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScenarioStep {
+    /// Milliseconds since the scenario start; never decreasing.
+    at_ms: u64,
+    /// Applied before the step's observations.
+    #[serde(default)]
+    command: Option<ScenarioCommand>,
+    /// Decoded as one protocol batch. A step without observations or a
+    /// command only advances the clock, releasing due deadlines.
+    #[serde(default)]
+    observations: Vec<ProtocolObservation>,
+}
+
+/// The runtime commands a fixture can issue; the rest carry channels or
+/// whole configs.
+#[derive(Debug, Clone, Copy, Deserialize)]
+enum ScenarioCommand {
+    ManualReset,
+    TogglePause,
+    StartTraining,
+    StopTraining,
+}
+
+impl ScenarioCommand {
+    fn runtime_command(self) -> RuntimeCommand {
+        match self {
+            Self::ManualReset => RuntimeCommand::ManualReset,
+            Self::TogglePause => RuntimeCommand::TogglePause,
+            Self::StartTraining => RuntimeCommand::StartTraining,
+            Self::StopTraining => RuntimeCommand::StopTraining,
+        }
+    }
+}
+
+type DomainEventTap = Box<dyn FnMut(&DomainEnvelope)>;
+
+thread_local! {
+    static DOMAIN_EVENT_TAP: RefCell<Option<DomainEventTap>> = const { RefCell::new(None) };
+}
+
+/// Called by `LiveCore` for every domain event, after the segment controller
+/// has stamped it and before the projections apply it.
+pub(crate) fn observe_domain_event(envelope: &DomainEnvelope) {
+    DOMAIN_EVENT_TAP.with_borrow_mut(|tap| {
+        if let Some(tap) = tap {
+            tap(envelope);
+        }
+    });
+}
+
+/// Runs `run` with `tap` seeing the domain events of every core driven on
+/// this thread, in sequence order.
+pub(crate) fn with_domain_event_tap<T>(
+    tap: impl FnMut(&DomainEnvelope) + 'static,
+    run: impl FnOnce() -> T,
+) -> T {
+    DOMAIN_EVENT_TAP.set(Some(Box::new(tap)));
+    let result = run();
+    DOMAIN_EVENT_TAP.set(None);
+    result
+}
+
+/// Collects domain events as `LiveCore` applies them, interleaved with the
+/// publications and commands the driver records.
+#[derive(Default)]
+struct Transcript {
+    lines: Mutex<Vec<String>>,
+}
+
+impl Transcript {
+    /// Runs `run` with this transcript taking the domain events.
+    fn recording<T>(self: &Arc<Self>, run: impl FnOnce() -> T) -> T {
+        let transcript = Arc::clone(self);
+        with_domain_event_tap(move |envelope| transcript.domain_event(envelope), run)
+    }
+
+    fn push(&self, line: String) {
+        self.lines.lock().push(line);
+    }
+
+    fn command(&self, time: MonoTimeMs, command: ScenarioCommand) {
+        self.push(format!("command t={} {command:?}", offset(time)));
+    }
+
+    fn publications(&self, time: MonoTimeMs, publications: Publications) -> Result<(), String> {
+        self.push_topics(&format!(" t={}", offset(time)), publications)
+    }
+
+    /// `replay` hands publications over without the time they were due at.
+    fn replayed(&self, publications: Publications) -> Result<(), String> {
+        self.push_topics("", publications)
+    }
+
+    fn push_topics(&self, at: &str, publications: Publications) -> Result<(), String> {
+        for publication in publications.topics {
+            let topic = publication.topic();
+            let payload = payload_json(&publication)
+                .map_err(|error| format!("failed to serialize {topic:?} payload: {error}"))?;
+            self.push(format!("publish{at} {topic:?} {payload}"));
+        }
+        Ok(())
+    }
+
+    fn take_lines(&self) -> Vec<String> {
+        std::mem::take(&mut *self.lines.lock())
+    }
+
+    fn domain_event(&self, envelope: &DomainEnvelope) {
+        let segment = envelope
+            .segment_id
+            .map_or_else(|| "-".to_string(), |segment| segment.0.to_string());
+        let event = match &envelope.event {
+            // The buff checkpoint is a hash map whose Debug order varies
+            // between runs; the death itself is what the transcript needs.
+            DomainEvent::DeathOccurred {
+                victim,
+                killer,
+                skill_key,
+                ..
+            } => format!(
+                "DeathOccurred {{ victim: {victim:?}, killer: {killer:?}, skill_key: {skill_key:?} }}"
+            ),
+            event => format!("{event:?}"),
+        };
+        self.push(format!(
+            "event #{} t={} seg={segment} {event}",
+            envelope.sequence,
+            offset(envelope.meta.mono_ms())
+        ));
+    }
+}
+
+fn offset(time: MonoTimeMs) -> String {
+    format!("+{}ms", time.0.saturating_sub(START_MONO_MS))
+}
+
+/// Payloads keep hash maps; going through `Value` sorts their keys, so a
+/// transcript does not depend on hash order.
+fn sorted_json<T: Serialize>(value: &T) -> serde_json::Result<String> {
+    serde_json::to_string(&serde_json::to_value(value)?)
+}
+
+fn payload_json(publication: &TopicPublication) -> serde_json::Result<String> {
+    match publication {
+        TopicPublication::Combat(payload) => sorted_json(payload),
+        TopicPublication::Status(payload) => sorted_json(payload),
+        TopicPublication::Buffs(payload) => sorted_json(payload),
+        TopicPublication::Monster(payload) => sorted_json(payload),
+        TopicPublication::Fantasy(payload) => sorted_json(payload),
+        TopicPublication::Minimap(payload) => sorted_json(&serde_json::json!({
+            "snapshot": payload.snapshot,
+            "skillCasts": payload.skill_casts,
+        })),
+        TopicPublication::Deaths(payload) => sorted_json(payload),
+        TopicPublication::Scene(payload) => sorted_json(payload),
+        TopicPublication::CaptureHealth(payload) => sorted_json(payload),
+    }
+}
+
+fn render_history(history: RecordedHistory) -> Result<String, String> {
+    Ok(match history {
+        RecordedHistory::Begin {
+            lane,
+            segment_id,
+            recording,
+        } => format!("begin lane={lane} seg={} {recording:?}", segment_id.0),
+        RecordedHistory::Event {
+            lane,
+            segment_id,
+            envelope,
+        } => format!(
+            "record lane={lane} seg={} {}",
+            segment_id.0,
+            sorted_json(&envelope)
+                .map_err(|error| format!("failed to serialize history envelope: {error}"))?
+        ),
+        RecordedHistory::Finalize { lane, finalization } => format!(
+            "finalize lane={lane} seg={} {:?} totals={}",
+            finalization.segment_id.0,
+            finalization.metadata,
+            sorted_json(&finalization.projection.detail.totals)
+                .map_err(|error| format!("failed to serialize history totals: {error}"))?
+        ),
+        RecordedHistory::BeginRun { lane, run } => format!("begin_run lane={lane} {run:?}"),
+        RecordedHistory::FinalizeRun { lane, finalize } => {
+            format!("finalize_run lane={lane} {finalize:?}")
+        }
+    })
+}
+
+fn load_scenario(path: &Path) -> Result<Scenario, String> {
+    let file = std::fs::File::open(path)
+        .map_err(|error| format!("failed to open {}: {error}", path.display()))?;
+    serde_json::from_reader(std::io::BufReader::new(file))
+        .map_err(|error| format!("failed to parse {}: {error}", path.display()))
+}
+
+/// Runs the scenario from a fresh core and returns its transcript.
+fn run_scenario(scenario: &Scenario) -> Result<String, String> {
+    let transcript = Arc::new(Transcript::default());
+    let (history_writer, history_join, history) = HistoryWriterHandle::recording()?;
+    let result = transcript.recording(|| drive(scenario, &transcript, history_writer.clone()));
+    let shutdown = history_writer.shutdown();
+    if history_join.join().is_err() {
+        return Err("scenario history writer panicked".to_string());
+    }
+    result?;
+    shutdown?;
+    render_transcript(&scenario.description, &transcript, history.try_iter())
+}
+
+/// Replays recorded session files from a fresh set of sessions and returns
+/// their transcript, laid out like a scenario's.
+fn replay_transcript(paths: &[PathBuf]) -> Result<String, String> {
+    let transcript = Arc::new(Transcript::default());
+    let (history_writer, history_join, history) = HistoryWriterHandle::recording()?;
+    let mut publish_error = None;
+    let result = transcript.recording(|| {
+        replay(
+            SessionEnvelopes::new(paths.iter().cloned()),
+            ReplaySpeed::Unthrottled,
+            |clock| {
+                LiveSessions::with_clock(
+                    Arc::new(NullSink),
+                    history_writer.clone(),
+                    MonitorRuntimeSnapshot::default(),
+                    clock,
+                )
+            },
+            |publications| {
+                if let Err(error) = transcript.replayed(publications) {
+                    publish_error.get_or_insert(error);
+                }
+            },
+        )
+    });
+    let shutdown = history_writer.shutdown();
+    if history_join.join().is_err() {
+        return Err("replay history writer panicked".to_string());
+    }
+    result?;
+    shutdown?;
+    if let Some(error) = publish_error {
+        return Err(error);
+    }
+    render_transcript("", &transcript, history.try_iter())
+}
+
+fn render_transcript(
+    description: &str,
+    live: &Transcript,
+    history: impl IntoIterator<Item = RecordedHistory>,
+) -> Result<String, String> {
+    let mut transcript = String::new();
+    if !description.is_empty() {
+        let _ = writeln!(transcript, "# {description}");
+    }
+    let _ = writeln!(transcript, "## live");
+    for line in live.take_lines() {
+        let _ = writeln!(transcript, "{line}");
+    }
+    let _ = writeln!(transcript, "## history");
+    for recorded in history {
+        let _ = writeln!(transcript, "{}", render_history(recorded)?);
+    }
+    Ok(transcript)
+}
+
+/// Mirrors `replay`: cadence publications due before a step are released
+/// first, and the session is shut down after the last step.
+fn drive(
+    scenario: &Scenario,
+    transcript: &Transcript,
+    history_writer: HistoryWriterHandle,
+) -> Result<(), String> {
+    let start = MonoTimeMs(START_MONO_MS);
+    let mut core = LiveCore::with_clock(
+        Arc::new(NullSink),
+        history_writer,
+        scenario.config.clone(),
+        ClockMode::Recorded {
+            start_mono: start,
+            start_wall_ms: START_WALL_MS,
+        },
+    )?;
+    transcript.publications(start, core.publish_now()?)?;
+
+    let mut last_at_ms = 0;
+    for (index, step) in scenario.steps.iter().enumerate() {
+        if step.at_ms < last_at_ms {
+            return Err(format!(
+                "step {index} at_ms={} is earlier than the previous step at_ms={last_at_ms}",
+                step.at_ms
+            ));
+        }
+        last_at_ms = step.at_ms;
+        let time = start.saturating_add(step.at_ms);
+
+        let mut previous_wakeup = None;
+        while let Some(wakeup) = core.next_wakeup()
+            && wakeup < time
+            && previous_wakeup != Some(wakeup)
+        {
+            transcript.publications(wakeup, core.take_due_publications(wakeup)?)?;
+            previous_wakeup = Some(wakeup);
+        }
+
+        if let Some(command) = step.command {
+            // Commands read the recorded clock, so move it to the step first.
+            transcript.publications(time, core.take_due_publications(time)?)?;
+            transcript.command(time, command);
+            core.handle_command(command.runtime_command())?;
+            transcript.publications(time, core.take_due_publications(time)?)?;
+        }
+        if !step.observations.is_empty() {
+            core.process_batch(ProtocolBatch {
+                meta: step_meta(index, time),
+                observations: step.observations.clone(),
+            })?;
+            transcript.publications(time, core.take_due_publications(time)?)?;
+        }
+    }
+
+    core.shutdown()?;
+    transcript.publications(start.saturating_add(last_at_ms), core.publish_now()?)?;
+    Ok(())
+}
+
+fn step_meta(index: usize, time: MonoTimeMs) -> EventMeta {
+    let batch = index as u64 + 1;
+    let elapsed_ms = i64::try_from(time.0.saturating_sub(START_MONO_MS)).unwrap_or(i64::MAX);
+    EventMeta {
+        batch_id: BatchId(batch),
+        capture_sequence: batch,
+        stream_id: 1,
+        stream_epoch: 1,
+        captured_wall_ms: START_WALL_MS.saturating_add(elapsed_ms),
+        captured_mono_ns: time.0.saturating_mul(1_000_000),
+        source_time_ms: None,
+    }
+}
+
+fn scenarios_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("scenarios")
+}
+
+fn fixture_names() -> Vec<String> {
+    let entries = std::fs::read_dir(scenarios_dir()).expect("read scenario fixtures");
+    let mut names = entries
+        .map(|entry| entry.expect("scenario fixture entry").path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == FIXTURE_EXTENSION)
+        })
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// Runs a fixture twice; the transcript must not depend on anything but
+/// the fixture.
+fn transcript(name: &str) -> String {
+    let path = scenarios_dir().join(format!("{name}.{FIXTURE_EXTENSION}"));
+    let scenario = load_scenario(&path).unwrap_or_else(|error| panic!("{error}"));
+    let first =
+        run_scenario(&scenario).unwrap_or_else(|error| panic!("scenario {name} failed: {error}"));
+    let second =
+        run_scenario(&scenario).unwrap_or_else(|error| panic!("scenario {name} failed: {error}"));
+    assert_eq!(first, second, "scenario {name} is not deterministic");
+    first
+}
+
+/// `None` when the transcript matches or `UPDATE_GOLDEN=1` rewrote it.
+fn golden_mismatch(name: &str, actual: &str) -> Option<String> {
+    let path = scenarios_dir().join(format!("{name}.{GOLDEN_EXTENSION}"));
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_some_and(|value| value == "1") {
+        std::fs::write(&path, actual)
+            .unwrap_or_else(|error| panic!("failed to write {}: {error}", path.display()));
+        eprintln!("wrote {}", path.display());
+        return None;
+    }
+    if !path.exists() {
+        return Some(format!("{name}.{GOLDEN_EXTENSION} is missing"));
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|error| panic!("failed to read {}: {error}", path.display()))
+        .replace("\r\n", "\n");
+    if expected == actual {
+        return None;
+    }
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(left), Some(right)) if left == right => line += 1,
+            (left, right) => {
+                return Some(format!(
+                    "{name}.{GOLDEN_EXTENSION} differs at line {line}\n  golden: {}\n  actual: {}",
+                    left.unwrap_or("<end>"),
+                    right.unwrap_or("<end>")
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::entity_id::canonical_player_uuid;
+    use crate::live::protocol::decoder::StreamDecoders;
+    use crate::live::runtime::events::{
+        CaptureEnvelope, PacketDirection, PacketKey, monotonic_now_ms, wall_now_ms,
+    };
+    use crate::live::runtime::segment::DEFAULT_MAX_SEGMENT_DURATION_MS;
+    use crate::packets::opcodes::{Pkt, WORLD_NTF_SERVICE_ID};
+    use crate::packets::session_recorder::{
+        SessionRecorder, SessionRecorderConfig, list_session_files,
+    };
+    use blueprotobuf_lib::blueprotobuf;
+    use prost::Message;
+
+    const RECORDED_CHAR_ID: i64 = 42;
+    const RECORDED_MONSTER: i64 = 900;
+
+    /// Lines of one kind (`event`, `begin`, ...) mentioning `needle`.
+    fn lines_with<'a>(transcript: &'a str, kind: &str, needle: &str) -> Vec<&'a str> {
+        transcript
+            .lines()
+            .filter(|line| line.starts_with(kind) && line.contains(needle))
+            .collect()
+    }
+
+    fn recorded_notify<M: Message>(
+        sequence: u64,
+        at_ms: u64,
+        opcode: Pkt,
+        message: &M,
+    ) -> CaptureEnvelope {
+        CaptureEnvelope {
+            capture_sequence: sequence,
+            stream_id: 1,
+            stream_epoch: 1,
+            captured_wall_ms: START_WALL_MS + at_ms as i64,
+            captured_mono_ns: (START_MONO_MS + at_ms) * 1_000_000,
+            direction: PacketDirection::ServerToClient,
+            key: PacketKey {
+                opcode: opcode as u32,
+                service_id: Some(u32::try_from(WORLD_NTF_SERVICE_ID).unwrap()),
+                method_id: Some(opcode as u32),
+            },
+            payload: message.encode_to_vec().into(),
+        }
+    }
+
+    fn recorded_hit(sequence: u64, at_ms: u64, skill_id: i32, value: i64) -> CaptureEnvelope {
+        let damage = blueprotobuf::SyncDamageInfo {
+            value: Some(value),
+            attacker_uuid: Some(canonical_player_uuid(RECORDED_CHAR_ID)),
+            owner_id: Some(skill_id),
+            ..Default::default()
+        };
+        let message = blueprotobuf::SyncNearDeltaInfo {
+            delta_infos: vec![blueprotobuf::AoiSyncDelta {
+                uuid: Some(RECORDED_MONSTER),
+                skill_effects: Some(blueprotobuf::SkillEffect {
+                    damages: vec![damage],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+        };
+        recorded_notify(sequence, at_ms, Pkt::SyncNearDeltaInfo, &message)
+    }
+
+    /// A short fight against one monster on the scenario clock.
+    fn recorded_fight() -> Vec<CaptureEnvelope> {
+        let container = blueprotobuf::SyncContainerData {
+            v_data: Some(blueprotobuf::CharSerialize {
+                char_id: Some(RECORDED_CHAR_ID),
+                ..Default::default()
+            }),
+        };
+        let monster = blueprotobuf::SyncNearEntities {
+            appear: vec![blueprotobuf::Entity {
+                uuid: Some(RECORDED_MONSTER),
+                ent_type: Some(blueprotobuf::EEntityType::EntMonster as i32),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        vec![
+            recorded_notify(1, 0, Pkt::SyncContainerData, &container),
+            recorded_notify(2, 0, Pkt::SyncNearEntities, &monster),
+            recorded_hit(3, 1_000, 1_701, 1_200),
+            recorded_hit(4, 2_000, 1_701, 800),
+            recorded_hit(5, 4_000, 1_702, 700),
+        ]
+    }
+
+    fn open_recorder(directory: &Path) -> SessionRecorder {
+        SessionRecorder::open(SessionRecorderConfig::new(directory.to_path_buf()))
+            .expect("open session recorder")
+    }
+
+    /// Records [`recorded_fight`] into `directory` and returns the session
+    /// files, as the capture thread would leave them.
+    fn record_session(directory: &Path) -> Vec<PathBuf> {
+        let mut recorder = open_recorder(directory);
+        for envelope in &recorded_fight() {
+            recorder.record(envelope).expect("record envelope");
+        }
+        recorder.flush().expect("flush session recorder");
+        drop(recorder);
+        list_session_files(directory).expect("list session files")
+    }
+
+    /// Moves envelopes built on the scenario clock onto the host clock, where
+    /// the capture thread stamps them.
+    fn on_host_clock(mut envelopes: Vec<CaptureEnvelope>) -> Vec<CaptureEnvelope> {
+        let mono_shift_ns = monotonic_now_ms().0.saturating_sub(START_MONO_MS) * 1_000_000;
+        let wall_shift_ms = wall_now_ms() - START_WALL_MS;
+        for envelope in &mut envelopes {
+            envelope.captured_mono_ns += mono_shift_ns;
+            envelope.captured_wall_ms += wall_shift_ms;
+        }
+        envelopes
+    }
+
+    /// Runs `envelopes` through live-clock sessions the way the live loop
+    /// does, teeing each one to a session recorder in `directory` first as
+    /// the capture thread does. Returns the transcript and the session files.
+    fn live_transcript(
+        directory: &Path,
+        envelopes: Vec<CaptureEnvelope>,
+    ) -> (String, Vec<PathBuf>) {
+        let transcript = Arc::new(Transcript::default());
+        let (history_writer, history_join, history) =
+            HistoryWriterHandle::recording().expect("start history writer");
+        let mut recorder = open_recorder(directory);
+        transcript.recording(|| {
+            let mut sessions = LiveSessions::with_clock(
+                Arc::new(NullSink),
+                history_writer.clone(),
+                MonitorRuntimeSnapshot::default(),
+                ClockMode::Live,
+            )
+            .expect("start live sessions");
+            let mut decoders = StreamDecoders::new();
+            sessions.publish_snapshot().expect("initial snapshot");
+            for envelope in envelopes {
+                recorder.record(&envelope).expect("record envelope");
+                let batch_time = MonoTimeMs(envelope.captured_mono_ns / 1_000_000);
+                // The live loop's wakeup timer, which fires on time between
+                // packets instead of being slept through here.
+                while let Some(wakeup) = sessions.next_wakeup()
+                    && wakeup < batch_time
+                {
+                    sessions.publish_due(wakeup).expect("publish due topics");
+                }
+                sessions
+                    .process_batch(decoders.decode(envelope))
+                    .expect("process batch");
+                sessions
+                    .publish_due(batch_time)
+                    .expect("publish due topics");
+            }
+            sessions
+                .shutdown()
+                .and_then(|()| sessions.publish_snapshot())
+                .expect("shut down live sessions");
+        });
+        recorder.flush().expect("flush session recorder");
+        drop(recorder);
+        history_writer.shutdown().expect("shut down history writer");
+        history_join.join().expect("history writer panicked");
+        let transcript = render_transcript("", &transcript, history.try_iter()).expect("render");
+        let paths = list_session_files(directory).expect("list session files");
+        (transcript, paths)
+    }
+
+    /// Publications are left out: live, their cadence follows the host clock.
+    fn without_publications(transcript: &str) -> String {
+        transcript
+            .lines()
+            .filter(|line| !line.starts_with("publish"))
+            .map(|line| format!("{line}\n"))
+            .collect()
+    }
+
+    #[test]
+    fn scenarios_match_their_golden_transcripts() {
+        let names = fixture_names();
+        assert!(!names.is_empty(), "no scenario fixtures found");
+        let mismatches = names
+            .iter()
+            .filter_map(|name| golden_mismatch(name, &transcript(name)))
+            .collect::<Vec<_>>();
+        assert!(
+            mismatches.is_empty(),
+            "{}\n\nrerun with {UPDATE_GOLDEN_ENV}=1 if the change is intended",
+            mismatches.join("\n")
+        );
+    }
+
+    #[test]
+    fn manual_reset_splits_combat_into_two_recorded_segments() {
+        let transcript = transcript("manual_reset");
+        let started = lines_with(&transcript, "event ", "SegmentStarted");
+        assert_eq!(started.len(), 2);
+        assert!(started.iter().all(|line| line.contains("FirstCombat")));
+        assert_eq!(lines_with(&transcript, "event ", "reason: Manual").len(), 1);
+        assert_eq!(
+            lines_with(&transcript, "event ", "reason: Shutdown").len(),
+            1
+        );
+        assert_eq!(lines_with(&transcript, "begin ", "lane=0").len(), 2);
+        assert_eq!(lines_with(&transcript, "finalize ", "lane=0").len(), 2);
+    }
+
+    #[test]
+    fn wipe_buff_on_the_local_player_ends_the_segment_after_the_boundary_delay() {
+        let transcript = transcript("wipe");
+        assert_eq!(lines_with(&transcript, "event ", "WipeDetected").len(), 1);
+        let ended = lines_with(&transcript, "event ", "SegmentEnded");
+        assert_eq!(ended.len(), 2);
+        assert!(ended[0].contains("reason: Wipe"));
+        assert!(ended[0].contains("t=+8000ms"));
+        assert!(ended[1].contains("reason: Shutdown"));
+    }
+
+    #[test]
+    fn training_window_closes_the_dummy_segment() {
+        let transcript = transcript("training_dummy");
+        let started = lines_with(&transcript, "event ", "SegmentStarted");
+        assert_eq!(started.len(), 1);
+        assert!(started[0].contains("TrainingStarted"));
+        let ended = lines_with(&transcript, "event ", "SegmentEnded");
+        assert_eq!(ended.len(), 1);
+        assert!(ended[0].contains("reason: TrainingElapsed"));
+    }
+
+    #[test]
+    fn a_recorded_session_replays_to_the_same_transcript() {
+        let directory = tempfile::tempdir().expect("temp dir");
+        let paths = record_session(directory.path());
+        assert_eq!(paths.len(), 1);
+
+        let first = replay_transcript(&paths)
+            .unwrap_or_else(|error| panic!("recorded session replay failed: {error}"));
+        let second = replay_transcript(&paths)
+            .unwrap_or_else(|error| panic!("recorded session replay failed: {error}"));
+
+        assert_eq!(
+            first, second,
+            "recorded session replay is not deterministic"
+        );
+        assert_eq!(lines_with(&first, "event ", "SegmentStarted").len(), 1);
+        assert_eq!(lines_with(&first, "begin ", "lane=1").len(), 1);
+        assert_eq!(lines_with(&first, "finalize ", "lane=1").len(), 1);
+    }
+
+    #[test]
+    fn a_live_run_and_the_replay_of_its_recording_agree() {
+        // Past the default maximum segment duration, so the segment ends on a
+        // deadline rather than at shutdown, which the live clock stamps with
+        // the host's time.
+        let quiet = blueprotobuf::SyncNearEntities {
+            disappear: vec![blueprotobuf::DisappearEntity {
+                uuid: Some(RECORDED_MONSTER),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut envelopes = recorded_fight();
+        envelopes.push(recorded_notify(
+            6,
+            DEFAULT_MAX_SEGMENT_DURATION_MS + 5_000,
+            Pkt::SyncNearEntities,
+            &quiet,
+        ));
+        let directory = tempfile::tempdir().expect("temp dir");
+        let (live, paths) = live_transcript(directory.path(), on_host_clock(envelopes));
+
+        let replayed = replay_transcript(&paths)
+            .unwrap_or_else(|error| panic!("recorded session replay failed: {error}"));
+
+        assert_eq!(
+            without_publications(&replayed),
+            live,
+            "the replay of a live recording diverged from the live run"
+        );
+        let ended = lines_with(&live, "event ", "SegmentEnded");
+        assert_eq!(ended.len(), 1);
+        assert!(ended[0].contains("reason: MaxDurationElapsed"));
+        assert_eq!(lines_with(&live, "finalize ", "lane=1").len(), 1);
+    }
+
+    #[test]
+    fn steps_must_not_go_back_in_time() {
+        let scenario: Scenario = serde_json::from_str(
+            r#"{"steps": [{"at_ms": 10, "command": "TogglePause"}, {"at_ms": 5}]}"#,
+        )
+        .expect("valid fixture");
+        let error = run_scenario(&scenario).expect_err("time went backwards");
+        assert!(error.contains("step 1"));
+    }
+}
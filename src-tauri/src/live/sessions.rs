@@ -0,0 +1,862 @@
+//! Concurrently captured game clients, each with its own live pipeline.
+//!
+//! A capture stream is one TCP connection, both directions under the same
+//! stream id, and a client opens a new one on every line or scene server
+//! change. Streams are therefore grouped into
+//! sessions by the local player they announce. The first stream is bound
+//! straight away; later ones are held back until they name their player, or a
+//! short window passes, and then join the session that owns that player, take
+//! over a session whose connection went quiet, or start a new session.
+//!
+//! Every session owns a [`LiveCore`] (entities, segments, projections) and a
+//! history writer lane. Live windows follow the selected session only.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use log::{info, warn};
+
+use crate::live::bootstrap_snapshot::MonitorRuntimeSnapshot;
+use crate::live::history_writer::HistoryWriterHandle;
+use crate::live::ipc::models::{LiveSessionSummary, LiveSessionsPayload};
+use crate::live::live_core::{ClockMode, LiveCore, LiveCoreFlow, Publications};
+use crate::live::projections::script::UserScript;
+use crate::live::runtime::events::{EntityUuid, MonoTimeMs, ProtocolBatch, ProtocolObservation};
+use crate::live::runtime_handle::RuntimeCommand;
+use crate::live::sink::LiveSink;
+
+/// How long a new stream may stay anonymous before it is routed anyway.
+const PENDING_WINDOW_MS: i64 = 3_000;
+/// Bounds the batches held for an anonymous stream.
+const MAX_PENDING_BATCHES: usize = 512;
+/// A session counts as handed over when it was quiet from this long after
+/// the new stream started; a reconnecting client stops the old connection.
+const HANDOVER_OVERLAP_MS: i64 = 1_000;
+/// Streams silent this long are forgotten.
+const STREAM_FORGET_MS: i64 = 10 * 60 * 1_000;
+const MAX_SESSIONS: usize = 8;
+const FIRST_SESSION_ID: u32 = 1;
+
+#[derive(Debug)]
+enum StreamRoute {
+    Bound {
+        session_id: u32,
+        last_wall_ms: i64,
+    },
+    Pending {
+        batches: Vec<ProtocolBatch>,
+        first_wall_ms: i64,
+    },
+}
+
+#[derive(Debug, Default)]
+struct SessionIdentity {
+    local_player: Option<EntityUuid>,
+    streams: BTreeSet<u64>,
+    /// `None` until the first stream is bound to the session.
+    last_wall_ms: Option<i64>,
+}
+
+/// Assigns capture streams to sessions; pure bookkeeping over batches.
+#[derive(Debug)]
+pub struct SessionRouter {
+    streams: HashMap<u64, StreamRoute>,
+    sessions: BTreeMap<u32, SessionIdentity>,
+    next_session_id: u32,
+}
+
+impl Default for SessionRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionRouter {
+    /// Starts with one unclaimed session so a single client always ends up in
+    /// [`FIRST_SESSION_ID`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            streams: HashMap::new(),
+            sessions: BTreeMap::from([(FIRST_SESSION_ID, SessionIdentity::default())]),
+            next_session_id: FIRST_SESSION_ID + 1,
+        }
+    }
+
+    /// Returns the batches that are ready to apply, tagged with their
+    /// session, in capture order. A held-back stream returns nothing until it
+    /// is bound and then releases everything it buffered.
+    pub fn route(&mut self, batch: ProtocolBatch) -> Vec<(u32, ProtocolBatch)> {
+        let stream_id = batch.meta.stream_id;
+        let wall_ms = batch.meta.captured_wall_ms;
+        match self.streams.get_mut(&stream_id) {
+            Some(StreamRoute::Bound {
+                session_id,
+                last_wall_ms,
+            }) => {
+                let session_id = *session_id;
+                *last_wall_ms = wall_ms;
+                if let Some(session) = self.sessions.get_mut(&session_id) {
+                    session.last_wall_ms = Some(wall_ms);
+                    if let Some(player) = announced_local_player(&batch) {
+                        session.local_player = Some(player);
+                    }
+                }
+                vec![(session_id, batch)]
+            }
+            Some(StreamRoute::Pending {
+                batches,
+                first_wall_ms,
+            }) => {
+                let first_wall_ms = *first_wall_ms;
+                batches.push(batch);
+                let player = batches.iter().rev().find_map(announced_local_player);
+                let window_closed = batches.len() >= MAX_PENDING_BATCHES
+                    || wall_ms.saturating_sub(first_wall_ms) >= PENDING_WINDOW_MS;
+                if !window_closed && player.is_none_or(|player| self.owner_of(player).is_none()) {
+                    return Vec::new();
+                }
+                let Some(StreamRoute::Pending { batches, .. }) = self.streams.remove(&stream_id)
+                else {
+                    unreachable!("stream was pending above");
+                };
+                self.bind(stream_id, player, first_wall_ms, wall_ms, batches)
+            }
+            None => {
+                self.forget_silent_streams(wall_ms);
+                let player = announced_local_player(&batch);
+                let first_stream = self
+                    .sessions
+                    .values()
+                    .all(|session| session.last_wall_ms.is_none());
+                if first_stream || player.is_some_and(|player| self.owner_of(player).is_some()) {
+                    return self.bind(stream_id, player, wall_ms, wall_ms, vec![batch]);
+                }
+                self.streams.insert(
+                    stream_id,
+                    StreamRoute::Pending {
+                        batches: vec![batch],
+                        first_wall_ms: wall_ms,
+                    },
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    /// Routes every held-back stream as if its window had closed, so nothing
+    /// captured before shutdown is lost.
+    pub fn flush_pending(&mut self) -> Vec<(u32, ProtocolBatch)> {
+        let mut pending: Vec<(u64, u64)> = self
+            .streams
+            .iter()
+            .filter_map(|(stream_id, route)| match route {
+                StreamRoute::Pending { batches, .. } => batches
+                    .first()
+                    .map(|batch| (batch.meta.capture_sequence, *stream_id)),
+                StreamRoute::Bound { .. } => None,
+            })
+            .collect();
+        pending.sort_unstable();
+        let mut routed = Vec::new();
+        for (_, stream_id) in pending {
+            let Some(StreamRoute::Pending {
+                batches,
+                first_wall_ms,
+            }) = self.streams.remove(&stream_id)
+            else {
+                continue;
+            };
+            let player = batches.iter().rev().find_map(announced_local_player);
+            let wall_ms = batches
+                .last()
+                .map_or(first_wall_ms, |batch| batch.meta.captured_wall_ms);
+            routed.extend(self.bind(stream_id, player, first_wall_ms, wall_ms, batches));
+        }
+        routed
+    }
+
+    fn bind(
+        &mut self,
+        stream_id: u64,
+        player: Option<EntityUuid>,
+        first_wall_ms: i64,
+        wall_ms: i64,
+        batches: Vec<ProtocolBatch>,
+    ) -> Vec<(u32, ProtocolBatch)> {
+        let session_id = self.choose_session(player, first_wall_ms);
+        let session = self.sessions.entry(session_id).or_default();
+        session.streams.insert(stream_id);
+        session.last_wall_ms = Some(wall_ms);
+        if player.is_some() {
+            session.local_player = player;
+        }
+        self.streams.insert(
+            stream_id,
+            StreamRoute::Bound {
+                session_id,
+                last_wall_ms: wall_ms,
+            },
+        );
+        info!(
+            target: "app::live",
+            "capture_stream_bound stream_id={stream_id} session_id={session_id} announced_player={}",
+            player.is_some()
+        );
+        batches
+            .into_iter()
+            .map(|batch| (session_id, batch))
+            .collect()
+    }
+
+    fn choose_session(&mut self, player: Option<EntityUuid>, first_wall_ms: i64) -> u32 {
+        if let Some(session_id) = player.and_then(|player| self.owner_of(player)) {
+            return session_id;
+        }
+        if let Some((session_id, _)) = self
+            .sessions
+            .iter()
+            .find(|(_, session)| session.last_wall_ms.is_none())
+        {
+            return *session_id;
+        }
+        let handover = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| player.is_none() || session.local_player.is_none())
+            .filter_map(|(session_id, session)| Some((*session_id, session.last_wall_ms?)))
+            .filter(|(_, last_wall_ms)| {
+                *last_wall_ms <= first_wall_ms.saturating_add(HANDOVER_OVERLAP_MS)
+            })
+            .max_by_key(|(_, last_wall_ms)| *last_wall_ms);
+        if let Some((session_id, _)) = handover {
+            return session_id;
+        }
+        if self.sessions.len() >= MAX_SESSIONS {
+            return self
+                .sessions
+                .iter()
+                .min_by_key(|(_, session)| session.last_wall_ms)
+                .map_or(FIRST_SESSION_ID, |(session_id, _)| *session_id);
+        }
+        let session_id = self.next_session_id;
+        self.next_session_id = self.next_session_id.saturating_add(1);
+        self.sessions.insert(session_id, SessionIdentity::default());
+        session_id
+    }
+
+    fn owner_of(&self, player: EntityUuid) -> Option<u32> {
+        self.sessions
+            .iter()
+            .find(|(_, session)| session.local_player == Some(player))
+            .map(|(session_id, _)| *session_id)
+    }
+
+    fn forget_silent_streams(&mut self, wall_ms: i64) {
+        let cutoff = wall_ms.saturating_sub(STREAM_FORGET_MS);
+        let sessions = &mut self.sessions;
+        self.streams.retain(|stream_id, route| match route {
+            StreamRoute::Bound {
+                session_id,
+                last_wall_ms,
+            } => {
+                let keep = *last_wall_ms >= cutoff;
+                if !keep && let Some(session) = sessions.get_mut(session_id) {
+                    session.streams.remove(stream_id);
+                }
+                keep
+            }
+            StreamRoute::Pending { first_wall_ms, .. } => *first_wall_ms >= cutoff,
+        });
+    }
+
+    fn session_ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.sessions.keys().copied()
+    }
+}
+
+fn announced_local_player(batch: &ProtocolBatch) -> Option<EntityUuid> {
+    batch
+        .observations
+        .iter()
+        .rev()
+        .find_map(|observation| match observation {
+            ProtocolObservation::LocalPlayerChanged { uuid } => *uuid,
+            _ => None,
+        })
+}
+
+/// One [`LiveCore`] per session, driven like a single core by the live loop.
+pub struct LiveSessions {
+    sink: Arc<dyn LiveSink>,
+    history_writer: HistoryWriterHandle,
+    config: MonitorRuntimeSnapshot,
+    scripts: Vec<UserScript>,
+    clock_mode: ClockMode,
+    router: SessionRouter,
+    cores: BTreeMap<u32, LiveCore>,
+    selected: u32,
+    reset_pending: bool,
+    published_sessions: Option<(Option<u32>, Vec<LiveSessionSummary>)>,
+    sessions_revision: u64,
+}
+
+impl LiveSessions {
+    pub fn new(
+        sink: Arc<dyn LiveSink>,
+        history_writer: HistoryWriterHandle,
+        config: MonitorRuntimeSnapshot,
+    ) -> Result<Self, String> {
+        Self::with_clock(sink, history_writer, config, ClockMode::Live)
+    }
+
+    pub fn with_clock(
+        sink: Arc<dyn LiveSink>,
+        history_writer: HistoryWriterHandle,
+        config: MonitorRuntimeSnapshot,
+        clock_mode: ClockMode,
+    ) -> Result<Self, String> {
+        let first = LiveCore::with_clock(
+            Arc::clone(&sink),
+            history_writer.lane(FIRST_SESSION_ID),
+            config.clone(),
+            clock_mode,
+        )?;
+        Ok(Self {
+            sink,
+            history_writer,
+            config,
+            scripts: Vec::new(),
+            clock_mode,
+            router: SessionRouter::new(),
+            cores: BTreeMap::from([(FIRST_SESSION_ID, first)]),
+            selected: FIRST_SESSION_ID,
+            reset_pending: false,
+            published_sessions: None,
+            sessions_revision: 0,
+        })
+    }
+
+    pub fn process_batch(&mut self, batch: ProtocolBatch) -> Result<(), String> {
+        let routed = self.router.route(batch);
+        self.apply_routed(routed)
+    }
+
+    fn apply_routed(&mut self, routed: Vec<(u32, ProtocolBatch)>) -> Result<(), String> {
+        for (session_id, batch) in routed {
+            if !self.cores.contains_key(&session_id) {
+                let clock_mode = match self.clock_mode {
+                    ClockMode::Live => ClockMode::Live,
+                    ClockMode::Recorded { .. } => ClockMode::Recorded {
+                        start_mono: batch.meta.mono_ms(),
+                        start_wall_ms: batch.meta.captured_wall_ms,
+                    },
+                };
+                let mut core = LiveCore::with_clock(
+                    Arc::clone(&self.sink),
+                    self.history_writer.lane(session_id),
+                    self.config.clone(),
+                    clock_mode,
+                )?;
+                if !self.scripts.is_empty() {
+                    core.handle_command(RuntimeCommand::ApplyUserScripts(self.scripts.clone()))?;
+                }
+                info!(target: "app::live", "live_session_started session_id={session_id}");
+                self.cores.insert(session_id, core);
+            }
+            self.cores
+                .get_mut(&session_id)
+                .expect("core inserted above")
+                .process_batch(batch)?;
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn next_wakeup(&mut self) -> Option<MonoTimeMs> {
+        self.cores
+            .values_mut()
+            .filter_map(LiveCore::next_wakeup)
+            .min()
+    }
+
+    /// Advances every session; only the selected one's topics are returned.
+    pub fn take_due_publications(&mut self, now: MonoTimeMs) -> Result<Publications, String> {
+        let mut selected = Publications::default();
+        for (session_id, core) in &mut self.cores {
+            let publications = core.take_due_publications(now)?;
+            if *session_id == self.selected && !self.reset_pending {
+                selected = publications;
+            }
+        }
+        self.finish_publications(selected)
+    }
+
+    pub fn publish_now(&mut self) -> Result<Publications, String> {
+        let publications = self.selected_core()?.publish_now()?;
+        self.finish_publications(publications)
+    }
+
+    /// Hands the topics due at `now` to the sink.
+    pub fn publish_due(&mut self, now: MonoTimeMs) -> Result<(), String> {
+        let publications = self.take_due_publications(now)?;
+        self.sink.publish(publications);
+        Ok(())
+    }
+
+    /// Hands every topic of the selected session to the sink.
+    pub fn publish_snapshot(&mut self) -> Result<(), String> {
+        let publications = self.publish_now()?;
+        self.sink.publish(publications);
+        Ok(())
+    }
+
+    pub fn handle_command(&mut self, command: RuntimeCommand) -> Result<LiveCoreFlow, String> {
+        match command {
+            RuntimeCommand::SelectSession(session_id) => {
+                if !self.cores.contains_key(&session_id) {
+                    warn!(target: "app::live", "select_unknown_session session_id={session_id}");
+                } else if session_id != self.selected {
+                    self.selected = session_id;
+                    self.reset_pending = true;
+                }
+                Ok(LiveCoreFlow::Continue)
+            }
+            RuntimeCommand::ApplyMonitorConfig(config) => {
+                self.config = (*config).clone();
+                for core in self.cores.values_mut() {
+                    core.handle_command(RuntimeCommand::ApplyMonitorConfig(config.clone()))?;
+                }
+                Ok(LiveCoreFlow::Continue)
+            }
+            RuntimeCommand::ApplyUserScripts(scripts) => {
+                self.scripts = scripts.clone();
+                for core in self.cores.values_mut() {
+                    core.handle_command(RuntimeCommand::ApplyUserScripts(scripts.clone()))?;
+                }
+                Ok(LiveCoreFlow::Continue)
+            }
+            RuntimeCommand::Shutdown { reply } => Ok(LiveCoreFlow::ShutdownRequested { reply }),
+            command => self.selected_core()?.handle_command(command),
+        }
+    }
+
+    pub fn shutdown(&mut self) -> Result<(), String> {
+        let routed = self.router.flush_pending();
+        self.apply_routed(routed)?;
+        for core in self.cores.values_mut() {
+            core.shutdown()?;
+        }
+        Ok(())
+    }
+
+    fn selected_core(&mut self) -> Result<&mut LiveCore, String> {
+        self.cores
+            .get_mut(&self.selected)
+            .ok_or_else(|| format!("live session {} is missing", self.selected))
+    }
+
+    /// Republishes the selected session after a switch and attaches the
+    /// session list whenever it changed.
+    fn finish_publications(
+        &mut self,
+        mut publications: Publications,
+    ) -> Result<Publications, String> {
+        if self.reset_pending {
+            self.reset_pending = false;
+            publications = self.selected_core()?.republish_all()?;
+            publications.reset_cache = true;
+        }
+        let summaries = self.session_summaries();
+        let current = (Some(self.selected), summaries);
+        if self.published_sessions.as_ref() != Some(&current) {
+            self.sessions_revision = self.sessions_revision.saturating_add(1);
+            publications.sessions = Some(LiveSessionsPayload {
+                revision: self.sessions_revision,
+                selected_session_id: current.0,
+                sessions: current.1.clone(),
+            });
+            self.published_sessions = Some(current);
+        }
+        Ok(publications)
+    }
+
+    fn session_summaries(&self) -> Vec<LiveSessionSummary> {
+        self.router
+            .session_ids()
+            .map(|session_id| {
+                let identity = &self.router.sessions[&session_id];
+                let entities = self.cores.get(&session_id).map(LiveCore::entities);
+                let local_player = entities
+                    .and_then(|entities| entities.local_player())
+                    .map(|player| player.uuid)
+                    .or(identity.local_player);
+                let local_player_name = entities
+                    .zip(local_player)
+                    .and_then(|(entities, uuid)| entities.entity(uuid))
+                    .and_then(|entity| entity.identity.name.clone());
+                LiveSessionSummary {
+                    session_id,
+                    local_player_uuid: local_player.map(|uuid| uuid.0.to_string()),
+                    local_player_name,
+                    stream_ids: identity.streams.iter().map(u64::to_string).collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::entity_id::canonical_player_uuid;
+    use crate::live::projection_set::TopicPublication;
+    use crate::live::protocol::decoder::StreamDecoders;
+    use crate::live::runtime::events::{
+        BatchId, CaptureEnvelope, DomainEvent, EventMeta, PacketDirection, PacketKey,
+    };
+    use crate::live::scenario::with_domain_event_tap;
+    use crate::live::sink::NullSink;
+    use crate::packets::opcodes::{
+        Pkt, WORLD_CALL_SERVICE_ID, WORLD_NTF_SERVICE_ID, world_call_method,
+    };
+    use crate::packets::packet_capture::stable_stream_id;
+    use crate::packets::utils::Server;
+    use crate::voice::models::VoiceRuntimeSnapshot;
+    use blueprotobuf_lib::blueprotobuf;
+    use prost::Message;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn batch(stream_id: u64, wall_ms: i64, player: Option<i64>) -> ProtocolBatch {
+        ProtocolBatch {
+            meta: EventMeta {
+                batch_id: BatchId(0),
+                capture_sequence: 0,
+                stream_id,
+                stream_epoch: 1,
+                captured_wall_ms: wall_ms,
+                captured_mono_ns: 0,
+                source_time_ms: None,
+            },
+            observations: player
+                .map(|uuid| ProtocolObservation::LocalPlayerChanged {
+                    uuid: Some(EntityUuid(uuid)),
+                })
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    fn sessions_of(routed: &[(u32, ProtocolBatch)]) -> Vec<(u32, u64)> {
+        routed
+            .iter()
+            .map(|(session_id, batch)| (*session_id, batch.meta.stream_id))
+            .collect()
+    }
+
+    #[test]
+    fn first_stream_is_bound_immediately_to_the_first_session() {
+        let mut router = SessionRouter::new();
+        let routed = router.route(batch(10, 0, None));
+        assert_eq!(sessions_of(&routed), vec![(FIRST_SESSION_ID, 10)]);
+        let routed = router.route(batch(10, 5, Some(7)));
+        assert_eq!(sessions_of(&routed), vec![(FIRST_SESSION_ID, 10)]);
+    }
+
+    #[test]
+    fn concurrent_clients_get_separate_sessions() {
+        let mut router = SessionRouter::new();
+        router.route(batch(10, 0, Some(7)));
+
+        // The second client stays anonymous while the first keeps talking.
+        assert!(router.route(batch(20, 100, None)).is_empty());
+        router.route(batch(10, 2_000, None));
+        assert!(router.route(batch(20, 2_500, None)).is_empty());
+        let routed = router.route(batch(20, 100 + PENDING_WINDOW_MS, Some(9)));
+        assert_eq!(sessions_of(&routed), vec![(2, 20), (2, 20), (2, 20)]);
+
+        assert_eq!(
+            sessions_of(&router.route(batch(10, 4_000, None))),
+            [(1, 10)]
+        );
+        assert_eq!(
+            sessions_of(&router.route(batch(20, 4_000, None))),
+            [(2, 20)]
+        );
+    }
+
+    #[test]
+    fn reconnect_announcing_a_known_player_rejoins_its_session() {
+        let mut router = SessionRouter::new();
+        router.route(batch(10, 0, Some(7)));
+        router.route(batch(20, 10, None));
+        router.route(batch(20, 10 + PENDING_WINDOW_MS, Some(9)));
+
+        let routed = router.route(batch(30, 60_000, Some(9)));
+        assert_eq!(sessions_of(&routed), vec![(2, 30)]);
+    }
+
+    #[test]
+    fn anonymous_reconnect_takes_over_the_quiet_session() {
+        let mut router = SessionRouter::new();
+        router.route(batch(10, 0, None));
+        router.route(batch(10, 1_000, None));
+
+        assert!(router.route(batch(20, 1_500, None)).is_empty());
+        let routed = router.route(batch(20, 1_500 + PENDING_WINDOW_MS, None));
+        assert_eq!(sessions_of(&routed), vec![(1, 20), (1, 20)]);
+    }
+
+    #[test]
+    fn shutdown_flush_routes_held_back_streams() {
+        let mut router = SessionRouter::new();
+        router.route(batch(10, 0, Some(7)));
+        assert!(router.route(batch(20, 100, None)).is_empty());
+        assert!(router.route(batch(20, 200, None)).is_empty());
+        router.route(batch(10, 1_500, None));
+
+        let routed = router.flush_pending();
+        assert_eq!(sessions_of(&routed), vec![(2, 20), (2, 20)]);
+        assert!(router.flush_pending().is_empty());
+    }
+
+    fn envelope<M: Message>(
+        sequence: u64,
+        server: Server,
+        direction: PacketDirection,
+        wall_ms: i64,
+        (service_id, method_id): (u64, u32),
+        message: &M,
+    ) -> CaptureEnvelope {
+        CaptureEnvelope {
+            capture_sequence: sequence,
+            stream_id: stable_stream_id(server),
+            stream_epoch: 1,
+            captured_wall_ms: wall_ms,
+            captured_mono_ns: u64::try_from(wall_ms).unwrap() * 1_000_000,
+            direction,
+            key: PacketKey {
+                opcode: method_id,
+                service_id: Some(u32::try_from(service_id).unwrap()),
+                method_id: Some(method_id),
+            },
+            payload: message.encode_to_vec().into(),
+        }
+    }
+
+    fn container(char_id: i64) -> blueprotobuf::SyncContainerData {
+        blueprotobuf::SyncContainerData {
+            v_data: Some(blueprotobuf::CharSerialize {
+                char_id: Some(char_id),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn both_directions_of_a_connection_share_one_session() {
+        const CONTAINER: (u64, u32) = (WORLD_NTF_SERVICE_ID, Pkt::SyncContainerData as u32);
+        const USE_SLOT: (u64, u32) = (WORLD_CALL_SERVICE_ID, world_call_method::USE_SLOT);
+        let first_client = Server::new([10, 0, 0, 1], 5003, [192, 168, 1, 9], 40_000);
+        let downlink = Server::new([10, 0, 0, 1], 5003, [192, 168, 1, 2], 50_123);
+        let uplink = Server::new([192, 168, 1, 2], 50_123, [10, 0, 0, 1], 5003);
+        let use_slot = blueprotobuf::UseSlot {
+            v_request: Some(blueprotobuf::UseSlotRequest {
+                slot_id: Some(11),
+                use_type: Some(blueprotobuf::EUseSlotType::UseSlotTypeOther as i32),
+                extra_data: None,
+            }),
+        };
+        // A second client behind the first: only its downlink announces the
+        // player, and the uplink must land in the same session.
+        let down = PacketDirection::ServerToClient;
+        let envelopes = [
+            envelope(1, first_client, down, 0, CONTAINER, &container(3)),
+            envelope(2, downlink, down, 1_000, CONTAINER, &container(42)),
+            envelope(3, first_client, down, 3_500, CONTAINER, &container(3)),
+            envelope(
+                4,
+                uplink,
+                PacketDirection::ClientToServer,
+                1_000 + PENDING_WINDOW_MS,
+                USE_SLOT,
+                &use_slot,
+            ),
+        ];
+
+        let (writer, writer_join) = HistoryWriterHandle::discarding().expect("start writer");
+        let mut sessions = LiveSessions::with_clock(
+            Arc::new(NullSink),
+            writer.clone(),
+            MonitorRuntimeSnapshot::default(),
+            ClockMode::Recorded {
+                start_mono: MonoTimeMs(0),
+                start_wall_ms: 0,
+            },
+        )
+        .expect("start headless sessions");
+        let slot_uses = Rc::new(RefCell::new(Vec::new()));
+        let seen = Rc::clone(&slot_uses);
+        let mut decoders = StreamDecoders::new();
+        with_domain_event_tap(
+            move |envelope| {
+                if let DomainEvent::LocalSlotUsed { user, slot_id } = envelope.event {
+                    seen.borrow_mut()
+                        .push((envelope.meta.stream_id, user.uuid, slot_id));
+                }
+            },
+            || {
+                for envelope in envelopes {
+                    sessions
+                        .process_batch(decoders.decode(envelope))
+                        .expect("process batch");
+                }
+            },
+        );
+
+        let connection = stable_stream_id(downlink);
+        assert_eq!(sessions.cores.len(), 2);
+        assert_eq!(
+            sessions.router.sessions[&2]
+                .streams
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![connection]
+        );
+        assert_eq!(
+            sessions.router.sessions[&2].local_player,
+            Some(EntityUuid(canonical_player_uuid(42)))
+        );
+        // The slot use resolved against the downlink's player, so the core
+        // that saw it also saw the container.
+        assert_eq!(
+            *slot_uses.borrow(),
+            vec![(connection, EntityUuid(canonical_player_uuid(42)), 11)]
+        );
+
+        sessions.shutdown().expect("shutdown sessions");
+        writer.shutdown().expect("shutdown writer");
+        writer_join.join().expect("writer thread");
+    }
+
+    #[test]
+    fn silent_streams_are_forgotten() {
+        let mut router = SessionRouter::new();
+        router.route(batch(10, 0, Some(7)));
+        router.route(batch(20, STREAM_FORGET_MS + 1, Some(7)));
+        assert!(!router.streams.contains_key(&10));
+        assert_eq!(
+            router.sessions[&FIRST_SESSION_ID]
+                .streams
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![20]
+        );
+    }
+
+    /// Stands in for the windows and the voice service.
+    #[derive(Default)]
+    struct CountingSink {
+        publications: Mutex<Vec<Publications>>,
+        voice_settings: AtomicUsize,
+    }
+
+    impl LiveSink for CountingSink {
+        fn publish(&self, publications: Publications) {
+            self.publications
+                .lock()
+                .expect("sink publications")
+                .push(publications);
+        }
+
+        fn apply_voice_settings(&self, _settings: &VoiceRuntimeSnapshot) {
+            self.voice_settings.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn sessions_run_headless_behind_a_sink() {
+        let (writer, writer_join) = HistoryWriterHandle::discarding().expect("start writer");
+        let sink = Arc::new(CountingSink::default());
+        let mut sessions = LiveSessions::with_clock(
+            Arc::clone(&sink) as Arc<dyn LiveSink>,
+            writer.clone(),
+            MonitorRuntimeSnapshot::default(),
+            ClockMode::Recorded {
+                start_mono: MonoTimeMs(0),
+                start_wall_ms: 0,
+            },
+        )
+        .expect("start headless sessions");
+
+        sessions
+            .handle_command(RuntimeCommand::ApplyMonitorConfig(Box::default()))
+            .expect("apply config");
+        assert_eq!(sink.voice_settings.load(Ordering::Relaxed), 1);
+
+        sessions
+            .process_batch(batch(10, 0, Some(7)))
+            .expect("process batch");
+        sessions.publish_snapshot().expect("publish snapshot");
+        sessions
+            .publish_due(MonoTimeMs(60_000))
+            .expect("publish due topics");
+
+        let received = sink.publications.lock().expect("sink publications");
+        assert_eq!(received.len(), 2);
+        assert!(!received[0].topics.is_empty());
+        let payload = received[0].sessions.as_ref().expect("session list");
+        assert_eq!(payload.sessions[0].local_player_uuid.as_deref(), Some("7"));
+        // The session list rides along only when it changed.
+        assert!(received[1].sessions.is_none());
+        drop(received);
+
+        sessions.shutdown().expect("shutdown sessions");
+        writer.shutdown().expect("shutdown writer");
+        writer_join.join().expect("writer thread");
+    }
+
+    #[test]
+    fn user_scripts_publish_through_the_status_topic() {
+        let (writer, writer_join) = HistoryWriterHandle::discarding().expect("start writer");
+        let mut sessions = LiveSessions::with_clock(
+            Arc::new(CountingSink::default()),
+            writer.clone(),
+            MonitorRuntimeSnapshot::default(),
+            ClockMode::Recorded {
+                start_mono: MonoTimeMs(0),
+                start_wall_ms: 0,
+            },
+        )
+        .expect("start headless sessions");
+        sessions.publish_now().expect("initial publish");
+
+        sessions
+            .handle_command(RuntimeCommand::ApplyUserScripts(vec![UserScript {
+                id: "stacks".to_string(),
+                source: r#"fn init() { counter("stacks", 3); }"#.to_string(),
+            }]))
+            .expect("apply scripts");
+        let publications = sessions.publish_now().expect("publish");
+        let status = publications
+            .topics
+            .iter()
+            .find_map(|topic| match topic {
+                TopicPublication::Status(status) => Some(status),
+                _ => None,
+            })
+            .expect("status republished");
+        assert_eq!(status.script_counters.len(), 1);
+        assert_eq!(status.script_counters[0].script_id, "stacks");
+        assert_eq!(status.script_counters[0].value, 3.0);
+
+        sessions.shutdown().expect("shutdown sessions");
+        writer.shutdown().expect("shutdown writer");
+        writer_join.join().expect("writer thread");
+    }
+}
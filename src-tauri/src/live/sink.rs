@@ -0,0 +1,109 @@
+//! Where the live domain's output leaves it.
+//!
+//! `LiveCore` and `LiveSessions` only produce values: publications for the
+//! windows and voice cues for playback. A [`LiveSink`] decides what happens
+//! to them, so the domain runs the same under the desktop app
+//! ([`TauriSink`]) and headless in tools and tests ([`NullSink`] or a custom
+//! sink).
+
+use log::{debug, warn};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::live::ipc::models::LiveScenePayload;
+use crate::live::ipc::publisher::LivePublicationCache;
+use crate::live::ipc::topic::Topic;
+use crate::live::live_core::Publications;
+use crate::voice::models::{VoiceCueIntent, VoiceRuntimeSnapshot};
+
+pub trait LiveSink: Send + Sync {
+    /// Delivers the publications the sessions hand over on the live loop.
+    fn publish(&self, publications: Publications);
+
+    /// Applies the playback half of a runtime config change.
+    fn apply_voice_settings(&self, _settings: &VoiceRuntimeSnapshot) {}
+
+    /// Plays a cue matched by the voice projection. Cues arrive in the order
+    /// the domain events produced them; recorded sessions never send any.
+    fn enqueue_voice_cue(&self, _cue: VoiceCueIntent) {}
+}
+
+/// Drops everything: for runs that only care about history or inspect
+/// publications as return values.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullSink;
+
+impl LiveSink for NullSink {
+    fn publish(&self, _publications: Publications) {}
+}
+
+/// The desktop app: publications land in the pull cache, scene changes are
+/// emitted to the windows and cues go to the `VoiceService`.
+pub struct TauriSink {
+    app: AppHandle,
+    publication_cache: LivePublicationCache,
+}
+
+impl TauriSink {
+    pub fn new(app: AppHandle, publication_cache: LivePublicationCache) -> Self {
+        Self {
+            app,
+            publication_cache,
+        }
+    }
+
+    /// Scene is the only live topic that remains event-driven. It is tiny and
+    /// must continue reaching `main` while that window is hidden so the
+    /// toolbox can control the overlay auto-hide policy.
+    fn emit_scene(&self, payload: &LiveScenePayload) {
+        let topic = Topic::Scene;
+        let event = topic
+            .event_name()
+            .expect("scene is the only event-backed live topic");
+        for label in topic.window_labels() {
+            let Some(window) = self.app.get_webview_window(label) else {
+                continue;
+            };
+            if let Err(error) = window.emit(event, payload) {
+                let message = error.to_string();
+                // 0x8007139F: webview minimized / hidden / mid-transition.
+                if message.contains("0x8007139F") || message.contains("not in the correct state") {
+                    debug!(target: "app::live", "emit_skipped_webview_busy event={event} label={label}");
+                } else {
+                    warn!(target: "app::live", "emit_failed event={event} label={label} error={message}");
+                }
+            }
+        }
+    }
+}
+
+impl LiveSink for TauriSink {
+    fn publish(&self, publications: Publications) {
+        if publications.reset_cache {
+            self.publication_cache.reset();
+        }
+        if let Some(sessions) = publications.sessions {
+            self.publication_cache.publish_sessions(sessions);
+        }
+        if let Some(scene) = self.publication_cache.publish(publications.topics) {
+            self.emit_scene(&scene);
+        }
+    }
+
+    fn apply_voice_settings(&self, settings: &VoiceRuntimeSnapshot) {
+        // Playback settings are a service concern, not projection state: sync
+        // them here so `VoiceProjection` stays free of the Tauri handle.
+        if let Some(service) = self.app.try_state::<crate::voice::VoiceService>() {
+            service.apply_runtime_settings(
+                settings.enabled,
+                settings.volume,
+                settings.queue_policy,
+            );
+        }
+    }
+
+    fn enqueue_voice_cue(&self, cue: VoiceCueIntent) {
+        if let Some(service) = self.app.try_state::<crate::voice::VoiceService>() {
+            service.enqueue_cue(cue);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Discovery of the `.rhai` files users drop into the app data `scripts` folder.
+
+use std::path::{Path, PathBuf};
+
+use log::warn;
+use tauri::{AppHandle, Manager};
+
+use crate::live::ipc::models::UserScriptStatus;
+use crate::live::projections::script::{MAX_SCRIPTS, UserScript, check_script};
+
+const SCRIPTS_DIR_NAME: &str = "scripts";
+const SCRIPT_EXTENSION: &str = "rhai";
+
+/// The scripts folder, created on first use so users can find it.
+pub(crate) fn user_scripts_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|error| error.to_string())?
+        .join(SCRIPTS_DIR_NAME);
+    std::fs::create_dir_all(&dir)
+        .map_err(|error| format!("create_dir_all {}: {error}", dir.display()))?;
+    Ok(dir)
+}
+
+/// Reads and compiles every script in `dir`, in file name order. Only scripts
+/// that compile are returned for the live runtime; every file gets a status.
+pub(crate) fn read_user_scripts(dir: &Path) -> (Vec<UserScript>, Vec<UserScriptStatus>) {
+    let mut paths = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .is_some_and(|extension| extension == SCRIPT_EXTENSION)
+            })
+            .collect::<Vec<_>>(),
+        Err(error) => {
+            warn!(
+                target: "app::live",
+                "user_scripts_dir_unreadable dir={} error={error}",
+                dir.display()
+            );
+            Vec::new()
+        }
+    };
+    paths.sort();
+
+    let mut scripts = Vec::new();
+    let mut statuses = Vec::new();
+    for path in paths {
+        let script_id = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let loaded = if scripts.len() >= MAX_SCRIPTS {
+            Err(format!("最多加载{MAX_SCRIPTS}个脚本"))
+        } else {
+            std::fs::read_to_string(&path)
+                .map_err(|error| error.to_string())
+                .and_then(|source| check_script(&source).map(|()| source))
+        };
+        match loaded {
+            Ok(source) => {
+                scripts.push(UserScript {
+                    id: script_id.clone(),
+                    source,
+                });
+                statuses.push(UserScriptStatus {
+                    script_id,
+                    error: None,
+                });
+            }
+            Err(error) => {
+                warn!(
+                    target: "app::live",
+                    "user_script_rejected path={} error={error}",
+                    path.display()
+                );
+                statuses.push(UserScriptStatus {
+                    script_id,
+                    error: Some(error),
+                });
+            }
+        }
+    }
+    (scripts, statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_compiling_rhai_files_in_name_order() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("b_stacks.rhai"),
+            "fn init() { counter(\"b\", 1); }",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("a_gauge.rhai"),
+            "fn init() { bar(\"a\", 1, 2); }",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("broken.rhai"), "fn init( {").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not a script").unwrap();
+
+        let (scripts, statuses) = read_user_scripts(dir.path());
+        assert_eq!(
+            scripts
+                .iter()
+                .map(|script| script.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a_gauge", "b_stacks"]
+        );
+        assert_eq!(statuses.len(), 3);
+        assert_eq!(statuses[2].script_id, "broken");
+        assert!(statuses[2].error.is_some());
+    }
+}
@@ -1,3 +1,5 @@
+// The C++ strategies take their options as plain arguments.
+#[allow(clippy::too_many_arguments)]
 #[cxx::bridge(namespace = "module_optimizer_ffi")]
 pub mod ffi {
     #[derive(Clone, Debug)]
@@ -347,7 +347,7 @@ fn prefilter_modules_by_total_scores(
         })
         .collect();
 
-    module_scores.sort_by(|a, b| b.1.cmp(&a.1)); // 降序排序
+    module_scores.sort_by_key(|(_, score)| std::cmp::Reverse(*score)); // 降序排序
     module_scores
         .into_iter()
         .take(max_count)
@@ -599,10 +599,7 @@ pub fn strategy_five_module_gpu_hybrid(
     let mut seen = HashSet::new();
     let mut merged = Vec::with_capacity(enumeration_results.len() + beam_results.len());
 
-    for solution in enumeration_results
-        .into_iter()
-        .chain(beam_results.into_iter())
-    {
+    for solution in enumeration_results.into_iter().chain(beam_results) {
         let mut key: Vec<i32> = solution.modules.iter().map(|module| module.uuid).collect();
         key.sort_unstable();
         if seen.insert(key) {
@@ -610,7 +607,7 @@ pub fn strategy_five_module_gpu_hybrid(
         }
     }
 
-    merged.sort_by(|left, right| right.score.cmp(&left.score));
+    merged.sort_by_key(|solution| std::cmp::Reverse(solution.score));
     merged.truncate(options.max_solutions.max(1) as usize);
     Ok(merged)
 }
@@ -1,11 +1,17 @@
 // https://doc.rust-lang.org/reference/items/modules.html#module-source-filenames
 // Preferred way is to name modules with their subfolder name now (no longer mod.rs)
+#[cfg(target_os = "linux")]
+pub mod af_packet;
 pub mod decode_worker;
+pub mod flow_signature;
 pub mod game_connections;
 pub mod npcap;
 pub mod opcodes;
 pub mod packet_capture;
 pub(crate) mod packet_process;
 pub mod parser;
+pub mod pcap_file;
 pub mod reassembler;
+pub mod remote_ingest;
+pub mod session_recorder;
 pub mod utils;
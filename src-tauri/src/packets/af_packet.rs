@@ -0,0 +1,172 @@
+//! Native Linux capture through an `AF_PACKET` socket.
+//!
+//! The socket is opened in cooked (`SOCK_DGRAM`) mode, so the kernel strips
+//! the link-layer header and every delivered frame starts at the IP header
+//! regardless of the interface type (Ethernet, Wi-Fi, tun, PPP).
+
+use log::info;
+use std::ffi::CString;
+use std::io;
+use std::mem::{size_of, zeroed};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+/// Bounds how long a dispatch blocks so cancellation is noticed promptly.
+const RECEIVE_TIMEOUT_SECS: libc::time_t = 1;
+const SOCKET_BUFFER_BYTES: libc::c_int = 64 * 1024 * 1024;
+const MAX_FRAME_BYTES: usize = 65_536;
+/// Frames drained per dispatch once the first one arrives.
+const DISPATCH_BATCH: usize = 256;
+
+pub struct AfPacketCapture {
+    fd: OwnedFd,
+    buffer: Vec<u8>,
+    /// Loopback frames are reported once outgoing and once incoming; only the
+    /// incoming copy is kept.
+    loopback_ifindex: Option<i32>,
+}
+
+impl AfPacketCapture {
+    /// Opens a capture on `device`, or on every interface when `device` is
+    /// empty or `any`. Requires `CAP_NET_RAW`.
+    pub fn new(device: &str) -> Result<Self, String> {
+        let protocol = (libc::ETH_P_ALL as u16).to_be();
+        let raw = unsafe {
+            libc::socket(
+                libc::AF_PACKET,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::c_int::from(protocol),
+            )
+        };
+        if raw < 0 {
+            return Err(format!(
+                "AF_PACKET socket failed: {}",
+                io::Error::last_os_error()
+            ));
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        set_socket_option(&fd, libc::SO_RCVBUF, &SOCKET_BUFFER_BYTES)?;
+        let timeout = libc::timeval {
+            tv_sec: RECEIVE_TIMEOUT_SECS,
+            tv_usec: 0,
+        };
+        set_socket_option(&fd, libc::SO_RCVTIMEO, &timeout)?;
+
+        let device = device.trim();
+        if !device.is_empty() && device != "any" {
+            let ifindex = interface_index(device)?;
+            let mut address: libc::sockaddr_ll = unsafe { zeroed() };
+            address.sll_family = libc::AF_PACKET as libc::c_ushort;
+            address.sll_protocol = protocol;
+            address.sll_ifindex = ifindex;
+            let result = unsafe {
+                libc::bind(
+                    fd.as_raw_fd(),
+                    (&address as *const libc::sockaddr_ll).cast(),
+                    size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+                )
+            };
+            if result != 0 {
+                return Err(format!(
+                    "AF_PACKET bind to {device} failed: {}",
+                    io::Error::last_os_error()
+                ));
+            }
+        }
+
+        info!(
+            target: "app::capture",
+            "AF_PACKET socket opened device={}",
+            if device.is_empty() { "any" } else { device }
+        );
+        Ok(Self {
+            fd,
+            buffer: vec![0u8; MAX_FRAME_BYTES],
+            loopback_ifindex: interface_index("lo").ok(),
+        })
+    }
+
+    /// Waits up to the receive timeout for a frame, then drains whatever else
+    /// is already queued. Returns the number of IP frames delivered.
+    pub fn dispatch_batch<F: FnMut(&[u8])>(&mut self, on_packet: &mut F) -> Result<i32, String> {
+        let mut delivered = 0;
+        for index in 0..DISPATCH_BATCH {
+            let flags = if index == 0 { 0 } else { libc::MSG_DONTWAIT };
+            let Some((address, len)) = self.receive(flags)? else {
+                break;
+            };
+            if !matches!(
+                u16::from_be(address.sll_protocol),
+                ETHERTYPE_IPV4 | ETHERTYPE_IPV6
+            ) {
+                continue;
+            }
+            if address.sll_pkttype == libc::PACKET_OUTGOING
+                && Some(address.sll_ifindex) == self.loopback_ifindex
+            {
+                continue;
+            }
+            on_packet(&self.buffer[..len]);
+            delivered += 1;
+        }
+        Ok(delivered)
+    }
+
+    fn receive(
+        &mut self,
+        flags: libc::c_int,
+    ) -> Result<Option<(libc::sockaddr_ll, usize)>, String> {
+        let mut address: libc::sockaddr_ll = unsafe { zeroed() };
+        let mut address_len = size_of::<libc::sockaddr_ll>() as libc::socklen_t;
+        let received = unsafe {
+            libc::recvfrom(
+                self.fd.as_raw_fd(),
+                self.buffer.as_mut_ptr().cast(),
+                self.buffer.len(),
+                flags,
+                (&mut address as *mut libc::sockaddr_ll).cast(),
+                &mut address_len,
+            )
+        };
+        if received < 0 {
+            let error = io::Error::last_os_error();
+            return match error.kind() {
+                io::ErrorKind::WouldBlock
+                | io::ErrorKind::TimedOut
+                | io::ErrorKind::Interrupted => Ok(None),
+                _ => Err(format!("AF_PACKET receive failed: {error}")),
+            };
+        }
+        Ok(Some((address, received as usize)))
+    }
+}
+
+fn set_socket_option<T>(fd: &OwnedFd, option: libc::c_int, value: &T) -> Result<(), String> {
+    let result = unsafe {
+        libc::setsockopt(
+            fd.as_raw_fd(),
+            libc::SOL_SOCKET,
+            option,
+            (value as *const T).cast(),
+            size_of::<T>() as libc::socklen_t,
+        )
+    };
+    if result != 0 {
+        return Err(format!(
+            "AF_PACKET setsockopt({option}) failed: {}",
+            io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+fn interface_index(name: &str) -> Result<i32, String> {
+    let name_c = CString::new(name).map_err(|e| e.to_string())?;
+    let index = unsafe { libc::if_nametoindex(name_c.as_ptr()) };
+    if index == 0 {
+        return Err(format!("unknown network interface {name}"));
+    }
+    i32::try_from(index).map_err(|e| e.to_string())
+}
@@ -1,6 +1,8 @@
 //! Lifecycle-managed capture-envelope decoder.
 
-use crate::live::protocol::decoder::ProtocolDecoder;
+use crate::live::protocol::decoder::StreamDecoders;
+use crate::live::protocol::discovery::ProtocolDiscovery;
+use crate::live::protocol::schema_drift::SchemaDriftLog;
 use crate::live::runtime::events::{CaptureEnvelope, ProtocolBatch};
 use log::info;
 use std::thread::JoinHandle;
@@ -32,25 +34,31 @@ impl DecodeWorkerHandle {
     }
 }
 
-/// Spawns the only protobuf decoder. Every accepted capture envelope produces
-/// exactly one protocol batch, including unsupported and malformed packets.
+/// Spawns the only protobuf decode thread, with one decoder per capture
+/// stream. Every accepted capture envelope produces exactly one protocol
+/// batch, including unsupported and malformed packets. Envelopes the decoder
+/// discards are shown to `discovery` first; each stream's schema drift goes
+/// to `schema_drift`.
 pub fn spawn_decode_worker(
     mut input: mpsc::Receiver<CaptureEnvelope>,
     output: mpsc::Sender<ProtocolBatch>,
+    discovery: ProtocolDiscovery,
+    schema_drift: SchemaDriftLog,
 ) -> DecodeWorkerHandle {
     let cancellation = CancellationToken::new();
     let worker_cancellation = cancellation.clone();
     let join = std::thread::Builder::new()
         .name("protocol-decoder".to_string())
         .spawn(move || {
-            let mut decoder = ProtocolDecoder::new();
+            let mut decoders = StreamDecoders::with_schema_drift_log(schema_drift);
             loop {
                 if worker_cancellation.is_cancelled() {
                     break;
                 }
                 match input.try_recv() {
                     Ok(envelope) => {
-                        let batch = decoder.decode(envelope);
+                        discovery.observe(&envelope);
+                        let batch = decoders.decode(envelope);
                         if !send_with_backpressure(&output, &worker_cancellation, batch) {
                             break;
                         }
@@ -123,7 +131,12 @@ mod tests {
         input_tx.blocking_send(envelope(2)).expect("second input");
         drop(input_tx);
 
-        let worker = spawn_decode_worker(input_rx, output_tx);
+        let worker = spawn_decode_worker(
+            input_rx,
+            output_tx,
+            ProtocolDiscovery::new(),
+            SchemaDriftLog::new(),
+        );
         worker.join().expect("worker joins");
 
         assert_eq!(output_rx.blocking_recv().unwrap().meta.capture_sequence, 1);
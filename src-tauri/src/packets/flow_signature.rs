@@ -0,0 +1,367 @@
+//! Payload-based recognition of game connections.
+//!
+//! The OS connection table can only name the owning process when the game
+//! runs on this host. Replayed captures, mirror ports and routers capturing on
+//! behalf of another machine have no such process, so a direction is instead
+//! recognised once its first bytes parse as a chain of length-prefixed frames
+//! (the layout `Reassembler` and `process_packet` consume) and one of them
+//! addresses a known game service.
+
+use crate::packets::opcodes::{
+    FragmentType, GRPC_TEAM_NTF_SERVICE_ID, WORLD_CALL_SERVICE_ID, WORLD_NTF_SERVICE_ID,
+};
+use crate::packets::utils::{Server, tcp_sequence_before};
+use std::collections::HashMap;
+
+const KNOWN_SERVICE_IDS: &[u64] = &[
+    WORLD_NTF_SERVICE_ID,
+    WORLD_CALL_SERVICE_ID,
+    GRPC_TEAM_NTF_SERVICE_ID,
+];
+
+const FRAME_HEADER_BYTES: usize = 6;
+/// Matches the frame `Reassembler`'s own sanity cap.
+const MAX_FRAME_BYTES: usize = 10 * 1024 * 1024;
+/// Give up on a direction when no offset even looks like a frame header.
+const EARLY_REJECT_BYTES: usize = 4 * 1024;
+/// Give up on a direction whose frames never name a known service.
+const PROBE_LIMIT_BYTES: usize = 256 * 1024;
+/// Frame alignment is only searched for near the start of a probe.
+const ALIGNMENT_WINDOW_BYTES: usize = 64 * 1024;
+const MAX_NESTING: usize = 2;
+const MIN_ALIGNED_FRAMES: usize = 2;
+/// Directions probed at once; further new flows are rejected until one ends.
+const MAX_PROBES: usize = 1024;
+
+/// In-order stream bytes with the TCP sequence number they start at.
+#[derive(Debug, PartialEq, Eq)]
+pub struct AlignedStart {
+    pub sequence: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SignatureVerdict {
+    /// Not enough bytes to decide yet.
+    Pending,
+    /// The bytes from `AlignedStart::sequence` onwards are frame aligned.
+    Match(AlignedStart),
+    NoMatch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureMode {
+    /// Require a frame that addresses a known game service.
+    Recognize,
+    /// The flow is already known to be the game; only find a frame boundary.
+    Align,
+}
+
+struct Probe {
+    start_sequence: u32,
+    next_sequence: u32,
+    buffer: Vec<u8>,
+}
+
+#[derive(Default)]
+pub struct FlowSignatureDetector {
+    probes: HashMap<Server, Probe>,
+}
+
+impl FlowSignatureDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe(
+        &mut self,
+        server: Server,
+        sequence: u32,
+        payload: &[u8],
+        mode: SignatureMode,
+    ) -> SignatureVerdict {
+        if payload.is_empty() {
+            return SignatureVerdict::Pending;
+        }
+        if !self.probes.contains_key(&server) && self.probes.len() >= MAX_PROBES {
+            return SignatureVerdict::NoMatch;
+        }
+
+        let probe = self.probes.entry(server).or_insert_with(|| Probe {
+            start_sequence: sequence,
+            next_sequence: sequence,
+            buffer: Vec::new(),
+        });
+        if sequence != probe.next_sequence {
+            if tcp_sequence_before(sequence, probe.next_sequence) {
+                // Retransmission of bytes the probe already holds.
+                return SignatureVerdict::Pending;
+            }
+            // Lost or reordered data: the buffered bytes are no longer
+            // contiguous, so alignment restarts at this segment.
+            probe.start_sequence = sequence;
+            probe.buffer.clear();
+        }
+        probe.buffer.extend_from_slice(payload);
+        probe.next_sequence = sequence.wrapping_add(payload.len() as u32);
+
+        let verdict = match find_aligned_offset(&probe.buffer, mode) {
+            Scan::Found(offset) => SignatureVerdict::Match(AlignedStart {
+                sequence: probe.start_sequence.wrapping_add(offset as u32),
+                data: probe.buffer[offset..].to_vec(),
+            }),
+            Scan::Plausible if probe.buffer.len() < PROBE_LIMIT_BYTES => SignatureVerdict::Pending,
+            Scan::Invalid if probe.buffer.len() < EARLY_REJECT_BYTES => SignatureVerdict::Pending,
+            Scan::Plausible | Scan::Invalid => SignatureVerdict::NoMatch,
+        };
+        if !matches!(verdict, SignatureVerdict::Pending) {
+            self.probes.remove(&server);
+        }
+        verdict
+    }
+
+    pub fn forget(&mut self, server: Server) {
+        self.probes.remove(&server);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scan {
+    Found(usize),
+    /// Some offset starts a well-formed chain that is not conclusive yet.
+    Plausible,
+    Invalid,
+}
+
+fn find_aligned_offset(buffer: &[u8], mode: SignatureMode) -> Scan {
+    let mut result = Scan::Invalid;
+    let last_offset = buffer
+        .len()
+        .saturating_sub(FRAME_HEADER_BYTES)
+        .min(ALIGNMENT_WINDOW_BYTES);
+    for offset in 0..=last_offset {
+        match walk_chain(&buffer[offset..], 0) {
+            Chain::Invalid => {}
+            Chain::Valid {
+                complete_frames,
+                recognized,
+            } => {
+                let conclusive = match mode {
+                    SignatureMode::Recognize => recognized,
+                    SignatureMode::Align => complete_frames > 0,
+                };
+                if conclusive {
+                    return Scan::Found(offset);
+                }
+                result = Scan::Plausible;
+                // Several consecutive frames are alignment enough; later
+                // offsets would only be re-walking the same stream.
+                if complete_frames >= MIN_ALIGNED_FRAMES {
+                    break;
+                }
+            }
+        }
+    }
+    result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Chain {
+    Invalid,
+    Valid {
+        complete_frames: usize,
+        recognized: bool,
+    },
+}
+
+/// Walks consecutive frames from the start of `bytes`. A trailing partial
+/// frame is fine as long as whatever part of its header is present is valid.
+fn walk_chain(bytes: &[u8], depth: usize) -> Chain {
+    let mut offset = 0;
+    let mut complete_frames = 0;
+    let mut recognized = false;
+    while offset < bytes.len() {
+        let Some(header) = bytes.get(offset..offset + FRAME_HEADER_BYTES) else {
+            // Too short to judge; only a non-empty chain may end this way.
+            return if complete_frames > 0 {
+                Chain::Valid {
+                    complete_frames,
+                    recognized,
+                }
+            } else {
+                Chain::Invalid
+            };
+        };
+        let frame_len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let packet_type = u16::from_be_bytes([header[4], header[5]]);
+        if !(FRAME_HEADER_BYTES..=MAX_FRAME_BYTES).contains(&frame_len)
+            || !is_known_fragment(packet_type & 0x7fff)
+        {
+            return Chain::Invalid;
+        }
+        let Some(frame) = bytes.get(offset..offset + frame_len) else {
+            break;
+        };
+        recognized |= frame_names_known_service(frame, packet_type, depth);
+        complete_frames += 1;
+        offset += frame_len;
+    }
+    Chain::Valid {
+        complete_frames,
+        recognized,
+    }
+}
+
+fn is_known_fragment(fragment_type: u16) -> bool {
+    matches!(
+        FragmentType::from(fragment_type),
+        FragmentType::Call
+            | FragmentType::Notify
+            | FragmentType::Return
+            | FragmentType::Echo
+            | FragmentType::FrameUp
+            | FragmentType::FrameDown
+    )
+}
+
+fn frame_names_known_service(frame: &[u8], packet_type: u16, depth: usize) -> bool {
+    let compressed = packet_type & 0x8000 != 0;
+    let payload = &frame[FRAME_HEADER_BYTES..];
+    match FragmentType::from(packet_type & 0x7fff) {
+        // Notify and Call payloads start with the service uuid; only the body
+        // after the fixed routing header is ever compressed.
+        FragmentType::Notify | FragmentType::Call => payload
+            .get(..8)
+            .map(|id| u64::from_be_bytes(id.try_into().expect("8-byte slice")))
+            .is_some_and(|service_id| KNOWN_SERVICE_IDS.contains(&service_id)),
+        FragmentType::FrameDown | FragmentType::FrameUp if depth < MAX_NESTING => {
+            let Some(nested) = payload.get(4..) else {
+                return false;
+            };
+            let chain = if compressed {
+                match zstd::decode_all(nested) {
+                    Ok(decoded) => walk_chain(&decoded, depth + 1),
+                    Err(_) => Chain::Invalid,
+                }
+            } else {
+                walk_chain(nested, depth + 1)
+            };
+            matches!(
+                chain,
+                Chain::Valid {
+                    recognized: true,
+                    ..
+                }
+            )
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server() -> Server {
+        Server::new([20, 0, 0, 1], 50000, [10, 0, 0, 1], 40000)
+    }
+
+    fn frame(fragment: u16, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = ((FRAME_HEADER_BYTES + payload.len()) as u32)
+            .to_be_bytes()
+            .to_vec();
+        bytes.extend_from_slice(&fragment.to_be_bytes());
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    fn notify(service_id: u64) -> Vec<u8> {
+        let mut payload = service_id.to_be_bytes().to_vec();
+        payload.extend_from_slice(&[0; 8]);
+        payload.extend_from_slice(b"body");
+        frame(FragmentType::Notify as u16, &payload)
+    }
+
+    fn frame_down(nested: &[u8], compressed: bool) -> Vec<u8> {
+        let mut payload = vec![0; 4];
+        if compressed {
+            payload.extend_from_slice(&zstd::encode_all(nested, 0).expect("zstd encodes"));
+            frame(FragmentType::FrameDown as u16 | 0x8000, &payload)
+        } else {
+            payload.extend_from_slice(nested);
+            frame(FragmentType::FrameDown as u16, &payload)
+        }
+    }
+
+    #[test]
+    fn recognizes_known_service_mid_stream() {
+        let mut stream = vec![0xAB; 5];
+        stream.extend_from_slice(&frame(FragmentType::Echo as u16, &[1, 2, 3]));
+        stream.extend_from_slice(&frame_down(&notify(WORLD_NTF_SERVICE_ID), true));
+
+        let mut detector = FlowSignatureDetector::new();
+        let verdict = detector.observe(server(), 1_000, &stream, SignatureMode::Recognize);
+        assert_eq!(
+            verdict,
+            SignatureVerdict::Match(AlignedStart {
+                sequence: 1_005,
+                data: stream[5..].to_vec(),
+            })
+        );
+    }
+
+    #[test]
+    fn waits_for_a_frame_split_across_segments() {
+        let stream = frame_down(&notify(GRPC_TEAM_NTF_SERVICE_ID), false);
+        let (head, tail) = stream.split_at(10);
+
+        let mut detector = FlowSignatureDetector::new();
+        assert_eq!(
+            detector.observe(server(), 7, head, SignatureMode::Recognize),
+            SignatureVerdict::Pending
+        );
+        assert_eq!(
+            detector.observe(server(), 17, tail, SignatureMode::Recognize),
+            SignatureVerdict::Match(AlignedStart {
+                sequence: 7,
+                data: stream.clone(),
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_well_formed_frames_for_unknown_services_and_noise() {
+        let mut detector = FlowSignatureDetector::new();
+        let noise: Vec<u8> = (0..EARLY_REJECT_BYTES as u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        assert_eq!(
+            detector.observe(server(), 0, &noise, SignatureMode::Recognize),
+            SignatureVerdict::NoMatch
+        );
+
+        let mut stream = Vec::new();
+        while stream.len() < PROBE_LIMIT_BYTES {
+            stream.extend_from_slice(&notify(42));
+        }
+        assert_eq!(
+            detector.observe(server(), 0, &stream, SignatureMode::Recognize),
+            SignatureVerdict::NoMatch
+        );
+    }
+
+    #[test]
+    fn align_mode_only_needs_a_frame_boundary() {
+        let mut stream = vec![0xCD; 3];
+        stream.extend_from_slice(&frame(FragmentType::Call as u16, &[0; 24]));
+
+        let mut detector = FlowSignatureDetector::new();
+        assert_eq!(
+            detector.observe(server(), 0, &stream, SignatureMode::Align),
+            SignatureVerdict::Match(AlignedStart {
+                sequence: 3,
+                data: stream[3..].to_vec(),
+            })
+        );
+    }
+}
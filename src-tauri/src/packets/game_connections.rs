@@ -1,5 +1,9 @@
+use crate::packets::flow_signature::{
+    AlignedStart, FlowSignatureDetector, SignatureMode, SignatureVerdict,
+};
 use crate::packets::utils::Server;
 use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
 
 const GAME_PROCESS_NAMES: &[&str] = &[
     "bpsr",
@@ -13,20 +17,24 @@ const GAME_PROCESS_NAMES: &[&str] = &[
     "star",
 ];
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Endpoint {
-    addr: [u8; 4],
+    addr: IpAddr,
     port: u16,
 }
 
 impl Endpoint {
-    fn new(addr: [u8; 4], port: u16) -> Self {
-        Self { addr, port }
+    fn new(addr: impl Into<IpAddr>, port: u16) -> Self {
+        Self {
+            addr: addr.into().to_canonical(),
+            port,
+        }
     }
 }
 
+/// Direction-independent identity of a TCP connection.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct FlowKey([u8; 12]);
+struct FlowKey(Endpoint, Endpoint);
 
 impl FlowKey {
     fn from_server(server: Server) -> Self {
@@ -37,20 +45,7 @@ impl FlowKey {
     }
 
     fn from_endpoints(a: Endpoint, b: Endpoint) -> Self {
-        let left = (a.addr, a.port);
-        let right = (b.addr, b.port);
-        let ((first_addr, first_port), (second_addr, second_port)) = if left <= right {
-            (left, right)
-        } else {
-            (right, left)
-        };
-
-        let mut key = [0u8; 12];
-        key[..4].copy_from_slice(&first_addr);
-        key[4..6].copy_from_slice(&first_port.to_be_bytes());
-        key[6..10].copy_from_slice(&second_addr);
-        key[10..12].copy_from_slice(&second_port.to_be_bytes());
-        Self(key)
+        if a <= b { Self(a, b) } else { Self(b, a) }
     }
 }
 
@@ -60,6 +55,20 @@ pub enum Verdict {
     NonGame,
 }
 
+/// How the first packets of a direction without a capture session are handled.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Admission {
+    Reject,
+    /// Start the session at this packet.
+    Accept,
+    /// Start the session at a frame boundary inside bytes buffered while the
+    /// flow was still being recognised; the current packet is included.
+    AcceptAligned(AlignedStart),
+}
+
+/// Bounds the memory spent remembering directions that never matched.
+const MAX_REJECTED_DIRECTIONS: usize = 65_536;
+
 struct TcpTableSnapshot {
     game_endpoints: HashSet<Endpoint>,
     non_game_flows: HashSet<FlowKey>,
@@ -75,6 +84,10 @@ pub struct GameConnectionFilter {
     pid_cache: HashMap<u32, bool>,
     process_names: HashSet<&'static str>,
     snapshot: SnapshotFn,
+    use_process_table: bool,
+    signatures: FlowSignatureDetector,
+    signature_flows: HashSet<FlowKey>,
+    rejected_directions: HashSet<Server>,
 }
 
 impl GameConnectionFilter {
@@ -85,6 +98,65 @@ impl GameConnectionFilter {
             pid_cache: HashMap::new(),
             process_names: GAME_PROCESS_NAMES.iter().copied().collect(),
             snapshot: platform::snapshot_tcp_table,
+            use_process_table: true,
+            signatures: FlowSignatureDetector::new(),
+            signature_flows: HashSet::new(),
+            rejected_directions: HashSet::new(),
+        }
+    }
+
+    /// For sources that do not observe this host (capture files, remote
+    /// agents): the local TCP table says nothing about their flows, so only
+    /// payload signatures are used.
+    pub fn without_process_table() -> Self {
+        Self {
+            use_process_table: false,
+            ..Self::new()
+        }
+    }
+
+    /// Decides whether a direction without a session belongs to the game.
+    /// The OS TCP table is consulted first; flows it cannot attribute fall
+    /// back to payload signature recognition.
+    pub fn admit(&mut self, server: Server, sequence: u32, payload: &[u8]) -> Admission {
+        if self.use_process_table && self.classify(server) == Verdict::Game {
+            return Admission::Accept;
+        }
+
+        let flow = FlowKey::from_server(server);
+        if self.signature_flows.contains(&flow) {
+            // The other direction identified the flow; this one only needs a
+            // frame boundary to start from.
+            return match self
+                .signatures
+                .observe(server, sequence, payload, SignatureMode::Align)
+            {
+                SignatureVerdict::Pending => Admission::Reject,
+                SignatureVerdict::Match(start) => Admission::AcceptAligned(start),
+                SignatureVerdict::NoMatch => Admission::Accept,
+            };
+        }
+        if self.rejected_directions.contains(&server) {
+            return Admission::Reject;
+        }
+
+        match self
+            .signatures
+            .observe(server, sequence, payload, SignatureMode::Recognize)
+        {
+            SignatureVerdict::Pending => Admission::Reject,
+            SignatureVerdict::Match(start) => {
+                log::info!(target: "app::capture", "game flow recognised by payload signature: {server}");
+                self.signature_flows.insert(flow);
+                Admission::AcceptAligned(start)
+            }
+            SignatureVerdict::NoMatch => {
+                if self.rejected_directions.len() >= MAX_REJECTED_DIRECTIONS {
+                    self.rejected_directions.clear();
+                }
+                self.rejected_directions.insert(server);
+                Admission::Reject
+            }
         }
     }
 
@@ -110,6 +182,8 @@ impl GameConnectionFilter {
 
     pub fn forget_flow(&mut self, server: Server) {
         self.non_game_flows.remove(&FlowKey::from_server(server));
+        self.signatures.forget(server);
+        self.rejected_directions.remove(&server);
     }
 
     fn contains_positive(&self, server: Server) -> bool {
@@ -117,7 +191,7 @@ impl GameConnectionFilter {
             || self.contains_endpoint(server.destination_addr(), server.destination_port())
     }
 
-    fn contains_endpoint(&self, addr: [u8; 4], port: u16) -> bool {
+    fn contains_endpoint(&self, addr: impl Into<IpAddr>, port: u16) -> bool {
         self.game_endpoints.contains(&Endpoint::new(addr, port))
     }
 
@@ -151,6 +225,7 @@ mod platform {
     use std::ptr::null_mut;
 
     const AF_INET: u32 = 2;
+    const AF_INET6: u32 = 23;
     const TCP_TABLE_OWNER_PID_ALL: u32 = 5;
     const NO_ERROR: u32 = 0;
     const ERROR_INSUFFICIENT_BUFFER: u32 = 122;
@@ -168,6 +243,25 @@ mod platform {
         owning_pid: u32,
     }
 
+    #[repr(C)]
+    struct Tcp6RowOwnerPid {
+        local_addr: [u8; 16],
+        local_scope_id: u32,
+        local_port: [u8; 4],
+        remote_addr: [u8; 16],
+        remote_scope_id: u32,
+        remote_port: [u8; 4],
+        state: u32,
+        owning_pid: u32,
+    }
+
+    /// Family-independent view of one owned TCP table row.
+    struct OwnedConnection {
+        local: Endpoint,
+        remote: Endpoint,
+        owning_pid: u32,
+    }
+
     #[link(name = "iphlpapi")]
     unsafe extern "system" {
         fn GetExtendedTcpTable(
@@ -196,13 +290,32 @@ mod platform {
         process_names: &HashSet<&'static str>,
         previous_pid_cache: &HashMap<u32, bool>,
     ) -> Result<TcpTableSnapshot, String> {
-        let rows = tcp_rows()?;
+        let mut rows: Vec<OwnedConnection> = tcp_rows::<TcpRowOwnerPid>(AF_INET)?
+            .into_iter()
+            .map(|row| OwnedConnection {
+                local: Endpoint::new(row.local_addr, port_from_bytes(row.local_port)),
+                remote: Endpoint::new(row.remote_addr, port_from_bytes(row.remote_port)),
+                owning_pid: row.owning_pid,
+            })
+            .collect();
+        // Hosts without an IPv6 stack fail this query; IPv4 classification
+        // must keep working regardless.
+        match tcp_rows::<Tcp6RowOwnerPid>(AF_INET6) {
+            Ok(rows6) => rows.extend(rows6.into_iter().map(|row| OwnedConnection {
+                local: Endpoint::new(row.local_addr, port_from_bytes(row.local_port)),
+                remote: Endpoint::new(row.remote_addr, port_from_bytes(row.remote_port)),
+                owning_pid: row.owning_pid,
+            })),
+            Err(err) => {
+                log::debug!(target: "app::capture", "IPv6 TCP table unavailable: {err}");
+            }
+        }
         let mut game_endpoints = HashSet::new();
         let mut non_game_flows = HashSet::new();
         let mut pid_cache = HashMap::new();
 
         for row in rows {
-            if row.remote_addr == [0, 0, 0, 0] {
+            if row.remote.addr.is_unspecified() {
                 continue;
             }
 
@@ -222,14 +335,11 @@ mod platform {
                 }
             };
 
-            let local = Endpoint::new(row.local_addr, port_from_bytes(row.local_port));
-            let remote = Endpoint::new(row.remote_addr, port_from_bytes(row.remote_port));
-
             if is_target {
-                game_endpoints.insert(local);
-                game_endpoints.insert(remote);
+                game_endpoints.insert(row.local);
+                game_endpoints.insert(row.remote);
             } else {
-                non_game_flows.insert(FlowKey::from_endpoints(local, remote));
+                non_game_flows.insert(FlowKey::from_endpoints(row.local, row.remote));
             }
         }
 
@@ -240,14 +350,14 @@ mod platform {
         })
     }
 
-    fn tcp_rows() -> Result<Vec<TcpRowOwnerPid>, String> {
+    fn tcp_rows<Row>(family: u32) -> Result<Vec<Row>, String> {
         let mut table_len = 0u32;
         let first = unsafe {
             GetExtendedTcpTable(
                 null_mut(),
                 &mut table_len,
                 0,
-                AF_INET,
+                family,
                 TCP_TABLE_OWNER_PID_ALL,
                 0,
             )
@@ -263,7 +373,7 @@ mod platform {
                 table.as_mut_ptr().cast(),
                 &mut table_len,
                 0,
-                AF_INET,
+                family,
                 TCP_TABLE_OWNER_PID_ALL,
                 0,
             )
@@ -279,7 +389,7 @@ mod platform {
 
         let count = u32::from_ne_bytes(table[0..4].try_into().map_err(|e| format!("{e}"))?);
         let rows_start = size_of::<u32>();
-        let row_size = size_of::<TcpRowOwnerPid>();
+        let row_size = size_of::<Row>();
         let mut rows = Vec::with_capacity(count as usize);
 
         for idx in 0..count as usize {
@@ -329,7 +439,164 @@ mod platform {
     }
 }
 
-#[cfg(not(windows))]
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{Endpoint, FlowKey, HashMap, HashSet, TcpTableSnapshot};
+    use std::fs;
+    use std::path::Path;
+
+    const TCP_TABLE_PATHS: &[&str] = &["/proc/net/tcp", "/proc/net/tcp6"];
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub(super) struct TcpRow {
+        pub(super) local: Endpoint,
+        pub(super) remote: Endpoint,
+        pub(super) inode: u64,
+    }
+
+    /// Linux has no per-row owner in the TCP table. Sockets are attributed by
+    /// walking `/proc/<pid>/fd` of game processes only, so a refresh costs one
+    /// directory scan per game process rather than one per process on the box.
+    pub fn snapshot_tcp_table(
+        process_names: &HashSet<&'static str>,
+        previous_pid_cache: &HashMap<u32, bool>,
+    ) -> Result<TcpTableSnapshot, String> {
+        let mut rows: Vec<TcpRow> = Vec::new();
+        for (index, path) in TCP_TABLE_PATHS.iter().enumerate() {
+            match fs::read_to_string(path) {
+                Ok(table) => rows.extend(table.lines().skip(1).filter_map(parse_tcp_row)),
+                // `tcp6` is absent when IPv6 is disabled.
+                Err(_) if index > 0 => {}
+                Err(e) => return Err(format!("failed to read {path}: {e}")),
+            }
+        }
+
+        let mut pid_cache = HashMap::new();
+        let mut game_inodes = HashSet::new();
+        let processes = fs::read_dir("/proc").map_err(|e| format!("failed to read /proc: {e}"))?;
+        for entry in processes.flatten() {
+            let Some(pid) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            let is_target = previous_pid_cache.get(&pid).copied().unwrap_or_else(|| {
+                process_name(&entry.path())
+                    .as_deref()
+                    .is_some_and(|name| process_names.contains(name))
+            });
+            pid_cache.insert(pid, is_target);
+            if is_target {
+                collect_socket_inodes(&entry.path(), &mut game_inodes);
+            }
+        }
+
+        let mut game_endpoints = HashSet::new();
+        let mut non_game_flows = HashSet::new();
+        for row in rows {
+            if row.remote.addr.is_unspecified() {
+                continue;
+            }
+            if row.inode != 0 && game_inodes.contains(&row.inode) {
+                game_endpoints.insert(row.local);
+                game_endpoints.insert(row.remote);
+            } else {
+                non_game_flows.insert(FlowKey::from_endpoints(row.local, row.remote));
+            }
+        }
+
+        Ok(TcpTableSnapshot {
+            game_endpoints,
+            non_game_flows,
+            pid_cache,
+        })
+    }
+
+    /// Parses one data line of `/proc/net/tcp` or `/proc/net/tcp6`:
+    /// `sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode ...`
+    pub(super) fn parse_tcp_row(line: &str) -> Option<TcpRow> {
+        let mut fields = line.split_whitespace();
+        let _slot = fields.next()?;
+        let local = parse_endpoint(fields.next()?)?;
+        let remote = parse_endpoint(fields.next()?)?;
+        let inode = fields.nth(6)?.parse().ok()?;
+        Some(TcpRow {
+            local,
+            remote,
+            inode,
+        })
+    }
+
+    /// `0100007F:1F90` -> 127.0.0.1:8080. Addresses are the raw network-order
+    /// words printed as host integers (four of them for IPv6); the port is
+    /// already in host order.
+    fn parse_endpoint(field: &str) -> Option<Endpoint> {
+        let (addr, port) = field.split_once(':')?;
+        let port = u16::from_str_radix(port, 16).ok()?;
+        match addr.len() {
+            8 => Some(Endpoint::new(parse_word(addr)?, port)),
+            32 => {
+                let mut bytes = [0u8; 16];
+                for (chunk, word) in bytes.chunks_exact_mut(4).zip(addr.as_bytes().chunks(8)) {
+                    chunk.copy_from_slice(&parse_word(std::str::from_utf8(word).ok()?)?);
+                }
+                Some(Endpoint::new(bytes, port))
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_word(hex: &str) -> Option<[u8; 4]> {
+        Some(u32::from_str_radix(hex, 16).ok()?.to_ne_bytes())
+    }
+
+    fn collect_socket_inodes(process_dir: &Path, inodes: &mut HashSet<u64>) {
+        let Ok(fds) = fs::read_dir(process_dir.join("fd")) else {
+            return;
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            if let Some(inode) = target
+                .to_str()
+                .and_then(|target| target.strip_prefix("socket:["))
+                .and_then(|target| target.strip_suffix(']'))
+                .and_then(|inode| inode.parse().ok())
+            {
+                inodes.insert(inode);
+            }
+        }
+    }
+
+    /// Executable name without extension. Games running under Wine/Proton
+    /// report a Windows path as their first argument, e.g.
+    /// `Z:\...\bpsr_steam.exe`.
+    fn process_name(process_dir: &Path) -> Option<String> {
+        let cmdline = fs::read(process_dir.join("cmdline")).ok()?;
+        let argv0 = cmdline.split(|byte| *byte == 0).next()?;
+        let argv0 = std::str::from_utf8(argv0).ok()?;
+        let name = if argv0.is_empty() {
+            fs::read_to_string(process_dir.join("comm")).ok()?
+        } else {
+            argv0.to_string()
+        };
+        normalize_process_name(&name)
+    }
+
+    pub(super) fn normalize_process_name(path: &str) -> Option<String> {
+        let file_name = path.trim().rsplit(['/', '\\']).next()?;
+        if file_name.is_empty() {
+            return None;
+        }
+        let lower = file_name.to_ascii_lowercase();
+        Some(lower.strip_suffix(".exe").unwrap_or(&lower).to_string())
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
 mod platform {
     use super::{HashMap, HashSet, TcpTableSnapshot};
 
@@ -366,6 +633,10 @@ mod tests {
         Endpoint::new(addr, port)
     }
 
+    fn endpoint_v6(addr: std::net::Ipv6Addr, port: u16) -> Endpoint {
+        Endpoint::new(addr, port)
+    }
+
     fn snapshot_with_game(
         _process_names: &HashSet<&'static str>,
         _previous_pid_cache: &HashMap<u32, bool>,
@@ -400,9 +671,22 @@ mod tests {
             pid_cache: HashMap::new(),
             process_names: GAME_PROCESS_NAMES.iter().copied().collect(),
             snapshot,
+            use_process_table: true,
+            signatures: FlowSignatureDetector::new(),
+            signature_flows: HashSet::new(),
+            rejected_directions: HashSet::new(),
         }
     }
 
+    /// A notify frame for the world service, as the server sends it.
+    fn world_notify_frame() -> Vec<u8> {
+        let mut frame = 26u32.to_be_bytes().to_vec();
+        frame.extend_from_slice(&2u16.to_be_bytes());
+        frame.extend_from_slice(&crate::packets::opcodes::WORLD_NTF_SERVICE_ID.to_be_bytes());
+        frame.extend_from_slice(&[0; 12]);
+        frame
+    }
+
     fn reset_calls() {
         SNAPSHOT_CALLS.with(|calls| calls.set(0));
     }
@@ -472,4 +756,118 @@ mod tests {
         assert_eq!(filter.classify(server()), Verdict::NonGame);
         assert_eq!(snapshot_calls(), 1);
     }
+
+    #[test]
+    fn admit_prefers_the_process_table() {
+        reset_calls();
+        let mut filter = filter_with_snapshot(snapshot_with_game);
+
+        assert_eq!(filter.admit(server(), 1, b"anything"), Admission::Accept);
+    }
+
+    #[test]
+    fn admit_falls_back_to_signatures_and_aligns_the_reverse_direction() {
+        let mut filter = GameConnectionFilter {
+            use_process_table: false,
+            ..filter_with_snapshot(empty_snapshot)
+        };
+        let frame = world_notify_frame();
+
+        let mut downlink = vec![0xEE, 0xEE];
+        downlink.extend_from_slice(&frame);
+        assert_eq!(
+            filter.admit(reversed_server(), 100, &downlink),
+            Admission::AcceptAligned(AlignedStart {
+                sequence: 102,
+                data: frame.clone(),
+            })
+        );
+
+        // The uplink only carries an echo frame, which is enough once the
+        // flow is known.
+        let echo = [0, 0, 0, 6, 0, 4];
+        assert_eq!(
+            filter.admit(server(), 7, &echo),
+            Admission::AcceptAligned(AlignedStart {
+                sequence: 7,
+                data: echo.to_vec(),
+            })
+        );
+    }
+
+    #[test]
+    fn admit_rejects_unrecognised_directions_for_good() {
+        reset_calls();
+        let mut filter = filter_with_snapshot(empty_snapshot);
+        let noise = vec![0xFF; 8 * 1024];
+
+        assert_eq!(filter.admit(server(), 0, &noise), Admission::Reject);
+        assert!(filter.rejected_directions.contains(&server()));
+        assert_eq!(
+            filter.admit(server(), 8 * 1024, &world_notify_frame()),
+            Admission::Reject
+        );
+    }
+
+    #[test]
+    fn classify_matches_ipv6_and_mapped_endpoints() {
+        let game = std::net::Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        let client = std::net::Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2);
+        let mut filter = filter_with_snapshot(empty_snapshot);
+        filter.game_endpoints.insert(endpoint_v6(game, 50000));
+        filter.game_endpoints.insert(endpoint([20, 0, 0, 1], 50000));
+
+        assert_eq!(
+            filter.classify(Server::new(client, 40000, game, 50000)),
+            Verdict::Game
+        );
+        let mapped = std::net::Ipv4Addr::new(20, 0, 0, 1).to_ipv6_mapped();
+        assert_eq!(
+            filter.classify(Server::new(client, 40000, mapped, 50000)),
+            Verdict::Game
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_proc_net_tcp_rows() {
+        let row = platform::parse_tcp_row(
+            "   3: 0F02000A:9C40 01000014:C350 01 00000000:00000000 00:00000000 00000000  1000        0 123456 1 0000000000000000 20 4 30 10 -1",
+        )
+        .expect("row parses");
+        assert_eq!(row.local, endpoint([10, 0, 2, 15], 40000));
+        assert_eq!(row.remote, endpoint([20, 0, 0, 1], 50000));
+        assert_eq!(row.inode, 123456);
+        assert!(platform::parse_tcp_row("  sl  local_address rem_address   st").is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_proc_net_tcp6_rows_and_unmaps_ipv4() {
+        let row = platform::parse_tcp_row(
+            "   0: 00000000000000000000000001000000:1F90 0000000000000000FFFF00000F02000A:9C40 01 00000000:00000000 00:00000000 00000000  1000        0 42 1 0000000000000000 20 4 30 10 -1",
+        )
+        .expect("row parses");
+        assert_eq!(row.local, endpoint_v6(std::net::Ipv6Addr::LOCALHOST, 8080));
+        assert_eq!(row.remote, endpoint([10, 0, 2, 15], 40000));
+        assert_eq!(row.inode, 42);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn normalizes_native_and_wine_process_names() {
+        assert_eq!(
+            platform::normalize_process_name("/opt/game/bpsr"),
+            Some("bpsr".to_string())
+        );
+        assert_eq!(
+            platform::normalize_process_name("Z:\\Games\\BPSR\\BPSR_STEAM.exe"),
+            Some("bpsr_steam".to_string())
+        );
+        assert_eq!(
+            platform::normalize_process_name("star\n"),
+            Some("star".to_string())
+        );
+        assert_eq!(platform::normalize_process_name("/usr/bin/"), None);
+    }
 }
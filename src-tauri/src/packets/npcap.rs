@@ -31,6 +31,12 @@ const NPCAP_TIMEOUT_MS: i32 = 1_000;
 const NPCAP_BUFFER_SIZE: i32 = 64 * 1024 * 1024;
 const NPCAP_IMMEDIATE: i32 = 1;
 
+/// Npcap on Windows; the system libpcap elsewhere exposes the same API.
+#[cfg(windows)]
+const PCAP_LIBRARY_NAMES: &[&str] = &["wpcap.dll"];
+#[cfg(not(windows))]
+const PCAP_LIBRARY_NAMES: &[&str] = &["libpcap.so.1", "libpcap.so", "libpcap.dylib"];
+
 #[repr(C)]
 pub struct PcapIf {
     pub next: *mut PcapIf,
@@ -78,11 +84,14 @@ pub struct NpcapContext {
 
 impl NpcapContext {
     pub fn new() -> Result<Self, String> {
-        unsafe {
-            let lib = Library::new("wpcap.dll")
-                .map_err(|e| format!("Failed to load wpcap.dll: {}", e))?;
-            Ok(Self { lib: Arc::new(lib) })
+        let mut last_error = String::new();
+        for &name in PCAP_LIBRARY_NAMES {
+            match unsafe { Library::new(name) } {
+                Ok(lib) => return Ok(Self { lib: Arc::new(lib) }),
+                Err(e) => last_error = format!("Failed to load {}: {}", name, e),
+            }
         }
+        Err(last_error)
     }
 
     pub fn list_devices(&self) -> Result<Vec<Device>, String> {
@@ -7,6 +7,9 @@ pub const WORLD_NTF_SERVICE_ID: u64 = 1_664_308_034;
 pub const WORLD_CALL_SERVICE_ID: u64 = 103_198_054;
 pub const GRPC_TEAM_NTF_SERVICE_ID: u64 = 966_773_353;
 
+/// Uplink world calls with a confirmed method id. Target selection, item use,
+/// emotes and dungeon votes are not listed yet: their ids have not been
+/// captured, and the discovery log reports them as unrouted methods.
 pub mod world_call_method {
     pub const USE_SLOT: u32 = 0x3d002;
 }
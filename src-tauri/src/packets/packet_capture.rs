@@ -1,15 +1,26 @@
 use crate::live::runtime::events::CaptureEnvelope;
-use crate::packets::game_connections::{GameConnectionFilter, Verdict};
+use crate::live::runtime::events::monotonic_now_ns;
+#[cfg(target_os = "linux")]
+use crate::packets::af_packet::AfPacketCapture;
+use crate::packets::flow_signature::AlignedStart;
+use crate::packets::game_connections::{Admission, GameConnectionFilter};
 use crate::packets::npcap::NpcapCapture;
-use crate::packets::packet_process::{CaptureEmitter, process_packet};
+use crate::packets::packet_process::{CaptureEmitter, CaptureTime, process_packet};
+use crate::packets::pcap_file::PcapReader;
 use crate::packets::reassembler::Reassembler;
+use crate::packets::remote_ingest::{RemoteIngest, RemotePacketFormat, SharedKey};
+use crate::packets::session_recorder::{SessionRecorder, SessionRecorderConfig};
 use crate::packets::utils::{Server, TCPReassembler, TcpInsertResult, tcp_sequence_before};
-use etherparse::NetSlice::Ipv4;
+use etherparse::NetSlice::{Ipv4, Ipv6};
 use etherparse::SlicedPacket;
 use etherparse::TransportSlice::Tcp;
 use log::{error, info, warn};
 use std::collections::HashMap;
+use std::fs::File;
 use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::BufReader;
+use std::net::IpAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::OnceLock;
 use std::sync::atomic::AtomicUsize;
@@ -28,6 +39,14 @@ const DLT_NULL: i32 = 0;
 const DLT_EN10MB: i32 = 1;
 const DLT_RAW: i32 = 12;
 const DLT_LOOP: i32 = 108;
+const DLT_LINUX_SLL: i32 = 113;
+
+const LINUX_SLL_HEADER_LEN: usize = 16;
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+
+/// Packets handed to the capture loop per `pump` call when replaying a file.
+const PCAP_FILE_BATCH: usize = 256;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum PacketFormat {
@@ -40,10 +59,38 @@ enum PacketFormat {
 pub enum CaptureMethod {
     WinDivert,
     Npcap(String),
+    /// Offline replay of a classic pcap or pcapng capture file.
+    PcapFile(PathBuf),
+    /// Linux `AF_PACKET` socket on one interface, or all when empty.
+    AfPacket(String),
+    /// Packets forwarded by remote capture agents that connect to `listen`.
+    Remote {
+        listen: String,
+        key: SharedKey,
+    },
 }
 
+type PacketCallback<'a> = dyn FnMut(PacketFormat, Option<CaptureTime>, &[u8]) + 'a;
+
 trait PacketSource: Send {
-    fn pump(&mut self, on_packet: &mut dyn FnMut(PacketFormat, &[u8])) -> Result<i32, String>;
+    fn pump(&mut self, on_packet: &mut PacketCallback<'_>) -> Result<i32, String>;
+
+    /// True once a finite source has delivered its last packet. Live sources
+    /// never finish; the capture thread restarts them on error instead.
+    fn is_exhausted(&self) -> bool {
+        false
+    }
+
+    /// Why an exhausted source stopped short of its end.
+    fn failure(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether packets come from this host, so the OS TCP table can tell game
+    /// flows apart. Other sources rely on payload signatures alone.
+    fn is_local(&self) -> bool {
+        true
+    }
 }
 
 struct WinDivertSource {
@@ -54,7 +101,7 @@ struct WinDivertSource {
 impl WinDivertSource {
     fn new() -> Result<Self, String> {
         let handle = WinDivert::network(
-            "!loopback && ip && tcp",
+            "!loopback && (ip || ipv6) && tcp",
             0,
             WinDivertFlags::new().set_sniff(),
         )
@@ -70,12 +117,12 @@ impl WinDivertSource {
 }
 
 impl PacketSource for WinDivertSource {
-    fn pump(&mut self, on_packet: &mut dyn FnMut(PacketFormat, &[u8])) -> Result<i32, String> {
+    fn pump(&mut self, on_packet: &mut PacketCallback<'_>) -> Result<i32, String> {
         let packet = self
             .handle
             .recv(Some(&mut self.buffer))
             .map_err(|e| e.to_string())?;
-        on_packet(PacketFormat::RawIp, packet.data.as_ref());
+        on_packet(PacketFormat::RawIp, None, packet.data.as_ref());
         Ok(1)
     }
 }
@@ -97,18 +144,166 @@ impl NpcapSource {
 }
 
 impl PacketSource for NpcapSource {
-    fn pump(&mut self, on_packet: &mut dyn FnMut(PacketFormat, &[u8])) -> Result<i32, String> {
+    fn pump(&mut self, on_packet: &mut PacketCallback<'_>) -> Result<i32, String> {
         let datalink = self.capture.datalink();
         let packet_format = packet_format_for_datalink(datalink);
         self.capture.dispatch_batch(-1, &mut |raw_pkt: &[u8]| {
             let Some(pkt) = normalize_slice_for_datalink(raw_pkt, datalink) else {
                 return;
             };
-            on_packet(packet_format, pkt);
+            on_packet(packet_format, None, pkt);
         })
     }
 }
 
+#[cfg(target_os = "linux")]
+struct AfPacketSource {
+    capture: AfPacketCapture,
+}
+
+#[cfg(target_os = "linux")]
+impl PacketSource for AfPacketSource {
+    fn pump(&mut self, on_packet: &mut PacketCallback<'_>) -> Result<i32, String> {
+        self.capture
+            .dispatch_batch(&mut |pkt: &[u8]| on_packet(PacketFormat::RawIp, None, pkt))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn open_af_packet(device: &str) -> Result<Box<dyn PacketSource>, String> {
+    let capture = AfPacketCapture::new(device)?;
+    Ok(Box::new(AfPacketSource { capture }))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_af_packet(_device: &str) -> Result<Box<dyn PacketSource>, String> {
+    Err("AF_PACKET capture is only available on Linux".to_string())
+}
+
+/// Packets forwarded over TCP by capture agents on other machines. Agents
+/// stamp nothing; capture time is when the frame reached the meter.
+struct RemoteSource {
+    ingest: RemoteIngest,
+}
+
+impl PacketSource for RemoteSource {
+    fn pump(&mut self, on_packet: &mut PacketCallback<'_>) -> Result<i32, String> {
+        self.ingest
+            .dispatch_batch(&mut |format: RemotePacketFormat, pkt: &[u8]| {
+                let packet_format = match format {
+                    RemotePacketFormat::RawIp => PacketFormat::RawIp,
+                    RemotePacketFormat::Ethernet => PacketFormat::Ethernet,
+                };
+                on_packet(packet_format, None, pkt);
+            })
+    }
+
+    fn is_local(&self) -> bool {
+        false
+    }
+}
+
+/// Replays a capture file as fast as the decode pipeline accepts it. Packet
+/// timestamps become capture time: wall time is taken verbatim and monotonic
+/// time is the file-relative offset anchored at the moment the file was opened.
+struct PcapFileSource {
+    reader: PcapReader<BufReader<File>>,
+    clock: FileClock,
+    exhausted: bool,
+    failure: Option<String>,
+}
+
+impl PcapFileSource {
+    fn new(path: &std::path::Path) -> Result<Self, String> {
+        let file =
+            File::open(path).map_err(|e| format!("failed to open {}: {e}", path.display()))?;
+        let reader = PcapReader::new(BufReader::new(file))?;
+        info!(target: "app::capture", "pcap file opened path={}", path.display());
+        Ok(Self {
+            reader,
+            clock: FileClock::new(monotonic_now_ns()),
+            exhausted: false,
+            failure: None,
+        })
+    }
+}
+
+impl PacketSource for PcapFileSource {
+    fn pump(&mut self, on_packet: &mut PacketCallback<'_>) -> Result<i32, String> {
+        let mut delivered = 0;
+        for _ in 0..PCAP_FILE_BATCH {
+            let record = match self.reader.next_record() {
+                Ok(Some(record)) => record,
+                Ok(None) => {
+                    self.exhausted = true;
+                    break;
+                }
+                // The file ends at its first unreadable record: restarting
+                // would replay everything before it again.
+                Err(error) => {
+                    error!(target: "app::capture", "pcap_file_read_failed err={error}");
+                    self.exhausted = true;
+                    self.failure = Some(error);
+                    break;
+                }
+            };
+            delivered += 1;
+            let packet_format = packet_format_for_datalink(record.datalink);
+            let Some(pkt) = normalize_slice_for_datalink(&record.data, record.datalink) else {
+                continue;
+            };
+            let capture_time = self.clock.capture_time(record.timestamp_ns);
+            on_packet(packet_format, Some(capture_time), pkt);
+        }
+        Ok(delivered)
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    fn failure(&self) -> Option<&str> {
+        self.failure.as_deref()
+    }
+
+    fn is_local(&self) -> bool {
+        false
+    }
+}
+
+/// Maps file timestamps onto the runtime's monotonic clock. Recorded time can
+/// step backwards (clock adjustments, merged captures); monotonic capture time
+/// never does, so regressions are clamped to the last emitted value.
+#[derive(Debug, Clone, Copy)]
+struct FileClock {
+    base_mono_ns: u64,
+    first_timestamp_ns: Option<u64>,
+    last_mono_ns: u64,
+}
+
+impl FileClock {
+    fn new(base_mono_ns: u64) -> Self {
+        Self {
+            base_mono_ns,
+            first_timestamp_ns: None,
+            last_mono_ns: base_mono_ns,
+        }
+    }
+
+    fn capture_time(&mut self, timestamp_ns: u64) -> CaptureTime {
+        let first = *self.first_timestamp_ns.get_or_insert(timestamp_ns);
+        let mono_ns = self
+            .base_mono_ns
+            .saturating_add(timestamp_ns.saturating_sub(first))
+            .max(self.last_mono_ns);
+        self.last_mono_ns = mono_ns;
+        CaptureTime {
+            mono_ns,
+            wall_ms: i64::try_from(timestamp_ns / 1_000_000).unwrap_or(i64::MAX),
+        }
+    }
+}
+
 struct SessionState {
     tcp_reassembler: TCPReassembler,
     reassembler: Reassembler,
@@ -135,9 +330,20 @@ impl SessionState {
 
 const CAPTURE_CHANNEL_CAP: usize = 4096;
 
+/// How one run of a capture source ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SourceEnd {
+    /// Cancelled, restarted or failed; live sources are opened again.
+    Interrupted,
+    /// A finite source delivered its last packet.
+    Exhausted,
+    /// A finite source could not be opened or read to its end.
+    Failed(String),
+}
+
 pub struct CaptureWorkerHandle {
     cancellation: CancellationToken,
-    join: Option<JoinHandle<()>>,
+    join: Option<JoinHandle<Option<String>>>,
 }
 
 impl Drop for CaptureWorkerHandle {
@@ -147,7 +353,9 @@ impl Drop for CaptureWorkerHandle {
 }
 
 impl CaptureWorkerHandle {
-    pub fn join(mut self) -> std::thread::Result<()> {
+    /// Stops the capture thread. Yields the error a finite source failed
+    /// with; a file that could not be opened or read is not a clean replay.
+    pub fn join(mut self) -> std::thread::Result<Option<String>> {
         self.cancellation.cancel();
         self.join
             .take()
@@ -174,7 +382,10 @@ impl CaptureRuntime {
     }
 }
 
-pub fn start_capture(method: CaptureMethod) -> CaptureRuntime {
+pub fn start_capture(
+    method: CaptureMethod,
+    recording: Option<SessionRecorderConfig>,
+) -> CaptureRuntime {
     let (packet_sender, packet_receiver) =
         tokio::sync::mpsc::channel::<CaptureEnvelope>(CAPTURE_CHANNEL_CAP);
     let outstanding = Arc::new(AtomicUsize::new(0));
@@ -191,6 +402,18 @@ pub fn start_capture(method: CaptureMethod) -> CaptureRuntime {
             }
             info!(target: "app::capture", "capture_start method=Npcap device={device}");
         }
+        CaptureMethod::PcapFile(path) => {
+            info!(target: "app::capture", "capture_start method=PcapFile path={}", path.display());
+        }
+        CaptureMethod::AfPacket(device) => {
+            info!(target: "app::capture", "capture_start method=AfPacket device={device}");
+        }
+        CaptureMethod::Remote { listen, key } => {
+            if key.is_empty() {
+                error!(target: "app::capture", "capture_start_failed method=Remote err=empty_key");
+            }
+            info!(target: "app::capture", "capture_start method=Remote listen={listen}");
+        }
     }
 
     let thread_cancellation = cancellation.clone();
@@ -209,9 +432,18 @@ pub fn start_capture(method: CaptureMethod) -> CaptureRuntime {
                 thread_cancellation.clone(),
                 thread_outstanding,
             );
+            if let Some(config) = recording {
+                match SessionRecorder::open(config) {
+                    Ok(recorder) => emitter.set_recorder(Some(recorder)),
+                    Err(e) => {
+                        error!(target: "app::capture", "session_recorder_open_failed err={e}");
+                    }
+                }
+            }
 
+            let mut failure = None;
             while !thread_cancellation.is_cancelled() && !emitter.is_stopped() {
-                read_packets(
+                let end = read_packets(
                     &mut emitter,
                     &mut restart_receiver,
                     &thread_cancellation,
@@ -221,6 +453,17 @@ pub fn start_capture(method: CaptureMethod) -> CaptureRuntime {
                 if thread_cancellation.is_cancelled() || emitter.is_stopped() {
                     break;
                 }
+                match end {
+                    SourceEnd::Exhausted => {
+                        info!(target: "app::capture", "capture source exhausted");
+                        break;
+                    }
+                    SourceEnd::Failed(error) => {
+                        failure = Some(error);
+                        break;
+                    }
+                    SourceEnd::Interrupted => {}
+                }
                 if *restart_receiver.borrow() {
                     let _ = restart_sender.send(false);
                     continue;
@@ -230,6 +473,7 @@ pub fn start_capture(method: CaptureMethod) -> CaptureRuntime {
                 wait_with_cancellation(&thread_cancellation, Duration::from_secs(1));
             }
             info!(target: "app::capture", "capture thread exiting");
+            failure
         })
         .expect("failed to spawn packet capture thread");
 
@@ -243,12 +487,14 @@ pub fn start_capture(method: CaptureMethod) -> CaptureRuntime {
     }
 }
 
+/// Runs one capture source until it fails, is cancelled or restarted.
+/// A finite source that ended or cannot be read must not restart.
 fn read_packets(
     emitter: &mut CaptureEmitter,
     restart_receiver: &mut watch::Receiver<bool>,
     cancellation: &CancellationToken,
     method: CaptureMethod,
-) {
+) -> SourceEnd {
     let read_span =
         tracing::info_span!(target: "app::capture", "capture_read_loop", method = ?method);
     let _read_guard = read_span.enter();
@@ -258,7 +504,7 @@ fn read_packets(
             Ok(s) => Box::new(s),
             Err(e) => {
                 error!(target: "app::capture", "capture_source_init_failed method=WinDivert err={e}");
-                return;
+                return SourceEnd::Interrupted;
             }
         },
         CaptureMethod::Npcap(device) => match NpcapSource::new(device) {
@@ -270,13 +516,54 @@ fn read_packets(
                     device,
                     e
                 );
-                return;
+                return SourceEnd::Interrupted;
+            }
+        },
+        CaptureMethod::PcapFile(path) => match PcapFileSource::new(path) {
+            Ok(s) => Box::new(s),
+            Err(e) => {
+                error!(
+                    target: "app::capture",
+                    "capture_source_init_failed method=PcapFile path={} err={}",
+                    path.display(),
+                    e
+                );
+                // A missing or corrupt file will not fix itself on restart.
+                return SourceEnd::Failed(e);
+            }
+        },
+        CaptureMethod::AfPacket(device) => match open_af_packet(device) {
+            Ok(s) => s,
+            Err(e) => {
+                error!(
+                    target: "app::capture",
+                    "capture_source_init_failed method=AfPacket device={} err={}",
+                    device,
+                    e
+                );
+                return SourceEnd::Interrupted;
+            }
+        },
+        CaptureMethod::Remote { listen, key } => match RemoteIngest::bind(listen, key) {
+            Ok(ingest) => Box::new(RemoteSource { ingest }),
+            Err(e) => {
+                error!(
+                    target: "app::capture",
+                    "capture_source_init_failed method=Remote listen={} err={}",
+                    listen,
+                    e
+                );
+                return SourceEnd::Interrupted;
             }
         },
     };
 
     let mut sessions: HashMap<Server, SessionState> = HashMap::new();
-    let mut game_connections = GameConnectionFilter::new();
+    let mut game_connections = if source.is_local() {
+        GameConnectionFilter::new()
+    } else {
+        GameConnectionFilter::without_process_table()
+    };
     let mut cleanup_last_run = Instant::now();
 
     // Shared mutable flag: set to `true` by the dispatch callback when it
@@ -284,7 +571,7 @@ fn read_packets(
     let mut needs_cleanup = false;
 
     loop {
-        let dispatch_result = source.pump(&mut |packet_format, pkt| {
+        let dispatch_result = source.pump(&mut |packet_format, capture_time, pkt| {
             let network_slices = match packet_format {
                 PacketFormat::RawIp => SlicedPacket::from_ip(pkt),
                 PacketFormat::Ethernet => SlicedPacket::from_ethernet(pkt),
@@ -293,31 +580,51 @@ fn read_packets(
             let Ok(network_slices) = network_slices else {
                 return;
             };
-            let Some(Ipv4(ip_packet)) = network_slices.net else {
-                return;
+            let (source_addr, destination_addr): (IpAddr, IpAddr) = match &network_slices.net {
+                Some(Ipv4(ip_packet)) => (
+                    ip_packet.header().source().into(),
+                    ip_packet.header().destination().into(),
+                ),
+                Some(Ipv6(ip_packet)) => (
+                    ip_packet.header().source().into(),
+                    ip_packet.header().destination().into(),
+                ),
+                _ => return,
             };
             let Some(Tcp(tcp_packet)) = network_slices.transport else {
                 return;
             };
 
             let curr_server = Server::new(
-                ip_packet.header().source(),
+                source_addr,
                 tcp_packet.to_header().source_port,
-                ip_packet.header().destination(),
+                destination_addr,
                 tcp_packet.to_header().destination_port,
             );
-            let verdict = game_connections.classify(curr_server);
-            let session_known = sessions.contains_key(&curr_server);
-            if !matches!(verdict, Verdict::Game) && !session_known {
-                return;
-            }
+            let aligned_start = if sessions.contains_key(&curr_server) {
+                None
+            } else {
+                match game_connections.admit(
+                    curr_server,
+                    tcp_packet.sequence_number(),
+                    tcp_packet.payload(),
+                ) {
+                    Admission::Reject => return,
+                    Admission::Accept => None,
+                    Admission::AcceptAligned(start) => Some(start),
+                }
+            };
 
+            emitter.set_capture_time(capture_time);
             let now = Instant::now();
             let stream_id = stable_stream_id(curr_server);
             let session = sessions
                 .entry(curr_server)
                 .or_insert_with(|| SessionState::new(now, stream_id));
             session.last_seen = now;
+            if let Some(start) = aligned_start {
+                prime_session(session, emitter, start);
+            }
 
             process_tcp_packet(
                 curr_server,
@@ -344,6 +651,13 @@ fn read_packets(
             }
         }
 
+        if source.is_exhausted() {
+            return match source.failure() {
+                Some(error) => SourceEnd::Failed(error.to_string()),
+                None => SourceEnd::Exhausted,
+            };
+        }
+
         if needs_cleanup {
             needs_cleanup = false;
             let before = sessions.len();
@@ -359,6 +673,7 @@ fn read_packets(
             break;
         }
     }
+    SourceEnd::Interrupted
 }
 
 fn process_tcp_packet(
@@ -411,24 +726,24 @@ fn process_tcp_packet(
         return;
     }
 
-    if let Some(expected) = session.tcp_reassembler.next_sequence() {
-        if tcp_sequence_before(sequence_number, expected) {
-            let backwards = expected.wrapping_sub(sequence_number);
-            if backwards > MAX_BACKTRACK_BYTES {
-                warn!(
-                    target: "app::capture",
-                    "Sequence regression detected for {curr_server}: expected {expected}, \
-                    got {sequence_number} (backwards {backwards} bytes). Resetting stream"
-                );
-                reset_stream(
-                    &mut session.tcp_reassembler,
-                    &mut session.reassembler,
-                    Some(sequence_number),
-                );
-                session.begin_new_epoch();
-                if !emitter.emit_reassembly_reset(session.stream_id, session.stream_epoch) {
-                    return;
-                }
+    if let Some(expected) = session.tcp_reassembler.next_sequence()
+        && tcp_sequence_before(sequence_number, expected)
+    {
+        let backwards = expected.wrapping_sub(sequence_number);
+        if backwards > MAX_BACKTRACK_BYTES {
+            warn!(
+                target: "app::capture",
+                "Sequence regression detected for {curr_server}: expected {expected}, \
+                got {sequence_number} (backwards {backwards} bytes). Resetting stream"
+            );
+            reset_stream(
+                &mut session.tcp_reassembler,
+                &mut session.reassembler,
+                Some(sequence_number),
+            );
+            session.begin_new_epoch();
+            if !emitter.emit_reassembly_reset(session.stream_id, session.stream_epoch) {
+                return;
             }
         }
     }
@@ -475,9 +790,35 @@ fn process_tcp_packet(
     }
 }
 
-fn stable_stream_id(server: Server) -> u64 {
+/// Starts a session recognised by payload signature at the frame boundary the
+/// detector found, replaying the bytes buffered while the flow was undecided.
+/// The packet that completed recognition is part of that buffer, so feeding it
+/// to `process_tcp_packet` afterwards is a harmless duplicate.
+fn prime_session(session: &mut SessionState, emitter: &mut CaptureEmitter, start: AlignedStart) {
+    reset_stream(
+        &mut session.tcp_reassembler,
+        &mut session.reassembler,
+        Some(start.sequence),
+    );
+    if let TcpInsertResult::Contiguous(buffer) = session
+        .tcp_reassembler
+        .insert_segment(start.sequence, &start.data)
+    {
+        session.reassembler.feed_bytes(bytes::Bytes::from(buffer));
+    }
+    while let Some(packet) = session.reassembler.try_next() {
+        process_packet(&packet, emitter, session.stream_id, session.stream_epoch);
+        if emitter.is_stopped() {
+            return;
+        }
+    }
+}
+
+/// Keyed by the connection rather than the direction, so uplink and downlink
+/// share one live session and one decoder.
+pub(crate) fn stable_stream_id(server: Server) -> u64 {
     let mut hasher = DefaultHasher::new();
-    server.hash(&mut hasher);
+    server.connection().hash(&mut hasher);
     hasher.finish()
 }
 
@@ -504,7 +845,7 @@ fn reset_stream(
 fn packet_format_for_datalink(datalink: i32) -> PacketFormat {
     match datalink {
         DLT_EN10MB => PacketFormat::Ethernet,
-        DLT_RAW | DLT_NULL | DLT_LOOP => PacketFormat::RawIp,
+        DLT_RAW | DLT_NULL | DLT_LOOP | DLT_LINUX_SLL => PacketFormat::RawIp,
         other => {
             log_unsupported_datalink(other);
             PacketFormat::Unsupported
@@ -522,14 +863,23 @@ fn normalize_slice_for_datalink(data: &[u8], datalink: i32) -> Option<&[u8]> {
             }
             let family = u32::from_ne_bytes([data[0], data[1], data[2], data[3]]);
             match family {
-                2 => Some(&data[4..]),
-                23 | 24 => None,
+                // AF_INET, then AF_INET6 as numbered by Windows, BSD,
+                // FreeBSD and macOS.
+                2 | 23 | 24 | 28 | 30 => Some(&data[4..]),
                 other => {
                     log_unsupported_loopback_family(other, datalink);
                     None
                 }
             }
         }
+        DLT_LINUX_SLL => {
+            // Linux "any" device cooked header; the protocol type is the
+            // trailing big-endian u16 of the 16-byte header.
+            let header = data.get(..LINUX_SLL_HEADER_LEN)?;
+            let protocol = u16::from_be_bytes([header[14], header[15]]);
+            matches!(protocol, ETHERTYPE_IPV4 | ETHERTYPE_IPV6)
+                .then(|| &data[LINUX_SLL_HEADER_LEN..])
+        }
         other => {
             log_unsupported_datalink(other);
             None
@@ -556,3 +906,83 @@ fn log_unsupported_datalink(datalink: i32) {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_clock_anchors_offsets_and_never_regresses() {
+        let mut clock = FileClock::new(1_000);
+        let first = clock.capture_time(1_700_000_000_000_000_000);
+        assert_eq!(first.mono_ns, 1_000);
+        assert_eq!(first.wall_ms, 1_700_000_000_000);
+
+        let later = clock.capture_time(1_700_000_000_250_000_000);
+        assert_eq!(later.mono_ns, 250_001_000);
+        assert_eq!(later.wall_ms, 1_700_000_000_250);
+
+        let regressed = clock.capture_time(1_700_000_000_100_000_000);
+        assert_eq!(regressed.mono_ns, 250_001_000);
+        assert_eq!(regressed.wall_ms, 1_700_000_000_100);
+    }
+
+    #[test]
+    fn truncated_pcap_tail_ends_the_file_after_the_readable_records() {
+        let mut bytes = Vec::new();
+        for field in [0xa1b2_c3d4u32, 0x0004_0002, 0, 0, 65_535, 101] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        for (sec, data) in [(1u32, &[0x45u8, 0, 0, 20][..]), (2, &[0x45, 0, 0, 20])] {
+            for field in [sec, 0, data.len() as u32, data.len() as u32] {
+                bytes.extend_from_slice(&field.to_le_bytes());
+            }
+            bytes.extend_from_slice(data);
+        }
+        bytes.truncate(bytes.len() - 2);
+        let file = tempfile::NamedTempFile::new().expect("temp file");
+        std::fs::write(file.path(), &bytes).expect("write capture");
+
+        let mut source = PcapFileSource::new(file.path()).expect("valid header");
+        let mut packets = 0;
+        let mut on_packet = |_: PacketFormat, _: Option<CaptureTime>, _: &[u8]| packets += 1;
+        assert_eq!(source.pump(&mut on_packet), Ok(1));
+        assert!(source.is_exhausted());
+        assert!(source.failure().is_some());
+        assert_eq!(packets, 1);
+    }
+
+    #[test]
+    fn linux_cooked_header_is_stripped_for_ipv4() {
+        let mut frame = vec![0u8; LINUX_SLL_HEADER_LEN];
+        frame[14..16].copy_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+        frame.extend_from_slice(&[0x45, 0x00]);
+        assert_eq!(
+            normalize_slice_for_datalink(&frame, DLT_LINUX_SLL),
+            Some(&[0x45, 0x00][..])
+        );
+
+        frame[14..16].copy_from_slice(&0x0806u16.to_be_bytes());
+        assert_eq!(normalize_slice_for_datalink(&frame, DLT_LINUX_SLL), None);
+    }
+
+    #[test]
+    fn loopback_header_is_stripped_for_both_ip_families() {
+        for family in [2u32, 24, 30] {
+            let mut frame = family.to_ne_bytes().to_vec();
+            frame.extend_from_slice(&[0x60, 0x00]);
+            assert_eq!(
+                normalize_slice_for_datalink(&frame, DLT_NULL),
+                Some(&[0x60, 0x00][..])
+            );
+        }
+
+        let mut frame = vec![0u8; LINUX_SLL_HEADER_LEN];
+        frame[14..16].copy_from_slice(&ETHERTYPE_IPV6.to_be_bytes());
+        frame.extend_from_slice(&[0x60, 0x00]);
+        assert_eq!(
+            normalize_slice_for_datalink(&frame, DLT_LINUX_SLL),
+            Some(&[0x60, 0x00][..])
+        );
+    }
+}
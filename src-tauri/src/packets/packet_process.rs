@@ -3,11 +3,12 @@ use crate::live::runtime::events::{
 };
 use crate::packets::opcodes::{CaptureEvent, FragmentType};
 use crate::packets::parser;
+use crate::packets::session_recorder::SessionRecorder;
 use bytes::Bytes;
-use log::debug;
+use log::{debug, warn};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 
@@ -16,17 +17,30 @@ use tokio_util::sync::CancellationToken;
 pub const SYNTHETIC_STREAM_GAP_OPCODE: u32 = u32::MAX - 1;
 pub const SYNTHETIC_REASSEMBLY_RESET_OPCODE: u32 = u32::MAX - 2;
 pub const SYNTHETIC_DECODE_ISSUE_OPCODE: u32 = u32::MAX - 3;
+pub const SYNTHETIC_BACKPRESSURE_STALL_OPCODE: u32 = u32::MAX - 4;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum CaptureDecodeIssueCategory {
     Malformed = 0,
     Truncated = 1,
+    Decompression = 2,
 }
 
 const BACKPRESSURE_POLL_INTERVAL: Duration = Duration::from_millis(1);
+/// Queue waits shorter than this are ordinary scheduling jitter.
+const BACKPRESSURE_STALL_REPORT: Duration = Duration::from_millis(20);
 pub(crate) const CAPTURE_PIPELINE_FENCE: usize = 1 << (usize::BITS - 1);
 
+/// Capture clocks supplied by the packet source instead of read at emit time.
+/// Offline sources (capture files) use it so recorded packet timestamps, not
+/// replay speed, drive event time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureTime {
+    pub mono_ns: u64,
+    pub wall_ms: i64,
+}
+
 /// Assigns capture-time ordering and clocks before an event can wait in the
 /// decode queue. A single emitter is owned by the capture thread, so sequence
 /// allocation does not require atomics and exactly matches enqueue order.
@@ -35,6 +49,8 @@ pub struct CaptureEmitter {
     cancellation: CancellationToken,
     outstanding: Arc<AtomicUsize>,
     next_sequence: u64,
+    capture_time: Option<CaptureTime>,
+    recorder: Option<SessionRecorder>,
 }
 
 impl CaptureEmitter {
@@ -48,13 +64,27 @@ impl CaptureEmitter {
             cancellation,
             outstanding,
             next_sequence: 1,
+            capture_time: None,
+            recorder: None,
         }
     }
 
+    /// Tees every accepted envelope into `recorder`. A write failure disables
+    /// recording for the rest of the capture rather than stalling it.
+    pub fn set_recorder(&mut self, recorder: Option<SessionRecorder>) {
+        self.recorder = recorder;
+    }
+
     pub fn is_stopped(&self) -> bool {
         self.cancellation.is_cancelled() || self.sender.is_closed()
     }
 
+    /// Pins the clocks stamped on subsequent envelopes. `None` restores the
+    /// live clocks.
+    pub fn set_capture_time(&mut self, capture_time: Option<CaptureTime>) {
+        self.capture_time = capture_time;
+    }
+
     /// Emits a decoded frame with bounded backpressure. Queue saturation never
     /// silently drops a packet; cancellation or receiver shutdown is the only
     /// way an accepted capture loop stops waiting.
@@ -142,6 +172,28 @@ impl CaptureEmitter {
         )
     }
 
+    /// Reports time the capture thread spent blocked on a full decode queue,
+    /// during which the kernel may have dropped packets.
+    fn emit_backpressure_stall(
+        &mut self,
+        stream_id: u64,
+        stream_epoch: u64,
+        stalled: Duration,
+    ) -> bool {
+        let stalled_ms = u32::try_from(stalled.as_millis()).unwrap_or(u32::MAX);
+        self.emit_envelope(
+            stream_id,
+            stream_epoch,
+            PacketDirection::ServerToClient,
+            PacketKey {
+                opcode: SYNTHETIC_BACKPRESSURE_STALL_OPCODE,
+                service_id: None,
+                method_id: None,
+            },
+            Bytes::copy_from_slice(&stalled_ms.to_be_bytes()),
+        )
+    }
+
     fn emit_envelope(
         &mut self,
         stream_id: u64,
@@ -155,8 +207,10 @@ impl CaptureEmitter {
         }
         let sequence = self.next_sequence;
         self.next_sequence = self.next_sequence.saturating_add(1);
-        let captured_mono_ns = monotonic_now_ns();
-        let captured_wall_ms = wall_now_ms();
+        let (captured_mono_ns, captured_wall_ms) = match self.capture_time {
+            Some(time) => (time.mono_ns, time.wall_ms),
+            None => (monotonic_now_ns(), wall_now_ms()),
+        };
         let mut envelope = CaptureEnvelope {
             capture_sequence: sequence,
             stream_id,
@@ -167,16 +221,25 @@ impl CaptureEmitter {
             key,
             payload,
         };
+        if let Some(recorder) = self.recorder.as_mut()
+            && let Err(error) = recorder.record(&envelope)
+        {
+            warn!(target: "app::capture", "session recording disabled: {error}");
+            self.recorder = None;
+        }
 
+        let is_stall_report = envelope.key.opcode == SYNTHETIC_BACKPRESSURE_STALL_OPCODE;
+        let mut stalled_since = None;
         loop {
             if self.cancellation.is_cancelled() {
                 self.outstanding.fetch_sub(1, Ordering::Release);
                 return false;
             }
             match self.sender.try_send(envelope) {
-                Ok(()) => return true,
+                Ok(()) => break,
                 Err(mpsc::error::TrySendError::Full(returned)) => {
                     envelope = returned;
+                    stalled_since.get_or_insert_with(Instant::now);
                     std::thread::sleep(BACKPRESSURE_POLL_INTERVAL);
                 }
                 Err(mpsc::error::TrySendError::Closed(_)) => {
@@ -185,6 +248,12 @@ impl CaptureEmitter {
                 }
             }
         }
+
+        let stalled = stalled_since.map_or(Duration::ZERO, |since| since.elapsed());
+        if stalled >= BACKPRESSURE_STALL_REPORT && !is_stall_report {
+            return self.emit_backpressure_stall(stream_id, stream_epoch, stalled);
+        }
+        true
     }
 }
 
@@ -244,7 +313,7 @@ fn process_nested_frame(
                     stream_id,
                     stream_epoch,
                     None,
-                    CaptureDecodeIssueCategory::Malformed,
+                    CaptureDecodeIssueCategory::Decompression,
                 );
             }
         }
@@ -419,4 +488,51 @@ mod tests {
         assert!(!emitter.emit_reassembly_reset(7, 1));
         assert_eq!(outstanding.load(Ordering::Acquire), 1);
     }
+
+    #[test]
+    fn backpressure_stall_is_reported_after_the_blocked_event() {
+        let cancellation = CancellationToken::new();
+        let (sender, mut receiver) = mpsc::channel(1);
+        let outstanding = Arc::new(AtomicUsize::new(0));
+        let mut emitter = CaptureEmitter::new(sender, cancellation, outstanding);
+        assert!(emitter.emit_reassembly_reset(7, 1));
+
+        let consumer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(60));
+            (0..3)
+                .map(|_| receiver.blocking_recv().expect("queued envelope"))
+                .collect::<Vec<_>>()
+        });
+        assert!(emitter.emit_reassembly_reset(7, 1));
+        let received = consumer.join().expect("consumer thread");
+
+        assert_eq!(received[1].key.opcode, SYNTHETIC_REASSEMBLY_RESET_OPCODE);
+        let stall = &received[2];
+        assert_eq!(stall.key.opcode, SYNTHETIC_BACKPRESSURE_STALL_OPCODE);
+        assert_eq!(stall.stream_id, 7);
+        let stalled_ms = u32::from_be_bytes(stall.payload[..4].try_into().expect("u32 payload"));
+        assert!(stalled_ms >= 20, "stalled_ms={stalled_ms}");
+    }
+
+    #[test]
+    fn pinned_capture_time_overrides_live_clocks() {
+        let cancellation = CancellationToken::new();
+        let (sender, mut receiver) = mpsc::channel(2);
+        let outstanding = Arc::new(AtomicUsize::new(0));
+        let mut emitter = CaptureEmitter::new(sender, cancellation, outstanding);
+
+        emitter.set_capture_time(Some(CaptureTime {
+            mono_ns: 5_000_000,
+            wall_ms: 1_700_000_000_000,
+        }));
+        assert!(emitter.emit_reassembly_reset(7, 1));
+        emitter.set_capture_time(None);
+        assert!(emitter.emit_reassembly_reset(7, 1));
+
+        let pinned = receiver.blocking_recv().expect("pinned envelope");
+        assert_eq!(pinned.captured_mono_ns, 5_000_000);
+        assert_eq!(pinned.captured_wall_ms, 1_700_000_000_000);
+        let live = receiver.blocking_recv().expect("live envelope");
+        assert_ne!(live.captured_wall_ms, 1_700_000_000_000);
+    }
 }
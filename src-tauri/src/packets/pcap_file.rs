@@ -0,0 +1,527 @@
+//! Streaming reader for offline classic pcap and pcapng captures.
+//!
+//! Only the pieces needed to feed recorded traffic back into the capture
+//! pipeline are understood: per-interface link types, timestamp resolution
+//! and packet blocks. Everything else (statistics, name resolution, custom
+//! blocks) is skipped by length.
+
+use std::io::{ErrorKind, Read};
+
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d_0d0a;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const PCAPNG_OBSOLETE_PACKET: u32 = 0x0000_0002;
+const PCAPNG_SIMPLE_PACKET: u32 = 0x0000_0003;
+const PCAPNG_ENHANCED_PACKET: u32 = 0x0000_0006;
+
+const PCAPNG_OPT_END: u16 = 0;
+const PCAPNG_OPT_IF_TSRESOL: u16 = 9;
+
+/// Upper bound for a single record/block body. Anything larger is treated as
+/// a corrupt file rather than an allocation request.
+const MAX_RECORD_BYTES: usize = 64 * 1024 * 1024;
+
+/// File link types that differ from the libpcap `DLT_*` values used by live
+/// capture. They are translated so the capture loop only sees `DLT_*`.
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+const DLT_RAW: i32 = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PcapRecord {
+    /// libpcap datalink (`DLT_*`) of the interface that captured the packet.
+    pub datalink: i32,
+    /// Capture timestamp in nanoseconds since the Unix epoch.
+    pub timestamp_ns: u64,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    fn u16(self, bytes: [u8; 2]) -> u16 {
+        match self {
+            Self::Little => u16::from_le_bytes(bytes),
+            Self::Big => u16::from_be_bytes(bytes),
+        }
+    }
+
+    fn u32(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            Self::Little => u32::from_le_bytes(bytes),
+            Self::Big => u32::from_be_bytes(bytes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PcapngInterface {
+    datalink: i32,
+    /// Timestamp units per second (`if_tsresol`, default 10^6).
+    units_per_second: u64,
+}
+
+enum Format {
+    Pcap {
+        order: ByteOrder,
+        datalink: i32,
+        nanos: bool,
+    },
+    Pcapng {
+        order: ByteOrder,
+        interfaces: Vec<PcapngInterface>,
+    },
+}
+
+pub struct PcapReader<R: Read> {
+    input: R,
+    format: Format,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Reads the file header and detects classic pcap vs pcapng.
+    pub fn new(mut input: R) -> Result<Self, String> {
+        let mut magic = [0u8; 4];
+        input
+            .read_exact(&mut magic)
+            .map_err(|e| format!("failed to read capture file magic: {e}"))?;
+
+        if u32::from_le_bytes(magic) == PCAPNG_SECTION_HEADER {
+            let order = read_section_header_body(&mut input)?;
+            return Ok(Self {
+                input,
+                format: Format::Pcapng {
+                    order,
+                    interfaces: Vec::new(),
+                },
+            });
+        }
+
+        let (order, nanos) = match (u32::from_le_bytes(magic), u32::from_be_bytes(magic)) {
+            (PCAP_MAGIC_MICROS, _) => (ByteOrder::Little, false),
+            (PCAP_MAGIC_NANOS, _) => (ByteOrder::Little, true),
+            (_, PCAP_MAGIC_MICROS) => (ByteOrder::Big, false),
+            (_, PCAP_MAGIC_NANOS) => (ByteOrder::Big, true),
+            (other, _) => return Err(format!("unrecognized capture file magic {other:#010x}")),
+        };
+
+        // version(2+2) thiszone(4) sigfigs(4) snaplen(4) network(4)
+        let mut header = [0u8; 20];
+        input
+            .read_exact(&mut header)
+            .map_err(|e| format!("failed to read pcap header: {e}"))?;
+        let network = order.u32(header[16..20].try_into().expect("4-byte slice"));
+
+        Ok(Self {
+            input,
+            format: Format::Pcap {
+                order,
+                datalink: datalink_for_linktype(network & 0x0fff_ffff),
+                nanos,
+            },
+        })
+    }
+
+    /// Returns the next packet record, or `None` at a clean end of file.
+    pub fn next_record(&mut self) -> Result<Option<PcapRecord>, String> {
+        match &mut self.format {
+            Format::Pcap {
+                order,
+                datalink,
+                nanos,
+            } => read_pcap_record(&mut self.input, *order, *datalink, *nanos),
+            Format::Pcapng { order, interfaces } => {
+                read_pcapng_record(&mut self.input, order, interfaces)
+            }
+        }
+    }
+}
+
+fn read_pcap_record<R: Read>(
+    input: &mut R,
+    order: ByteOrder,
+    datalink: i32,
+    nanos: bool,
+) -> Result<Option<PcapRecord>, String> {
+    let mut header = [0u8; 16];
+    if !read_exact_or_eof(input, &mut header)? {
+        return Ok(None);
+    }
+    let ts_sec = u64::from(order.u32(header[0..4].try_into().expect("4-byte slice")));
+    let ts_frac = u64::from(order.u32(header[4..8].try_into().expect("4-byte slice")));
+    let caplen = order.u32(header[8..12].try_into().expect("4-byte slice")) as usize;
+    if caplen > MAX_RECORD_BYTES {
+        return Err(format!("pcap record length {caplen} exceeds limit"));
+    }
+
+    let mut data = vec![0u8; caplen];
+    input
+        .read_exact(&mut data)
+        .map_err(|e| format!("truncated pcap record: {e}"))?;
+
+    let frac_ns = if nanos { ts_frac } else { ts_frac * 1_000 };
+    Ok(Some(PcapRecord {
+        datalink,
+        timestamp_ns: ts_sec.saturating_mul(1_000_000_000).saturating_add(frac_ns),
+        data,
+    }))
+}
+
+fn read_pcapng_record<R: Read>(
+    input: &mut R,
+    order: &mut ByteOrder,
+    interfaces: &mut Vec<PcapngInterface>,
+) -> Result<Option<PcapRecord>, String> {
+    loop {
+        let mut block_type = [0u8; 4];
+        if !read_exact_or_eof(input, &mut block_type)? {
+            return Ok(None);
+        }
+
+        // A new section may switch byte order and always resets interfaces.
+        if u32::from_le_bytes(block_type) == PCAPNG_SECTION_HEADER {
+            *order = read_section_header_body(input)?;
+            interfaces.clear();
+            continue;
+        }
+
+        let block_type = order.u32(block_type);
+        let mut length = [0u8; 4];
+        input
+            .read_exact(&mut length)
+            .map_err(|e| format!("truncated pcapng block header: {e}"))?;
+        let total_length = order.u32(length) as usize;
+        if total_length < 12 || !total_length.is_multiple_of(4) || total_length > MAX_RECORD_BYTES {
+            return Err(format!("invalid pcapng block length {total_length}"));
+        }
+
+        // Body plus the trailing copy of the block length.
+        let mut body = vec![0u8; total_length - 8];
+        input
+            .read_exact(&mut body)
+            .map_err(|e| format!("truncated pcapng block: {e}"))?;
+        body.truncate(total_length - 12);
+
+        match block_type {
+            PCAPNG_INTERFACE_DESCRIPTION => interfaces.push(parse_interface(&body, *order)?),
+            PCAPNG_ENHANCED_PACKET => {
+                return parse_enhanced_packet(&body, *order, interfaces).map(Some);
+            }
+            PCAPNG_OBSOLETE_PACKET => {
+                return parse_obsolete_packet(&body, *order, interfaces).map(Some);
+            }
+            PCAPNG_SIMPLE_PACKET => {
+                return parse_simple_packet(&body, *order, interfaces).map(Some);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Reads the remainder of a section header block after its type field and
+/// returns the byte order declared by the section.
+fn read_section_header_body<R: Read>(input: &mut R) -> Result<ByteOrder, String> {
+    let mut prefix = [0u8; 8];
+    input
+        .read_exact(&mut prefix)
+        .map_err(|e| format!("truncated pcapng section header: {e}"))?;
+    let bom: [u8; 4] = prefix[4..8].try_into().expect("4-byte slice");
+    let order = if u32::from_le_bytes(bom) == PCAPNG_BYTE_ORDER_MAGIC {
+        ByteOrder::Little
+    } else if u32::from_be_bytes(bom) == PCAPNG_BYTE_ORDER_MAGIC {
+        ByteOrder::Big
+    } else {
+        return Err("pcapng section header has an invalid byte-order magic".to_string());
+    };
+
+    let total_length = order.u32(prefix[0..4].try_into().expect("4-byte slice")) as usize;
+    if total_length < 28 || !total_length.is_multiple_of(4) || total_length > MAX_RECORD_BYTES {
+        return Err(format!(
+            "invalid pcapng section header length {total_length}"
+        ));
+    }
+    let mut rest = vec![0u8; total_length - 12];
+    input
+        .read_exact(&mut rest)
+        .map_err(|e| format!("truncated pcapng section header: {e}"))?;
+    Ok(order)
+}
+
+fn parse_interface(body: &[u8], order: ByteOrder) -> Result<PcapngInterface, String> {
+    if body.len() < 8 {
+        return Err("pcapng interface block too short".to_string());
+    }
+    let linktype = u32::from(order.u16(body[0..2].try_into().expect("2-byte slice")));
+    let mut units_per_second = 1_000_000;
+
+    let mut options = &body[8..];
+    while options.len() >= 4 {
+        let code = order.u16(options[0..2].try_into().expect("2-byte slice"));
+        let len = usize::from(order.u16(options[2..4].try_into().expect("2-byte slice")));
+        if code == PCAPNG_OPT_END {
+            break;
+        }
+        let padded = len.div_ceil(4) * 4;
+        let Some(value) = options.get(4..4 + len) else {
+            break;
+        };
+        if code == PCAPNG_OPT_IF_TSRESOL && len >= 1 {
+            units_per_second = tsresol_units(value[0]);
+        }
+        options = options.get(4 + padded..).unwrap_or_default();
+    }
+
+    Ok(PcapngInterface {
+        datalink: datalink_for_linktype(linktype),
+        units_per_second,
+    })
+}
+
+fn parse_enhanced_packet(
+    body: &[u8],
+    order: ByteOrder,
+    interfaces: &[PcapngInterface],
+) -> Result<PcapRecord, String> {
+    if body.len() < 20 {
+        return Err("pcapng enhanced packet block too short".to_string());
+    }
+    let interface_id = order.u32(body[0..4].try_into().expect("4-byte slice")) as usize;
+    let ts_high = u64::from(order.u32(body[4..8].try_into().expect("4-byte slice")));
+    let ts_low = u64::from(order.u32(body[8..12].try_into().expect("4-byte slice")));
+    let caplen = order.u32(body[12..16].try_into().expect("4-byte slice")) as usize;
+    let interface = interfaces
+        .get(interface_id)
+        .ok_or_else(|| format!("pcapng packet references unknown interface {interface_id}"))?;
+    let data = body
+        .get(20..20 + caplen)
+        .ok_or_else(|| "pcapng enhanced packet data truncated".to_string())?;
+
+    Ok(PcapRecord {
+        datalink: interface.datalink,
+        timestamp_ns: units_to_ns((ts_high << 32) | ts_low, interface.units_per_second),
+        data: data.to_vec(),
+    })
+}
+
+fn parse_obsolete_packet(
+    body: &[u8],
+    order: ByteOrder,
+    interfaces: &[PcapngInterface],
+) -> Result<PcapRecord, String> {
+    if body.len() < 20 {
+        return Err("pcapng packet block too short".to_string());
+    }
+    let interface_id = usize::from(order.u16(body[0..2].try_into().expect("2-byte slice")));
+    let ts_high = u64::from(order.u32(body[4..8].try_into().expect("4-byte slice")));
+    let ts_low = u64::from(order.u32(body[8..12].try_into().expect("4-byte slice")));
+    let caplen = order.u32(body[12..16].try_into().expect("4-byte slice")) as usize;
+    let interface = interfaces
+        .get(interface_id)
+        .ok_or_else(|| format!("pcapng packet references unknown interface {interface_id}"))?;
+    let data = body
+        .get(20..20 + caplen)
+        .ok_or_else(|| "pcapng packet data truncated".to_string())?;
+
+    Ok(PcapRecord {
+        datalink: interface.datalink,
+        timestamp_ns: units_to_ns((ts_high << 32) | ts_low, interface.units_per_second),
+        data: data.to_vec(),
+    })
+}
+
+/// Simple packet blocks carry no timestamp; they inherit a zero timestamp and
+/// are only accepted so that files mixing block kinds still replay.
+fn parse_simple_packet(
+    body: &[u8],
+    order: ByteOrder,
+    interfaces: &[PcapngInterface],
+) -> Result<PcapRecord, String> {
+    if body.len() < 4 {
+        return Err("pcapng simple packet block too short".to_string());
+    }
+    let original_len = order.u32(body[0..4].try_into().expect("4-byte slice")) as usize;
+    let interface = interfaces
+        .first()
+        .ok_or_else(|| "pcapng simple packet without an interface".to_string())?;
+    let available = body.len() - 4;
+    Ok(PcapRecord {
+        datalink: interface.datalink,
+        timestamp_ns: 0,
+        data: body[4..4 + original_len.min(available)].to_vec(),
+    })
+}
+
+fn tsresol_units(tsresol: u8) -> u64 {
+    let exponent = u32::from(tsresol & 0x7f);
+    if tsresol & 0x80 == 0 {
+        10u64.checked_pow(exponent).unwrap_or(u64::MAX)
+    } else {
+        2u64.checked_pow(exponent).unwrap_or(u64::MAX)
+    }
+}
+
+fn units_to_ns(units: u64, units_per_second: u64) -> u64 {
+    let ns = u128::from(units) * 1_000_000_000 / u128::from(units_per_second.max(1));
+    u64::try_from(ns).unwrap_or(u64::MAX)
+}
+
+fn datalink_for_linktype(linktype: u32) -> i32 {
+    match linktype {
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => DLT_RAW,
+        other => i32::try_from(other).unwrap_or(-1),
+    }
+}
+
+/// Fills `buf` completely, returning `false` when the reader is already at a
+/// clean end of file. A partial read is reported as a truncated file.
+fn read_exact_or_eof<R: Read>(input: &mut R, buf: &mut [u8]) -> Result<bool, String> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match input.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err("capture file ends mid-record".to_string()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(format!("failed to read capture file: {e}")),
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn classic_pcap(magic: u32, linktype: u32, records: &[(u32, u32, &[u8])]) -> Vec<u8> {
+        let mut file = Vec::new();
+        file.extend_from_slice(&magic.to_le_bytes());
+        file.extend_from_slice(&2u16.to_le_bytes());
+        file.extend_from_slice(&4u16.to_le_bytes());
+        file.extend_from_slice(&0i32.to_le_bytes());
+        file.extend_from_slice(&0u32.to_le_bytes());
+        file.extend_from_slice(&65_535u32.to_le_bytes());
+        file.extend_from_slice(&linktype.to_le_bytes());
+        for (sec, frac, data) in records {
+            file.extend_from_slice(&sec.to_le_bytes());
+            file.extend_from_slice(&frac.to_le_bytes());
+            file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            file.extend_from_slice(data);
+        }
+        file
+    }
+
+    fn pcapng_block(block_type: u32, body: &[u8]) -> Vec<u8> {
+        let padded = body.len().div_ceil(4) * 4;
+        let total = (12 + padded) as u32;
+        let mut block = Vec::new();
+        block.extend_from_slice(&block_type.to_le_bytes());
+        block.extend_from_slice(&total.to_le_bytes());
+        block.extend_from_slice(body);
+        block.resize(8 + padded, 0);
+        block.extend_from_slice(&total.to_le_bytes());
+        block
+    }
+
+    fn pcapng_file(tsresol: Option<u8>, packets: &[(u64, &[u8])]) -> Vec<u8> {
+        let mut shb = Vec::new();
+        shb.extend_from_slice(&PCAPNG_BYTE_ORDER_MAGIC.to_le_bytes());
+        shb.extend_from_slice(&1u16.to_le_bytes());
+        shb.extend_from_slice(&0u16.to_le_bytes());
+        shb.extend_from_slice(&(-1i64).to_le_bytes());
+        let mut file = pcapng_block(PCAPNG_SECTION_HEADER, &shb);
+
+        let mut idb = Vec::new();
+        idb.extend_from_slice(&1u16.to_le_bytes());
+        idb.extend_from_slice(&0u16.to_le_bytes());
+        idb.extend_from_slice(&0u32.to_le_bytes());
+        if let Some(tsresol) = tsresol {
+            idb.extend_from_slice(&PCAPNG_OPT_IF_TSRESOL.to_le_bytes());
+            idb.extend_from_slice(&1u16.to_le_bytes());
+            idb.extend_from_slice(&[tsresol, 0, 0, 0]);
+            idb.extend_from_slice(&[0, 0, 0, 0]);
+        }
+        file.extend(pcapng_block(PCAPNG_INTERFACE_DESCRIPTION, &idb));
+        // Unknown blocks (e.g. interface statistics) must be skipped.
+        file.extend(pcapng_block(0x0000_0005, &[0u8; 8]));
+
+        for (timestamp, data) in packets {
+            let mut epb = Vec::new();
+            epb.extend_from_slice(&0u32.to_le_bytes());
+            epb.extend_from_slice(&((timestamp >> 32) as u32).to_le_bytes());
+            epb.extend_from_slice(&(*timestamp as u32).to_le_bytes());
+            epb.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            epb.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            epb.extend_from_slice(data);
+            file.extend(pcapng_block(PCAPNG_ENHANCED_PACKET, &epb));
+        }
+        file
+    }
+
+    fn read_all(bytes: Vec<u8>) -> Vec<PcapRecord> {
+        let mut reader = PcapReader::new(Cursor::new(bytes)).expect("valid header");
+        let mut records = Vec::new();
+        while let Some(record) = reader.next_record().expect("valid record") {
+            records.push(record);
+        }
+        records
+    }
+
+    #[test]
+    fn reads_classic_microsecond_pcap() {
+        let records = read_all(classic_pcap(
+            PCAP_MAGIC_MICROS,
+            1,
+            &[(10, 250_000, b"abc"), (11, 0, b"defg")],
+        ));
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].datalink, 1);
+        assert_eq!(records[0].timestamp_ns, 10_250_000_000);
+        assert_eq!(records[0].data, b"abc");
+        assert_eq!(records[1].timestamp_ns, 11_000_000_000);
+    }
+
+    #[test]
+    fn reads_classic_nanosecond_pcap_and_maps_raw_linktype() {
+        let records = read_all(classic_pcap(
+            PCAP_MAGIC_NANOS,
+            LINKTYPE_RAW,
+            &[(1, 5, b"x")],
+        ));
+        assert_eq!(records[0].datalink, DLT_RAW);
+        assert_eq!(records[0].timestamp_ns, 1_000_000_005);
+    }
+
+    #[test]
+    fn reads_pcapng_with_default_and_custom_resolution() {
+        let micro = read_all(pcapng_file(None, &[(1_500_000, b"abcde")]));
+        assert_eq!(micro.len(), 1);
+        assert_eq!(micro[0].timestamp_ns, 1_500_000_000);
+        assert_eq!(micro[0].data, b"abcde");
+
+        let nano = read_all(pcapng_file(Some(9), &[(42, b"z")]));
+        assert_eq!(nano[0].timestamp_ns, 42);
+    }
+
+    #[test]
+    fn truncated_record_is_an_error() {
+        let mut bytes = classic_pcap(PCAP_MAGIC_MICROS, 1, &[(1, 0, b"abcdef")]);
+        bytes.truncate(bytes.len() - 2);
+        let mut reader = PcapReader::new(Cursor::new(bytes)).expect("valid header");
+        assert!(reader.next_record().is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_magic() {
+        assert!(PcapReader::new(Cursor::new(vec![0u8; 24])).is_err());
+    }
+}
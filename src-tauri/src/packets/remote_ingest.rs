@@ -0,0 +1,487 @@
+//! Ingest of raw packets forwarded by a remote capture agent.
+//!
+//! Lets one machine capture (the gaming PC) while the meter runs elsewhere.
+//! The meter listens on TCP; an agent connects, proves it knows the shared
+//! key and then streams frames:
+//!
+//! ```text
+//! meter -> agent  "RLRI" | version u8 | nonce [32]
+//! agent -> meter  HMAC-SHA256(key, "agent" | nonce) [32]
+//! meter -> agent  status u8 (0 = accepted, 1 = rejected)
+//! agent -> meter  { length u32 BE | format u8 | packet [length - 1] }*
+//! ```
+//!
+//! The key only authenticates the agent; frames travel unencrypted, like the
+//! game traffic they carry.
+
+use hmac::{Hmac, Mac};
+use log::{info, warn};
+use sha2::Sha256;
+use std::fmt;
+use std::io::{ErrorKind, Read, Write};
+#[cfg(test)]
+use std::net::ToSocketAddrs;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+const MAGIC: &[u8; 4] = b"RLRI";
+const PROTOCOL_VERSION: u8 = 1;
+const NONCE_BYTES: usize = 32;
+const PROOF_BYTES: usize = 32;
+const HELLO_BYTES: usize = MAGIC.len() + 1 + NONCE_BYTES;
+const STATUS_ACCEPTED: u8 = 0;
+const STATUS_REJECTED: u8 = 1;
+const AGENT_PROOF_LABEL: &[u8] = b"agent";
+
+const FORMAT_RAW_IP: u8 = 0;
+const FORMAT_ETHERNET: u8 = 1;
+
+/// Largest frame body (format byte plus packet) accepted from an agent.
+const MAX_FRAME_BYTES: usize = 65_536 + 1;
+/// Agents that have not authenticated by then are dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Concurrent agents; further connections are refused until one leaves.
+const MAX_AGENTS: usize = 4;
+/// Bytes read from one agent per dispatch so others are not starved.
+const READ_BUDGET_BYTES: usize = 1024 * 1024;
+const READ_CHUNK_BYTES: usize = 64 * 1024;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Shared secret between the meter and its agents. Redacted in `Debug` so it
+/// never reaches capture spans or logs.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SharedKey(String);
+
+impl SharedKey {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self(key.into())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl fmt::Debug for SharedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedKey(***)")
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RemotePacketFormat {
+    /// Starts at the IP header.
+    RawIp,
+    /// Starts at an Ethernet II header.
+    Ethernet,
+}
+
+impl RemotePacketFormat {
+    fn from_wire(byte: u8) -> Option<Self> {
+        match byte {
+            FORMAT_RAW_IP => Some(Self::RawIp),
+            FORMAT_ETHERNET => Some(Self::Ethernet),
+            _ => None,
+        }
+    }
+
+    #[cfg(test)]
+    fn to_wire(self) -> u8 {
+        match self {
+            Self::RawIp => FORMAT_RAW_IP,
+            Self::Ethernet => FORMAT_ETHERNET,
+        }
+    }
+}
+
+enum AgentState {
+    Handshake {
+        nonce: [u8; NONCE_BYTES],
+        deadline: Instant,
+    },
+    Streaming,
+}
+
+struct AgentConnection {
+    stream: TcpStream,
+    peer: SocketAddr,
+    state: AgentState,
+    buffer: Vec<u8>,
+    closed: bool,
+}
+
+/// Meter side: accepts agents and hands their packets to the capture loop.
+/// Never blocks, so the caller keeps checking for cancellation.
+pub struct RemoteIngest {
+    listener: TcpListener,
+    key: SharedKey,
+    agents: Vec<AgentConnection>,
+    scratch: Vec<u8>,
+}
+
+impl RemoteIngest {
+    pub fn bind(listen: &str, key: &SharedKey) -> Result<Self, String> {
+        if key.is_empty() {
+            return Err("remote ingest requires a shared key".to_string());
+        }
+        let listener =
+            TcpListener::bind(listen).map_err(|e| format!("failed to listen on {listen}: {e}"))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("failed to configure listener on {listen}: {e}"))?;
+        let local = listener
+            .local_addr()
+            .map_err(|e| format!("failed to read listener address: {e}"))?;
+        info!(target: "app::capture", "remote ingest listening addr={local}");
+        Ok(Self {
+            listener,
+            key: key.clone(),
+            agents: Vec::new(),
+            scratch: vec![0u8; READ_CHUNK_BYTES],
+        })
+    }
+
+    #[cfg(test)]
+    pub fn local_addr(&self) -> Result<SocketAddr, String> {
+        self.listener
+            .local_addr()
+            .map_err(|e| format!("failed to read listener address: {e}"))
+    }
+
+    /// Accepts pending agents, advances handshakes and delivers every complete
+    /// frame received so far. Returns the number of packets delivered.
+    pub fn dispatch_batch<F>(&mut self, on_packet: &mut F) -> Result<i32, String>
+    where
+        F: FnMut(RemotePacketFormat, &[u8]),
+    {
+        self.accept_pending()?;
+
+        let now = Instant::now();
+        let mut delivered = 0;
+        for agent in &mut self.agents {
+            agent.fill(&mut self.scratch);
+            if let AgentState::Handshake { nonce, deadline } = agent.state {
+                if agent.buffer.len() >= PROOF_BYTES {
+                    agent.authenticate(&self.key, &nonce);
+                } else if now >= deadline {
+                    warn!(target: "app::capture", "remote_agent_handshake_timeout peer={}", agent.peer);
+                    agent.closed = true;
+                }
+            }
+            if matches!(agent.state, AgentState::Streaming) {
+                delivered += agent.drain_frames(on_packet);
+            }
+        }
+
+        self.agents.retain(|agent| {
+            if agent.closed && matches!(agent.state, AgentState::Streaming) {
+                info!(target: "app::capture", "remote_agent_disconnected peer={}", agent.peer);
+            }
+            !agent.closed
+        });
+        Ok(delivered)
+    }
+
+    fn accept_pending(&mut self) -> Result<(), String> {
+        loop {
+            let (stream, peer) = match self.listener.accept() {
+                Ok(accepted) => accepted,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(format!("remote ingest accept failed: {e}")),
+            };
+            if self.agents.len() >= MAX_AGENTS {
+                warn!(target: "app::capture", "remote_agent_refused peer={peer} reason=too_many_agents");
+                continue;
+            }
+            match AgentConnection::open(stream, peer) {
+                Ok(agent) => self.agents.push(agent),
+                Err(e) => {
+                    warn!(target: "app::capture", "remote_agent_open_failed peer={peer} err={e}");
+                }
+            }
+        }
+    }
+}
+
+impl AgentConnection {
+    fn open(mut stream: TcpStream, peer: SocketAddr) -> Result<Self, String> {
+        let nonce = fresh_nonce()?;
+        let mut hello = Vec::with_capacity(HELLO_BYTES);
+        hello.extend_from_slice(MAGIC);
+        hello.push(PROTOCOL_VERSION);
+        hello.extend_from_slice(&nonce);
+        // Written while still blocking (accepted sockets inherit the listener's
+        // mode on some platforms): the hello is tiny and the socket is new.
+        stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+        stream.write_all(&hello).map_err(|e| e.to_string())?;
+        stream.set_nonblocking(true).map_err(|e| e.to_string())?;
+        let _ = stream.set_nodelay(true);
+        Ok(Self {
+            stream,
+            peer,
+            state: AgentState::Handshake {
+                nonce,
+                deadline: Instant::now() + HANDSHAKE_TIMEOUT,
+            },
+            buffer: Vec::new(),
+            closed: false,
+        })
+    }
+
+    /// Reads whatever is available without blocking, up to the read budget.
+    fn fill(&mut self, scratch: &mut [u8]) {
+        let mut read = 0;
+        while !self.closed && read < READ_BUDGET_BYTES {
+            match self.stream.read(scratch) {
+                Ok(0) => self.closed = true,
+                Ok(n) => {
+                    self.buffer.extend_from_slice(&scratch[..n]);
+                    read += n;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => {
+                    warn!(target: "app::capture", "remote_agent_read_failed peer={} err={e}", self.peer);
+                    self.closed = true;
+                }
+            }
+        }
+    }
+
+    fn authenticate(&mut self, key: &SharedKey, nonce: &[u8; NONCE_BYTES]) {
+        let accepted = agent_mac(key, nonce)
+            .verify_slice(&self.buffer[..PROOF_BYTES])
+            .is_ok();
+        self.buffer.drain(..PROOF_BYTES);
+        let status = if accepted {
+            STATUS_ACCEPTED
+        } else {
+            STATUS_REJECTED
+        };
+        // A one-byte write to a socket that has sent nothing yet cannot block
+        // in practice; failing to deliver it only costs the agent a clear error.
+        let _ = self.stream.write_all(&[status]);
+        if accepted {
+            info!(target: "app::capture", "remote_agent_authenticated peer={}", self.peer);
+            self.state = AgentState::Streaming;
+        } else {
+            warn!(target: "app::capture", "remote_agent_rejected peer={} reason=bad_key", self.peer);
+            self.closed = true;
+        }
+    }
+
+    fn drain_frames<F>(&mut self, on_packet: &mut F) -> i32
+    where
+        F: FnMut(RemotePacketFormat, &[u8]),
+    {
+        let mut offset = 0;
+        let mut delivered = 0;
+        while let Some(header) = self.buffer.get(offset..offset + 4) {
+            let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+            if length == 0 || length > MAX_FRAME_BYTES {
+                warn!(
+                    target: "app::capture",
+                    "remote_agent_protocol_error peer={} frame_len={length}",
+                    self.peer
+                );
+                self.closed = true;
+                break;
+            }
+            let Some(body) = self.buffer.get(offset + 4..offset + 4 + length) else {
+                break;
+            };
+            offset += 4 + length;
+            match RemotePacketFormat::from_wire(body[0]) {
+                Some(format) => {
+                    on_packet(format, &body[1..]);
+                    delivered += 1;
+                }
+                None => {
+                    warn!(
+                        target: "app::capture",
+                        "remote_agent_unknown_format peer={} format={}",
+                        self.peer,
+                        body[0]
+                    );
+                }
+            }
+        }
+        self.buffer.drain(..offset);
+        delivered
+    }
+}
+
+/// Loopback stand-in for a remote agent: connects to a meter and forwards
+/// packets the way a real agent does.
+#[cfg(test)]
+pub struct RemoteAgent {
+    stream: TcpStream,
+}
+
+#[cfg(test)]
+impl RemoteAgent {
+    pub fn connect(addr: impl ToSocketAddrs, key: &SharedKey) -> Result<Self, String> {
+        let mut stream =
+            TcpStream::connect(addr).map_err(|e| format!("failed to connect to meter: {e}"))?;
+        stream
+            .set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+            .map_err(|e| e.to_string())?;
+        let _ = stream.set_nodelay(true);
+
+        let mut hello = [0u8; HELLO_BYTES];
+        stream
+            .read_exact(&mut hello)
+            .map_err(|e| format!("failed to read meter hello: {e}"))?;
+        if &hello[..MAGIC.len()] != MAGIC {
+            return Err("peer is not a remote ingest endpoint".to_string());
+        }
+        let version = hello[MAGIC.len()];
+        if version != PROTOCOL_VERSION {
+            return Err(format!(
+                "unsupported remote ingest version {version}, expected {PROTOCOL_VERSION}"
+            ));
+        }
+        let mut nonce = [0u8; NONCE_BYTES];
+        nonce.copy_from_slice(&hello[MAGIC.len() + 1..]);
+
+        stream
+            .write_all(&agent_proof(key, &nonce))
+            .map_err(|e| format!("failed to send agent proof: {e}"))?;
+        let mut status = [0u8; 1];
+        stream
+            .read_exact(&mut status)
+            .map_err(|e| format!("failed to read meter verdict: {e}"))?;
+        if status[0] != STATUS_ACCEPTED {
+            return Err("meter rejected the shared key".to_string());
+        }
+        stream.set_read_timeout(None).map_err(|e| e.to_string())?;
+        Ok(Self { stream })
+    }
+
+    pub fn send_packet(&mut self, format: RemotePacketFormat, packet: &[u8]) -> Result<(), String> {
+        let length = packet.len() + 1;
+        if length > MAX_FRAME_BYTES {
+            return Err(format!("packet of {} bytes is too large", packet.len()));
+        }
+        let mut frame = Vec::with_capacity(4 + length);
+        frame.extend_from_slice(&(length as u32).to_be_bytes());
+        frame.push(format.to_wire());
+        frame.extend_from_slice(packet);
+        self.stream
+            .write_all(&frame)
+            .map_err(|e| format!("failed to forward packet: {e}"))
+    }
+}
+
+/// HMAC-SHA256 over the proof label and the challenge nonce.
+fn agent_mac(key: &SharedKey, nonce: &[u8; NONCE_BYTES]) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(AGENT_PROOF_LABEL);
+    mac.update(nonce);
+    mac
+}
+
+#[cfg(test)]
+fn agent_proof(key: &SharedKey, nonce: &[u8; NONCE_BYTES]) -> [u8; PROOF_BYTES] {
+    agent_mac(key, nonce).finalize().into_bytes().into()
+}
+
+pub(crate) fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Challenge nonces come from the operating system's CSPRNG.
+fn fresh_nonce() -> Result<[u8; NONCE_BYTES], String> {
+    let mut nonce = [0u8; NONCE_BYTES];
+    getrandom::fill(&mut nonce).map_err(|e| format!("failed to draw a challenge nonce: {e}"))?;
+    Ok(nonce)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn pump_until<F>(ingest: &mut RemoteIngest, mut done: F) -> Vec<(RemotePacketFormat, Vec<u8>)>
+    where
+        F: FnMut(&[(RemotePacketFormat, Vec<u8>)]) -> bool,
+    {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut packets = Vec::new();
+        while !done(&packets) {
+            assert!(Instant::now() < deadline, "timed out waiting for packets");
+            ingest
+                .dispatch_batch(&mut |format, packet: &[u8]| {
+                    packets.push((format, packet.to_vec()))
+                })
+                .expect("dispatch");
+            thread::sleep(Duration::from_millis(1));
+        }
+        packets
+    }
+
+    #[test]
+    fn loopback_agent_authenticates_and_forwards_packets() {
+        let key = SharedKey::new("correct horse");
+        let mut ingest = RemoteIngest::bind("127.0.0.1:0", &key).expect("bind");
+        let addr = ingest.local_addr().expect("addr");
+
+        let agent = thread::spawn(move || {
+            let mut agent = RemoteAgent::connect(addr, &key)?;
+            agent.send_packet(RemotePacketFormat::RawIp, &[0x45, 0, 0, 20])?;
+            agent.send_packet(RemotePacketFormat::Ethernet, &[0xaa; 1500])?;
+            Ok::<_, String>(agent)
+        });
+
+        let packets = pump_until(&mut ingest, |packets| packets.len() == 2);
+        assert_eq!(
+            packets[0],
+            (RemotePacketFormat::RawIp, vec![0x45, 0, 0, 20])
+        );
+        assert_eq!(packets[1], (RemotePacketFormat::Ethernet, vec![0xaa; 1500]));
+        let agent = agent.join().expect("agent thread");
+        assert!(agent.is_ok());
+    }
+
+    #[test]
+    fn agent_with_the_wrong_key_is_rejected_and_dropped() {
+        let mut ingest = RemoteIngest::bind("127.0.0.1:0", &SharedKey::new("meter")).expect("bind");
+        let addr = ingest.local_addr().expect("addr");
+
+        let agent =
+            thread::spawn(move || RemoteAgent::connect(addr, &SharedKey::new("guess")).err());
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !agent.is_finished() {
+            assert!(Instant::now() < deadline, "agent never got a verdict");
+            ingest
+                .dispatch_batch(&mut |_, _: &[u8]| panic!("unauthenticated packet delivered"))
+                .expect("dispatch");
+            thread::sleep(Duration::from_millis(1));
+        }
+        let err = agent.join().expect("agent thread");
+        assert_eq!(err.as_deref(), Some("meter rejected the shared key"));
+        ingest
+            .dispatch_batch(&mut |_, _: &[u8]| panic!("unauthenticated packet delivered"))
+            .expect("dispatch");
+        assert!(ingest.agents.is_empty());
+    }
+
+    #[test]
+    fn empty_key_refuses_to_listen() {
+        assert!(RemoteIngest::bind("127.0.0.1:0", &SharedKey::new("")).is_err());
+        assert_eq!(format!("{:?}", SharedKey::new("secret")), "SharedKey(***)");
+    }
+}
@@ -0,0 +1,423 @@
+//! Append-only "black box" recording of the capture envelope stream.
+//!
+//! Every [`CaptureEnvelope`] accepted by the capture emitter, including the
+//! synthetic stream-gap, reassembly-reset and decode-issue envelopes, is
+//! written verbatim so a session can later be fed back into the decoder.
+//!
+//! # File format (version 1)
+//!
+//! All integers are little-endian.
+//!
+//! ```text
+//! header  := magic[8] = "BPSRSESS"
+//!            version: u16
+//!            reserved: u16 (0)
+//!            created_wall_ms: i64
+//! record* := record_len: u32          // bytes after this field
+//!            capture_sequence: u64
+//!            stream_id: u64
+//!            stream_epoch: u64
+//!            captured_wall_ms: i64
+//!            captured_mono_ns: u64
+//!            direction: u8             // 0 = server->client, 1 = client->server
+//!            key_flags: u8             // bit 0: service_id present, bit 1: method_id present
+//!            opcode: u32
+//!            service_id: u32           // 0 when absent
+//!            method_id: u32            // 0 when absent
+//!            payload: [u8; record_len - RECORD_FIXED_BYTES]
+//! ```
+//!
+//! A crash can leave a partial trailing record; readers treat it as the end
+//! of the file. Files rotate once they exceed the configured size and the
+//! oldest files beyond the retention count are deleted.
+
+use crate::live::runtime::events::{CaptureEnvelope, PacketDirection, PacketKey, wall_now_ms};
+use bytes::Bytes;
+use log::{info, warn};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+pub const SESSION_FILE_MAGIC: &[u8; 8] = b"BPSRSESS";
+pub const SESSION_FILE_VERSION: u16 = 1;
+pub const SESSION_FILE_EXTENSION: &str = "bpsrsess";
+
+const HEADER_BYTES: usize = 8 + 2 + 2 + 8;
+/// Fixed portion of a record body (everything but the payload).
+const RECORD_FIXED_BYTES: usize = 8 * 5 + 1 + 1 + 4 * 3;
+const MAX_RECORD_BYTES: usize = 64 * 1024 * 1024;
+
+const KEY_HAS_SERVICE: u8 = 0b01;
+const KEY_HAS_METHOD: u8 = 0b10;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+const WRITE_BUFFER_BYTES: usize = 256 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionRecorderConfig {
+    pub directory: PathBuf,
+    /// A file is closed and a new one started once it grows past this size.
+    pub max_file_bytes: u64,
+    /// Number of session files kept on disk, including the active one.
+    pub max_files: usize,
+}
+
+impl SessionRecorderConfig {
+    pub const DEFAULT_MAX_FILE_BYTES: u64 = 256 * 1024 * 1024;
+    pub const DEFAULT_MAX_FILES: usize = 16;
+
+    #[cfg(test)]
+    pub fn new(directory: PathBuf) -> Self {
+        Self {
+            directory,
+            max_file_bytes: Self::DEFAULT_MAX_FILE_BYTES,
+            max_files: Self::DEFAULT_MAX_FILES,
+        }
+    }
+}
+
+/// Writes envelopes on the capture thread. Writes are buffered and flushed at
+/// most once per [`FLUSH_INTERVAL`], on rotation, and on drop.
+pub struct SessionRecorder {
+    config: SessionRecorderConfig,
+    writer: Option<BufWriter<File>>,
+    current_bytes: u64,
+    next_file_index: u64,
+    last_flush: Instant,
+}
+
+impl SessionRecorder {
+    pub fn open(config: SessionRecorderConfig) -> Result<Self, String> {
+        fs::create_dir_all(&config.directory)
+            .map_err(|e| format!("create_dir_all {}: {e}", config.directory.display()))?;
+        let mut recorder = Self {
+            config,
+            writer: None,
+            current_bytes: 0,
+            next_file_index: 0,
+            last_flush: Instant::now(),
+        };
+        recorder.rotate()?;
+        Ok(recorder)
+    }
+
+    pub fn record(&mut self, envelope: &CaptureEnvelope) -> Result<(), String> {
+        if self.current_bytes >= self.config.max_file_bytes {
+            self.rotate()?;
+        }
+        let Some(writer) = self.writer.as_mut() else {
+            return Err("session recorder has no open file".to_string());
+        };
+
+        let record = encode_record(envelope);
+        writer
+            .write_all(&record)
+            .map_err(|e| format!("session record write failed: {e}"))?;
+        self.current_bytes = self.current_bytes.saturating_add(record.len() as u64);
+
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.last_flush = Instant::now();
+        match self.writer.as_mut() {
+            Some(writer) => writer
+                .flush()
+                .map_err(|e| format!("session record flush failed: {e}")),
+            None => Ok(()),
+        }
+    }
+
+    fn rotate(&mut self) -> Result<(), String> {
+        self.flush()?;
+        self.writer = None;
+
+        let created_wall_ms = wall_now_ms();
+        let path = self.config.directory.join(format!(
+            "session-{created_wall_ms}-{:04}.{SESSION_FILE_EXTENSION}",
+            self.next_file_index
+        ));
+        self.next_file_index = self.next_file_index.saturating_add(1);
+
+        let file = File::create(&path).map_err(|e| format!("create {}: {e}", path.display()))?;
+        let mut writer = BufWriter::with_capacity(WRITE_BUFFER_BYTES, file);
+        let mut header = Vec::with_capacity(HEADER_BYTES);
+        header.extend_from_slice(SESSION_FILE_MAGIC);
+        header.extend_from_slice(&SESSION_FILE_VERSION.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&created_wall_ms.to_le_bytes());
+        writer
+            .write_all(&header)
+            .map_err(|e| format!("write header {}: {e}", path.display()))?;
+
+        self.writer = Some(writer);
+        self.current_bytes = HEADER_BYTES as u64;
+        info!(target: "app::capture", "session recording file opened path={}", path.display());
+
+        prune_session_files(&self.config.directory, self.config.max_files);
+        Ok(())
+    }
+}
+
+impl Drop for SessionRecorder {
+    fn drop(&mut self) {
+        if let Err(error) = self.flush() {
+            warn!(target: "app::capture", "session recorder final flush failed: {error}");
+        }
+    }
+}
+
+/// Session files in `directory`, oldest first. Names embed the creation time
+/// and a per-recorder counter, so lexical order is chronological.
+pub fn list_session_files(directory: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries =
+        fs::read_dir(directory).map_err(|e| format!("read_dir {}: {e}", directory.display()))?;
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == SESSION_FILE_EXTENSION)
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn prune_session_files(directory: &Path, max_files: usize) {
+    let files = match list_session_files(directory) {
+        Ok(files) => files,
+        Err(error) => {
+            warn!(target: "app::capture", "session retention scan failed: {error}");
+            return;
+        }
+    };
+    let excess = files.len().saturating_sub(max_files.max(1));
+    for path in files.into_iter().take(excess) {
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                info!(target: "app::capture", "session recording pruned path={}", path.display())
+            }
+            Err(error) => {
+                warn!(target: "app::capture", "session prune failed path={} error={error}", path.display());
+            }
+        }
+    }
+}
+
+fn encode_record(envelope: &CaptureEnvelope) -> Vec<u8> {
+    let body_len = RECORD_FIXED_BYTES + envelope.payload.len();
+    let mut record = Vec::with_capacity(4 + body_len);
+    record.extend_from_slice(&(body_len as u32).to_le_bytes());
+    record.extend_from_slice(&envelope.capture_sequence.to_le_bytes());
+    record.extend_from_slice(&envelope.stream_id.to_le_bytes());
+    record.extend_from_slice(&envelope.stream_epoch.to_le_bytes());
+    record.extend_from_slice(&envelope.captured_wall_ms.to_le_bytes());
+    record.extend_from_slice(&envelope.captured_mono_ns.to_le_bytes());
+    record.push(match envelope.direction {
+        PacketDirection::ServerToClient => 0,
+        PacketDirection::ClientToServer => 1,
+    });
+    let mut key_flags = 0u8;
+    if envelope.key.service_id.is_some() {
+        key_flags |= KEY_HAS_SERVICE;
+    }
+    if envelope.key.method_id.is_some() {
+        key_flags |= KEY_HAS_METHOD;
+    }
+    record.push(key_flags);
+    record.extend_from_slice(&envelope.key.opcode.to_le_bytes());
+    record.extend_from_slice(&envelope.key.service_id.unwrap_or_default().to_le_bytes());
+    record.extend_from_slice(&envelope.key.method_id.unwrap_or_default().to_le_bytes());
+    record.extend_from_slice(&envelope.payload);
+    record
+}
+
+fn decode_record(body: &[u8]) -> Result<CaptureEnvelope, String> {
+    if body.len() < RECORD_FIXED_BYTES {
+        return Err(format!("session record too short: {} bytes", body.len()));
+    }
+    let u64_at = |offset: usize| {
+        u64::from_le_bytes(body[offset..offset + 8].try_into().expect("8-byte slice"))
+    };
+    let u32_at = |offset: usize| {
+        u32::from_le_bytes(body[offset..offset + 4].try_into().expect("4-byte slice"))
+    };
+    let direction = match body[40] {
+        0 => PacketDirection::ServerToClient,
+        1 => PacketDirection::ClientToServer,
+        other => return Err(format!("invalid session record direction {other}")),
+    };
+    let key_flags = body[41];
+
+    Ok(CaptureEnvelope {
+        capture_sequence: u64_at(0),
+        stream_id: u64_at(8),
+        stream_epoch: u64_at(16),
+        captured_wall_ms: i64::from_le_bytes(body[24..32].try_into().expect("8-byte slice")),
+        captured_mono_ns: u64_at(32),
+        direction,
+        key: PacketKey {
+            opcode: u32_at(42),
+            service_id: (key_flags & KEY_HAS_SERVICE != 0).then(|| u32_at(46)),
+            method_id: (key_flags & KEY_HAS_METHOD != 0).then(|| u32_at(50)),
+        },
+        payload: Bytes::copy_from_slice(&body[RECORD_FIXED_BYTES..]),
+    })
+}
+
+/// Sequential reader for a single session file.
+pub struct SessionReader<R: Read> {
+    input: R,
+}
+
+impl SessionReader<BufReader<File>> {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("open {}: {e}", path.display()))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read> SessionReader<R> {
+    pub fn new(mut input: R) -> Result<Self, String> {
+        let mut header = [0u8; HEADER_BYTES];
+        input
+            .read_exact(&mut header)
+            .map_err(|e| format!("failed to read session header: {e}"))?;
+        if &header[0..8] != SESSION_FILE_MAGIC {
+            return Err("not a session recording".to_string());
+        }
+        let version = u16::from_le_bytes([header[8], header[9]]);
+        if version != SESSION_FILE_VERSION {
+            return Err(format!("unsupported session recording version {version}"));
+        }
+        Ok(Self { input })
+    }
+
+    /// Returns the next envelope, or `None` at the end of the file. A partial
+    /// trailing record (interrupted write) also ends the file.
+    pub fn next_envelope(&mut self) -> Result<Option<CaptureEnvelope>, String> {
+        let mut len = [0u8; 4];
+        if !read_full(&mut self.input, &mut len)? {
+            return Ok(None);
+        }
+        let body_len = u32::from_le_bytes(len) as usize;
+        if !(RECORD_FIXED_BYTES..=MAX_RECORD_BYTES).contains(&body_len) {
+            return Err(format!("invalid session record length {body_len}"));
+        }
+        let mut body = vec![0u8; body_len];
+        if !read_full(&mut self.input, &mut body)? {
+            warn!(target: "app::capture", "session recording ends with a partial record");
+            return Ok(None);
+        }
+        decode_record(&body).map(Some)
+    }
+}
+
+/// Fills `buf`, returning `false` when the input ends first.
+fn read_full<R: Read>(input: &mut R, buf: &mut [u8]) -> Result<bool, String> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match input.read(&mut buf[filled..]) {
+            Ok(0) => return Ok(false),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(format!("failed to read session recording: {e}")),
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::packet_process::SYNTHETIC_STREAM_GAP_OPCODE;
+
+    fn envelope(sequence: u64, payload: &'static [u8]) -> CaptureEnvelope {
+        CaptureEnvelope {
+            capture_sequence: sequence,
+            stream_id: 42,
+            stream_epoch: 3,
+            captured_wall_ms: 1_700_000_000_000 + sequence as i64,
+            captured_mono_ns: sequence * 1_000_000,
+            direction: PacketDirection::ClientToServer,
+            key: PacketKey {
+                opcode: 0x3d002,
+                service_id: Some(103_198_054),
+                method_id: Some(0x3d002),
+            },
+            payload: Bytes::from_static(payload),
+        }
+    }
+
+    #[test]
+    fn records_round_trip_through_a_session_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let gap = CaptureEnvelope {
+            direction: PacketDirection::ServerToClient,
+            key: PacketKey {
+                opcode: SYNTHETIC_STREAM_GAP_OPCODE,
+                service_id: None,
+                method_id: None,
+            },
+            ..envelope(2, &[0, 0, 0, 1, 0, 0, 0, 9])
+        };
+        {
+            let mut recorder =
+                SessionRecorder::open(SessionRecorderConfig::new(dir.path().to_path_buf()))
+                    .expect("recorder");
+            recorder.record(&envelope(1, b"payload")).unwrap();
+            recorder.record(&gap).unwrap();
+        }
+
+        let files = list_session_files(dir.path()).unwrap();
+        assert_eq!(files.len(), 1);
+        let mut reader = SessionReader::open(&files[0]).unwrap();
+        assert_eq!(
+            reader.next_envelope().unwrap(),
+            Some(envelope(1, b"payload"))
+        );
+        assert_eq!(reader.next_envelope().unwrap(), Some(gap));
+        assert_eq!(reader.next_envelope().unwrap(), None);
+    }
+
+    #[test]
+    fn rotation_keeps_only_the_newest_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let config = SessionRecorderConfig {
+            directory: dir.path().to_path_buf(),
+            max_file_bytes: 1,
+            max_files: 2,
+        };
+        let mut recorder = SessionRecorder::open(config).expect("recorder");
+        for sequence in 1..=4 {
+            recorder.record(&envelope(sequence, b"x")).unwrap();
+        }
+        drop(recorder);
+
+        let files = list_session_files(dir.path()).unwrap();
+        assert_eq!(files.len(), 2);
+        let mut newest = SessionReader::open(&files[1]).unwrap();
+        assert_eq!(newest.next_envelope().unwrap().unwrap().capture_sequence, 4);
+    }
+
+    #[test]
+    fn partial_trailing_record_ends_the_stream() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(SESSION_FILE_MAGIC);
+        bytes.extend_from_slice(&SESSION_FILE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&0u16.to_le_bytes());
+        bytes.extend_from_slice(&0i64.to_le_bytes());
+        bytes.extend(encode_record(&envelope(1, b"complete")));
+        let partial = encode_record(&envelope(2, b"partial"));
+        bytes.extend_from_slice(&partial[..partial.len() - 3]);
+
+        let mut reader = SessionReader::new(bytes.as_slice()).unwrap();
+        assert_eq!(reader.next_envelope().unwrap().unwrap().capture_sequence, 1);
+        assert_eq!(reader.next_envelope().unwrap(), None);
+    }
+}
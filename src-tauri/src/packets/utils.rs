@@ -1,26 +1,35 @@
 use std::collections::BTreeMap;
 use std::fmt;
+use std::net::{IpAddr, SocketAddr};
 use std::time::{Duration, Instant};
 
+/// One direction of a TCP connection. Addresses are IPv4 or IPv6; an
+/// IPv4-mapped IPv6 address is stored as plain IPv4 so both spellings of the
+/// same connection compare equal.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Server {
-    src_addr: [u8; 4],
+    src_addr: IpAddr,
     src_port: u16,
-    dst_addr: [u8; 4],
+    dst_addr: IpAddr,
     dst_port: u16,
 }
 
 impl Server {
-    pub fn new(src_addr: [u8; 4], src_port: u16, dst_addr: [u8; 4], dst_port: u16) -> Self {
+    pub fn new(
+        src_addr: impl Into<IpAddr>,
+        src_port: u16,
+        dst_addr: impl Into<IpAddr>,
+        dst_port: u16,
+    ) -> Self {
         Self {
-            src_addr,
+            src_addr: src_addr.into().to_canonical(),
             src_port,
-            dst_addr,
+            dst_addr: dst_addr.into().to_canonical(),
             dst_port,
         }
     }
 
-    pub fn source_addr(&self) -> [u8; 4] {
+    pub fn source_addr(&self) -> IpAddr {
         self.src_addr
     }
 
@@ -28,32 +37,38 @@ impl Server {
         self.src_port
     }
 
-    pub fn destination_addr(&self) -> [u8; 4] {
+    pub fn destination_addr(&self) -> IpAddr {
         self.dst_addr
     }
 
     pub fn destination_port(&self) -> u16 {
         self.dst_port
     }
+
+    /// Both endpoints in a fixed order, so the two directions of one
+    /// connection share a key.
+    pub fn connection(&self) -> (SocketAddr, SocketAddr) {
+        let source = SocketAddr::new(self.src_addr, self.src_port);
+        let destination = SocketAddr::new(self.dst_addr, self.dst_port);
+        if source <= destination {
+            (source, destination)
+        } else {
+            (destination, source)
+        }
+    }
 }
 
 impl fmt::Display for Server {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{}:{} -> {}:{}",
-            ip_to_str(&self.src_addr),
-            self.src_port,
-            ip_to_str(&self.dst_addr),
-            self.dst_port
+            "{} -> {}",
+            SocketAddr::new(self.src_addr, self.src_port),
+            SocketAddr::new(self.dst_addr, self.dst_port)
         )
     }
 }
 
-fn ip_to_str(ip: &[u8; 4]) -> String {
-    format!("{}.{}.{}.{}", ip[0], ip[1], ip[2], ip[3])
-}
-
 #[inline]
 pub fn tcp_sequence_before(a: u32, b: u32) -> bool {
     (a.wrapping_sub(b) as i32) < 0
@@ -301,7 +316,7 @@ impl TCPReassembler {
 
 #[cfg(test)]
 mod tests {
-    use super::{GAP_SKIP_BUFFERED_BYTES, GapSkipReason, TCPReassembler, TcpInsertResult};
+    use super::{GAP_SKIP_BUFFERED_BYTES, GapSkipReason, Server, TCPReassembler, TcpInsertResult};
     use std::time::Duration;
 
     #[test]
@@ -462,4 +477,17 @@ mod tests {
         );
         assert_eq!(reassembler.fast_path_hits(), 1);
     }
+
+    #[test]
+    fn server_displays_and_canonicalizes_both_families() {
+        let v4 = Server::new([10, 0, 0, 1], 40000, [20, 0, 0, 1], 50000);
+        assert_eq!(v4.to_string(), "10.0.0.1:40000 -> 20.0.0.1:50000");
+
+        let mapped = std::net::Ipv4Addr::new(20, 0, 0, 1).to_ipv6_mapped();
+        assert_eq!(Server::new([10, 0, 0, 1], 40000, mapped, 50000), v4);
+
+        let loopback = std::net::Ipv6Addr::LOCALHOST;
+        let v6 = Server::new(loopback, 1, loopback, 2);
+        assert_eq!(v6.to_string(), "[::1]:1 -> [::1]:2");
+    }
 }
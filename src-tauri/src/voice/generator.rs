@@ -54,6 +54,7 @@ pub trait SidecarRunner: Send + Sync {
         model_path: &Path,
     ) -> VoiceResult<FineTunedModelInspection>;
 
+    #[allow(clippy::too_many_arguments)]
     fn run_batch(
         &self,
         app_handle: &AppHandle,
@@ -445,6 +446,7 @@ fn emit_progress<R: Runtime>(app_handle: &AppHandle<R>, progress: VoiceGeneratio
     }
 }
 
+#[cfg_attr(not(windows), allow(unused_variables))]
 fn hide_window(command: &mut Command) {
     #[cfg(windows)]
     {
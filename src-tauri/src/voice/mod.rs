@@ -1903,6 +1903,16 @@ fn select_backend(
     Ok(backend)
 }
 
+#[cfg(not(windows))]
+fn kill_process_by_pid(pid: u32) {
+    let result = std::process::Command::new("kill")
+        .args(["-9", &pid.to_string()])
+        .status();
+    if let Err(error) = result {
+        warn!(target: "app::voice", "failed to terminate sidecar pid {pid}: {error}");
+    }
+}
+
 #[cfg(windows)]
 fn kill_process_by_pid(pid: u32) {
     use std::os::windows::process::CommandExt;
@@ -1924,31 +1934,33 @@ mod tests {
     fn replacing_a_fine_tuned_model_only_stales_matching_assets() {
         let old_sha = "a".repeat(64);
         let other_sha = "b".repeat(64);
-        let mut catalog = models::VoiceCatalog::default();
-        catalog.assets = vec![
-            VoiceAssetMeta {
-                source: VoiceAssetSource::FineTuned {
-                    model_sha256: old_sha.clone(),
-                    speaker_name: "old".into(),
-                    speaker_token_id: 3000,
+        let mut catalog = models::VoiceCatalog {
+            assets: vec![
+                VoiceAssetMeta {
+                    source: VoiceAssetSource::FineTuned {
+                        model_sha256: old_sha.clone(),
+                        speaker_name: "old".into(),
+                        speaker_token_id: 3000,
+                    },
+                    ..VoiceAssetMeta::default()
                 },
-                ..VoiceAssetMeta::default()
-            },
-            VoiceAssetMeta {
-                source: VoiceAssetSource::FineTuned {
-                    model_sha256: other_sha,
-                    speaker_name: "other".into(),
-                    speaker_token_id: 3001,
+                VoiceAssetMeta {
+                    source: VoiceAssetSource::FineTuned {
+                        model_sha256: other_sha,
+                        speaker_name: "other".into(),
+                        speaker_token_id: 3001,
+                    },
+                    ..VoiceAssetMeta::default()
                 },
-                ..VoiceAssetMeta::default()
-            },
-            VoiceAssetMeta {
-                source: VoiceAssetSource::CloneProfile {
-                    profile_id: "profile-1".into(),
+                VoiceAssetMeta {
+                    source: VoiceAssetSource::CloneProfile {
+                        profile_id: "profile-1".into(),
+                    },
+                    ..VoiceAssetMeta::default()
                 },
-                ..VoiceAssetMeta::default()
-            },
-        ];
+            ],
+            ..models::VoiceCatalog::default()
+        };
 
         mark_fine_tuned_assets_stale(&mut catalog, &old_sha);
 
@@ -1976,12 +1988,14 @@ mod tests {
 
     #[test]
     fn reconcile_keeps_the_active_asset_over_a_newer_unconfirmed_one() {
-        let mut catalog = models::VoiceCatalog::default();
-        catalog.phrases = vec![phrase_stub("phrase-1", Some("asset-old"))];
-        catalog.assets = vec![
-            asset_stub("asset-old", "phrase-1", 100),
-            asset_stub("asset-new", "phrase-1", 200),
-        ];
+        let mut catalog = models::VoiceCatalog {
+            phrases: vec![phrase_stub("phrase-1", Some("asset-old"))],
+            assets: vec![
+                asset_stub("asset-old", "phrase-1", 100),
+                asset_stub("asset-new", "phrase-1", 200),
+            ],
+            ..models::VoiceCatalog::default()
+        };
 
         let removed = reconcile_single_asset_per_phrase(&mut catalog);
 
@@ -1999,13 +2013,15 @@ mod tests {
 
     #[test]
     fn reconcile_falls_back_to_the_newest_asset_when_none_is_active() {
-        let mut catalog = models::VoiceCatalog::default();
-        catalog.phrases = vec![phrase_stub("phrase-1", None)];
-        catalog.assets = vec![
-            asset_stub("asset-1", "phrase-1", 100),
-            asset_stub("asset-2", "phrase-1", 300),
-            asset_stub("asset-3", "phrase-1", 200),
-        ];
+        let mut catalog = models::VoiceCatalog {
+            phrases: vec![phrase_stub("phrase-1", None)],
+            assets: vec![
+                asset_stub("asset-1", "phrase-1", 100),
+                asset_stub("asset-2", "phrase-1", 300),
+                asset_stub("asset-3", "phrase-1", 200),
+            ],
+            ..models::VoiceCatalog::default()
+        };
 
         let mut removed = reconcile_single_asset_per_phrase(&mut catalog);
         removed.sort();
@@ -2027,9 +2043,11 @@ mod tests {
 
     #[test]
     fn reconcile_is_a_no_op_for_phrases_with_a_single_asset() {
-        let mut catalog = models::VoiceCatalog::default();
-        catalog.phrases = vec![phrase_stub("phrase-1", Some("asset-1"))];
-        catalog.assets = vec![asset_stub("asset-1", "phrase-1", 100)];
+        let mut catalog = models::VoiceCatalog {
+            phrases: vec![phrase_stub("phrase-1", Some("asset-1"))],
+            assets: vec![asset_stub("asset-1", "phrase-1", 100)],
+            ..models::VoiceCatalog::default()
+        };
 
         let removed = reconcile_single_asset_per_phrase(&mut catalog);
 
@@ -2058,13 +2076,15 @@ mod tests {
             locale: presets::VoicePresetLocale::ZhCn,
             revision: 1,
         };
-        let mut catalog = models::VoiceCatalog::default();
-        catalog.profiles = vec![preset_profile_stub(
-            "preset-zh",
-            Some(tag.clone()),
-            "model-v1",
-            "sha-current",
-        )];
+        let catalog = models::VoiceCatalog {
+            profiles: vec![preset_profile_stub(
+                "preset-zh",
+                Some(tag.clone()),
+                "model-v1",
+                "sha-current",
+            )],
+            ..models::VoiceCatalog::default()
+        };
 
         let found = find_preset_profile_id_in_catalog(&catalog, &tag, "model-v1", "sha-current");
 
@@ -2081,13 +2101,15 @@ mod tests {
             locale: presets::VoicePresetLocale::EnUs,
             revision: 1,
         };
-        let mut catalog = models::VoiceCatalog::default();
-        catalog.profiles = vec![preset_profile_stub(
-            "preset-en-old",
-            Some(stored_tag),
-            "model-v1",
-            "sha-current",
-        )];
+        let catalog = models::VoiceCatalog {
+            profiles: vec![preset_profile_stub(
+                "preset-en-old",
+                Some(stored_tag),
+                "model-v1",
+                "sha-current",
+            )],
+            ..models::VoiceCatalog::default()
+        };
 
         // The bundled reference audio moved on to revision 2: the
         // revision-1 profile must not be reused, so generation re-extracts
@@ -2104,13 +2126,15 @@ mod tests {
             locale: presets::VoicePresetLocale::JaJp,
             revision: 0,
         };
-        let mut catalog = models::VoiceCatalog::default();
-        catalog.profiles = vec![preset_profile_stub(
-            "preset-ja-old-model",
-            Some(tag.clone()),
-            "model-v1",
-            "sha-old",
-        )];
+        let catalog = models::VoiceCatalog {
+            profiles: vec![preset_profile_stub(
+                "preset-ja-old-model",
+                Some(tag.clone()),
+                "model-v1",
+                "sha-old",
+            )],
+            ..models::VoiceCatalog::default()
+        };
 
         assert_eq!(
             find_preset_profile_id_in_catalog(&catalog, &tag, "model-v2", "sha-new"),
@@ -2128,16 +2152,18 @@ mod tests {
             locale: presets::VoicePresetLocale::JaJp,
             revision: 0,
         };
-        let mut catalog = models::VoiceCatalog::default();
-        catalog.profiles = vec![
-            preset_profile_stub("manual-clone", None, "model-v1", "sha-current"),
-            preset_profile_stub(
-                "preset-ja",
-                Some(other_locale_tag),
-                "model-v1",
-                "sha-current",
-            ),
-        ];
+        let catalog = models::VoiceCatalog {
+            profiles: vec![
+                preset_profile_stub("manual-clone", None, "model-v1", "sha-current"),
+                preset_profile_stub(
+                    "preset-ja",
+                    Some(other_locale_tag),
+                    "model-v1",
+                    "sha-current",
+                ),
+            ],
+            ..models::VoiceCatalog::default()
+        };
 
         assert_eq!(
             find_preset_profile_id_in_catalog(&catalog, &tag, "model-v1", "sha-current"),
@@ -2356,8 +2382,10 @@ mod tests {
 
     #[test]
     fn automatic_backend_selection_uses_vulkan_then_cpu() {
-        let mut inventory = VoiceBackendInventory::default();
-        inventory.cpu = ready_backend(EngineBackend::Cpu);
+        let mut inventory = VoiceBackendInventory {
+            cpu: ready_backend(EngineBackend::Cpu),
+            ..VoiceBackendInventory::default()
+        };
         assert_eq!(
             select_backend(VoiceGenerationBackend::Auto, &inventory).unwrap(),
             EngineBackend::Cpu
@@ -2383,18 +2411,10 @@ mod tests {
 
     #[test]
     fn unavailable_manual_backend_does_not_fall_back() {
-        let mut inventory = VoiceBackendInventory::default();
-        inventory.cpu = ready_backend(EngineBackend::Cpu);
+        let inventory = VoiceBackendInventory {
+            cpu: ready_backend(EngineBackend::Cpu),
+            ..VoiceBackendInventory::default()
+        };
         assert!(select_backend(VoiceGenerationBackend::Vulkan, &inventory).is_err());
     }
 }
-
-#[cfg(not(windows))]
-fn kill_process_by_pid(pid: u32) {
-    let result = std::process::Command::new("kill")
-        .args(["-9", &pid.to_string()])
-        .status();
-    if let Err(error) = result {
-        warn!(target: "app::voice", "failed to terminate sidecar pid {pid}: {error}");
-    }
-}
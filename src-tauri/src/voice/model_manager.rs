@@ -677,7 +677,7 @@ where
             dest_path,
             attempt_source,
             cancel,
-            |progress| emit_progress(progress),
+            &mut emit_progress,
         )
         .await
         {
@@ -50,6 +50,72 @@ async getLiveScene() : Promise<Result<LiveScenePayload, string>> {
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * Capture loss and decode-failure counters for diagnosing a suspicious parse.
+ */
+async getCaptureHealth() : Promise<Result<LiveCaptureHealthPayload, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_capture_health") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Captured game clients and which one the live windows currently show.
+ */
+async getLiveSessions() : Promise<Result<LiveSessionsPayload, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_live_sessions") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Unrouted messages recorded while protocol discovery is enabled.
+ */
+async getProtocolDiscovery() : Promise<Result<ProtocolDiscoveryPayload, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_protocol_discovery") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setProtocolDiscoveryEnabled(enabled: boolean) : Promise<void> {
+    await TAURI_INVOKE("set_protocol_discovery_enabled", { enabled });
+},
+async clearProtocolDiscovery() : Promise<void> {
+    await TAURI_INVOKE("clear_protocol_discovery");
+},
+async getLocalApiStatus() : Promise<LocalApiStatusPayload> {
+    return await TAURI_INVOKE("get_local_api_status");
+},
+/**
+ * Persists the settings, then restarts the listener with them. A port that
+ * cannot be bound is reported after the settings are saved.
+ */
+async saveAndApplyLocalApiSettings(settings: LocalApiSettings) : Promise<Result<LocalApiStatusPayload, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("save_and_apply_local_api_settings", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Rereads the scripts folder and restarts the live runtime's scripts from it.
+ * Files that fail to read or compile are reported and left out.
+ */
+async reloadUserScripts() : Promise<Result<UserScriptsPayload, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("reload_user_scripts") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async migrateHudLayout(complete: boolean) : Promise<Result<HudLayoutMigration, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("migrate_hud_layout", { complete }) };
@@ -96,6 +162,14 @@ async stopTrainingDummy() : Promise<Result<null, string>> {
     else return { status: "error", error: e  as any };
 }
 },
+async selectLiveSession(sessionId: number) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("select_live_session", { sessionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async saveAndApplyMonitorRuntimeSnapshot(snapshot: MonitorRuntimeSnapshot) : Promise<Result<null, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("save_and_apply_monitor_runtime_snapshot", { snapshot }) };
@@ -104,6 +178,18 @@ async saveAndApplyMonitorRuntimeSnapshot(snapshot: MonitorRuntimeSnapshot) : Pro
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * Replays recorded session files through a private live core and writes the
+ * resulting encounters to history. `speed` is `max`, `realtime` or `<n>x`.
+ */
+async replayRecordedSession(paths: string[], speed: string) : Promise<Result<ReplayStats, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("replay_recorded_session", { paths, speed }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async getUniqueSceneIds() : Promise<Result<SceneIdsResult, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("get_unique_scene_ids") };
@@ -152,6 +238,22 @@ async getEncounterRange(encounterId: number, startMs: number, endMs: number) : P
     else return { status: "error", error: e  as any };
 }
 },
+async getDungeonRuns(limit: number, offset: number, sceneId: number | null) : Promise<Result<DungeonRunsResult, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_dungeon_runs", { limit, offset, sceneId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getDungeonRunDetail(runId: number) : Promise<Result<DungeonRunDetailDto, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_dungeon_run_detail", { runId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async deleteEncounter(encounterId: number) : Promise<Result<null, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("delete_encounter", { encounterId }) };
@@ -176,9 +278,9 @@ async toggleFavoriteEncounter(id: number, isFavorite: boolean) : Promise<Result<
     else return { status: "error", error: e  as any };
 }
 },
-async savePacketCaptureSettings(method: string, npcapDevice: string) : Promise<Result<null, string>> {
+async savePacketCaptureSettings(settings: PacketCaptureSettings) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("save_packet_capture_settings", { method, npcapDevice }) };
+    return { status: "ok", data: await TAURI_INVOKE("save_packet_capture_settings", { settings }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
@@ -280,7 +382,8 @@ async openLogDir() : Promise<Result<null, string>> {
 }
 },
 /**
- * Creates a debug ZIP containing the most recent application log file and returns the path.
+ * Creates a debug ZIP containing the most recent application log file and the protocol
+ * schema drift report, and returns the path.
  *
  * If `destination_path` is provided, the ZIP is written there. Otherwise it is created
  * in the app log directory.
@@ -525,6 +628,20 @@ maxHp: number | null;
  * Whether the boss is in ActorStateDead.
  */
 isDead: boolean }
+/**
+ * Encounter time touched by packet loss or decode failures.
+ */
+export type CaptureAffectedRange = { kind: HistoryQualityFlag; startMs: number; endMs: number; occurrences: number }
+/**
+ * Rotating recordings of the capture stream; zero limits use the
+ * recorder's defaults.
+ */
+export type CaptureRecordingSettings = { enabled: boolean; maxFileMb: number; maxFiles: number }
+/**
+ * Loss and decode counters for one TCP stream. `stream_id` is a string
+ * because stream ids span the full u64 range.
+ */
+export type CaptureStreamHealth = { streamId: string; droppedSegments: number; lostBytes: number; reassemblyResets: number; malformedFrames: number; zstdFailures: number; unknownOpcodes: number; backpressureStalls: number; backpressureStallMs: number; lastIssueWallMs: number | null }
 export type BossMonsterIdsResult = { ids: number[] }
 export type BossSummaryDto = { monsterId: number; maxHp: number | null; isDefeated: boolean }
 /**
@@ -582,9 +699,63 @@ export type DeathRecord = { victimEntityUuid: string; deathTimestampMs: string;
 /**
  * Damage snapshots in chronological order (oldest first).
  */
-recentDamages?: DamageSnapshot[]; victimBuffs?: DeathBuffSnapshot[]; participantBuffs?: DeathParticipantBuffSnapshot[] }
+recentDamages?: DamageSnapshot[]; victimBuffs?: DeathBuffSnapshot[]; participantBuffs?: DeathParticipantBuffSnapshot[];
+/**
+ * When the victim was revived, if it has been since this death.
+ */
+revivedTimestampMs?: string | null }
 export type Device = { name: string; description: string | null }
+export type DiscoveredMessage = { kind: DiscoveredMessageKind; reason: DiscoveryReason; serviceId: number | null; methodId: number | null; count: number; firstSeenWallMs: number; lastSeenWallMs: number; minPayloadBytes: number; maxPayloadBytes: number;
+/**
+ * Schemaless dump of the most recent payload.
+ */
+sample: WireDump }
+/**
+ * `Notify` is server to client, `Call` client to server.
+ */
+export type DiscoveredMessageKind = "notify" | "call"
+export type DiscoveryReason = "unknownOpcode" | "unroutedService" | "unroutedMethod"
+export type DungeonRunDetailDto = { summary: DungeonRunSummaryDto; clearStartedAtMs: number | null; clearedAtMs: number | null; splits: DungeonRunSplitDto[]; encounters: EncounterSummaryDto[] }
+export type DungeonRunSplitDto = { targetId: number; completedAtMs: number;
+/**
+ * Since the clear started, or since the scene was entered when the
+ * dungeon flow never reached playing.
+ */
+elapsedMs: number }
+export type DungeonRunSummaryDto = { id: number; sceneId: number; dungeonDifficulty: number | null; startedAtMs: number; endedAtMs: number;
+/**
+ * From the dungeon flow entering playing to it ending; `None` when the
+ * run was left before the clear.
+ */
+clearTimeMs: number | null; wipes: number; deaths: number; encounterCount: number }
+export type DungeonRunsResult = { rows: DungeonRunSummaryDto[]; totalCount: number }
 export type EffectSlotConfig = { slotId: number; threshold: number | null; resetBuffId: number; resetSourceConfigId?: number | null; resetBuffTarget?: ResetBuffTarget; onBuffAdd?: CounterAction; onBuffChange?: CounterAction; onBuffRemove?: CounterAction; freezeDurationMs?: number | null; onFreezeExpire?: CounterAction; altFreeze?: AltFreezeConfig | null; thresholdModifier?: AttrModifier | null; freezeDurationModifier?: AttrModifier | null; resetSkillKeys?: number[] | null; onResetSkill?: CounterAction; dungeonStartFreezeMs?: number | null }
+/**
+ * How long one buff from one source was up on an entity within the
+ * projected window.
+ */
+export type EncounterBuffUptimeData = { entityId: string; baseId: number;
+/**
+ * `None` when the buff's source was unknown.
+ */
+sourceEntityId: string | null; uptimeMs: number;
+/**
+ * Uptime weighted by stack count: two stacks for one second is 2000.
+ */
+stackMs: number; uptimePct: number;
+/**
+ * Mean stack count over the window, counting time without the buff as
+ * zero stacks.
+ */
+averageStacks: number }
+/**
+ * The most damage an entity dealt within one rolling window length.
+ */
+export type EncounterBurstData = { windowMs: number; damage: string; dps: string;
+/**
+ * Offset of the last hit inside the peak window.
+ */
+endMs: number }
 export type EncounterChartPointData = { offsetMs: number; damage: string; healing: string; damageTaken: string }
 /**
  * Sparse per-entity bucket series: one row per (entity, metric), holding only
@@ -596,21 +767,47 @@ export type EncounterDetailData = { encounterId: number; summary: EncounterSumma
 /**
  * Always recomputed from chunks on load; stored snapshots leave it empty.
  */
-series?: EncounterChartSeriesData[]; markers: EncounterMarkerData[] }
+series?: EncounterChartSeriesData[]; markers: EncounterMarkerData[];
+/**
+ * Empty unless the encounter recorded at least one phase boundary.
+ */
+phases?: EncounterPhaseData[];
+/**
+ * Configured buffs seen in the window, by entity, buff and source.
+ */
+buffUptimes?: EncounterBuffUptimeData[] }
 export type EncounterEntityData = { entityId: string; displayUid: number; name: string | null; classId: number | null; classSpec: number | null;
 /**
  * Resolved spec display name; `None` for monsters / unknown specs.
  * `Option` fields decode as `None` from projections stored before this
  * field existed, and are backfilled from `class_spec` at query time.
  */
-classSpecName?: string | null; abilityScore: number | null; seasonStrength: number | null; monsterId: number | null; totals: EncounterTotalsData; skills: EncounterSkillData[]; damageTargets: EncounterTargetBreakdownData[]; healingTargets: EncounterTargetBreakdownData[]; takenSources: EncounterSourceBreakdownData[]; deaths: EncounterDeathData[] }
+classSpecName?: string | null; abilityScore: number | null; seasonStrength: number | null; monsterId: number | null; totals: EncounterTotalsData; skills: EncounterSkillData[]; damageTargets: EncounterTargetBreakdownData[]; healingTargets: EncounterTargetBreakdownData[]; takenSources: EncounterSourceBreakdownData[]; deaths: EncounterDeathData[];
+/**
+ * Peak damage per rolling window length, narrowest first. Projections
+ * stored before bursts were tracked decode with none.
+ */
+bursts?: EncounterBurstData[] }
 export type EncounterFiltersDto = { bossMonsterIds: number[] | null; sceneIds: number[] | null; playerName: string | null; playerNames: string[] | null; dateFromMs: number | null; dateToMs: number | null; isFavorite: boolean | null }
 export type EncounterMarkerData = { offsetMs: number; sequence: number; casterEntityId: string; skillId: string; kind: HistoryCastKind;
 /**
  * Fantasy remodel tier when recorded. Absent on older encounters and non-fantasy casts.
  */
 remodelLevel?: number | null }
-export type EncounterRangeData = { encounterId: number; qualityFlags: HistoryQualityFlag[]; startMs: number; endMsExclusive: number; bucketMs: number; totals: EncounterTotalsData; entities: EncounterEntityData[]; chartPoints: EncounterChartPointData[]; series?: EncounterChartSeriesData[]; markers: EncounterMarkerData[] }
+/**
+ * One boss phase, clipped to the projected window. Phase 1 starts with the
+ * encounter and has no trigger.
+ */
+export type EncounterPhaseData = { phase: number; name: string; trigger: HistoryPhaseTrigger | null; detail: number | null; startMs: number; endMsExclusive: number; totals: EncounterTotalsData }
+export type EncounterRangeData = { encounterId: number; qualityFlags: HistoryQualityFlag[]; startMs: number; endMsExclusive: number; bucketMs: number; totals: EncounterTotalsData; entities: EncounterEntityData[]; chartPoints: EncounterChartPointData[]; series?: EncounterChartSeriesData[]; markers: EncounterMarkerData[];
+/**
+ * Empty unless the encounter recorded at least one phase boundary.
+ */
+phases?: EncounterPhaseData[];
+/**
+ * Configured buffs seen in the window, by entity, buff and source.
+ */
+buffUptimes?: EncounterBuffUptimeData[] }
 export type EncounterSkillData = { skillId: string; metric: HistoryMetric; property: number | null; damageMode: number | null; stats: EncounterStatsData }
 export type EncounterSourceBreakdownData = { sourceMonsterId: number | null; stats: EncounterStatsData; skills: EncounterSkillData[] }
 export type EncounterStatsData = { total: string; effectiveTotal: string; hits: string; criticalHits: string; criticalTotal: string; luckyHits: string; luckyTotal: string; triggerHits: string; blockedHits: string; luckyBlockHits: string }
@@ -641,7 +838,28 @@ export type GpuSupport = { cuda_available: boolean; opencl_available: boolean }
 export type HateEntry = { entityUuid: string; hateVal: number }
 export type HistoryCastKind = "boss_skill" | "fantasy" | "key_skill"
 export type HistoryMetric = "damage" | "healing" | "damage_taken"
-export type HistoryQualityFlag = "incompleteSegment" | "missingEntityContext" | "saturatedAmount"
+/**
+ * What closed the previous boss phase.
+ */
+export type HistoryPhaseTrigger =
+/**
+ * The tracked boss fell below an HP threshold; `detail` is the percent.
+ */
+"boss_hp" |
+/**
+ * The boss started a mechanic not seen earlier in the segment; `detail`
+ * is its base skill id.
+ */
+"boss_mechanic" |
+/**
+ * A new dungeon objective appeared; `detail` is its target id.
+ */
+"objective" |
+/**
+ * A different boss took over; `detail` is its monster id.
+ */
+"boss_changed"
+export type HistoryQualityFlag = "incompleteSegment" | "missingEntityContext" | "saturatedAmount" | "packetLoss" | "decodeFailure"
 export type HudFrame = { active: boolean; epoch: number; status: LiveStatusPayload | null; buffs: LiveBuffsPayload | null; monster: LiveMonsterPayload | null; fantasy: LiveFantasyPayload | null; snapshot: MinimapSnapshotUpdate | null; skillCasts: MinimapSkillCast[]; skillCastCursor: number; castsReset: boolean }
 export type HudFrameRequest = { epoch: number | null; statusRevision: number | null; buffsRevision: number | null; monsterRevision: number | null; fantasyRevision: number | null; snapshotRevision: number | null; skillCastCursor: number | null; gameInterest: boolean; monsterInterest: boolean; minimapInterest: boolean }
 export type HudLayoutMigration = { translateMinimap: boolean; minimapOffsetX: number; minimapOffsetY: number }
@@ -650,6 +868,12 @@ export type I18nRuntimeSnapshot = { locale: AppLocale }
  * Local player buff list (`live-buffs`), 50ms throttle.
  */
 export type LiveBuffsPayload = { revision: number; localBuffs: BuffUpdateState[] }
+/**
+ * Capture pipeline health (`live-capture-health`), 1s throttle. Stream
+ * counters are cumulative for the capture; `affected_ranges` are offsets into
+ * the active encounter, or the last one once it has ended.
+ */
+export type LiveCaptureHealthPayload = { revision: number; activeSegmentId: number | null; streams: CaptureStreamHealth[]; affectedRanges: CaptureAffectedRange[] }
 /**
  * Combat / segment topic for the live meter window (`live-combat`).
  * `scene_id`/`dungeon_difficulty` live only on the nested `combat` payload;
@@ -660,7 +884,15 @@ export type LiveCombatPayload = { revision: number; activeSegmentId: number | nu
 /**
  * Represents a raw
  */
-export type LiveDataPayload = { elapsedMs: string; activeCombatTimeMs: string; fightStartTimestampMs: string; totalDmg: string; totalDmgBossOnly: string; totalHeal: string; totalEffectiveHeal: string; localPlayerUuid: string; sceneId: number | null; dungeonDifficulty: number | null; isPaused: boolean; bosses: BossHealth[]; entities: RawEntityData[] }
+export type LiveDataPayload = { elapsedMs: string; activeCombatTimeMs: string; fightStartTimestampMs: string; totalDmg: string; totalDmgBossOnly: string; totalHeal: string; totalEffectiveHeal: string; localPlayerUuid: string; sceneId: number | null; dungeonDifficulty: number | null; isPaused: boolean; bosses: BossHealth[]; entities: RawEntityData[];
+/**
+ * Boss phases of the segment; empty until a second phase opens.
+ */
+phases: EncounterPhaseData[];
+/**
+ * Uptime of the configured buffs over the segment so far.
+ */
+buffUptimes: EncounterBuffUptimeData[] }
 /**
  * Player death replays (`live-deaths`), 50ms throttle. Dirty only when a
  * record is appended or the segment resets, so it never rides the combat
@@ -681,8 +913,26 @@ export type LiveFantasyPayload = { revision: number; teammateFantasies: Teammate
  */
 export type LiveMonsterPayload = { revision: number; bossBuffs: Partial<{ [key in string]: BuffUpdateState[] }>; teammateBuffs: Partial<{ [key in string]: BuffUpdateState[] }>; bossMechanics: BossDbmEvent[]; hateLists: Partial<{ [key in string]: HateEntry[] }>; stun: StunEntry[]; playerNames: Partial<{ [key in string]: string }>; monsterIds: Partial<{ [key in string]: number }> }
 export type LivePullWindow = "live" | "hud-overlay"
-export type LiveRuntimeSnapshot = { eventUpdateRateMs: number; trainingWindowMs: number }
+export type LiveRuntimeSnapshot = { eventUpdateRateMs: number; trainingWindowMs: number; segmentPolicy: SegmentPolicy;
+/**
+ * Adds each player's damage per boss and elite to the live payload.
+ */
+targetBreakdown: boolean;
+/**
+ * Buff base ids whose uptime is tracked on the local player, teammates
+ * and bosses.
+ */
+uptimeBuffIds: number[] }
 export type LiveScenePayload = { revision: number; sceneId: number | null; dungeonDifficulty: number | null }
+/**
+ * One game client and the TCP streams it has used so far (strings, as in
+ * [`CaptureStreamHealth`]).
+ */
+export type LiveSessionSummary = { sessionId: number; localPlayerUuid: string | null; localPlayerName: string | null; streamIds: string[] }
+/**
+ * Game clients seen by the capture. Live windows show the selected one.
+ */
+export type LiveSessionsPayload = { revision: number; selectedSessionId: number | null; sessions: LiveSessionSummary[] }
 /**
  * Skill CD / panel attrs / fight resource / shields / counters
  * (`live-status`). Published on the same 50ms cadence as the other overlay
@@ -694,9 +944,28 @@ export type LiveStatusPayload = { revision: number; counters: CounterUpdateState
  * Highest deep-sleep (800522) `seasonId` resolved from the last
  * container sync/patch; `0` before any season data has been observed.
  */
-seasonId: number; seasonActiveTemplateIds: number[]; skillCds: SkillCdState[]; panelAttrs: PanelAttrState[]; shieldCurrentHp: number; shieldMaxHp: number; shieldEntries: ShieldDetailEntry[]; fightResource: FightResourceState | null }
+seasonId: number; seasonActiveTemplateIds: number[]; skillCds: SkillCdState[]; panelAttrs: PanelAttrState[]; shieldCurrentHp: number; shieldMaxHp: number; shieldEntries: ShieldDetailEntry[]; fightResource: FightResourceState | null;
+/**
+ * Counters and bars published by user scripts, in script then name order.
+ */
+scriptCounters: ScriptCounterState[]; scriptErrors: ScriptErrorState[] }
 export type LiveWindowFrame = { active: boolean; epoch: number; combat: LiveCombatPayload | null; fantasy: LiveFantasyPayload | null; deaths: LiveDeathsPayload | null }
 export type LiveWindowFrameRequest = { epoch: number | null; combatRevision: number | null; fantasyRevision: number | null; deathsRevision: number | null; includeDeaths: boolean }
+export type LocalApiSettings = { enabled: boolean; port: number;
+/**
+ * Listen on every interface instead of loopback only.
+ */
+allowLan: boolean; token: string | null; 
+/**
+ * Browser origins allowed besides the app's own, e.g.
+ * `https://overlay.example`. `null` admits pages opened from a local
+ * file, such as an OBS browser source pointed at one.
+ */
+allowedOrigins: string[] }
+/**
+ * Local API settings and where the listener is bound while it runs.
+ */
+export type LocalApiStatusPayload = { settings: LocalApiSettings; listenAddress: string | null }
 /**
  * A single active buff fact currently known to the minimap.
  */
@@ -781,6 +1050,11 @@ facing: number | null;
  * Whether the entity is currently in the dead actor state.
  */
 isDead: boolean;
+/**
+ * Bumped each time the entity teleports or is pulled back. Positions
+ * from different epochs must not be joined into one trail.
+ */
+positionEpoch: number;
 /**
  * Top-level summoner/owner UUID when present.
  */
@@ -889,6 +1163,23 @@ export type MonitorRuntimeSnapshot = { i18n: I18nRuntimeSnapshot; live: LiveRunt
  */
 export type MonsterBuffSourceScope = "anySource" | "localPlayerSource"
 export type MonsterRuntimeSnapshot = { enabled: boolean; globalIds: number[]; selfAppliedIds: number[]; monitorAllSelfApplied: boolean }
+/**
+ * The `packetCapture.json` that `live_main` reads when capture starts.
+ */
+export type PacketCaptureSettings = { method: string; npcapDevice: string; 
+/**
+ * Capture file replayed by the `PcapFile` method.
+ */
+pcapFile: string; 
+/**
+ * Where the `Remote` method listens for capture agents; empty means
+ * loopback on the default port.
+ */
+remoteListen: string; 
+/**
+ * Key shared with the remote capture agents.
+ */
+remoteKey: string; sessionRecording: CaptureRecordingSettings }
 export type PanelAttrState = { attrId: number; value: number }
 /**
  * Damage taken by a defender, aggregated by the attacking monster's template.
@@ -900,12 +1191,101 @@ export type PerSourceStats = {
 sourceMonsterId: number | null; totalValue: string; taken: RawCombatStats; skills: Partial<{ [key in number]: RawSkillStats }> }
 export type PlayerNamesResult = { names: string[] }
 export type PlayerSummaryDto = { name: string; classId: number }
+/**
+ * Messages the decoder discarded while protocol discovery was enabled, most
+ * frequent first.
+ */
+export type ProtocolDiscoveryPayload = { enabled: boolean; messages: DiscoveredMessage[];
+/**
+ * Sightings of further keys once the table was full.
+ */
+droppedSightings: number }
 export type RawCombatStats = { total: string; effectiveTotal: string; hits: string; critHits: string; critTotal: string; luckyHits: string; luckyTotal: string; triggerHits: string; blockHits: string; luckyBlockHits: string }
-export type RawEntityData = { entityUuid: string; displayUid: number; name: string; classId: number; classSpec: number; className: string; classSpecName: string; abilityScore: number; seasonStrength: number; damage: RawCombatStats; damageBossOnly: RawCombatStats; healing: RawCombatStats; taken: RawCombatStats; dmgSkills: Partial<{ [key in number]: RawSkillStats }>; healSkills: Partial<{ [key in number]: RawSkillStats }>; takenSkills: Partial<{ [key in number]: RawSkillStats }>; takenPerSource: PerSourceStats[] }
+export type RawEntityData = { entityUuid: string; displayUid: number; name: string; classId: number; classSpec: number; className: string; classSpecName: string; abilityScore: number; seasonStrength: number; damage: RawCombatStats; damageBossOnly: RawCombatStats; healing: RawCombatStats; taken: RawCombatStats; dmgSkills: Partial<{ [key in number]: RawSkillStats }>; healSkills: Partial<{ [key in number]: RawSkillStats }>; takenSkills: Partial<{ [key in number]: RawSkillStats }>; takenPerSource: PerSourceStats[];
+/**
+ * Outgoing rates over the trailing windows, narrowest first; empty for
+ * entities that only took damage.
+ */
+rolling: RawRollingWindow[];
+/**
+ * Damage per boss or elite target, largest first; empty unless the
+ * per-target breakdown is enabled.
+ */
+targets: RawTargetStats[] }
+/**
+ * Damage and healing per second over one trailing window of combat, and
+ * the best damage rate a window of that length reached this segment.
+ */
+export type RawRollingWindow = { windowMs: number; dps: string; hps: string; peakDps: string;
+/**
+ * Segment offset at which the peak window ended; `None` before the
+ * first damage.
+ */
+peakEndMs: number | null }
 export type RawSkillStats = { totalValue: string; effectiveTotalValue: string; hits: string; critHits: string; critTotalValue: string; luckyHits: string; luckyTotalValue: string; property: number | null; damageMode: number | null; triggerHits: string; blockHits: string; luckyBlockHits: string }
+/**
+ * Damage one entity dealt to a single boss or elite.
+ */
+export type RawTargetStats = {
+/**
+ * The target's entity UUID, serialized as a string for JS safety.
+ */
+entityUuid: string; monsterId: number | null; isBoss: boolean; totalValue: string; hits: string;
+/**
+ * Damage dealt as a percentage of the target's max HP; `None` while
+ * its max HP is unknown.
+ */
+hpSharePct: number | null }
 export type RecentEncountersResult = { rows: EncounterSummaryDto[]; totalCount: number }
+export type ReplayStats = { envelopes: number; publications: number;
+/**
+ * Recorded time between the first and last envelope.
+ */
+recordedDurationMs: number }
 export type ResetBuffTarget = "selfPlayer" | "anyTeam"
 export type SceneIdsResult = { ids: number[] }
+/**
+ * A value a user script published with `counter()` or `bar()`; `max` is set
+ * only for bars.
+ */
+export type ScriptCounterState = { scriptId: string; name: string; value: number; max: number | null }
+/**
+ * Why a user script stopped running; it stays stopped until the scripts are
+ * reloaded.
+ */
+export type ScriptErrorState = { scriptId: string; message: string }
+/**
+ * What ends a standard segment besides manual resets, resyncs and the
+ * lifetime cap.
+ */
+export type SegmentMode =
+/**
+ * Split on new dungeon objectives and wipes (raid heuristics).
+ */
+"objectiveAndWipe" |
+/**
+ * One segment per dungeon run: split when a new run starts or the scene
+ * changes, never on objectives or wipes.
+ */
+"dungeonRun" |
+/**
+ * Split only when the scene changes.
+ */
+"sceneChange" |
+/**
+ * Only damage to bosses opens and feeds a segment; it ends on a wipe, a
+ * new objective or a boss death.
+ */
+"bossOnly"
+export type SegmentPolicy = { mode: SegmentMode;
+/**
+ * Grace period between an automatic boundary and the segment end.
+ */
+boundaryDelayMs: number; maxSegmentDurationMs: number;
+/**
+ * Monsters whose first local hit opens an armed training segment.
+ */
+trainingDummyMonsterIds: number[] }
 /**
  * A single shield entry parsed from attr 60050.
  */
@@ -984,6 +1364,15 @@ export type TeammateRuntimeSnapshot = { enabled: boolean; anySourceIds: number[]
 export type TickAttrCondition = { attrId: number; requiredValue: number }
 export type TrainingDummyPhase = "idle" | "armed" | "running" | "finished"
 export type TrainingDummyState = { phase: TrainingDummyPhase }
+/**
+ * Load result for one `.rhai` file in the scripts folder.
+ */
+export type UserScriptStatus = { scriptId: string;
+/**
+ * Read or compile error; `None` when the script was handed to the live runtime.
+ */
+error: string | null }
+export type UserScriptsPayload = { directory: string; scripts: UserScriptStatus[] }
 /**
  * A single generated take (WAV) for a phrase, using a specific profile/model/params.
  */
@@ -1129,6 +1518,19 @@ export type VoiceTrigger =
  * `seconds_before` seconds.
  */
 { kind: "counterExpiring"; ruleId: number; slotId: number; secondsBefore: number }
+/**
+ * Protobuf wire format read without a schema. `trailing_hex` holds what
+ * followed the last field that parsed; `truncated` marks a sample cut short
+ * before dumping.
+ */
+export type WireDump = { payloadBytes: number; truncated: boolean; fields: WireField[]; trailingHex: string | null }
+export type WireField = { number: number; value: WireValue }
+/**
+ * 64-bit integers are strings, as in [`CaptureStreamHealth`]. Length-delimited
+ * fields are guessed as text, then a nested message, then packed varints,
+ * falling back to raw bytes.
+ */
+export type WireValue = { wireType: "varint"; value: string; zigzag: string } | { wireType: "fixed64"; value: string; double: number } | { wireType: "fixed32"; value: number; float: number } | { wireType: "message"; len: number; fields: WireField[] } | { wireType: "text"; value: string } | { wireType: "packedVarints"; values: string[] } | { wireType: "bytes"; len: number; hex: string }
 
 /** tauri-specta globals **/
 
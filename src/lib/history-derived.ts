@@ -198,6 +198,10 @@ export function historyEntityToRaw(entity: EncounterEntityData): HistoryEntity {
         skills: historySkillRecord(source.skills, "damage_taken"),
       }),
     ),
+    // Trailing windows only exist live; history keeps the peaks in `bursts`.
+    rolling: [],
+    // Per-target damage is already carried in `dmgPerTarget` below.
+    targets: [],
     monsterId: entity.monsterId,
     dmgPerTarget: entity.damageTargets.map((target) =>
       historyPerTarget(target, "damage"),
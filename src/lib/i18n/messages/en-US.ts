@@ -137,6 +137,29 @@ export const enUSMessages = {
   "appSettings.tabs.diagnostics": "Diagnostics",
   "appSettings.language.title": "Language",
   "appSettings.language.description": "Switch the interface display language.",
+  "appSettings.localApi.title": "Local API",
+  "appSettings.localApi.description": "Serves the live topics over HTTP and WebSocket for OBS browser sources, Stream Deck plugins and scripts. Endpoints: /api/topics, /api/topics/{topic} and /api/ws.",
+  "appSettings.localApi.enabled": "Enable Local API",
+  "appSettings.localApi.port": "Port",
+  "appSettings.localApi.allowLan": "Allow LAN Access",
+  "appSettings.localApi.allowLanDescription": "Listen on every interface instead of 127.0.0.1 only. Requires an access token.",
+  "appSettings.localApi.token": "Access Token",
+  "appSettings.localApi.tokenDescription": "Sent as \"Authorization: Bearer <token>\" or ?token=. Leave empty to allow any local client.",
+  "appSettings.localApi.allowedOrigins": "Allowed Browser Origins",
+  "appSettings.localApi.allowedOriginsDescription": "Web pages other than this app are refused unless their origin is listed here, one per line (e.g. https://overlay.example). Use null for pages opened from a local file. Clients that send no origin, such as OBS and scripts, are always allowed.",
+  "appSettings.localApi.apply": "Apply",
+  "appSettings.localApi.listening": "Listening on {address}",
+  "appSettings.localApi.stopped": "Not running",
+  "appSettings.localApi.toast.applied": "Local API settings applied",
+  "appSettings.localApi.toast.applyFailed": "Failed to apply local API settings: {error}",
+  "appSettings.userScripts.title": "User Scripts",
+  "appSettings.userScripts.description": ".rhai scripts in the scripts folder load at startup. Reload after editing them; counters and bars they publish appear in the game HUD's script counter area.",
+  "appSettings.userScripts.empty": "No scripts in the folder",
+  "appSettings.userScripts.loaded": "Loaded",
+  "appSettings.userScripts.failed": "Failed to load",
+  "appSettings.userScripts.reload": "Reload Scripts",
+  "appSettings.userScripts.toast.reloaded": "Reloaded {count} scripts",
+  "appSettings.userScripts.toast.reloadFailed": "Failed to reload scripts: {error}",
   "appSettings.appearance.scope":
     "The appearance settings below are local, global settings — they never travel with an exported loadout.",
   "appSettings.appearance.colorScopeMain":
@@ -464,6 +487,7 @@ export const enUSMessages = {
   "history.detail.tabs.tanked": "Damage Taken",
   "history.detail.tabs.healing": "Healing",
   "history.detail.tabs.death": "Death Replay",
+  "history.detail.tabs.buffs": "Buff Uptime",
   "history.detail.actions.backToHistory": "Back to history",
   "history.detail.actions.openWebsiteTitle":
     "Open this encounter on resonance-logs.com",
@@ -518,6 +542,14 @@ export const enUSMessages = {
   "history.detail.skills.title": "Skill Details",
   "history.detail.healTargets.title": "Healing Target Breakdown",
   "history.detail.healTargets.empty": "No target healing data",
+  "history.detail.buffs.empty":
+    "No configured buffs were recorded in this window. Choose uptime buffs in the live settings.",
+  "history.detail.buffs.unknownSource": "Unknown",
+  "history.detail.buffs.table.entity": "Entity",
+  "history.detail.buffs.table.buff": "Buff",
+  "history.detail.buffs.table.source": "Source",
+  "history.detail.buffs.table.uptime": "Uptime",
+  "history.detail.buffs.table.averageStacks": "Avg. Stacks",
   "history.detail.death.empty":
     "No player deaths were recorded for this encounter.",
   "history.detail.death.notFound": "Death record not found.",
@@ -637,6 +669,25 @@ export const enUSMessages = {
   "settings.live.trainingWindow": "Training Duration",
   "settings.live.trainingWindowDescription":
     "Starts when you hit a dummy and ends when this duration elapses. Default 3:03 (183 seconds). Changing this does not affect a run already in progress.",
+  "settings.live.segmentMode": "Segment Mode",
+  "settings.live.segmentModeDescription":
+    "Decides when the current encounter ends automatically and a new one starts.",
+  "settings.live.segmentMode.objectiveAndWipe": "Split on objective / wipe",
+  "settings.live.segmentMode.dungeonRun": "One per dungeon run",
+  "settings.live.segmentMode.sceneChange": "Split on scene change only",
+  "settings.live.segmentMode.bossOnly": "Bosses only (ignore trash)",
+  "settings.live.segmentBoundaryDelay": "Segment Delay",
+  "settings.live.segmentBoundaryDelayDescription":
+    "How long recording continues after an automatic split is triggered (0-30000ms).",
+  "settings.live.maxSegmentDuration": "Max Segment Duration",
+  "settings.live.maxSegmentDurationDescription":
+    "Ends an encounter automatically once it lasts this long (1-240 minutes).",
+  "settings.live.targetBreakdown": "Per-Target Breakdown",
+  "settings.live.targetBreakdownDescription":
+    "Adds each player's damage on every boss and elite, with its share of the target's HP, to the combat topic of the local API. The meter windows do not show it.",
+  "settings.live.uptimeBuffIds": "Buff Uptime",
+  "settings.live.uptimeBuffIdsDescription":
+    "Comma-separated buff IDs (up to 30) whose uptime and average stacks are tracked on you, your team and bosses, live and in history.",
   "settings.network.packetCapture": "Packet Capture",
   "settings.network.method": "Capture Method",
   "settings.network.methodDescription":
@@ -649,6 +700,29 @@ export const enUSMessages = {
     "Choose the network adapter used to capture traffic.",
   "settings.network.deviceLoading": "Loading devices...",
   "settings.network.devicePlaceholder": "Select device",
+  "settings.network.interface": "Network Interface",
+  "settings.network.interfaceDescription":
+    "Linux interface to capture on, such as eth0. Leave empty to capture on every interface. Needs CAP_NET_RAW or root.",
+  "settings.network.methodPcapFile": "Capture file",
+  "settings.network.methodRemote": "Remote agent",
+  "settings.network.remoteListen": "Listen Address",
+  "settings.network.remoteListenDescription":
+    "Address remote capture agents connect to. Leave empty for 127.0.0.1:47100; use the LAN address or 0.0.0.0 to accept agents on other machines.",
+  "settings.network.remoteKey": "Shared Key",
+  "settings.network.remoteKeyDescription":
+    "Agents must present this key. Remote capture does not start without one.",
+  "settings.network.pcapFile": "Capture File",
+  "settings.network.pcapFileDescription":
+    "A .pcap or .pcapng file that is replayed instead of live traffic.",
+  "settings.network.pcapFileBrowse": "Browse",
+  "settings.network.sessionRecording": "Session Recording",
+  "settings.network.sessionRecordingEnabled": "Record Capture Sessions",
+  "settings.network.sessionRecordingDescription":
+    "Save the captured game traffic to rotating files in the app data folder so sessions can be replayed later (requires app restart).",
+  "settings.network.sessionRecordingMaxFileMb": "Maximum File Size (MB)",
+  "settings.network.sessionRecordingMaxFiles": "Files to Keep",
+  "settings.network.sessionRecordingMaxFilesDescription":
+    "The oldest recording is deleted once this many files exist.",
   "settings.network.restartHint":
     "Fully quit and restart the app after changing the capture method or network adapter for the new setting to take effect.",
   "settings.shortcuts.clearHint": "Right-click to clear a shortcut",
@@ -1336,6 +1410,7 @@ export const enUSMessages = {
   "skillMonitor.panelAttr.textStyle": "Text style",
   "skillMonitor.overlay.customPanel": "Custom Monitor Area: {state}",
   "skillMonitor.overlay.shieldDetail": "HP/Shield Area: {state}",
+  "skillMonitor.overlay.scriptCounters": "Script Counter Area: {state}",
   "skillMonitor.overlay.help":
     "Click a button to toggle visibility. Saved per profile",
   "monsterMonitor.title": "Monster Monitor",
@@ -1553,6 +1628,9 @@ export const enUSMessages = {
   "gameOverlay.group.skillCd": "Skill CD Area",
   "gameOverlay.group.textBuff": "Text Buff Area",
   "gameOverlay.group.shieldDetail": "HP/Shield Area",
+  "gameOverlay.group.scriptCounters": "Script Counter Area",
+  "gameOverlay.scriptCounters.empty": "No user script has published a counter yet",
+  "gameOverlay.scriptCounters.stopped": "Stopped",
   "gameOverlay.groupedBuff.empty":
     "Create buff groups on the Skill Monitor page first",
   "gameOverlay.timer.infinite": "∞",
@@ -136,6 +136,29 @@ export const jaJPMessages = {
   "appSettings.language.title": "言語",
   "appSettings.language.description":
     "インターフェースの表示言語を切り替えます。",
+  "appSettings.localApi.title": "ローカル API",
+  "appSettings.localApi.description": "OBS ブラウザソース、Stream Deck プラグイン、スクリプト向けにライブトピックを HTTP と WebSocket で提供します。エンドポイント：/api/topics、/api/topics/{topic}、/api/ws。",
+  "appSettings.localApi.enabled": "ローカル API を有効化",
+  "appSettings.localApi.port": "ポート",
+  "appSettings.localApi.allowLan": "LAN からのアクセスを許可",
+  "appSettings.localApi.allowLanDescription": "127.0.0.1 だけでなくすべてのインターフェースで待ち受けます。アクセストークンが必要です。",
+  "appSettings.localApi.token": "アクセストークン",
+  "appSettings.localApi.tokenDescription": "\"Authorization: Bearer <トークン>\" または ?token= で送信します。空欄の場合はローカルのすべてのクライアントを許可します。",
+  "appSettings.localApi.allowedOrigins": "許可するブラウザオリジン",
+  "appSettings.localApi.allowedOriginsDescription": "このアプリ以外の Web ページは、ここにオリジンを 1 行ずつ記載した場合のみアクセスできます（例：https://overlay.example）。ローカルファイルから開いたページには null を指定します。OBS やスクリプトなどオリジンを送信しないクライアントは常に許可されます。",
+  "appSettings.localApi.apply": "適用",
+  "appSettings.localApi.listening": "{address} で待ち受け中",
+  "appSettings.localApi.stopped": "停止中",
+  "appSettings.localApi.toast.applied": "ローカル API の設定を適用しました",
+  "appSettings.localApi.toast.applyFailed": "ローカル API の設定を適用できませんでした: {error}",
+  "appSettings.userScripts.title": "ユーザースクリプト",
+  "appSettings.userScripts.description": "スクリプトフォルダ内の .rhai スクリプトは起動時に読み込まれます。編集後は再読み込みしてください。スクリプトが出力したカウンターとバーはゲーム HUD のスクリプトカウンターエリアに表示されます。",
+  "appSettings.userScripts.empty": "フォルダにスクリプトがありません",
+  "appSettings.userScripts.loaded": "読み込み済み",
+  "appSettings.userScripts.failed": "読み込み失敗",
+  "appSettings.userScripts.reload": "スクリプトを再読み込み",
+  "appSettings.userScripts.toast.reloaded": "{count} 個のスクリプトを再読み込みしました",
+  "appSettings.userScripts.toast.reloadFailed": "スクリプトを再読み込みできませんでした: {error}",
   "appSettings.appearance.scope":
     "以下の外観設定は端末ごとのグローバル設定です。構成のエクスポートには含まれません。",
   "appSettings.appearance.colorScopeMain":
@@ -447,6 +470,7 @@ export const jaJPMessages = {
   "history.detail.tabs.tanked": "被ダメージ",
   "history.detail.tabs.healing": "回復",
   "history.detail.tabs.death": "死亡リプレイ",
+  "history.detail.tabs.buffs": "バフ稼働率",
   "history.detail.actions.backToHistory": "履歴に戻る",
   "history.detail.actions.openWebsiteTitle":
     "resonance-logs.com でこのエンカウントを開く",
@@ -501,6 +525,14 @@ export const jaJPMessages = {
   "history.detail.skills.title": "スキル詳細",
   "history.detail.healTargets.title": "回復ターゲット内訳",
   "history.detail.healTargets.empty": "ターゲット回復データがありません",
+  "history.detail.buffs.empty":
+    "この範囲では設定済みのバフが記録されていません。ライブ設定で稼働率を集計するバフを選択してください。",
+  "history.detail.buffs.unknownSource": "不明",
+  "history.detail.buffs.table.entity": "対象",
+  "history.detail.buffs.table.buff": "バフ",
+  "history.detail.buffs.table.source": "付与元",
+  "history.detail.buffs.table.uptime": "稼働率",
+  "history.detail.buffs.table.averageStacks": "平均スタック",
   "history.detail.death.empty":
     "このエンカウントではプレイヤーの死亡は記録されていません。",
   "history.detail.death.notFound": "死亡記録が見つかりません。",
@@ -623,6 +655,24 @@ export const jaJPMessages = {
   "settings.live.trainingWindow": "訓練時間",
   "settings.live.trainingWindowDescription":
     "自分がダミーに命中した時点から計測し、この時間で終了します。デフォルトは 3:03（183 秒）。進行中のラウンドには反映されません。",
+  "settings.live.segmentMode": "区切り方式",
+  "settings.live.segmentModeDescription": "現在の戦闘を自動で終了し、新しい区間を始めるタイミングを決めます。",
+  "settings.live.segmentMode.objectiveAndWipe": "目標 / 全滅で区切る",
+  "settings.live.segmentMode.dungeonRun": "ダンジョン 1 回ごと",
+  "settings.live.segmentMode.sceneChange": "シーン切替時のみ",
+  "settings.live.segmentMode.bossOnly": "ボスのみ（雑魚を無視）",
+  "settings.live.segmentBoundaryDelay": "区切り遅延",
+  "settings.live.segmentBoundaryDelayDescription":
+    "自動区切りの発生後も記録を続ける時間（0-30000ms）。",
+  "settings.live.maxSegmentDuration": "区間の最大時間",
+  "settings.live.maxSegmentDurationDescription":
+    "この時間（1-240 分）を超えた戦闘を自動で終了します。",
+  "settings.live.targetBreakdown": "ターゲット別内訳",
+  "settings.live.targetBreakdownDescription":
+    "各プレイヤーのボス・エリート別ダメージと、対象の最大 HP に対する割合をローカル API の combat トピックに追加します。メーター画面には表示されません。",
+  "settings.live.uptimeBuffIds": "バフ稼働率",
+  "settings.live.uptimeBuffIdsDescription":
+    "カンマ区切りのバフ ID（最大 30 個）について、自分・チームメンバー・ボスへの稼働率と平均スタック数を集計し、ライブと履歴に記録します。",
   "settings.network.packetCapture": "パケットキャプチャ",
   "settings.network.method": "キャプチャ方式",
   "settings.network.methodDescription":
@@ -635,6 +685,29 @@ export const jaJPMessages = {
     "トラフィックキャプチャに使用するネットワークアダプターを選択します。",
   "settings.network.deviceLoading": "デバイスを読み込み中...",
   "settings.network.devicePlaceholder": "デバイスを選択",
+  "settings.network.interface": "ネットワークインターフェース",
+  "settings.network.interfaceDescription":
+    "キャプチャする Linux のインターフェース（例: eth0）。空欄にするとすべてのインターフェースでキャプチャします。CAP_NET_RAW 権限または root が必要です。",
+  "settings.network.methodPcapFile": "キャプチャファイル",
+  "settings.network.methodRemote": "リモートエージェント",
+  "settings.network.remoteListen": "待ち受けアドレス",
+  "settings.network.remoteListenDescription":
+    "リモートキャプチャエージェントの接続先アドレスです。空欄の場合は 127.0.0.1:47100 になります。他のマシンのエージェントを受け入れるには LAN のアドレスか 0.0.0.0 を指定してください。",
+  "settings.network.remoteKey": "共有キー",
+  "settings.network.remoteKeyDescription":
+    "エージェントはこのキーを提示する必要があります。キーがない場合、リモートキャプチャは開始されません。",
+  "settings.network.pcapFile": "キャプチャファイル",
+  "settings.network.pcapFileDescription":
+    "ライブトラフィックの代わりに再生する .pcap または .pcapng ファイルです。",
+  "settings.network.pcapFileBrowse": "参照",
+  "settings.network.sessionRecording": "セッション記録",
+  "settings.network.sessionRecordingEnabled": "キャプチャセッションを記録",
+  "settings.network.sessionRecordingDescription":
+    "キャプチャしたゲーム通信をアプリのデータフォルダーにローテーションしながら保存し、後で再生できるようにします（アプリの再起動が必要です）。",
+  "settings.network.sessionRecordingMaxFileMb": "最大ファイルサイズ（MB）",
+  "settings.network.sessionRecordingMaxFiles": "保持するファイル数",
+  "settings.network.sessionRecordingMaxFilesDescription":
+    "この数に達すると最も古い記録が削除されます。",
   "settings.network.restartHint":
     "キャプチャ方式またはネットワークアダプタを変更した後は、アプリを完全に終了して再起動しないと新しい設定は反映されません。",
   "settings.shortcuts.clearHint": "右クリックでショートカットをクリア",
@@ -1323,6 +1396,7 @@ export const jaJPMessages = {
   "skillMonitor.panelAttr.textStyle": "テキストスタイル",
   "skillMonitor.overlay.customPanel": "カスタムモニターエリア: {state}",
   "skillMonitor.overlay.shieldDetail": "HP/シールドエリア: {state}",
+  "skillMonitor.overlay.scriptCounters": "スクリプトカウンターエリア: {state}",
   "skillMonitor.overlay.help":
     "ボタンをクリックして表示を切替。プロファイルごとに保存",
   "monsterMonitor.title": "モンスターモニター",
@@ -1542,6 +1616,9 @@ export const jaJPMessages = {
   "gameOverlay.group.skillCd": "スキル CD エリア",
   "gameOverlay.group.textBuff": "テキスト Buff エリア",
   "gameOverlay.group.shieldDetail": "HP/シールドエリア",
+  "gameOverlay.group.scriptCounters": "スクリプトカウンターエリア",
+  "gameOverlay.scriptCounters.empty": "ユーザースクリプトのカウンターはまだありません",
+  "gameOverlay.scriptCounters.stopped": "停止中",
   "gameOverlay.groupedBuff.empty":
     "先にスキルモニターページで Buff グループを作成してください",
   "gameOverlay.timer.infinite": "∞",
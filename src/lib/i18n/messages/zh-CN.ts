@@ -131,6 +131,29 @@ export const zhCNMessages = {
   "appSettings.tabs.diagnostics": "诊断",
   "appSettings.language.title": "语言",
   "appSettings.language.description": "切换界面显示语言。",
+  "appSettings.localApi.title": "本地接口",
+  "appSettings.localApi.description": "通过 HTTP 和 WebSocket 提供实时数据主题，供 OBS 浏览器源、Stream Deck 插件和脚本使用。接口：/api/topics、/api/topics/{topic} 和 /api/ws。",
+  "appSettings.localApi.enabled": "启用本地接口",
+  "appSettings.localApi.port": "端口",
+  "appSettings.localApi.allowLan": "允许局域网访问",
+  "appSettings.localApi.allowLanDescription": "监听所有网卡而不仅是 127.0.0.1，需要设置访问令牌。",
+  "appSettings.localApi.token": "访问令牌",
+  "appSettings.localApi.tokenDescription": "以 \"Authorization: Bearer <令牌>\" 或 ?token= 传入。留空则允许任何本机客户端访问。",
+  "appSettings.localApi.allowedOrigins": "允许的浏览器来源",
+  "appSettings.localApi.allowedOriginsDescription": "除本应用外的网页需在此列出其来源才可访问，每行一个（如 https://overlay.example）。填写 null 可允许从本地文件打开的页面。OBS、脚本等不发送来源的客户端始终允许。",
+  "appSettings.localApi.apply": "应用",
+  "appSettings.localApi.listening": "正在监听 {address}",
+  "appSettings.localApi.stopped": "未运行",
+  "appSettings.localApi.toast.applied": "本地接口设置已应用",
+  "appSettings.localApi.toast.applyFailed": "应用本地接口设置失败：{error}",
+  "appSettings.userScripts.title": "用户脚本",
+  "appSettings.userScripts.description": "脚本文件夹中的 .rhai 脚本会在启动时加载。编辑后点击重新加载；脚本发布的计数和进度条显示在游戏悬浮窗的脚本计数区。",
+  "appSettings.userScripts.empty": "文件夹中没有脚本",
+  "appSettings.userScripts.loaded": "已加载",
+  "appSettings.userScripts.failed": "加载失败",
+  "appSettings.userScripts.reload": "重新加载脚本",
+  "appSettings.userScripts.toast.reloaded": "已重新加载 {count} 个脚本",
+  "appSettings.userScripts.toast.reloadFailed": "重新加载脚本失败：{error}",
   "appSettings.appearance.scope":
     "以下外观设置为本机全局配置，不随方案导出分享。",
   "appSettings.appearance.colorScopeMain":
@@ -412,6 +435,7 @@ export const zhCNMessages = {
   "history.detail.tabs.tanked": "承伤",
   "history.detail.tabs.healing": "治疗",
   "history.detail.tabs.death": "死亡回放",
+  "history.detail.tabs.buffs": "增益覆盖",
   "history.detail.actions.backToHistory": "返回历史",
   "history.detail.actions.openWebsiteTitle":
     "在 resonance-logs.com 打开该战斗记录",
@@ -464,6 +488,13 @@ export const zhCNMessages = {
   "history.detail.skills.title": "技能明细",
   "history.detail.healTargets.title": "治疗目标分布",
   "history.detail.healTargets.empty": "暂无目标治疗数据",
+  "history.detail.buffs.empty": "此时间范围内没有记录到已配置的增益。请在实时设置中选择要统计覆盖率的增益。",
+  "history.detail.buffs.unknownSource": "未知",
+  "history.detail.buffs.table.entity": "目标",
+  "history.detail.buffs.table.buff": "增益",
+  "history.detail.buffs.table.source": "来源",
+  "history.detail.buffs.table.uptime": "覆盖率",
+  "history.detail.buffs.table.averageStacks": "平均层数",
   "history.detail.death.empty": "本次战斗没有记录到玩家死亡。",
   "history.detail.death.notFound": "未找到该死亡记录。",
   "history.detail.death.backToList": "返回列表",
@@ -574,6 +605,20 @@ export const zhCNMessages = {
   "settings.live.trainingWindow": "打桩时长",
   "settings.live.trainingWindowDescription":
     "从本人命中木桩起计时，到点结束本轮。默认 3:03（183 秒）。进行中的一轮不会改时长。",
+  "settings.live.segmentMode": "分段方式",
+  "settings.live.segmentModeDescription": "决定何时自动结束当前战斗并开始新的一段。",
+  "settings.live.segmentMode.objectiveAndWipe": "按目标/团灭分段",
+  "settings.live.segmentMode.dungeonRun": "每次副本一段",
+  "settings.live.segmentMode.sceneChange": "仅切换场景时分段",
+  "settings.live.segmentMode.bossOnly": "仅首领（忽略小怪）",
+  "settings.live.segmentBoundaryDelay": "分段延迟",
+  "settings.live.segmentBoundaryDelayDescription": "触发自动分段后继续记录的时间（0-30000ms）。",
+  "settings.live.maxSegmentDuration": "单段最长时长",
+  "settings.live.maxSegmentDurationDescription": "一段战斗持续超过此时长（1-240 分钟）时自动结束。",
+  "settings.live.targetBreakdown": "分目标统计",
+  "settings.live.targetBreakdownDescription": "在本地接口的 combat 主题中附带每名玩家对各首领和精英的伤害及其占目标血量的比例，统计窗口不显示。",
+  "settings.live.uptimeBuffIds": "Buff 覆盖率",
+  "settings.live.uptimeBuffIdsDescription": "以逗号分隔的 Buff ID（最多 30 个），统计其在自己、队友和首领身上的覆盖率与平均层数，实时显示并写入历史记录。",
   "settings.network.packetCapture": "抓包",
   "settings.network.method": "捕获方式",
   "settings.network.methodDescription":
@@ -584,6 +629,29 @@ export const zhCNMessages = {
   "settings.network.deviceDescription": "选择用于捕获流量的网卡。",
   "settings.network.deviceLoading": "正在加载设备...",
   "settings.network.devicePlaceholder": "选择设备",
+  "settings.network.interface": "网络接口",
+  "settings.network.interfaceDescription":
+    "要抓包的 Linux 网络接口，例如 eth0。留空则在所有接口上抓包。需要 CAP_NET_RAW 权限或 root。",
+  "settings.network.methodPcapFile": "抓包文件",
+  "settings.network.methodRemote": "远程代理",
+  "settings.network.remoteListen": "监听地址",
+  "settings.network.remoteListenDescription":
+    "远程抓包代理连接的地址。留空则为 127.0.0.1:47100；若要接受其他机器上的代理，请填写局域网地址或 0.0.0.0。",
+  "settings.network.remoteKey": "共享密钥",
+  "settings.network.remoteKeyDescription":
+    "代理必须提供此密钥。未设置密钥时不会启动远程抓包。",
+  "settings.network.pcapFile": "抓包文件",
+  "settings.network.pcapFileDescription":
+    "回放 .pcap 或 .pcapng 文件，代替实时流量。",
+  "settings.network.pcapFileBrowse": "浏览",
+  "settings.network.sessionRecording": "会话录制",
+  "settings.network.sessionRecordingEnabled": "录制抓包会话",
+  "settings.network.sessionRecordingDescription":
+    "将捕获的游戏流量按文件轮换保存到应用数据目录，以便之后回放（需要重启应用）。",
+  "settings.network.sessionRecordingMaxFileMb": "单个文件上限（MB）",
+  "settings.network.sessionRecordingMaxFiles": "保留文件数",
+  "settings.network.sessionRecordingMaxFilesDescription":
+    "文件数达到上限后删除最旧的录制。",
   "settings.network.restartHint":
     "更改捕获方式或网卡后需完全退出并重新启动应用，新设置才会生效。",
   "settings.shortcuts.clearHint": "右键可清除快捷键",
@@ -1222,6 +1290,7 @@ export const zhCNMessages = {
   "skillMonitor.panelAttr.textStyle": "文字样式",
   "skillMonitor.overlay.customPanel": "自定义监控区：{state}",
   "skillMonitor.overlay.shieldDetail": "血量护盾区：{state}",
+  "skillMonitor.overlay.scriptCounters": "脚本计数区：{state}",
   "skillMonitor.overlay.help": "点击按钮切换显示状态（按方案保存）",
   "monsterMonitor.title": "怪物监控",
   "monsterMonitor.description": "监控 Boss buff 等数据",
@@ -1429,6 +1498,9 @@ export const zhCNMessages = {
   "gameOverlay.group.skillCd": "技能CD区",
   "gameOverlay.group.textBuff": "无图标Buff区",
   "gameOverlay.group.shieldDetail": "血量护盾区",
+  "gameOverlay.group.scriptCounters": "脚本计数区",
+  "gameOverlay.scriptCounters.empty": "用户脚本尚未发布计数",
+  "gameOverlay.scriptCounters.stopped": "已停止",
   "gameOverlay.groupedBuff.empty": "请先在技能监控页创建 Buff 分组",
   "gameOverlay.timer.infinite": "∞",
   "gameOverlay.timer.empty": "--",
@@ -37,6 +37,8 @@ function entity(damage: RawCombatStats): RawEntityData {
     healSkills: {},
     takenSkills: {},
     takenPerSource: [],
+    rolling: [],
+    targets: [],
   };
 }
 
@@ -55,6 +57,8 @@ function payload(damage: RawCombatStats): LiveDataPayload {
     isPaused: false,
     bosses: [],
     entities: [entity(damage)],
+    phases: [],
+    buffUptimes: [],
   };
 }
 
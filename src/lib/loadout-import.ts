@@ -239,6 +239,8 @@ const categorySchema = v.picklist(["food", "alchemy"]);
 const categoryPointRecordSchema = v.record(categorySchema, pointSchema);
 const categoryNumberRecordSchema = v.record(categorySchema, finiteNumberSchema);
 
+const overlayDefaults = createDefaultSkillMonitorProfile();
+
 const overlayPositionsSchema = v.object({
   skillCdGroup: pointSchema,
   resourceGroup: pointSchema,
@@ -247,6 +249,11 @@ const overlayPositionsSchema = v.object({
   panelAttrGroup: pointSchema,
   customPanelGroup: pointSchema,
   shieldDetailGroup: pointSchema,
+  // Added with user scripts; older exports don't have the script group.
+  scriptCounterGroup: v.optional(
+    pointSchema,
+    defaultClone(overlayDefaults.overlayPositions.scriptCounterGroup),
+  ),
   iconBuffPositions: v.record(numericKeySchema, pointSchema),
   skillDurationPositions: v.record(numericKeySchema, pointSchema),
   categoryIconPositions: v.optional(categoryPointRecordSchema, {}),
@@ -259,6 +266,10 @@ const overlaySizesSchema = v.object({
   panelAttrGroupScale: finiteNumberSchema,
   customPanelGroupScale: finiteNumberSchema,
   shieldDetailGroupScale: finiteNumberSchema,
+  scriptCounterGroupScale: v.optional(
+    finiteNumberSchema,
+    overlayDefaults.overlaySizes.scriptCounterGroupScale,
+  ),
   panelAttrGap: finiteNumberSchema,
   panelAttrFontSize: finiteNumberSchema,
   panelAttrColumnGap: finiteNumberSchema,
@@ -280,6 +291,10 @@ const overlayVisibilitySchema = v.object({
   showPanelAttrGroup: v.boolean(),
   showCustomPanelGroup: v.boolean(),
   showShieldDetailGroup: v.boolean(),
+  showScriptCounterGroup: v.optional(
+    v.boolean(),
+    overlayDefaults.overlayVisibility.showScriptCounterGroup,
+  ),
 });
 
 const monsterOverlayPositionsSchema = v.object({
@@ -462,6 +477,15 @@ const liveGeneralSchema = v.object({
   abbreviatedDecimalPlaces: v.optional(finiteNumberSchema, 1),
   eventUpdateRateMs: v.optional(finiteNumberSchema, 200),
   trainingWindowMs: v.optional(finiteNumberSchema, 183000),
+  segmentMode: v.optional(
+    v.picklist(["objectiveAndWipe", "dungeonRun", "sceneChange", "bossOnly"]),
+    "objectiveAndWipe",
+  ),
+  segmentBoundaryDelayMs: v.optional(finiteNumberSchema, 3000),
+  maxSegmentDurationMs: v.optional(finiteNumberSchema, 1200000),
+  trainingDummyMonsterIds: v.optional(numberArraySchema, [115, 122]),
+  targetBreakdown: v.optional(v.boolean(), false),
+  uptimeBuffIds: v.optional(numberArraySchema, []),
 });
 
 const liveStatsSchema = v.record(v.string(), v.boolean());
@@ -295,6 +295,16 @@ export function buildMonitorRuntimeSnapshot(): MonitorRuntimeSnapshot {
     live: {
       eventUpdateRateMs: SETTINGS.live.general.state.eventUpdateRateMs,
       trainingWindowMs: SETTINGS.live.general.state.trainingWindowMs,
+      segmentPolicy: {
+        mode: SETTINGS.live.general.state.segmentMode,
+        boundaryDelayMs: SETTINGS.live.general.state.segmentBoundaryDelayMs,
+        maxSegmentDurationMs: SETTINGS.live.general.state.maxSegmentDurationMs,
+        trainingDummyMonsterIds: [
+          ...SETTINGS.live.general.state.trainingDummyMonsterIds,
+        ],
+      },
+      targetBreakdown: SETTINGS.live.general.state.targetBreakdown,
+      uptimeBuffIds: [...SETTINGS.live.general.state.uptimeBuffIds],
     },
     skill: buildSkillRuntimeSnapshot(),
     monster: buildMonsterRuntimeSnapshot(),
@@ -838,6 +838,7 @@ export type OverlayPositions = {
   panelAttrGroup: Point;
   customPanelGroup: Point;
   shieldDetailGroup: Point;
+  scriptCounterGroup: Point;
   iconBuffPositions: Record<number, Point>;
   skillDurationPositions: Record<number, Point>;
   categoryIconPositions?: Partial<Record<BuffCategoryKey, Point>>;
@@ -850,6 +851,7 @@ export type OverlaySizes = {
   panelAttrGroupScale: number;
   customPanelGroupScale: number;
   shieldDetailGroupScale: number;
+  scriptCounterGroupScale: number;
   panelAttrGap: number;
   panelAttrFontSize: number;
   panelAttrColumnGap: number;
@@ -866,6 +868,7 @@ export type OverlayVisibility = {
   showPanelAttrGroup: boolean;
   showCustomPanelGroup: boolean;
   showShieldDetailGroup: boolean;
+  showScriptCounterGroup: boolean;
 };
 
 export type OverlayTextStyle = {
@@ -1192,6 +1195,7 @@ function createDefaultOverlayPositions(): OverlayPositions {
     panelAttrGroup: { x: 700, y: 40 },
     customPanelGroup: { x: 700, y: 280 },
     shieldDetailGroup: { x: 40, y: 550 },
+    scriptCounterGroup: { x: 360, y: 550 },
     iconBuffPositions: {},
     skillDurationPositions: {},
     categoryIconPositions: {},
@@ -1206,6 +1210,7 @@ function createDefaultOverlaySizes(): OverlaySizes {
     panelAttrGroupScale: 1,
     customPanelGroupScale: 1,
     shieldDetailGroupScale: 1,
+    scriptCounterGroupScale: 1,
     panelAttrGap: 4,
     panelAttrFontSize: 14,
     panelAttrColumnGap: 12,
@@ -1224,6 +1229,7 @@ function createDefaultOverlayVisibility(): OverlayVisibility {
     showPanelAttrGroup: true,
     showCustomPanelGroup: true,
     showShieldDetailGroup: false,
+    showScriptCounterGroup: true,
   };
 }
 
@@ -1915,6 +1921,16 @@ const DEFAULT_GENERAL_SETTINGS = {
   abbreviatedDecimalPlaces: 1,
   eventUpdateRateMs: 200,
   trainingWindowMs: 183_000,
+  segmentMode: "objectiveAndWipe" as
+    | "objectiveAndWipe"
+    | "dungeonRun"
+    | "sceneChange"
+    | "bossOnly",
+  segmentBoundaryDelayMs: 3_000,
+  maxSegmentDurationMs: 1_200_000,
+  trainingDummyMonsterIds: [115, 122],
+  targetBreakdown: false,
+  uptimeBuffIds: [] as number[],
 };
 
 export const DEFAULT_CLASS_COLORS: Record<string, string> = {
@@ -2596,7 +2612,14 @@ export const SETTINGS = {
   appVersion: new RuneStore("appVersion", { value: "" }, RUNE_STORE_OPTIONS),
   packetCapture: new RuneStore(
     "packetCapture",
-    { method: "Npcap", npcapDevice: "" },
+    {
+      method: "Npcap",
+      npcapDevice: "",
+      pcapFile: "",
+      remoteListen: "",
+      remoteKey: "",
+      sessionRecording: { enabled: false, maxFileMb: 256, maxFiles: 16 },
+    },
     RUNE_STORE_OPTIONS,
   ),
 };
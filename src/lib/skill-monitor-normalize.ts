@@ -21,6 +21,7 @@ export const DEFAULT_OVERLAY_SIZES: OverlaySizes = {
   panelAttrGroupScale: 1,
   customPanelGroupScale: 1,
   shieldDetailGroupScale: 1,
+  scriptCounterGroupScale: 1,
   panelAttrGap: 4,
   panelAttrFontSize: 14,
   panelAttrColumnGap: 12,
@@ -147,6 +148,9 @@ export function ensureOverlaySizes(profile: SkillMonitorProfile): OverlaySizes {
     shieldDetailGroupScale:
       current?.shieldDetailGroupScale ??
       DEFAULT_OVERLAY_SIZES.shieldDetailGroupScale,
+    scriptCounterGroupScale:
+      current?.scriptCounterGroupScale ??
+      DEFAULT_OVERLAY_SIZES.scriptCounterGroupScale,
     panelAttrGap: clampRounded(
       current?.panelAttrGap ?? DEFAULT_OVERLAY_SIZES.panelAttrGap,
       0,
@@ -80,5 +80,7 @@ export function buildSourceEntities(
     healSkills: {},
     takenSkills: src.skills,
     takenPerSource: [],
+    rolling: [],
+    targets: [],
   }));
 }
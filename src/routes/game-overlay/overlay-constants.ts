@@ -40,6 +40,7 @@ export const DEFAULT_OVERLAY_POSITIONS: OverlayPositions = {
   panelAttrGroup: { x: 700, y: 40 },
   customPanelGroup: { x: 700, y: 280 },
   shieldDetailGroup: { x: 40, y: 550 },
+  scriptCounterGroup: { x: 360, y: 550 },
   iconBuffPositions: {},
   skillDurationPositions: {},
   categoryIconPositions: {},
@@ -52,4 +53,5 @@ export const DEFAULT_OVERLAY_VISIBILITY: OverlayVisibility = {
   showPanelAttrGroup: true,
   showCustomPanelGroup: true,
   showShieldDetailGroup: false,
+  showScriptCounterGroup: true,
 };
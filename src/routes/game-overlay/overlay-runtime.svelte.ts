@@ -5,6 +5,7 @@ import type {
   ShieldDetailEntry,
   SkillCdState,
 } from "$lib/api";
+import type { ScriptCounterState, ScriptErrorState } from "$lib/bindings";
 import {
   liveBuffsStore,
   liveStatusStore,
@@ -139,6 +140,16 @@ export function shieldDetailEntries(): ShieldDetailEntry[] {
   return liveStatusStore.data?.shieldEntries ?? [];
 }
 
+/** Counters and bars published by user scripts, in script then name order. */
+export function scriptCounters(): ScriptCounterState[] {
+  return liveStatusStore.data?.scriptCounters ?? [];
+}
+
+/** User scripts that stopped on an error and stay stopped until reloaded. */
+export function scriptErrors(): ScriptErrorState[] {
+  return liveStatusStore.data?.scriptErrors ?? [];
+}
+
 export function buffDefinitions() {
   return overlayRuntime.buffDefinitions;
 }
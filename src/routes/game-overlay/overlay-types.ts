@@ -11,7 +11,8 @@ export type OverlayScaleKey =
   | "textBuffPanelScale"
   | "panelAttrGroupScale"
   | "customPanelGroupScale"
-  | "shieldDetailGroupScale";
+  | "shieldDetailGroupScale"
+  | "scriptCounterGroupScale";
 
 export type SkillDisplay = {
   isActive: boolean;
@@ -71,6 +71,9 @@ export function ensureOverlayPositions(
       current?.customPanelGroup ?? DEFAULT_OVERLAY_POSITIONS.customPanelGroup,
     shieldDetailGroup:
       current?.shieldDetailGroup ?? DEFAULT_OVERLAY_POSITIONS.shieldDetailGroup,
+    scriptCounterGroup:
+      current?.scriptCounterGroup ??
+      DEFAULT_OVERLAY_POSITIONS.scriptCounterGroup,
     iconBuffPositions: current?.iconBuffPositions ?? {},
     skillDurationPositions: current?.skillDurationPositions ?? {},
     categoryIconPositions: current?.categoryIconPositions ?? {},
@@ -99,6 +102,9 @@ export function ensureOverlayVisibility(
     showShieldDetailGroup:
       current?.showShieldDetailGroup ??
       DEFAULT_OVERLAY_VISIBILITY.showShieldDetailGroup,
+    showScriptCounterGroup:
+      current?.showScriptCounterGroup ??
+      DEFAULT_OVERLAY_VISIBILITY.showScriptCounterGroup,
   };
 }
 
@@ -19,6 +19,7 @@ function entity(uuid: string, name: string): MinimapEntity {
     monsterId: null,
     facing: null,
     isDead: false,
+    positionEpoch: 0,
     topSummonerId: null,
   };
 }
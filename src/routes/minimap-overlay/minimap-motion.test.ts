@@ -0,0 +1,62 @@
+import { describe, expect, it } from "vitest";
+import type { MinimapEntity } from "$lib/api";
+import { MINIMAP_GLIDE_MS, MinimapMotion } from "./minimap-motion";
+
+function entity(x: number, z: number, positionEpoch = 0): MinimapEntity {
+  return {
+    entityUuid: "mate",
+    entityType: "char",
+    kind: "teammate",
+    x,
+    y: 0,
+    z,
+    name: null,
+    monsterId: null,
+    facing: null,
+    isDead: false,
+    positionEpoch,
+    topSummonerId: null,
+  };
+}
+
+describe("MinimapMotion", () => {
+  it("glides between snapshot positions", () => {
+    const motion = new MinimapMotion();
+    motion.track([entity(0, 0)], 0);
+    motion.track([entity(10, 20)], 100);
+
+    expect(motion.position(entity(10, 20), 100)).toEqual({ x: 0, z: 0 });
+    expect(
+      motion.position(entity(10, 20), 100 + MINIMAP_GLIDE_MS / 2),
+    ).toEqual({ x: 5, z: 10 });
+    expect(motion.isGliding(100 + MINIMAP_GLIDE_MS / 2)).toBe(true);
+    expect(motion.position(entity(10, 20), 100 + MINIMAP_GLIDE_MS)).toEqual({
+      x: 10,
+      z: 20,
+    });
+    expect(motion.isGliding(100 + MINIMAP_GLIDE_MS)).toBe(false);
+  });
+
+  it("jumps when the position epoch changes", () => {
+    const motion = new MinimapMotion();
+    motion.track([entity(0, 0)], 0);
+    motion.track([entity(500, 500, 1)], 100);
+
+    expect(motion.position(entity(500, 500, 1), 100)).toEqual({
+      x: 500,
+      z: 500,
+    });
+    expect(motion.isGliding(100)).toBe(false);
+  });
+
+  it("keeps gliding when the same target is tracked again", () => {
+    const motion = new MinimapMotion();
+    motion.track([entity(0, 0)], 0);
+    motion.track([entity(10, 0)], 100);
+    motion.track([entity(10, 0)], 100 + MINIMAP_GLIDE_MS / 2);
+
+    expect(
+      motion.position(entity(10, 0), 100 + MINIMAP_GLIDE_MS / 2),
+    ).toEqual({ x: 5, z: 0 });
+  });
+});
@@ -0,0 +1,92 @@
+import type { MinimapEntity } from "$lib/api";
+
+/** How long a dot glides from where it was drawn to its new position. */
+export const MINIMAP_GLIDE_MS = 150;
+
+type Glide = {
+  fromX: number;
+  fromZ: number;
+  toX: number;
+  toZ: number;
+  startedMs: number;
+  positionEpoch: number;
+};
+
+/**
+ * Smooths entity dots between pulled snapshots. A new `positionEpoch` means
+ * the server moved the entity (teleport, forced pull-back), so the dot jumps
+ * there instead of sliding across the map.
+ */
+export class MinimapMotion {
+  #glides = new Map<string, Glide>();
+
+  /**
+   * Retargets every entity at `nowMs`. Idempotent while positions are
+   * unchanged, so it is safe to call on every drawn frame.
+   */
+  track(entities: MinimapEntity[], nowMs: number): void {
+    const seen = new Set<string>();
+    for (const entity of entities) {
+      seen.add(entity.entityUuid);
+      const glide = this.#glides.get(entity.entityUuid);
+      if (!glide || glide.positionEpoch !== entity.positionEpoch) {
+        this.#glides.set(entity.entityUuid, {
+          fromX: entity.x,
+          fromZ: entity.z,
+          toX: entity.x,
+          toZ: entity.z,
+          startedMs: nowMs,
+          positionEpoch: entity.positionEpoch,
+        });
+        continue;
+      }
+      if (glide.toX === entity.x && glide.toZ === entity.z) continue;
+      const current = positionAt(glide, nowMs);
+      this.#glides.set(entity.entityUuid, {
+        fromX: current.x,
+        fromZ: current.z,
+        toX: entity.x,
+        toZ: entity.z,
+        startedMs: nowMs,
+        positionEpoch: entity.positionEpoch,
+      });
+    }
+    for (const uuid of this.#glides.keys()) {
+      if (!seen.has(uuid)) this.#glides.delete(uuid);
+    }
+  }
+
+  /** Where to draw `entity` at `nowMs`; untracked entities draw as-is. */
+  position(entity: MinimapEntity, nowMs: number): { x: number; z: number } {
+    const glide = this.#glides.get(entity.entityUuid);
+    return glide ? positionAt(glide, nowMs) : { x: entity.x, z: entity.z };
+  }
+
+  /** Whether any dot still needs another frame to reach its position. */
+  isGliding(nowMs: number): boolean {
+    for (const glide of this.#glides.values()) {
+      if (
+        nowMs - glide.startedMs < MINIMAP_GLIDE_MS &&
+        (glide.fromX !== glide.toX || glide.fromZ !== glide.toZ)
+      ) {
+        return true;
+      }
+    }
+    return false;
+  }
+
+  clear(): void {
+    this.#glides.clear();
+  }
+}
+
+function positionAt(glide: Glide, nowMs: number): { x: number; z: number } {
+  const t = Math.min(
+    1,
+    Math.max(0, (nowMs - glide.startedMs) / MINIMAP_GLIDE_MS),
+  );
+  return {
+    x: glide.fromX + (glide.toX - glide.fromX) * t,
+    z: glide.fromZ + (glide.toZ - glide.fromZ) * t,
+  };
+}
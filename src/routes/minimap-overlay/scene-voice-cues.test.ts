@@ -23,6 +23,7 @@ function entity(
     facing: null,
     topSummonerId: null,
     isDead: false,
+    positionEpoch: 0,
   };
 }
 